@@ -58,6 +58,31 @@ impl Client {
         Self::parse_response(resp)
     }
 
+    /// GET a file, e.g. a rendered graph. Errors still come as JSON.
+    pub fn get_bytes(&self, path: &str) -> Result<Vec<u8>, String> {
+        let url = format!("{}{}", self.server, path);
+        let mut req = self.http.get(&url);
+        if let Some(auth) = self.auth_header() {
+            req = req.header("Authorization", auth);
+        }
+        let resp = req.send().map_err(|e| format!("Request failed: {}", e))?;
+        let is_json = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .is_some_and(|t| t.as_bytes().starts_with(b"application/json"));
+        if is_json || !resp.status().is_success() {
+            let json = Self::parse_response(resp)?;
+            return Err(json
+                .get("error")
+                .and_then(|e| e.as_str())
+                .unwrap_or("Unknown error")
+                .to_string());
+        }
+        resp.bytes()
+            .map(|b| b.to_vec())
+            .map_err(|e| format!("Failed to read response: {}", e))
+    }
+
     pub fn post(&self, path: &str, body: &Value) -> Result<Value, String> {
         let url = format!("{}{}", self.server, path);
         let mut req = self.http.post(&url).json(body);
@@ -78,6 +103,17 @@ impl Client {
         Self::parse_response(resp)
     }
 
+    /// POST a raw text body, e.g. a file to import.
+    pub fn post_text(&self, path: &str, body: String) -> Result<Value, String> {
+        let url = format!("{}{}", self.server, path);
+        let mut req = self.http.post(&url).header("Content-Type", "text/plain").body(body);
+        if let Some(auth) = self.auth_header() {
+            req = req.header("Authorization", auth);
+        }
+        let resp = req.send().map_err(|e| format!("Request failed: {}", e))?;
+        Self::parse_response(resp)
+    }
+
     pub fn patch(&self, path: &str, body: &Value) -> Result<Value, String> {
         let url = format!("{}{}", self.server, path);
         let mut req = self.http.patch(&url).json(body);
@@ -1,4 +1,4 @@
-use clap::Subcommand;
+use clap::{Args, Subcommand};
 
 #[derive(Subcommand)]
 pub enum Commands {
@@ -18,7 +18,11 @@ pub enum Commands {
     /// Show current user info
     Me,
 
-    /// Manage client token (for Pico W or similar devices)
+    /// Manage monitored devices (each board pings with its own token)
+    #[command(subcommand)]
+    Device(DeviceCommands),
+
+    /// Manage tokens: the account token (CLI/API), device heartbeat tokens and named tokens
     #[command(subcommand)]
     Token(TokenCommands),
 
@@ -26,6 +30,18 @@ pub enum Commands {
     #[command(subcommand)]
     Ntfy(NtfyCommands),
 
+    /// Manage notification channels (ntfy, webhooks, Telegram, email, Matrix, Discord, Slack, Gotify), each can be switched on and off
+    #[command(subcommand)]
+    Channel(ChannelCommands),
+
+    /// Manage reminders for long outages ("still down after 1h"), with escalating priority
+    #[command(subcommand)]
+    Escalation(EscalationCommands),
+
+    /// Manage maintenance windows (weekly or one-off), outages during them are not notified
+    #[command(subcommand)]
+    Maintenance(MaintenanceCommands),
+
     /// Set notification language (e.g., "uk", "en")
     Language {
         /// Language code (e.g., "uk" for Ukrainian, "en" for English)
@@ -38,7 +54,64 @@ pub enum Commands {
     /// Resume monitoring (restore pre-pause state)
     Unpause,
 
-    /// Manage monitoring settings (up_delay, maintenance window)
+    /// Show recorded state transitions (newest first)
+    Events {
+        /// Only events at or after this time (Unix timestamp, or relative: 30m, 12h, 7d)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only events before this time (Unix timestamp, or relative: 30m, 12h, 7d)
+        #[arg(long)]
+        until: Option<String>,
+        /// Maximum number of events to show
+        #[arg(long, default_value = "20")]
+        limit: i64,
+        /// Continue from a previous page (printed as "Next cursor")
+        #[arg(long)]
+        cursor: Option<String>,
+    },
+
+    /// Show availability statistics (up/down time, outages, MTTR, MTBF)
+    Stats {
+        /// Reporting window: 24h, 7d or 30d
+        #[arg(long, default_value = "24h")]
+        window: String,
+    },
+
+    /// Save an uptime graph (timeline and availability per hour or day) as SVG or PNG
+    Graph {
+        /// Reporting window: 24h, 7d or 30d
+        #[arg(long, default_value = "24h")]
+        window: String,
+        /// svg or png, taken from the file extension by default
+        #[arg(long)]
+        format: Option<String>,
+        /// File to write, e.g. uptime.svg
+        #[arg(short, long)]
+        output: String,
+    },
+
+    /// Show the planned outages of your queue (see `settings queue`)
+    Schedule,
+
+    /// Print the URL to subscribe to outages and maintenance windows in a calendar app
+    Calendar {
+        /// Token to put into the URL, preferably a named one that can be revoked (see `token create`)
+        #[arg(long)]
+        token: Option<String>,
+    },
+
+    /// Print the link to your status page, which notifications open when clicked
+    StatusPage {
+        /// Replace the link with a new one (WARNING: the old link stops working immediately)
+        #[arg(long)]
+        regenerate: bool,
+    },
+
+    /// Manage shareable status pages grouping several devices, e.g. all apartments of a building
+    #[command(subcommand)]
+    Page(PageCommands),
+
+    /// Manage monitoring settings (up_delay, time zone, quiet hours, outage queue, outage debouncing)
     #[command(subcommand)]
     Settings(SettingsCommands),
 
@@ -56,21 +129,138 @@ pub enum SettingsCommands {
         /// Timeout in seconds (minimum 10)
         seconds: i16,
     },
-    /// Set or clear daily maintenance window (times in HH:MM UTC)
-    Maintenance {
-        /// Start time in HH:MM UTC (omit both times to clear)
+    /// Set or clear daily quiet hours of the account (local times in HH:MM), see `settings notifications --quiet`
+    QuietHours {
+        /// Start time in HH:MM (omit both times to clear)
         start: Option<String>,
-        /// End time in HH:MM UTC
+        /// End time in HH:MM
         end: Option<String>,
+        /// Also set the account's time zone the times are in (IANA name, e.g. Europe/Kyiv)
+        #[arg(long)]
+        tz: Option<String>,
+    },
+    /// Set the account's time zone: maintenance windows and quiet hours follow its daylight saving time
+    Timezone {
+        /// IANA time zone name, e.g. Europe/Kyiv or UTC
+        tz: String,
+    },
+    /// Set or clear the account's queue of the planned outage schedule, outages are then told apart
+    /// as planned or unplanned
+    Queue {
+        /// Queue name, e.g. 3.1 (omit to clear)
+        queue: Option<String>,
+    },
+    /// Only notify outages that last at least this long (0 notifies right away)
+    MinOutage {
+        /// Seconds
+        seconds: i32,
+    },
+    /// Enable or disable flap detection: a device going up and down too often sends one
+    /// "unstable connection" notice instead, until it is stable again
+    Flapping {
+        /// Up/down changes within the window that count as flapping (omit to disable)
+        changes: Option<i16>,
+        /// Window in minutes
+        #[arg(long)]
+        window: Option<i16>,
+        /// Minutes without changes before the device counts as stable again
+        #[arg(long)]
+        stable: Option<i16>,
+    },
+    /// Heartbeats in a row before a down device counts as up again (1 recovers on the first one)
+    Recovery {
+        /// Number of heartbeats, each within the up delay of the previous one
+        heartbeats: i16,
+    },
+    /// Show or change how each kind of notification is sent (for all devices of the account)
+    Notifications {
+        /// connected, down, up or escalation (omit to show the settings of all of them)
+        event: Option<String>,
+        /// min, low, default, high or max (escalation steps have their own)
+        #[arg(long)]
+        priority: Option<String>,
+        /// ntfy tags, comma separated, e.g. rotating_light ("" for the default)
+        #[arg(long)]
+        tags: Option<String>,
+        /// Icon URL shown by ntfy ("" to remove)
+        #[arg(long)]
+        icon: Option<String>,
+        /// URL opened when the notification is clicked ("" to remove)
+        #[arg(long)]
+        click: Option<String>,
+        /// During quiet hours: send, silent (min priority), defer (one digest when they end) or drop ("" for the default)
+        #[arg(long)]
+        quiet: Option<String>,
+        /// Send this kind of notification again
+        #[arg(long, conflicts_with = "disable")]
+        enable: bool,
+        /// Stop sending this kind of notification
+        #[arg(long)]
+        disable: bool,
+        /// Back to the defaults
+        #[arg(long, conflicts_with_all = ["priority", "tags", "icon", "click", "quiet", "enable", "disable"])]
+        reset: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DeviceCommands {
+    /// List devices and their current state
+    List,
+    /// Register a new device and print its heartbeat token (shown only once)
+    Add {
+        /// Device name, used in notifications (e.g., "Generator")
+        name: String,
+        /// Heartbeat timeout in seconds (default 60)
+        #[arg(long)]
+        delay: Option<u16>,
+    },
+    /// Show a device
+    Show {
+        /// Device ID
+        id: String,
+    },
+    /// Rename a device
+    Rename {
+        /// Device ID
+        id: String,
+        /// New name
+        name: String,
+    },
+    /// Delete a device and its history
+    Remove {
+        /// Device ID
+        id: String,
     },
 }
 
 #[derive(Subcommand)]
 pub enum TokenCommands {
-    /// Show current access token
-    Show,
-    /// Regenerate access token (WARNING: invalidates current token on all devices)
-    Regenerate,
+    /// Create a named account token, or a heartbeat token with --heartbeat (shown only once)
+    Create {
+        /// Token name (e.g., "home-assistant")
+        name: String,
+        /// Create a heartbeat token for the device (see --device) instead
+        #[arg(long)]
+        heartbeat: bool,
+        /// Expire the token after this long (e.g., 30m, 12h, 90d), never expires by default
+        #[arg(long)]
+        expires_in: Option<String>,
+    },
+    /// List named tokens with their expiry and last use
+    List,
+    /// Revoke a named token (it stops working immediately)
+    Revoke {
+        /// Token ID
+        id: String,
+    },
+    /// Regenerate the account token, or a device heartbeat token with --heartbeat
+    /// (WARNING: the old token stops working immediately)
+    Regenerate {
+        /// Regenerate the heartbeat token of the device (see --device) instead
+        #[arg(long)]
+        heartbeat: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -83,6 +273,154 @@ pub enum NtfyCommands {
     Disable,
 }
 
+#[derive(Subcommand)]
+pub enum EscalationCommands {
+    /// List escalation steps
+    List,
+    /// Remind after an outage lasted this long, e.g. `escalation add 60 --priority max`
+    Add {
+        /// Minutes since the outage started
+        minutes: i32,
+        /// ntfy style priority: min, low, default, high or max
+        #[arg(long, default_value = "high")]
+        priority: String,
+        /// Channel to notify on top of the enabled ones, may be a disabled channel kept for escalations
+        #[arg(long)]
+        channel: Option<String>,
+    },
+    /// Remove an escalation step
+    Remove {
+        /// Step ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MaintenanceCommands {
+    /// List maintenance windows
+    List,
+    /// Add a window for the device given with --device, or all devices without it, e.g.
+    /// `maintenance add 10:00 14:00 --days tue --label "grid works"` or `maintenance add --from 2026-10-20T09:00 --until 2026-10-20T17:00`
+    Add(MaintenanceWindowArgs),
+    /// Replace a window (takes the same arguments as `add`)
+    Update {
+        /// Window ID
+        id: String,
+        #[command(flatten)]
+        window: MaintenanceWindowArgs,
+    },
+    /// Remove a window
+    Remove {
+        /// Window ID
+        id: String,
+    },
+}
+
+/// A weekly window (start and end times, --days) or a one-off one (--from, --until), in the account's
+/// time zone (see `settings timezone`).
+#[derive(Args)]
+pub struct MaintenanceWindowArgs {
+    /// Start time in HH:MM, every week on --days
+    #[arg(requires = "end", conflicts_with_all = ["from", "until"])]
+    pub start: Option<String>,
+    /// End time in HH:MM, before the start time to run past midnight
+    pub end: Option<String>,
+    /// Days of the week, comma separated (e.g. mon,wed,fri), every day by default
+    #[arg(long, requires = "start")]
+    pub days: Option<String>,
+    /// Start of a one-off window, local YYYY-MM-DDTHH:MM
+    #[arg(long, requires = "until")]
+    pub from: Option<String>,
+    /// End of a one-off window, local YYYY-MM-DDTHH:MM
+    #[arg(long, requires = "from")]
+    pub until: Option<String>,
+    /// Note shown in the list, e.g. "grid works"
+    #[arg(long)]
+    pub label: Option<String>,
+}
+
+#[derive(Subcommand)]
+pub enum PageCommands {
+    /// List your status pages
+    List,
+    /// Add a page, e.g. `page add shevchenka-12 "Shevchenka St. 12" --monitor <device-id>="Apt. 1" --visibility unlisted`
+    Add(PageArgs),
+    /// Replace a page (takes the same arguments as `add`)
+    Update {
+        /// Page ID
+        id: String,
+        #[command(flatten)]
+        page: PageArgs,
+    },
+    /// Remove a page
+    Remove {
+        /// Page ID
+        id: String,
+    },
+}
+
+#[derive(Args)]
+pub struct PageArgs {
+    /// Part of the page's URL: lowercase letters, digits and dashes
+    pub slug: String,
+    /// Heading of the page
+    pub title: String,
+    /// Device to show as <device-id> or <device-id>=<name>, in page order (admins may list devices of other accounts)
+    #[arg(long = "monitor", required = true)]
+    pub monitors: Vec<String>,
+    /// private (not served), unlisted (anyone with the link) or public (also listed at /api/v1/pages);
+    /// new pages are private and updated ones keep theirs by default
+    #[arg(long)]
+    pub visibility: Option<String>,
+}
+
+#[derive(Subcommand)]
+pub enum ChannelCommands {
+    /// List notification channels
+    List,
+    /// Add a channel, e.g. `channel add webhook url=https://... secret=...` or `channel add email to=me@example.com`
+    Add {
+        /// Channel kind: ntfy, webhook, telegram, email, matrix, discord, slack or gotify
+        kind: String,
+        /// Settings as key=value pairs (webhook: url, secret; email: to, comma separated;
+        /// matrix: homeserver, access_token, room_id; discord, slack: url; gotify: server, token, priority;
+        /// ntfy on your own server: server, topic, username, password or token, priority)
+        config: Vec<String>,
+    },
+    /// Add a channel from a URL, or update the channel already pointing there, e.g.
+    /// `channel url gotifys://gotify.example.com/<token>`
+    Url {
+        /// ntfy://topic, ntfys://[user:pass@]host/topic, gotifys://host/token, jsons://host/path or mailto:me@example.com
+        url: String,
+        /// Add the channel switched off
+        #[arg(long)]
+        disabled: bool,
+    },
+    /// Change settings of a channel (key=value, `key=` removes the setting)
+    Set {
+        /// Channel ID
+        id: String,
+        /// Settings as key=value pairs
+        #[arg(required = true)]
+        config: Vec<String>,
+    },
+    /// Enable a channel
+    Enable {
+        /// Channel ID
+        id: String,
+    },
+    /// Disable a channel (it is kept with its settings)
+    Disable {
+        /// Channel ID
+        id: String,
+    },
+    /// Remove a channel
+    Remove {
+        /// Channel ID
+        id: String,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum AdminCommands {
     /// List all users
@@ -106,4 +444,26 @@ pub enum AdminCommands {
         /// User ID
         id: String,
     },
+    /// List notifications waiting for delivery
+    Outbox {
+        /// Only list the ones that ran out of delivery attempts
+        #[arg(long)]
+        failed: bool,
+    },
+    /// Queue an undeliverable notification again
+    RetryNotification {
+        /// Notification ID (see `admin outbox --failed`)
+        id: String,
+    },
+    /// Import a planned outage schedule from a JSON, CSV or ICS file
+    ImportSchedule {
+        /// Schedule file
+        file: String,
+        /// json, csv or ics (default: the file extension)
+        #[arg(long)]
+        format: Option<String>,
+        /// Queue of ICS events without CATEGORIES
+        #[arg(long)]
+        queue: Option<String>,
+    },
 }
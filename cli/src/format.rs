@@ -17,6 +17,46 @@ pub fn format_timestamp(ts: &str) -> String {
     }
 }
 
+/// Format Unix seconds as "YYYY-MM-DD HH:MM:SS UTC" (civil-from-days, no date crate needed).
+pub fn format_epoch(secs: i64) -> String {
+    let days = secs.div_euclid(86400);
+    let rem = secs.rem_euclid(86400);
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+/// Format a number of seconds as a compact duration, e.g. "1d 4h 12m".
+pub fn format_secs(secs: i64) -> String {
+    let (d, h, m) = (secs / 86400, (secs % 86400) / 3600, (secs % 3600) / 60);
+    let mut parts = Vec::new();
+    if d > 0 {
+        parts.push(format!("{}d", d));
+    }
+    if h > 0 {
+        parts.push(format!("{}h", h));
+    }
+    if m > 0 || parts.is_empty() {
+        parts.push(format!("{}m", m));
+    }
+    parts.join(" ")
+}
+
 pub fn get_str<'a>(v: &'a Value, key: &str) -> &'a str {
     v.get(key).and_then(|x| x.as_str()).unwrap_or("-")
 }
@@ -63,6 +103,14 @@ where
     }
 }
 
+/// Whether the user state has an enabled ntfy channel.
+fn ntfy_enabled(state: &Value) -> bool {
+    state
+        .get("channels")
+        .and_then(|c| c.as_array())
+        .is_some_and(|c| c.iter().any(|c| get_str(c, "kind") == "Ntfy" && get_bool(c, "enabled")))
+}
+
 pub fn format_me(json: &Value) {
     if let Some(user_wrapper) = json.get("user") {
         let user = user_wrapper.get("user").unwrap_or(user_wrapper);
@@ -74,13 +122,6 @@ pub fn format_me(json: &Value) {
         println!("Type:         {}", get_str(user, "user_type"));
         println!("Language:     {}", get_str(user, "language_code"));
         println!("Created:      {}", format_timestamp(get_str(user, "created_at")));
-        println!("Up delay:     {}s", get_i64(user, "up_delay"));
-        let mw_start = user.get("maint_window_start_utc").and_then(|v| v.as_i64());
-        let mw_end = user.get("maint_window_end_utc").and_then(|v| v.as_i64());
-        match (mw_start, mw_end) {
-            (Some(s), Some(e)) => println!("Maintenance:  {:02}:{:02}-{:02}:{:02} UTC", s / 60, s % 60, e / 60, e % 60),
-            _ => println!("Maintenance:  [not set]"),
-        }
         println!(
             "Invites:      {}/{}",
             get_i64(user, "invites_used"),
@@ -89,10 +130,121 @@ pub fn format_me(json: &Value) {
 
         if let Some(ntfy) = ntfy {
             println!();
-            println!("Ntfy.sh       {}", bool_icon(get_bool(ntfy, "enabled")));
+            println!("Ntfy.sh       {}", bool_icon(ntfy_enabled(user_wrapper)));
             println!("  Topic:      {}", get_str(ntfy, "topic"));
             println!("  Username:   {}", get_str(ntfy, "username"));
         }
+
+        if let Some(devices) = user_wrapper.get("devices").and_then(|d| d.as_array()) {
+            println!();
+            print_devices_table(devices);
+        }
+    } else {
+        print_json(json);
+    }
+}
+
+/// Format a daily window (`<prefix>_start`/`<prefix>_end`) given in minutes from local midnight,
+/// followed by the account's time zone if `v` has it.
+fn format_daily_window(v: &Value, prefix: &str) -> String {
+    let start = v.get(format!("{prefix}_start")).and_then(|v| v.as_i64());
+    let end = v.get(format!("{prefix}_end")).and_then(|v| v.as_i64());
+    let window = match (start, end) {
+        (Some(s), Some(e)) => format!("{:02}:{:02}-{:02}:{:02}", s / 60, s % 60, e / 60, e % 60),
+        _ => return "[not set]".to_string(),
+    };
+    match v.get("timezone").and_then(|tz| tz.as_str()) {
+        Some(tz) => format!("{window} {tz}"),
+        None => window,
+    }
+}
+
+/// Format the account's quiet hours.
+pub fn format_quiet_hours(v: &Value) -> String {
+    format_daily_window(v, "quiet_hours")
+}
+
+/// Queue of the planned outage schedule the account is in.
+pub fn format_outage_queue(v: &Value) -> String {
+    v.get("outage_queue")
+        .and_then(|q| q.as_str())
+        .unwrap_or("[not set]")
+        .to_string()
+}
+
+/// Format the flap detection settings of a device.
+pub fn format_flap_detection(v: &Value) -> String {
+    match v.get("flap_threshold").and_then(|v| v.as_i64()) {
+        Some(changes) => format!(
+            "{} changes in {}m, stable after {}m",
+            changes,
+            get_i64(v, "flap_window_minutes"),
+            get_i64(v, "flap_stable_minutes")
+        ),
+        None => "[off]".to_string(),
+    }
+}
+
+/// Format how many heartbeats a down device needs to count as up again.
+pub fn format_recovery(v: &Value) -> String {
+    match get_i64(v, "recovery_heartbeats") {
+        1 => "first heartbeat".to_string(),
+        n => format!("{} heartbeats in a row", n),
+    }
+}
+
+fn print_devices_table(devices: &[Value]) {
+    println!("{:<36} {:<16} {:<13} {:>6}", "ID", "NAME", "STATUS", "DELAY");
+    println!("{}", "-".repeat(74));
+    for item in devices {
+        let device = item.get("device").unwrap_or(item);
+        let status = item.get("uptime").map(|u| get_str(u, "status")).unwrap_or("-");
+        println!(
+            "{:<36} {:<16} {:<13} {:>5}s",
+            get_str(device, "id"),
+            get_str(device, "name"),
+            status,
+            get_i64(device, "up_delay")
+        );
+    }
+}
+
+pub fn format_devices_list(json: &Value) {
+    if let Some(devices) = json.get("devices").and_then(|d| d.as_array()) {
+        if devices.is_empty() {
+            println!("No devices found.");
+            return;
+        }
+        print_devices_table(devices);
+        println!();
+        println!("Total: {} device(s)", devices.len());
+    } else {
+        print_json(json);
+    }
+}
+
+pub fn format_device(json: &Value) {
+    if let Some(item) = json.get("device") {
+        let device = item.get("device").unwrap_or(item);
+        println!("Device");
+        println!("======");
+        println!("ID:           {}", get_str(device, "id"));
+        println!("Name:         {}", get_str(device, "name"));
+        // Only present right after `device add`, the server doesn't keep plaintext tokens
+        if let Some(token) = json.get("access_token").and_then(|t| t.as_str()) {
+            println!("Token:        {}", token);
+        }
+        println!("Up delay:     {}s", get_i64(device, "up_delay"));
+        println!("Min outage:   {}s", get_i64(device, "min_outage_secs"));
+        println!("Flapping:     {}", format_flap_detection(device));
+        println!("Recovery:     {}", format_recovery(device));
+        if let Some(uptime) = item.get("uptime") {
+            println!("Status:       {}", get_str(uptime, "status"));
+            if let Some(since) = uptime.get("flapping_since").and_then(|s| s.as_i64()) {
+                println!("Unstable:     since {}", format_epoch(since));
+            }
+            println!("Last seen:    {}", format_epoch(get_i64(uptime, "touched_at")));
+        }
     } else {
         print_json(json);
     }
@@ -108,11 +260,10 @@ pub fn format_users_list(json: &Value) {
         println!("{}", "-".repeat(65));
         for user_wrapper in users {
             let user = user_wrapper.get("user").unwrap_or(user_wrapper);
-            let ntfy = user_wrapper.get("ntfy");
 
             let user_type = get_str(user, "user_type");
             let id = get_str(user, "id");
-            let ntfy_on = ntfy.map(|n| get_bool(n, "enabled")).unwrap_or(false);
+            let ntfy_on = ntfy_enabled(user_wrapper);
             let invites = format!("{}/{}", get_i64(user, "invites_used"), get_i64(user, "invites_limit"));
 
             println!("{:<8} {:<36} {:>7} {:>10}", user_type, id, bool_icon(ntfy_on), invites);
@@ -145,6 +296,41 @@ pub fn format_invites_list(json: &Value) {
     }
 }
 
+pub fn format_outbox_list(json: &Value) {
+    if let Some(items) = json.get("outbox").and_then(|o| o.as_array()) {
+        if items.is_empty() {
+            println!("No queued notifications.");
+            return;
+        }
+        println!(
+            "{:<36} {:<10} {:>8} {:<23} {:<30}",
+            "ID", "EVENT", "ATTEMPTS", "CREATED", "STATUS"
+        );
+        println!("{}", "-".repeat(111));
+        for item in items {
+            let status = match item.get("failed_at").and_then(|f| f.as_i64()) {
+                Some(at) => format!("failed {}", format_epoch(at)),
+                None => format!("next {}", format_epoch(get_i64(item, "next_attempt_at"))),
+            };
+            println!(
+                "{:<36} {:<10} {:>8} {:<23} {:<30}",
+                get_str(item, "id"),
+                get_str(item, "event"),
+                get_i64(item, "attempts"),
+                format_epoch(get_i64(item, "created_at")),
+                status
+            );
+            if let Some(err) = item.get("last_error").and_then(|e| e.as_str()) {
+                println!("  {}", err);
+            }
+        }
+        println!();
+        println!("Total: {} notification(s)", items.len());
+    } else {
+        print_json(json);
+    }
+}
+
 pub fn format_ntfy(json: &Value) {
     if let Some(ntfy) = json.get("ntfy") {
         println!("Ntfy.sh Settings");
@@ -163,12 +349,425 @@ pub fn format_settings_update(json: &Value) {
     if let Some(v) = json.get("up_delay").and_then(|v| v.as_i64()) {
         println!("  up_delay:     {}s", v);
     }
-    let start = json.get("maint_window_start_utc");
-    let end = json.get("maint_window_end_utc");
-    if start.is_some() || end.is_some() {
-        match (start.and_then(|v| v.as_i64()), end.and_then(|v| v.as_i64())) {
-            (Some(s), Some(e)) => println!("  maintenance:  {:02}:{:02}-{:02}:{:02} UTC", s / 60, s % 60, e / 60, e % 60),
-            _ => println!("  maintenance:  [cleared]"),
+    if let Some(tz) = json.get("timezone").and_then(|v| v.as_str()) {
+        println!("  time zone:    {}", tz);
+    }
+    if json.get("quiet_hours_start").is_some() {
+        println!("  quiet hours:  {}", format_quiet_hours(json));
+    }
+    if json.get("outage_queue").is_some() {
+        println!("  queue:        {}", format_outage_queue(json));
+    }
+    if let Some(v) = json.get("min_outage_secs").and_then(|v| v.as_i64()) {
+        println!("  min_outage:   {}s", v);
+    }
+    if json.get("flap_threshold").is_some() {
+        println!("  flapping:     {}", format_flap_detection(json));
+    }
+    if json.get("recovery_heartbeats").is_some() {
+        println!("  recovery:     {}", format_recovery(json));
+    }
+}
+
+/// Notification settings per event kind, from `GET /api/v1/me/settings`.
+pub fn format_notification_settings(json: &Value) {
+    let Some(kinds) = json.get("notifications").and_then(|n| n.as_object()) else {
+        print_json(json);
+        return;
+    };
+    println!(
+        "{:<11} {:<5} {:<8} {:<24} {:<7} CLICK",
+        "EVENT", "SENT", "PRIORITY", "TAGS", "QUIET"
+    );
+    println!("{}", "-".repeat(98));
+    for (kind, settings) in kinds {
+        let tags: Vec<&str> = settings
+            .get("tags")
+            .and_then(|t| t.as_array())
+            .map(|t| t.iter().filter_map(|t| t.as_str()).collect())
+            .unwrap_or_default();
+        println!(
+            "{:<11} {:<5} {:<8} {:<24} {:<7} {}",
+            kind,
+            bool_icon(get_bool(settings, "enabled")),
+            settings.get("priority").and_then(|p| p.as_str()).unwrap_or("per step"),
+            tags.join(","),
+            get_str(settings, "quiet"),
+            settings.get("click").and_then(|c| c.as_str()).unwrap_or("-")
+        );
+        if let Some(icon) = settings.get("icon").and_then(|i| i.as_str()) {
+            println!("{:<11} icon: {}", "", icon);
         }
     }
 }
+
+pub fn format_events(json: &Value) {
+    if let Some(events) = json.get("events").and_then(|e| e.as_array()) {
+        if events.is_empty() {
+            println!("No events found.");
+            return;
+        }
+        println!(
+            "{:<23} {:<8} {:<13} {:<13} {:<11} {:>12}",
+            "AT", "DEVICE", "FROM", "TO", "SOURCE", "PREVIOUS"
+        );
+        println!("{}", "-".repeat(85));
+        for event in events {
+            // Short device id prefix, enough to tell devices apart
+            let device: String = get_str(event, "device_id").chars().take(8).collect();
+            println!(
+                "{:<23} {:<8} {:<13} {:<13} {:<11} {:>12}",
+                format_epoch(get_i64(event, "at")),
+                device,
+                get_str(event, "from_status"),
+                get_str(event, "to_status"),
+                get_str(event, "source"),
+                format_secs(get_i64(event, "duration_in_previous_state"))
+            );
+        }
+        if let Some(cursor) = json.get("next_cursor").and_then(|c| c.as_str()) {
+            println!();
+            println!("Next cursor:  {}", cursor);
+        }
+    } else {
+        print_json(json);
+    }
+}
+
+pub fn format_stats(json: &Value) {
+    if let Some(stats) = json.get("stats") {
+        let secs_or_dash = |key: &str| match stats.get(key).and_then(|v| v.as_i64()) {
+            Some(v) => format_secs(v),
+            None => "-".to_string(),
+        };
+        println!("Stats ({})", get_str(json, "window"));
+        println!("==========");
+        match stats.get("availability").and_then(|v| v.as_f64()) {
+            Some(a) => println!("Availability: {:.2}%", a),
+            None => println!("Availability: -"),
+        }
+        println!("Up:           {}", format_secs(get_i64(stats, "up")));
+        println!("Down:         {}", format_secs(get_i64(stats, "down")));
+        println!("Paused:       {}", format_secs(get_i64(stats, "paused")));
+        println!("Maintenance:  {}", format_secs(get_i64(stats, "maintenance")));
+        println!("No data:      {}", format_secs(get_i64(stats, "unknown")));
+        println!();
+        println!("Outages:      {}", get_i64(stats, "outages"));
+        println!("Longest:      {}", format_secs(get_i64(stats, "longest_outage")));
+        println!("MTTR:         {}", secs_or_dash("mttr"));
+        println!("MTBF:         {}", secs_or_dash("mtbf"));
+    } else {
+        print_json(json);
+    }
+}
+
+fn epoch_or(v: &Value, key: &str, fallback: &str) -> String {
+    match v.get(key).and_then(|x| x.as_i64()) {
+        Some(ts) => format_epoch(ts),
+        None => fallback.to_string(),
+    }
+}
+
+pub fn format_tokens_list(json: &Value) {
+    if let Some(tokens) = json.get("tokens").and_then(|t| t.as_array()) {
+        if tokens.is_empty() {
+            println!("No named tokens found.");
+            return;
+        }
+        println!(
+            "{:<36} {:<20} {:<9} {:<23} {:<23}",
+            "ID", "NAME", "SCOPE", "EXPIRES", "LAST USED"
+        );
+        println!("{}", "-".repeat(115));
+        for token in tokens {
+            println!(
+                "{:<36} {:<20} {:<9} {:<23} {:<23}",
+                get_str(token, "id"),
+                get_str(token, "name"),
+                get_str(token, "scope"),
+                epoch_or(token, "expires_at", "never"),
+                epoch_or(token, "last_used_at", "never")
+            );
+        }
+        println!();
+        println!("Total: {} token(s)", tokens.len());
+    } else {
+        print_json(json);
+    }
+}
+
+pub fn format_token_created(json: &Value) {
+    if let Some(token) = json.get("token") {
+        println!("Token created successfully!");
+        println!();
+        println!("ID:           {}", get_str(token, "id"));
+        println!("Name:         {}", get_str(token, "name"));
+        println!("Scope:        {}", get_str(token, "scope"));
+        if let Some(device) = token.get("device_id").and_then(|d| d.as_str()) {
+            println!("Device:       {}", device);
+        }
+        println!("Expires:      {}", epoch_or(token, "expires_at", "never"));
+        println!("Token:        {}", get_str(json, "access_token"));
+        println!();
+        println!("Save it now, it can't be shown again.");
+    } else {
+        print_json(json);
+    }
+}
+
+pub fn format_channels_list(json: &Value) {
+    if let Some(channels) = json.get("channels").and_then(|c| c.as_array()) {
+        if channels.is_empty() {
+            println!("No notification channels, nothing will be sent.");
+            return;
+        }
+        println!("{:<36} {:<8} {:<5} TARGET", "ID", "KIND", "STATE");
+        println!("{}", "-".repeat(90));
+        for channel in channels {
+            println!(
+                "{:<36} {:<8} {:<5} {}",
+                get_str(channel, "id"),
+                get_str(channel, "kind").to_lowercase(),
+                bool_icon(get_bool(channel, "enabled")),
+                get_str(channel, "description")
+            );
+        }
+        println!();
+        println!("Total: {} channel(s)", channels.len());
+    } else {
+        print_json(json);
+    }
+}
+
+pub fn format_channel_added(json: &Value) {
+    let Some(channel) = json.get("channel") else {
+        print_json(json);
+        return;
+    };
+    println!("Channel added: {}", get_str(channel, "id"));
+    if let Some(link) = channel.get("link") {
+        println!();
+        match link.get("bot").and_then(|b| b.as_str()) {
+            Some(bot) => println!("Send this to @{} in Telegram:", bot),
+            None => println!("Send this to the bot in Telegram:"),
+        }
+        println!("  /start {}", get_str(link, "code"));
+        if let Some(url) = link.get("url").and_then(|u| u.as_str()) {
+            println!("or open {}", url);
+        }
+        println!("Valid until {}.", epoch_or(link, "expires_at", "-"));
+    }
+    if get_str(channel, "kind") == "Email" {
+        println!("{}", get_str(channel, "description"));
+        println!("A confirmation link was mailed to each address, nothing is sent to them before it is opened.");
+    }
+}
+
+/// "after 1h 30m" style delay of an escalation step.
+fn format_delay_minutes(minutes: i64) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+pub fn format_escalation_list(json: &Value) {
+    if let Some(steps) = json.get("steps").and_then(|s| s.as_array()) {
+        if steps.is_empty() {
+            println!("No escalation steps, outages are notified once.");
+            return;
+        }
+        println!("{:<36} {:<10} {:<8} EXTRA CHANNEL", "ID", "AFTER", "PRIORITY");
+        println!("{}", "-".repeat(90));
+        for step in steps {
+            println!(
+                "{:<36} {:<10} {:<8} {}",
+                get_str(step, "id"),
+                format_delay_minutes(get_i64(step, "delay_minutes")),
+                get_str(step, "priority"),
+                step.get("channel_id").and_then(|c| c.as_str()).unwrap_or("-")
+            );
+        }
+        println!();
+        println!("Total: {} step(s)", steps.len());
+    } else {
+        print_json(json);
+    }
+}
+
+pub fn format_escalation_added(json: &Value) {
+    match json.get("step") {
+        Some(step) => println!(
+            "Escalation step added: {} (still down after {}, priority {})",
+            get_str(step, "id"),
+            format_delay_minutes(get_i64(step, "delay_minutes")),
+            get_str(step, "priority")
+        ),
+        None => print_json(json),
+    }
+}
+
+/// Result of `channel url`, which either adds a channel or updates an existing one.
+pub fn format_channel_applied(json: &Value) {
+    match json.get("created").and_then(|c| c.as_bool()) {
+        Some(true) => format_channel_added(json),
+        _ => format_channel_updated(json),
+    }
+}
+
+pub fn format_channel_updated(json: &Value) {
+    if let Some(channel) = json.get("channel") {
+        println!(
+            "Channel {} {} {}",
+            get_str(channel, "id"),
+            bool_icon(get_bool(channel, "enabled")),
+            get_str(channel, "description")
+        );
+    } else {
+        print_json(json);
+    }
+}
+
+/// Format when a maintenance window applies: "tue,thu 10:00-14:00" or "2026-10-20T09:00 - 2026-10-20T17:00".
+fn format_maintenance_when(window: &Value) -> String {
+    let hhmm = |key: &str| {
+        let m = get_i64(window, key);
+        format!("{:02}:{:02}", m / 60, m % 60)
+    };
+    match window.get("weekdays").and_then(|d| d.as_array()) {
+        Some(days) => {
+            let days: Vec<&str> = days.iter().filter_map(|d| d.as_str()).collect();
+            let days = if days.len() == 7 {
+                "daily".to_string()
+            } else {
+                days.join(",")
+            };
+            format!("{} {}-{}", days, hhmm("start_minute"), hhmm("end_minute"))
+        }
+        None => format!("{} - {}", get_str(window, "starts_at"), get_str(window, "ends_at")),
+    }
+}
+
+pub fn format_maintenance_list(json: &Value) {
+    if let Some(windows) = json.get("windows").and_then(|w| w.as_array()) {
+        if windows.is_empty() {
+            println!("No maintenance windows.");
+            return;
+        }
+        println!("Times are local to {}", get_str(json, "timezone"));
+        println!();
+        println!("{:<36} {:<36} {:<36} {:<6} LABEL", "ID", "WHEN", "DEVICE", "ACTIVE");
+        println!("{}", "-".repeat(130));
+        for window in windows {
+            println!(
+                "{:<36} {:<36} {:<36} {:<6} {}",
+                get_str(window, "id"),
+                format_maintenance_when(window),
+                window.get("device_id").and_then(|d| d.as_str()).unwrap_or("all devices"),
+                if get_bool(window, "active") { "yes" } else { "-" },
+                window.get("label").and_then(|l| l.as_str()).unwrap_or("-")
+            );
+        }
+        println!();
+        println!("Total: {} window(s)", windows.len());
+    } else {
+        print_json(json);
+    }
+}
+
+pub fn format_maintenance_saved(json: &Value) {
+    match json.get("window") {
+        Some(window) => println!(
+            "Maintenance window {}: {} {}{}",
+            get_str(window, "id"),
+            format_maintenance_when(window),
+            get_str(json, "timezone"),
+            if get_bool(window, "active") { " (active now)" } else { "" }
+        ),
+        None => print_json(json),
+    }
+}
+
+/// Link to a status page: its `url`, or the CLI's server with its `path` when the server doesn't know
+/// its public address.
+fn page_link(page: &Value, server: &str) -> String {
+    match page.get("url").and_then(|u| u.as_str()) {
+        Some(url) => url.to_string(),
+        None => format!("{}{}", server.trim_end_matches('/'), get_str(page, "path")),
+    }
+}
+
+pub fn format_page_list(json: &Value, server: &str) {
+    if let Some(pages) = json.get("pages").and_then(|p| p.as_array()) {
+        if pages.is_empty() {
+            println!("No status pages.");
+            return;
+        }
+        println!("{:<36} {:<10} {:<8} {:<30} LINK", "ID", "VISIBILITY", "DEVICES", "TITLE");
+        println!("{}", "-".repeat(130));
+        for page in pages {
+            println!(
+                "{:<36} {:<10} {:<8} {:<30} {}",
+                get_str(page, "id"),
+                get_str(page, "visibility"),
+                page.get("monitors").and_then(|m| m.as_array()).map_or(0, |m| m.len()),
+                get_str(page, "title"),
+                page_link(page, server)
+            );
+        }
+        println!();
+        println!("Total: {} page(s)", pages.len());
+    } else {
+        print_json(json);
+    }
+}
+
+pub fn format_page_saved(json: &Value, server: &str) {
+    match json.get("page") {
+        Some(page) => {
+            println!("Status page {} ({})", get_str(page, "id"), get_str(page, "visibility"));
+            println!("HTML: {}", page_link(page, server));
+            println!("JSON: {}/json", page_link(page, server));
+        }
+        None => print_json(json),
+    }
+}
+
+/// The account's queue and its upcoming planned outages, from `GET /api/v1/me/schedule`.
+pub fn format_schedule(json: &Value) {
+    let queues: Vec<&str> = json
+        .get("queues")
+        .and_then(|q| q.as_array())
+        .map(|q| q.iter().filter_map(|q| q.as_str()).collect())
+        .unwrap_or_default();
+    let Some(queue) = json.get("queue").and_then(|q| q.as_str()) else {
+        println!("No outage queue set, pick one with `settings queue <queue>`.");
+        if !queues.is_empty() {
+            println!("Known queues: {}", queues.join(", "));
+        }
+        return;
+    };
+    println!("Queue {} (times are local to {})", queue, get_str(json, "timezone"));
+    match json.get("planned_until").and_then(|u| u.as_str()) {
+        Some(until) => println!("Planned outage now, until {}", until.replace('T', " ")),
+        None => println!("No planned outage now"),
+    }
+    let outages = json.get("outages").and_then(|o| o.as_array()).cloned().unwrap_or_default();
+    if outages.is_empty() {
+        println!("No upcoming planned outages.");
+        return;
+    }
+    println!();
+    println!("{:<18} {:<18}", "FROM", "UNTIL");
+    println!("{}", "-".repeat(37));
+    for outage in &outages {
+        println!(
+            "{:<18} {:<18}",
+            get_str(outage, "starts_at").replace('T', " "),
+            get_str(outage, "ends_at").replace('T', " ")
+        );
+    }
+    println!();
+    println!("Total: {} planned outage(s)", outages.len());
+}
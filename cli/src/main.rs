@@ -23,6 +23,10 @@ struct Cli {
     #[arg(long, global = true)]
     raw: bool,
 
+    /// Device ID for pause/unpause/events/stats/settings/token (optional with a single device)
+    #[arg(long, global = true)]
+    device: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -34,10 +38,144 @@ fn require_token(token: &Option<String>) {
     }
 }
 
+/// Parse a duration like 30m, 12h or 7d into seconds.
+fn parse_duration_arg(value: &str) -> Result<u64, String> {
+    let (num, unit) = value.split_at(value.len().saturating_sub(1));
+    let n: u64 = num
+        .parse()
+        .map_err(|_| format!("Invalid duration '{}', expected e.g. 30m, 12h or 7d", value))?;
+    match unit {
+        "m" => Ok(n * 60),
+        "h" => Ok(n * 3600),
+        "d" => Ok(n * 86400),
+        _ => Err(format!("Invalid time unit in '{}', expected m, h or d", value)),
+    }
+}
+
+/// Channel kinds are typed lowercase on the command line, the API names them like "Webhook".
+fn channel_kind_arg(kind: &str) -> String {
+    let mut chars = kind.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+        None => String::new(),
+    }
+}
+
+/// Parse key=value pairs into a JSON object, an empty value becomes null (removes the key on update).
+fn parse_config_args(pairs: &[String]) -> Result<serde_json::Value, String> {
+    let mut config = serde_json::Map::new();
+    for pair in pairs {
+        let Some((key, value)) = pair.split_once('=') else {
+            return Err(format!("Invalid setting '{}', expected key=value", pair));
+        };
+        let value = if value.is_empty() {
+            serde_json::Value::Null
+        } else {
+            value.into()
+        };
+        config.insert(key.to_string(), value);
+    }
+    Ok(config.into())
+}
+
+/// Parse a time argument: either a Unix timestamp or a relative "ago" value (30m, 12h, 7d).
+fn parse_time_arg(value: &str) -> Result<u64, String> {
+    if let Ok(ts) = value.parse::<u64>() {
+        return Ok(ts);
+    }
+    let secs = parse_duration_arg(value)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok(now.saturating_sub(secs))
+}
+
+/// Parse a time of day given as HH:MM into minutes from midnight.
+fn parse_hhmm(t: &str) -> Result<i16, String> {
+    let parts: Vec<&str> = t.split(':').collect();
+    if parts.len() != 2 {
+        return Err(format!("Invalid time format '{}', expected HH:MM", t));
+    }
+    let h: i16 = parts[0].parse().map_err(|_| format!("Invalid hour in '{}'", t))?;
+    let m: i16 = parts[1].parse().map_err(|_| format!("Invalid minute in '{}'", t))?;
+    if !(0..=23).contains(&h) || !(0..=59).contains(&m) {
+        return Err(format!("Time '{}' out of range (00:00-23:59)", t));
+    }
+    Ok(h * 60 + m)
+}
+
+/// Settings update for a daily window like quiet hours (`<prefix>_start`/`<prefix>_end`), cleared without times.
+/// `tz` changes the account's time zone along with it.
+fn daily_window_body(
+    prefix: &str,
+    start: Option<String>,
+    end: Option<String>,
+    tz: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let (start, end) = match (start, end) {
+        (Some(s), Some(e)) => (serde_json::json!(parse_hhmm(&s)?), serde_json::json!(parse_hhmm(&e)?)),
+        (None, None) => (serde_json::Value::Null, serde_json::Value::Null),
+        _ => return Err("Provide both start and end times, or neither to clear".to_string()),
+    };
+    let mut body = serde_json::json!({});
+    body[format!("{prefix}_start")] = start;
+    body[format!("{prefix}_end")] = end;
+    if let Some(tz) = tz {
+        body["timezone"] = serde_json::json!(tz);
+    }
+    Ok(body)
+}
+
+/// Maintenance window to add or replace, for `device` or all devices.
+fn maintenance_window_body(args: MaintenanceWindowArgs, device: &Option<String>) -> Result<serde_json::Value, String> {
+    let mut body = serde_json::json!({"device_id": device, "label": args.label});
+    match (args.start, args.end, args.from, args.until) {
+        (Some(start), Some(end), None, None) => {
+            let days = args.days.unwrap_or_else(|| "mon,tue,wed,thu,fri,sat,sun".to_string());
+            body["weekdays"] = serde_json::json!(days.split(',').map(str::trim).collect::<Vec<_>>());
+            body["start_minute"] = serde_json::json!(parse_hhmm(&start)?);
+            body["end_minute"] = serde_json::json!(parse_hhmm(&end)?);
+        }
+        (None, None, Some(from), Some(until)) => {
+            body["starts_at"] = serde_json::json!(from);
+            body["ends_at"] = serde_json::json!(until);
+        }
+        _ => return Err("Provide start and end times (weekly), or --from and --until (one-off)".to_string()),
+    }
+    Ok(body)
+}
+
+/// Status page to add or replace.
+fn page_body(args: PageArgs) -> serde_json::Value {
+    let monitors: Vec<serde_json::Value> = args
+        .monitors
+        .iter()
+        .map(|m| match m.split_once('=') {
+            Some((id, name)) => serde_json::json!({"device_id": id, "name": name}),
+            None => serde_json::json!({"device_id": m}),
+        })
+        .collect();
+    let mut body = serde_json::json!({"slug": args.slug, "title": args.title, "monitors": monitors});
+    if let Some(visibility) = args.visibility {
+        body["visibility"] = serde_json::json!(visibility.to_lowercase());
+    }
+    body
+}
+
+/// Append the `device` query parameter to a path if one was given.
+fn with_device(path: &str, device: &Option<String>) -> String {
+    match device {
+        Some(id) => format!("{}{}device={}", path, if path.contains('?') { "&" } else { "?" }, id),
+        None => path.to_string(),
+    }
+}
+
 fn main() {
     let cli = Cli::parse();
-    let client = Client::new(cli.server, cli.token.clone());
+    let client = Client::new(cli.server.clone(), cli.token.clone());
 
+    let settings_path = with_device("/api/v1/me/settings", &cli.device);
     match cli.command {
         Commands::Init {
             invite,
@@ -57,18 +195,19 @@ fn main() {
                 body["invite"] = serde_json::json!(inv);
             }
             handle_response_with(client.post("/api/v1/users", &body), cli.raw, |json| {
-                if let Some(token) = json
-                    .get("state")
-                    .and_then(|s| s.get("user"))
-                    .and_then(|u| u.get("access_token"))
-                    .and_then(|t| t.as_str())
-                {
+                // Tokens are only ever returned here (the server stores hashes), so print both now
+                if let Some(token) = json.get("access_token").and_then(|t| t.as_str()) {
                     let label = if is_admin { "Admin" } else { "User" };
                     println!("{} created successfully!", label);
                     println!("Your access token: {}", token);
                     println!("\nUse this token with the CLI:");
                     println!("  export OUBOT_TOKEN={}", token);
                     println!("  oubot-cli me");
+                    if let Some(heartbeat) = json.get("heartbeat_token").and_then(|t| t.as_str()) {
+                        println!("\nHeartbeat token for your device (OUBOT_TOKEN in the firmware build):");
+                        println!("  {}", heartbeat);
+                    }
+                    println!("\nSave both now, they can't be shown again (only regenerated).");
                 } else {
                     print_json(json);
                 }
@@ -80,30 +219,181 @@ fn main() {
             handle_response_with(client.get("/api/v1/me"), cli.raw, format_me);
         }
 
+        Commands::Device(cmd) => {
+            require_token(&cli.token);
+            match cmd {
+                DeviceCommands::List => {
+                    handle_response_with(client.get("/api/v1/me/devices"), cli.raw, format_devices_list);
+                }
+                DeviceCommands::Add { name, delay } => {
+                    let mut body = serde_json::json!({"name": name});
+                    if let Some(d) = delay {
+                        body["up_delay"] = serde_json::json!(d);
+                    }
+                    handle_response_with(client.post("/api/v1/me/devices", &body), cli.raw, |json| {
+                        println!("Device created successfully!");
+                        println!();
+                        format_device(json);
+                    });
+                }
+                DeviceCommands::Show { id } => {
+                    handle_response_with(client.get(&format!("/api/v1/me/devices/{}", id)), cli.raw, format_device);
+                }
+                DeviceCommands::Rename { id, name } => {
+                    let body = serde_json::json!({"name": name});
+                    let path = format!("/api/v1/me/devices/{}", id);
+                    handle_response_with(client.patch(&path, &body), cli.raw, format_device);
+                }
+                DeviceCommands::Remove { id } => {
+                    handle_response(client.delete(&format!("/api/v1/me/devices/{}", id)), cli.raw);
+                }
+            }
+        }
+
         Commands::Token(cmd) => {
             require_token(&cli.token);
             match cmd {
-                TokenCommands::Show => match client.get("/api/v1/me") {
-                    Ok(json) => {
-                        if let Some(token) = json
-                            .get("user")
-                            .and_then(|u| u.get("user"))
-                            .and_then(|u| u.get("access_token"))
-                            .and_then(|t| t.as_str())
-                        {
-                            println!("{}", token);
-                        } else {
-                            eprintln!("Error: Could not extract token from response");
-                            std::process::exit(1);
+                TokenCommands::Create {
+                    name,
+                    heartbeat,
+                    expires_in,
+                } => {
+                    let mut body = serde_json::json!({
+                        "name": name,
+                        "scope": if heartbeat { "Heartbeat" } else { "Account" },
+                    });
+                    if heartbeat && let Some(device) = &cli.device {
+                        body["device"] = serde_json::json!(device);
+                    }
+                    if let Some(expires_in) = expires_in {
+                        match parse_duration_arg(&expires_in) {
+                            Ok(secs) => body["expires_in"] = serde_json::json!(secs),
+                            Err(e) => {
+                                eprintln!("Error: {}", e);
+                                std::process::exit(1);
+                            }
                         }
                     }
-                    Err(e) => {
+                    handle_response_with(client.post("/api/v1/me/tokens", &body), cli.raw, format_token_created);
+                }
+                TokenCommands::List => {
+                    handle_response_with(client.get("/api/v1/me/tokens"), cli.raw, format_tokens_list);
+                }
+                TokenCommands::Revoke { id } => {
+                    handle_response(client.delete(&format!("/api/v1/me/tokens/{}", id)), cli.raw);
+                }
+                TokenCommands::Regenerate { heartbeat } => {
+                    let path = if heartbeat {
+                        with_device("/api/v1/me/regenerate-token?kind=heartbeat", &cli.device)
+                    } else {
+                        "/api/v1/me/regenerate-token?kind=account".to_string()
+                    };
+                    handle_response(client.post_empty(&path), cli.raw);
+                }
+            }
+        }
+
+        Commands::Channel(cmd) => {
+            require_token(&cli.token);
+            match cmd {
+                ChannelCommands::List => {
+                    handle_response_with(client.get("/api/v1/me/channels"), cli.raw, format_channels_list);
+                }
+                ChannelCommands::Add { kind, config } => {
+                    let config = parse_config_args(&config).unwrap_or_else(|e| {
                         eprintln!("Error: {}", e);
                         std::process::exit(1);
+                    });
+                    let mut body = serde_json::json!({"kind": channel_kind_arg(&kind)});
+                    if !config.as_object().is_some_and(|c| c.is_empty()) {
+                        body["config"] = config;
                     }
-                },
-                TokenCommands::Regenerate => {
-                    handle_response(client.post_empty("/api/v1/me/regenerate-token"), cli.raw);
+                    handle_response_with(client.post("/api/v1/me/channels", &body), cli.raw, format_channel_added);
+                }
+                ChannelCommands::Url { url, disabled } => {
+                    let mut body = serde_json::json!({"url": url});
+                    if disabled {
+                        body["enabled"] = serde_json::json!(false);
+                    }
+                    handle_response_with(client.patch("/api/v1/me/channels", &body), cli.raw, format_channel_applied);
+                }
+                ChannelCommands::Set { id, config } => {
+                    let config = parse_config_args(&config).unwrap_or_else(|e| {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1);
+                    });
+                    let body = serde_json::json!({"config": config});
+                    handle_response_with(
+                        client.patch(&format!("/api/v1/me/channels/{}", id), &body),
+                        cli.raw,
+                        format_channel_updated,
+                    );
+                }
+                ChannelCommands::Enable { ref id } | ChannelCommands::Disable { ref id } => {
+                    let body = serde_json::json!({"enabled": matches!(cmd, ChannelCommands::Enable { .. })});
+                    handle_response_with(
+                        client.patch(&format!("/api/v1/me/channels/{}", id), &body),
+                        cli.raw,
+                        format_channel_updated,
+                    );
+                }
+                ChannelCommands::Remove { id } => {
+                    handle_response(client.delete(&format!("/api/v1/me/channels/{}", id)), cli.raw);
+                }
+            }
+        }
+
+        Commands::Escalation(cmd) => {
+            require_token(&cli.token);
+            match cmd {
+                EscalationCommands::List => {
+                    handle_response_with(client.get("/api/v1/me/escalation"), cli.raw, format_escalation_list);
+                }
+                EscalationCommands::Add {
+                    minutes,
+                    priority,
+                    channel,
+                } => {
+                    let body = serde_json::json!({
+                        "delay_minutes": minutes,
+                        "priority": priority.to_lowercase(),
+                        "channel_id": channel,
+                    });
+                    handle_response_with(client.post("/api/v1/me/escalation", &body), cli.raw, format_escalation_added);
+                }
+                EscalationCommands::Remove { id } => {
+                    handle_response(client.delete(&format!("/api/v1/me/escalation/{}", id)), cli.raw);
+                }
+            }
+        }
+
+        Commands::Maintenance(cmd) => {
+            require_token(&cli.token);
+            match cmd {
+                MaintenanceCommands::List => {
+                    handle_response_with(client.get("/api/v1/me/maintenance"), cli.raw, format_maintenance_list);
+                }
+                MaintenanceCommands::Add(window) => {
+                    let body = maintenance_window_body(window, &cli.device).unwrap_or_else(|e| {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1);
+                    });
+                    handle_response_with(
+                        client.post("/api/v1/me/maintenance", &body),
+                        cli.raw,
+                        format_maintenance_saved,
+                    );
+                }
+                MaintenanceCommands::Update { id, window } => {
+                    let body = maintenance_window_body(window, &cli.device).unwrap_or_else(|e| {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1);
+                    });
+                    let path = format!("/api/v1/me/maintenance/{}", id);
+                    handle_response_with(client.patch(&path, &body), cli.raw, format_maintenance_saved);
+                }
+                MaintenanceCommands::Remove { id } => {
+                    handle_response(client.delete(&format!("/api/v1/me/maintenance/{}", id)), cli.raw);
                 }
             }
         }
@@ -115,14 +405,31 @@ fn main() {
                     handle_response_with(client.get("/api/v1/me/ntfy"), cli.raw, format_ntfy);
                 }
                 NtfyCommands::Enable | NtfyCommands::Disable => {
-                    let enabled = matches!(cmd, NtfyCommands::Enable);
-                    let body = serde_json::json!({"enabled": enabled});
-                    handle_response_with(client.patch("/api/v1/me/ntfy", &body), cli.raw, |json| {
-                        println!(
-                            "Ntfy notifications {}",
-                            if get_bool(json, "enabled") { "enabled" } else { "disabled" }
-                        );
-                    });
+                    // The ntfy toggle is just the enabled flag of the ntfy channel
+                    let channel_id = match client.get("/api/v1/me/ntfy") {
+                        Ok(json) => json
+                            .get("ntfy")
+                            .and_then(|n| n.get("channel_id"))
+                            .and_then(|id| id.as_str())
+                            .map(str::to_string),
+                        Err(e) => {
+                            eprintln!("Error: {}", e);
+                            std::process::exit(1);
+                        }
+                    };
+                    let Some(channel_id) = channel_id else {
+                        eprintln!("Error: No ntfy channel, add one with `channel add ntfy`");
+                        std::process::exit(1);
+                    };
+                    let body = serde_json::json!({"enabled": matches!(cmd, NtfyCommands::Enable)});
+                    handle_response_with(
+                        client.patch(&format!("/api/v1/me/channels/{}", channel_id), &body),
+                        cli.raw,
+                        |json| {
+                            let enabled = json.get("channel").is_some_and(|c| get_bool(c, "enabled"));
+                            println!("Ntfy notifications {}", if enabled { "enabled" } else { "disabled" });
+                        },
+                    );
                 }
             }
         }
@@ -141,94 +448,257 @@ fn main() {
 
         Commands::Pause => {
             require_token(&cli.token);
-            handle_response_with(client.post_empty("/api/v1/me/pause"), cli.raw, |json| {
-                if let Some(msg) = json.get("message").and_then(|m| m.as_str()) {
-                    println!("{}", msg);
-                } else {
-                    print_json(json);
-                }
-            });
+            handle_response_with(
+                client.post_empty(&with_device("/api/v1/me/pause", &cli.device)),
+                cli.raw,
+                |json| {
+                    if let Some(msg) = json.get("message").and_then(|m| m.as_str()) {
+                        println!("{}", msg);
+                    } else {
+                        print_json(json);
+                    }
+                },
+            );
         }
 
         Commands::Unpause => {
             require_token(&cli.token);
-            handle_response_with(client.post_empty("/api/v1/me/unpause"), cli.raw, |json| {
-                if let Some(msg) = json.get("message").and_then(|m| m.as_str()) {
-                    println!("{}", msg);
-                } else {
-                    print_json(json);
+            handle_response_with(
+                client.post_empty(&with_device("/api/v1/me/unpause", &cli.device)),
+                cli.raw,
+                |json| {
+                    if let Some(msg) = json.get("message").and_then(|m| m.as_str()) {
+                        println!("{}", msg);
+                    } else {
+                        print_json(json);
+                    }
+                },
+            );
+        }
+
+        Commands::Events {
+            since,
+            until,
+            limit,
+            cursor,
+        } => {
+            require_token(&cli.token);
+            let mut query = vec![format!("limit={}", limit)];
+            for (key, value) in [("since", since), ("until", until)] {
+                if let Some(v) = value {
+                    match parse_time_arg(&v) {
+                        Ok(ts) => query.push(format!("{}={}", key, ts)),
+                        Err(e) => {
+                            eprintln!("Error: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
                 }
+            }
+            if let Some(c) = cursor {
+                query.push(format!("cursor={}", c));
+            }
+            if let Some(d) = &cli.device {
+                query.push(format!("device={}", d));
+            }
+            let path = format!("/api/v1/me/events?{}", query.join("&"));
+            handle_response_with(client.get(&path), cli.raw, format_events);
+        }
+
+        Commands::Stats { window } => {
+            require_token(&cli.token);
+            let path = with_device(&format!("/api/v1/me/stats?window={}", window), &cli.device);
+            handle_response_with(client.get(&path), cli.raw, format_stats);
+        }
+
+        Commands::Graph { window, format, output } => {
+            require_token(&cli.token);
+            let format = format
+                .or_else(|| output.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()))
+                .unwrap_or_else(|| "svg".to_string());
+            let path = with_device(&format!("/api/v1/me/graph?window={}&format={}", window, format), &cli.device);
+            let graph = client.get_bytes(&path).unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            });
+            std::fs::write(&output, graph).unwrap_or_else(|e| {
+                eprintln!("Error: Failed to write {}: {}", output, e);
+                std::process::exit(1);
             });
+            println!("Saved the {} graph to {}", window, output);
+        }
+
+        Commands::Schedule => {
+            require_token(&cli.token);
+            handle_response_with(client.get("/api/v1/me/schedule"), cli.raw, format_schedule);
+        }
+
+        Commands::Calendar { token } => {
+            require_token(&cli.token);
+            let token = token.or(cli.token).unwrap_or_default();
+            println!("{}/api/v1/me/outages.ics?token={}", cli.server.trim_end_matches('/'), token);
+        }
+
+        Commands::StatusPage { regenerate } => {
+            require_token(&cli.token);
+            let result = if regenerate {
+                client.post_empty("/api/v1/me/regenerate-token?kind=status_page")
+            } else {
+                client.get("/api/v1/me/status-page")
+            };
+            handle_response_with(result, cli.raw, |json| match json.get("url").and_then(|u| u.as_str()) {
+                Some(url) => println!("{}", url),
+                // The server doesn't know its public address, the CLI's one is the best guess
+                None => println!("{}{}", cli.server.trim_end_matches('/'), get_str(json, "path")),
+            });
+        }
+
+        Commands::Page(cmd) => {
+            require_token(&cli.token);
+            match cmd {
+                PageCommands::List => {
+                    handle_response_with(client.get("/api/v1/me/pages"), cli.raw, |json| {
+                        format_page_list(json, &cli.server)
+                    });
+                }
+                PageCommands::Add(page) => {
+                    handle_response_with(client.post("/api/v1/me/pages", &page_body(page)), cli.raw, |json| {
+                        format_page_saved(json, &cli.server)
+                    });
+                }
+                PageCommands::Update { id, page } => {
+                    let path = format!("/api/v1/me/pages/{}", id);
+                    handle_response_with(client.patch(&path, &page_body(page)), cli.raw, |json| {
+                        format_page_saved(json, &cli.server)
+                    });
+                }
+                PageCommands::Remove { id } => {
+                    handle_response(client.delete(&format!("/api/v1/me/pages/{}", id)), cli.raw);
+                }
+            }
         }
 
         Commands::Settings(cmd) => {
             require_token(&cli.token);
             match cmd {
                 SettingsCommands::Show => {
-                    handle_response_with(client.get("/api/v1/me/settings"), cli.raw, |json| {
+                    handle_response_with(client.get(&settings_path), cli.raw, |json| {
                         println!("Settings");
                         println!("========");
+                        println!("Device:       {}", get_str(json, "device_id"));
                         println!("Up delay:     {}s", get_i64(json, "up_delay"));
-                        let start = json.get("maint_window_start_utc").and_then(|v| v.as_i64());
-                        let end = json.get("maint_window_end_utc").and_then(|v| v.as_i64());
-                        match (start, end) {
-                            (Some(s), Some(e)) => {
-                                println!("Maintenance:  {:02}:{:02}-{:02}:{:02} UTC", s / 60, s % 60, e / 60, e % 60);
-                            }
-                            _ => println!("Maintenance:  [not set]"),
-                        }
+                        println!("Time zone:    {}", get_str(json, "timezone"));
+                        println!("Quiet hours:  {}", format_quiet_hours(json));
+                        println!("Queue:        {}", format_outage_queue(json));
+                        println!("Min outage:   {}s", get_i64(json, "min_outage_secs"));
+                        println!("Flapping:     {}", format_flap_detection(json));
+                        println!("Recovery:     {}", format_recovery(json));
                     });
                 }
                 SettingsCommands::Delay { seconds } => {
                     let body = serde_json::json!({"up_delay": seconds});
-                    handle_response_with(client.patch("/api/v1/me/settings", &body), cli.raw, format_settings_update);
-                }
-                SettingsCommands::Maintenance { start, end } => {
-                    let body = match (start, end) {
-                        (Some(s), Some(e)) => {
-                            let parse_time = |t: &str| -> Result<i16, String> {
-                                let parts: Vec<&str> = t.split(':').collect();
-                                if parts.len() != 2 {
-                                    return Err(format!("Invalid time format '{}', expected HH:MM", t));
-                                }
-                                let h: i16 = parts[0].parse().map_err(|_| format!("Invalid hour in '{}'", t))?;
-                                let m: i16 = parts[1].parse().map_err(|_| format!("Invalid minute in '{}'", t))?;
-                                if !(0..=23).contains(&h) || !(0..=59).contains(&m) {
-                                    return Err(format!("Time '{}' out of range (00:00-23:59)", t));
-                                }
-                                Ok(h * 60 + m)
-                            };
-                            let start_min = match parse_time(&s) {
-                                Ok(v) => v,
-                                Err(e) => {
-                                    eprintln!("Error: {}", e);
-                                    std::process::exit(1);
-                                }
-                            };
-                            let end_min = match parse_time(&e) {
-                                Ok(v) => v,
-                                Err(e) => {
-                                    eprintln!("Error: {}", e);
-                                    std::process::exit(1);
-                                }
-                            };
-                            serde_json::json!({
-                                "maint_window_start_utc": start_min,
-                                "maint_window_end_utc": end_min
-                            })
-                        }
-                        (None, None) => {
-                            serde_json::json!({
-                                "maint_window_start_utc": null,
-                                "maint_window_end_utc": null
-                            })
-                        }
-                        _ => {
-                            eprintln!("Error: Provide both start and end times, or neither to clear");
+                    handle_response_with(client.patch(&settings_path, &body), cli.raw, format_settings_update);
+                }
+                SettingsCommands::QuietHours { start, end, tz } => {
+                    let body = match daily_window_body("quiet_hours", start, end, tz) {
+                        Ok(body) => body,
+                        Err(e) => {
+                            eprintln!("Error: {}", e);
                             std::process::exit(1);
                         }
                     };
-                    handle_response_with(client.patch("/api/v1/me/settings", &body), cli.raw, format_settings_update);
+                    handle_response_with(client.patch(&settings_path, &body), cli.raw, format_settings_update);
+                }
+                SettingsCommands::Timezone { tz } => {
+                    let body = serde_json::json!({"timezone": tz});
+                    handle_response_with(client.patch(&settings_path, &body), cli.raw, format_settings_update);
+                }
+                SettingsCommands::Queue { queue } => {
+                    let body = serde_json::json!({"outage_queue": queue});
+                    handle_response_with(client.patch(&settings_path, &body), cli.raw, format_settings_update);
+                }
+                SettingsCommands::MinOutage { seconds } => {
+                    let body = serde_json::json!({"min_outage_secs": seconds});
+                    handle_response_with(client.patch(&settings_path, &body), cli.raw, format_settings_update);
+                }
+                SettingsCommands::Flapping { changes, window, stable } => {
+                    let mut body = serde_json::json!({"flap_threshold": changes});
+                    if let Some(window) = window {
+                        body["flap_window_minutes"] = window.into();
+                    }
+                    if let Some(stable) = stable {
+                        body["flap_stable_minutes"] = stable.into();
+                    }
+                    handle_response_with(client.patch(&settings_path, &body), cli.raw, format_settings_update);
+                }
+                SettingsCommands::Recovery { heartbeats } => {
+                    let body = serde_json::json!({"recovery_heartbeats": heartbeats});
+                    handle_response_with(client.patch(&settings_path, &body), cli.raw, format_settings_update);
+                }
+                SettingsCommands::Notifications {
+                    event: None,
+                    priority: None,
+                    tags: None,
+                    icon: None,
+                    click: None,
+                    quiet: None,
+                    enable: false,
+                    disable: false,
+                    reset: false,
+                } => {
+                    handle_response_with(client.get(&settings_path), cli.raw, format_notification_settings);
+                }
+                SettingsCommands::Notifications {
+                    event,
+                    priority,
+                    tags,
+                    icon,
+                    click,
+                    quiet,
+                    enable,
+                    disable,
+                    reset,
+                } => {
+                    let Some(event) = event else {
+                        eprintln!("Error: Name the event kind to change: connected, down, up or escalation");
+                        std::process::exit(1);
+                    };
+                    // Empty strings reset a setting to its default
+                    let or_null = |v: String| {
+                        if v.is_empty() {
+                            serde_json::Value::Null
+                        } else {
+                            serde_json::json!(v)
+                        }
+                    };
+                    let mut changes = serde_json::json!({});
+                    if let Some(priority) = priority {
+                        changes["priority"] = serde_json::json!(priority.to_lowercase());
+                    }
+                    if let Some(tags) = tags {
+                        changes["tags"] = if tags.is_empty() {
+                            serde_json::Value::Null
+                        } else {
+                            serde_json::json!(tags.split(',').map(str::trim).collect::<Vec<_>>())
+                        };
+                    }
+                    if let Some(icon) = icon {
+                        changes["icon"] = or_null(icon);
+                    }
+                    if let Some(click) = click {
+                        changes["click"] = or_null(click);
+                    }
+                    if let Some(quiet) = quiet {
+                        changes["quiet"] = or_null(quiet.to_lowercase());
+                    }
+                    if enable || disable {
+                        changes["enabled"] = serde_json::json!(enable);
+                    }
+                    if reset {
+                        changes = serde_json::Value::Null;
+                    }
+                    let body = serde_json::json!({"notifications": {event.to_lowercase(): changes}});
+                    handle_response_with(client.patch(&settings_path, &body), cli.raw, format_notification_settings);
                 }
             }
         }
@@ -261,6 +731,39 @@ fn main() {
                 AdminCommands::DeleteUser { id } => {
                     handle_response(client.delete(&format!("/api/v1/admin/users/{}", id)), cli.raw);
                 }
+                AdminCommands::Outbox { failed } => {
+                    let path = if failed {
+                        "/api/v1/admin/outbox?failed=true"
+                    } else {
+                        "/api/v1/admin/outbox"
+                    };
+                    handle_response_with(client.get(path), cli.raw, format_outbox_list);
+                }
+                AdminCommands::RetryNotification { id } => {
+                    handle_response(client.post_empty(&format!("/api/v1/admin/outbox/{}/retry", id)), cli.raw);
+                }
+                AdminCommands::ImportSchedule { file, format, queue } => {
+                    let Some(format) = format.or_else(|| file.rsplit_once('.').map(|(_, ext)| ext.to_lowercase())) else {
+                        eprintln!("Error: Give the format with --format (json, csv or ics)");
+                        std::process::exit(1);
+                    };
+                    let body = std::fs::read_to_string(&file).unwrap_or_else(|e| {
+                        eprintln!("Error: Failed to read {}: {}", file, e);
+                        std::process::exit(1);
+                    });
+                    let mut path = format!("/api/v1/admin/schedule?format={}", format);
+                    if let Some(queue) = queue {
+                        path.push_str(&format!("&queue={}", queue));
+                    }
+                    handle_response_with(client.post_text(&path, body), cli.raw, |json| {
+                        println!("Imported {} planned outage(s)", get_i64(json, "imported"));
+                        if let Some(queues) = json.get("queues").and_then(|q| q.as_object()) {
+                            for (queue, count) in queues {
+                                println!("  queue {:<8} {}", queue, count);
+                            }
+                        }
+                    });
+                }
             }
         }
     }
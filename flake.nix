@@ -83,6 +83,9 @@
       runAsRoot = ''
         #!${pkgs.runtimeShell}
         cp ${./Rocket.toml} /Rocket.toml
+        # Fonts for the labels of PNG graphs.
+        mkdir -p /usr/share/fonts
+        cp -r ${pkgs.dejavu_fonts}/share/fonts/truetype /usr/share/fonts/dejavu
       '';
       config = {
         Cmd = ["${oubot}/bin/oubot"];
@@ -242,7 +245,7 @@
           for file in $(find $src/src -name '*.rs'); do
             while IFS= read -r line_num; do
               sig=$(sed -n "$line_num,$((line_num+3))p" "$file")
-              if ! echo "$sig" | grep -qE '(BAuth|AdminAuth|RateLimitGuard)'; then
+              if ! echo "$sig" | grep -qE '(BAuth|FeedAuth|AdminAuth|DeviceAuth|RateLimitGuard)'; then
                 echo "FAIL: $(basename $file):$line_num - route handler missing rate-limit guard"
                 echo "  $sig"
                 FAIL=1
@@ -250,7 +253,7 @@
             done < <(grep -nE '#\[(get|post|put|patch|delete)\(' "$file" | cut -d: -f1)
           done
           if [ "$FAIL" = "1" ]; then
-            echo "Every route handler must include BAuth, AdminAuth, or RateLimitGuard."
+            echo "Every route handler must include BAuth, FeedAuth, AdminAuth, DeviceAuth, or RateLimitGuard."
             exit 1
           fi
 
@@ -295,9 +298,30 @@
           });
       api-v1-up-test-success = import ./tests/api-v1-up-test-success.nix (checkArgs ./tests/api-v1-up-test-success.py);
       api-v1-up-duration-message = import ./tests/api-v1-up-duration-message.nix (checkArgs ./tests/api-v1-up-duration-message.py);
+      api-v1-events = import ./tests/api-v1-events.nix (checkArgs ./tests/api-v1-events.py);
+      api-v1-webhook = import ./tests/api-v1-webhook.nix (checkArgs ./tests/api-v1-webhook.py);
+      api-v1-telegram = import ./tests/api-v1-telegram.nix (checkArgs ./tests/api-v1-telegram.py);
+      api-v1-email = import ./tests/api-v1-email.nix (checkArgs ./tests/api-v1-email.py);
+      api-v1-chat = import ./tests/api-v1-chat.nix (checkArgs ./tests/api-v1-chat.py);
+      api-v1-gotify = import ./tests/api-v1-gotify.nix (checkArgs ./tests/api-v1-gotify.py);
+      api-v1-outbox = import ./tests/api-v1-outbox.nix (checkArgs ./tests/api-v1-outbox.py);
+      api-v1-flapping = import ./tests/api-v1-flapping.nix (checkArgs ./tests/api-v1-flapping.py);
+      api-v1-recovery = import ./tests/api-v1-recovery.nix (checkArgs ./tests/api-v1-recovery.py);
+      api-v1-escalation = import ./tests/api-v1-escalation.nix (checkArgs ./tests/api-v1-escalation.py);
+      api-v1-notification-settings = import ./tests/api-v1-notification-settings.nix (checkArgs ./tests/api-v1-notification-settings.py);
+      api-v1-quiet-hours = import ./tests/api-v1-quiet-hours.nix (checkArgs ./tests/api-v1-quiet-hours.py);
+      api-v1-maintenance = import ./tests/api-v1-maintenance.nix (checkArgs ./tests/api-v1-maintenance.py);
+      api-v1-schedule = import ./tests/api-v1-schedule.nix (checkArgs ./tests/api-v1-schedule.py);
+      api-v1-calendar = import ./tests/api-v1-calendar.nix (checkArgs ./tests/api-v1-calendar.py);
+      api-v1-graph = import ./tests/api-v1-graph.nix (checkArgs ./tests/api-v1-graph.py);
+      api-v1-status-page = import ./tests/api-v1-status-page.nix (checkArgs ./tests/api-v1-status-page.py);
+      api-v1-pages = import ./tests/api-v1-pages.nix (checkArgs ./tests/api-v1-pages.py);
       cli-lifecycle = import ./tests/cli-lifecycle.nix (checkArgsWithCliBash ./tests/cli-lifecycle.sh);
       cli-settings = import ./tests/cli-settings.nix (checkArgsWithCliBash ./tests/cli-settings.sh);
       cli-admin = import ./tests/cli-admin.nix (checkArgsWithCliBash ./tests/cli-admin.sh);
+      cli-devices = import ./tests/cli-devices.nix (checkArgsWithCliBash ./tests/cli-devices.sh);
+      cli-tokens = import ./tests/cli-tokens.nix (checkArgsWithCliBash ./tests/cli-tokens.sh);
+      cli-channels = import ./tests/cli-channels.nix (checkArgsWithCliBash ./tests/cli-channels.sh);
       security-auth = import ./tests/security-auth.nix (checkArgs noopScript);
       docker-e2e = import ./tests/docker-e2e.nix (checkArgsWithDocker noopScript);
     };
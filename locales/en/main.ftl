@@ -4,6 +4,49 @@ notification-power-on = Power is back!
 notification-power-off = Power outage!
 notification-maintenance = Under maintenance!
 
+# Prefixes the title with the device name for accounts with several devices
+notification-title-with-device = { $device }: { $title }
+
+# Flap detection: one notice instead of a storm of Up/Down notifications
+notification-flapping = Unstable connection!
+notification-flapping-message = { $count } ups and downs within { $window }. Alerts are paused until the device stays stable for { $stable }.
+notification-stable = Connection is stable again
+notification-stable-up = Power is on.
+notification-stable-down = Power is off.
+
+# Escalation: reminders while an outage goes on
+notification-still-down = Still no power
+notification-still-down-message = Power has been off for { $duration }
+
+# Planned outage schedules: whether an outage was announced for the account's queue
+notification-planned-outage = Planned outage (queue { $queue }, until { $until })
+notification-unplanned-outage = Unplanned outage
+
+# Calendar feed (outages.ics) of recorded outages and maintenance windows
+calendar-name = Power outages
+calendar-outage = No power
+calendar-maintenance = Maintenance
+
+# Status page (the ntfy click target)
+status-page-title = Power status
+status-up = Power is on for { $duration }
+status-down = No power for { $duration }
+status-paused = Monitoring is paused
+status-unknown = Waiting for the first heartbeat
+status-last-heartbeat = Last heartbeat { $duration } ago
+status-outages = Outages
+status-no-outages = No outages in this period
+status-outage = { $start } – { $end }, { $duration }
+status-outage-ongoing = since { $start }, { $duration } so far
+status-updated = Updated { $time }
+status-page-not-found = Unknown status page. The link may have been replaced by a new one.
+
+# Quiet hours: notifications held back until they end, sent as one
+notification-digest = { $count ->
+    [one] {$count} notification during quiet hours
+   *[other] {$count} notifications during quiet hours
+}
+
 # Duration parts (used to assemble duration strings)
 duration-days = { $count ->
     [one] {$count} day
@@ -15,3 +58,18 @@ duration-minutes = {$count} min
 # Duration messages
 duration-power-was-off = Power was off for { $duration }
 duration-power-was-on = Power was on for { $duration }
+
+# Telegram bot replies
+telegram-linked = Done! Power notifications will be sent to this chat.
+telegram-link-invalid = Unknown or expired code. Add a Telegram channel with `oubot-cli channel add telegram` and send the code it prints.
+
+# Email recipient verification
+email-verify-subject = Confirm power outage notifications
+email-verify-body = { $address } was added to power outage notifications.
+
+    Open this link to start receiving them:
+    { $link }
+
+    If you didn't expect this email, ignore it and nothing will be sent.
+email-verified = { $address } is confirmed, power notifications will be sent to it.
+email-verify-invalid = Unknown or expired link. Ask for a new one by adding the address to the email channel again.
@@ -4,6 +4,54 @@ notification-power-on = Світло з'явилося!
 notification-power-off = Відключення світла!
 notification-maintenance = На обслуговуванні!
 
+# Prefixes the title with the device name for accounts with several devices
+notification-title-with-device = { $device }: { $title }
+
+# Flap detection: one notice instead of a storm of Up/Down notifications
+notification-flapping = Нестабільне з'єднання!
+notification-flapping-message = { $count ->
+    [one] {$count} зміна стану
+    [few] {$count} зміни стану
+   *[other] {$count} змін стану
+} за { $window }. Сповіщення призупинено, доки девайс не буде стабільним { $stable }.
+notification-stable = З'єднання знову стабільне
+notification-stable-up = Світло є.
+notification-stable-down = Світла немає.
+
+# Escalation: reminders while an outage goes on
+notification-still-down = Світла досі немає
+notification-still-down-message = Світла немає вже { $duration }
+
+# Planned outage schedules: whether an outage was announced for the account's queue
+notification-planned-outage = Планове відключення (черга { $queue }, до { $until })
+notification-unplanned-outage = Позапланове відключення
+
+# Calendar feed (outages.ics) of recorded outages and maintenance windows
+calendar-name = Відключення світла
+calendar-outage = Немає світла
+calendar-maintenance = Технічні роботи
+
+# Status page (the ntfy click target)
+status-page-title = Стан світла
+status-up = Світло є вже { $duration }
+status-down = Світла немає вже { $duration }
+status-paused = Моніторинг призупинено
+status-unknown = Очікуємо перший сигнал від пристрою
+status-last-heartbeat = Останній сигнал { $duration } тому
+status-outages = Відключення
+status-no-outages = За цей період відключень не було
+status-outage = { $start } – { $end }, { $duration }
+status-outage-ongoing = з { $start }, триває { $duration }
+status-updated = Оновлено { $time }
+status-page-not-found = Невідома сторінка стану. Можливо, посилання замінили на нове.
+
+# Quiet hours: notifications held back until they end, sent as one
+notification-digest = { $count ->
+    [one] {$count} сповіщення за тихі години
+    [few] {$count} сповіщення за тихі години
+   *[other] {$count} сповіщень за тихі години
+}
+
 # Duration parts (used to assemble duration strings)
 duration-days = { $count ->
     [one] {$count} день
@@ -16,3 +64,18 @@ duration-minutes = {$count} хв
 # Duration messages
 duration-power-was-off = Світла не було { $duration }
 duration-power-was-on = Світло було { $duration }
+
+# Telegram bot replies
+telegram-linked = Готово! Сповіщення про світло надходитимуть у цей чат.
+telegram-link-invalid = Невідомий або прострочений код. Додайте канал Telegram командою `oubot-cli channel add telegram` і надішліть код, який вона покаже.
+
+# Email recipient verification
+email-verify-subject = Підтвердіть сповіщення про світло
+email-verify-body = Адресу { $address } додано до сповіщень про відключення світла.
+
+    Відкрийте посилання, щоб почати їх отримувати:
+    { $link }
+
+    Якщо ви не очікували цього листа, просто проігноруйте його, і нічого надсилатися не буде.
+email-verified = Адресу { $address } підтверджено, сповіщення про світло надходитимуть на неї.
+email-verify-invalid = Невідоме або прострочене посилання. Щоб отримати нове, додайте адресу до каналу email ще раз.
@@ -0,0 +1,2 @@
+DROP TABLE uptime_events;
+DROP TYPE event_source_enum;
@@ -0,0 +1,17 @@
+-- What caused a recorded state transition
+CREATE TYPE event_source_enum AS ENUM ('heartbeat', 'timeout', 'pause', 'unpause', 'maintenance');
+
+-- Append-only history of uptime state transitions (uptime_states only keeps the latest one)
+CREATE TABLE uptime_events (
+  id uuid PRIMARY KEY,
+  user_id uuid REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  from_status status_enum NOT NULL,
+  to_status status_enum NOT NULL,
+  at TIMESTAMP NOT NULL,
+  -- Seconds spent in from_status before this transition
+  duration_in_previous_state BIGINT NOT NULL,
+  source event_source_enum NOT NULL
+);
+
+-- Newest-first keyset pagination for /api/v1/me/events
+CREATE INDEX uptime_events_user_at_idx ON uptime_events (user_id, at DESC, id DESC);
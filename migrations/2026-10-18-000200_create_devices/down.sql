@@ -0,0 +1,36 @@
+-- Restore per-user settings from each user's oldest device
+ALTER TABLE users ADD COLUMN up_delay SMALLINT NOT NULL DEFAULT 60;
+ALTER TABLE users ADD COLUMN maint_window_start_utc SMALLINT DEFAULT NULL;
+ALTER TABLE users ADD COLUMN maint_window_end_utc SMALLINT DEFAULT NULL;
+UPDATE users u
+SET up_delay = d.up_delay,
+    maint_window_start_utc = d.maint_window_start_utc,
+    maint_window_end_utc = d.maint_window_end_utc
+FROM (SELECT DISTINCT ON (user_id) * FROM devices ORDER BY user_id, created_at) d
+WHERE d.user_id = u.id;
+ALTER TABLE users ALTER COLUMN up_delay DROP DEFAULT;
+ALTER TABLE users ADD CONSTRAINT maint_window_both_or_neither
+    CHECK ((maint_window_start_utc IS NULL) = (maint_window_end_utc IS NULL));
+ALTER TABLE users ADD CONSTRAINT maint_window_valid_range
+    CHECK (
+        maint_window_start_utc IS NULL
+        OR (maint_window_start_utc >= 0 AND maint_window_start_utc < 1440
+            AND maint_window_end_utc >= 0 AND maint_window_end_utc < 1440
+            AND maint_window_start_utc != maint_window_end_utc)
+    );
+
+-- History and state of additional devices can't be represented per-user, so it is dropped
+DELETE FROM uptime_events e USING devices d
+WHERE d.id = e.device_id
+  AND d.created_at > (SELECT min(created_at) FROM devices o WHERE o.user_id = d.user_id);
+DROP INDEX uptime_events_device_at_idx;
+ALTER TABLE uptime_events DROP COLUMN device_id;
+
+ALTER TABLE uptime_states ADD COLUMN user_id uuid REFERENCES users (id) ON DELETE CASCADE;
+UPDATE uptime_states s SET user_id = d.user_id FROM devices d WHERE d.id = s.device_id;
+DELETE FROM uptime_states s USING devices d
+WHERE d.id = s.device_id
+  AND d.created_at > (SELECT min(created_at) FROM devices o WHERE o.user_id = d.user_id);
+ALTER TABLE uptime_states DROP COLUMN device_id;
+
+DROP TABLE devices;
@@ -0,0 +1,47 @@
+-- Devices: a user can monitor several boards, each with its own token, timeout and uptime state
+CREATE TABLE devices (
+  id uuid PRIMARY KEY,
+  user_id uuid REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  created_at TIMESTAMP DEFAULT now() NOT NULL,
+  name TEXT NOT NULL,
+  access_token TEXT NOT NULL UNIQUE,
+  up_delay SMALLINT NOT NULL,
+  maint_window_start_utc SMALLINT DEFAULT NULL,
+  maint_window_end_utc SMALLINT DEFAULT NULL,
+  CONSTRAINT devices_name_per_user UNIQUE (user_id, name),
+  CONSTRAINT maint_window_both_or_neither
+    CHECK ((maint_window_start_utc IS NULL) = (maint_window_end_utc IS NULL)),
+  CONSTRAINT maint_window_valid_range
+    CHECK (
+        maint_window_start_utc IS NULL
+        OR (maint_window_start_utc >= 0 AND maint_window_start_utc < 1440
+            AND maint_window_end_utc >= 0 AND maint_window_end_utc < 1440
+            AND maint_window_start_utc != maint_window_end_utc)
+    )
+);
+
+-- Every existing user gets a 'default' device that takes over their settings. It keeps the
+-- account token for now, the server replaces it with a new heartbeat token on startup
+-- (see db::split_shared_device_tokens).
+INSERT INTO devices (id, user_id, created_at, name, access_token, up_delay, maint_window_start_utc, maint_window_end_utc)
+SELECT gen_random_uuid(), id, created_at, 'default', access_token, up_delay, maint_window_start_utc, maint_window_end_utc
+FROM users;
+
+-- Uptime state and history now belong to a device
+ALTER TABLE uptime_states ADD COLUMN device_id uuid REFERENCES devices (id) ON DELETE CASCADE;
+UPDATE uptime_states s SET device_id = d.id FROM devices d WHERE d.user_id = s.user_id;
+DELETE FROM uptime_states WHERE device_id IS NULL;
+ALTER TABLE uptime_states ALTER COLUMN device_id SET NOT NULL;
+ALTER TABLE uptime_states DROP COLUMN user_id;
+
+ALTER TABLE uptime_events ADD COLUMN device_id uuid REFERENCES devices (id) ON DELETE CASCADE;
+UPDATE uptime_events e SET device_id = d.id FROM devices d WHERE d.user_id = e.user_id;
+ALTER TABLE uptime_events ALTER COLUMN device_id SET NOT NULL;
+CREATE INDEX uptime_events_device_at_idx ON uptime_events (device_id, at DESC, id DESC);
+
+-- Per-device settings moved to devices
+ALTER TABLE users DROP CONSTRAINT maint_window_valid_range;
+ALTER TABLE users DROP CONSTRAINT maint_window_both_or_neither;
+ALTER TABLE users DROP COLUMN maint_window_end_utc;
+ALTER TABLE users DROP COLUMN maint_window_start_utc;
+ALTER TABLE users DROP COLUMN up_delay;
@@ -0,0 +1,5 @@
+-- @WARNING: Hashed tokens can't be turned back into plaintext; affected users and
+--  devices need their tokens regenerated after rolling back.
+ALTER TABLE devices RENAME CONSTRAINT devices_token_hash_key TO devices_access_token_key;
+ALTER TABLE devices RENAME COLUMN token_hash TO access_token;
+ALTER TABLE users RENAME COLUMN token_hash TO access_token;
@@ -0,0 +1,6 @@
+-- Tokens are stored as HMAC-SHA256(OUBOT_TOKEN_SECRET, token), hex encoded.
+-- The secret is not available here, so existing plaintext values are left in place and
+-- rehashed by the server on its next startup.
+ALTER TABLE users RENAME COLUMN access_token TO token_hash;
+ALTER TABLE devices RENAME COLUMN access_token TO token_hash;
+ALTER TABLE devices RENAME CONSTRAINT devices_access_token_key TO devices_token_hash_key;
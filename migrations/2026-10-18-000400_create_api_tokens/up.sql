@@ -0,0 +1,20 @@
+-- What a token is allowed to do (see bauth::resolve_token)
+CREATE TYPE token_scope_enum AS ENUM ('account', 'heartbeat');
+
+-- Additional named tokens on top of the account token and per-device heartbeat tokens
+CREATE TABLE api_tokens (
+  id uuid PRIMARY KEY,
+  user_id uuid REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  -- Device a heartbeat token pings for, NULL for account tokens
+  device_id uuid REFERENCES devices (id) ON DELETE CASCADE,
+  name TEXT NOT NULL,
+  scope token_scope_enum NOT NULL,
+  token_hash TEXT NOT NULL UNIQUE,
+  created_at TIMESTAMP DEFAULT now() NOT NULL,
+  expires_at TIMESTAMP DEFAULT NULL,
+  -- Updated in batches, so it can lag behind by up to a flush interval
+  last_used_at TIMESTAMP DEFAULT NULL,
+  last_used_ip TEXT DEFAULT NULL,
+  CONSTRAINT api_tokens_name_per_user UNIQUE (user_id, name),
+  CONSTRAINT api_tokens_device_iff_heartbeat CHECK ((scope = 'heartbeat') = (device_id IS NOT NULL))
+);
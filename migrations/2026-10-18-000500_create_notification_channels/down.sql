@@ -0,0 +1,8 @@
+ALTER TABLE ntfy_users ADD COLUMN enabled BOOLEAN DEFAULT TRUE NOT NULL;
+UPDATE ntfy_users SET enabled = EXISTS (
+  SELECT 1 FROM notification_channels c JOIN users u ON u.id = c.user_id
+  WHERE u.ntfy_id = ntfy_users.id AND c.kind = 'ntfy' AND c.enabled
+);
+
+DROP TABLE notification_channels;
+DROP TYPE channel_kind_enum;
@@ -0,0 +1,20 @@
+-- Every way a user is notified (the managed ntfy topic, webhooks, ...) is a channel
+CREATE TYPE channel_kind_enum AS ENUM ('ntfy', 'webhook');
+
+CREATE TABLE notification_channels (
+  id uuid PRIMARY KEY,
+  user_id uuid REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  kind channel_kind_enum NOT NULL,
+  enabled BOOLEAN DEFAULT TRUE NOT NULL,
+  -- Kind specific settings, validated by the server (see src/channels)
+  config JSONB DEFAULT '{}' NOT NULL,
+  created_at TIMESTAMP DEFAULT now() NOT NULL
+);
+CREATE INDEX notification_channels_user_id ON notification_channels (user_id);
+
+-- The managed ntfy topic becomes the first channel of every account
+INSERT INTO notification_channels (id, user_id, kind, enabled, config, created_at)
+SELECT gen_random_uuid(), users.id, 'ntfy', ntfy_users.enabled, jsonb_build_object('topic', ntfy_users.topic), users.created_at
+FROM users JOIN ntfy_users ON ntfy_users.id = users.ntfy_id;
+
+ALTER TABLE ntfy_users DROP COLUMN enabled;
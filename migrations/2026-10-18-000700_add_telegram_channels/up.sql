@@ -0,0 +1,2 @@
+-- Telegram chats linked through the bot (see src/channels/telegram.rs)
+ALTER TYPE channel_kind_enum ADD VALUE 'telegram';
@@ -0,0 +1,5 @@
+DELETE FROM notification_channels WHERE kind = 'email';
+ALTER TYPE channel_kind_enum RENAME TO channel_kind_enum_old;
+CREATE TYPE channel_kind_enum AS ENUM ('ntfy', 'webhook', 'telegram');
+ALTER TABLE notification_channels ALTER COLUMN kind TYPE channel_kind_enum USING kind::text::channel_kind_enum;
+DROP TYPE channel_kind_enum_old;
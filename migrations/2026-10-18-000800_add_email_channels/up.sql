@@ -0,0 +1,2 @@
+-- Email recipients verified through a mailed link (see src/channels/email.rs)
+ALTER TYPE channel_kind_enum ADD VALUE 'email';
@@ -0,0 +1,4 @@
+-- Matrix rooms and Discord/Slack incoming webhooks (see src/channels)
+ALTER TYPE channel_kind_enum ADD VALUE 'matrix';
+ALTER TYPE channel_kind_enum ADD VALUE 'discord';
+ALTER TYPE channel_kind_enum ADD VALUE 'slack';
@@ -0,0 +1,7 @@
+DELETE FROM notification_channels WHERE kind = 'gotify';
+-- ntfy channels on the users' own servers came with gotify, keep only the managed one
+DELETE FROM notification_channels WHERE kind = 'ntfy' AND config ? 'server';
+ALTER TYPE channel_kind_enum RENAME TO channel_kind_enum_old;
+CREATE TYPE channel_kind_enum AS ENUM ('ntfy', 'webhook', 'telegram', 'email', 'matrix', 'discord', 'slack');
+ALTER TABLE notification_channels ALTER COLUMN kind TYPE channel_kind_enum USING kind::text::channel_kind_enum;
+DROP TYPE channel_kind_enum_old;
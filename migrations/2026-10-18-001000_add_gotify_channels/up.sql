@@ -0,0 +1,2 @@
+-- Gotify applications (see src/channels/gotify.rs)
+ALTER TYPE channel_kind_enum ADD VALUE 'gotify';
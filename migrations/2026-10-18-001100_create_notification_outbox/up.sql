@@ -0,0 +1,17 @@
+-- Notifications waiting for delivery, one row per channel. Rows are inserted in the same
+-- transaction as the state change and deleted once delivered (see src/background.rs).
+CREATE TABLE notification_outbox (
+  id uuid PRIMARY KEY,
+  user_id uuid REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  channel_id uuid REFERENCES notification_channels (id) ON DELETE CASCADE NOT NULL,
+  event TEXT NOT NULL,
+  -- channels::Notification as JSON, plus its priority
+  payload JSONB NOT NULL,
+  attempts INTEGER DEFAULT 0 NOT NULL,
+  next_attempt_at TIMESTAMP NOT NULL,
+  last_error TEXT,
+  created_at TIMESTAMP DEFAULT now() NOT NULL,
+  -- Set once all attempts failed, the row is kept for inspection by admins
+  failed_at TIMESTAMP
+);
+CREATE INDEX notification_outbox_due_idx ON notification_outbox (next_attempt_at) WHERE failed_at IS NULL;
@@ -0,0 +1,6 @@
+ALTER TABLE uptime_states DROP COLUMN held_outage_secs;
+ALTER TABLE uptime_states DROP COLUMN flapping_since;
+ALTER TABLE devices DROP COLUMN flap_stable_minutes;
+ALTER TABLE devices DROP COLUMN flap_window_minutes;
+ALTER TABLE devices DROP COLUMN flap_threshold;
+ALTER TABLE devices DROP COLUMN min_outage_secs;
@@ -0,0 +1,13 @@
+-- Debouncing of notifications (see DeviceState::record_flip in src/db/models.rs)
+-- Seconds a device must stay down before "Power outage!" is sent, 0 = right away
+ALTER TABLE devices ADD COLUMN min_outage_secs INTEGER DEFAULT 0 NOT NULL;
+-- Up/Down flips within flap_window_minutes that mark a device as flapping, NULL = disabled
+ALTER TABLE devices ADD COLUMN flap_threshold SMALLINT DEFAULT NULL;
+ALTER TABLE devices ADD COLUMN flap_window_minutes SMALLINT DEFAULT 10 NOT NULL;
+-- Minutes without a flip before a flapping device counts as stable again
+ALTER TABLE devices ADD COLUMN flap_stable_minutes SMALLINT DEFAULT 15 NOT NULL;
+
+-- Set while a device is flapping, its individual Up/Down notifications are suppressed
+ALTER TABLE uptime_states ADD COLUMN flapping_since TIMESTAMP DEFAULT NULL;
+-- Set while the "Power outage!" notification waits for min_outage_secs, to how long power was on
+ALTER TABLE uptime_states ADD COLUMN held_outage_secs BIGINT DEFAULT NULL;
@@ -0,0 +1,3 @@
+ALTER TABLE uptime_states DROP COLUMN recovery_count;
+ALTER TABLE uptime_states DROP COLUMN recovery_started_at;
+ALTER TABLE devices DROP COLUMN recovery_heartbeats;
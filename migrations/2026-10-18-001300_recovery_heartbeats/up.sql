@@ -0,0 +1,7 @@
+-- Heartbeats in a row (each within up_delay of the previous one) before a Down device counts as
+-- Up again, so the grid flickering back for a few seconds is not reported as "Power is back!"
+ALTER TABLE devices ADD COLUMN recovery_heartbeats SMALLINT DEFAULT 1 NOT NULL;
+
+-- Progress of a recovery that is not confirmed yet, see UptimeState::touch in src/db/models.rs
+ALTER TABLE uptime_states ADD COLUMN recovery_started_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE uptime_states ADD COLUMN recovery_count SMALLINT DEFAULT 0 NOT NULL;
@@ -0,0 +1,3 @@
+ALTER TABLE uptime_states DROP COLUMN escalated_minutes;
+DROP TABLE escalation_steps;
+DROP TYPE priority_enum;
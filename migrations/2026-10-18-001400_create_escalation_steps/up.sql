@@ -0,0 +1,18 @@
+-- Reminders while a device stays down: "still down after 1h", "after 4h", ... (see
+-- background_handle_down in src/background.rs)
+CREATE TYPE priority_enum AS ENUM ('min', 'low', 'default', 'high', 'max');
+
+CREATE TABLE escalation_steps (
+  id uuid PRIMARY KEY,
+  user_id uuid REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  -- Minutes since the outage started
+  delay_minutes INTEGER NOT NULL,
+  priority priority_enum NOT NULL,
+  -- Also notified by this step, even while the channel is disabled
+  channel_id uuid REFERENCES notification_channels (id) ON DELETE SET NULL,
+  created_at TIMESTAMP DEFAULT now() NOT NULL,
+  UNIQUE (user_id, delay_minutes)
+);
+
+-- delay_minutes of the last step sent for the current outage, 0 = none yet
+ALTER TABLE uptime_states ADD COLUMN escalated_minutes INTEGER DEFAULT 0 NOT NULL;
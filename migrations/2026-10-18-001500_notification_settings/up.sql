@@ -0,0 +1,4 @@
+-- Per event kind (connected, down, up, escalation) overrides of how notifications are sent:
+-- {"down": {"priority": "max", "tags": ["rotating_light"], "click": "https://...", "enabled": true}}
+-- Missing keys use the defaults, see EventSettings in src/notifications.rs
+ALTER TABLE users ADD COLUMN notification_settings JSONB DEFAULT '{}' NOT NULL;
@@ -0,0 +1,4 @@
+ALTER TABLE users DROP CONSTRAINT quiet_hours_valid_range;
+ALTER TABLE users DROP CONSTRAINT quiet_hours_both_or_neither;
+ALTER TABLE users DROP COLUMN quiet_hours_end;
+ALTER TABLE users DROP COLUMN quiet_hours_start;
@@ -0,0 +1,14 @@
+-- Daily quiet hours of the account in minutes from local midnight (see users.timezone). Status
+-- changes are still tracked and recorded, notifications sent meanwhile follow the "quiet" setting
+-- of their event kind (send, silent, defer or drop, see EventSettings in src/notifications.rs)
+ALTER TABLE users ADD COLUMN quiet_hours_start SMALLINT DEFAULT NULL;
+ALTER TABLE users ADD COLUMN quiet_hours_end SMALLINT DEFAULT NULL;
+ALTER TABLE users ADD CONSTRAINT quiet_hours_both_or_neither
+    CHECK ((quiet_hours_start IS NULL) = (quiet_hours_end IS NULL));
+ALTER TABLE users ADD CONSTRAINT quiet_hours_valid_range
+    CHECK (
+        quiet_hours_start IS NULL
+        OR (quiet_hours_start >= 0 AND quiet_hours_start < 1440
+            AND quiet_hours_end >= 0 AND quiet_hours_end < 1440
+            AND quiet_hours_start != quiet_hours_end)
+    );
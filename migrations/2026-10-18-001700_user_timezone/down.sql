@@ -0,0 +1,4 @@
+-- Local times are kept as they are, they are only right for accounts in UTC
+ALTER TABLE devices RENAME COLUMN maint_window_end TO maint_window_end_utc;
+ALTER TABLE devices RENAME COLUMN maint_window_start TO maint_window_start_utc;
+ALTER TABLE users DROP COLUMN timezone;
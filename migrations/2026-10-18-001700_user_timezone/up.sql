@@ -0,0 +1,5 @@
+-- IANA time zone of the account. Maintenance windows become minutes from local midnight in
+-- that zone like quiet hours, existing ones keep their meaning with the UTC default.
+ALTER TABLE users ADD COLUMN timezone TEXT DEFAULT 'UTC' NOT NULL;
+ALTER TABLE devices RENAME COLUMN maint_window_start_utc TO maint_window_start;
+ALTER TABLE devices RENAME COLUMN maint_window_end_utc TO maint_window_end;
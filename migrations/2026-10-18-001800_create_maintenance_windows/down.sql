@@ -0,0 +1,26 @@
+ALTER TABLE devices ADD COLUMN maint_window_start SMALLINT DEFAULT NULL;
+ALTER TABLE devices ADD COLUMN maint_window_end SMALLINT DEFAULT NULL;
+
+-- Only daily windows of a single device can be represented, the first one of each device is kept
+UPDATE devices d SET
+    maint_window_start = w.start_minute,
+    maint_window_end = w.end_minute
+FROM (
+    SELECT DISTINCT ON (device_id) device_id, start_minute, end_minute
+    FROM maintenance_windows
+    WHERE device_id IS NOT NULL AND weekdays = 127
+    ORDER BY device_id, created_at
+) w
+WHERE d.id = w.device_id;
+
+ALTER TABLE devices ADD CONSTRAINT maint_window_both_or_neither
+    CHECK ((maint_window_start IS NULL) = (maint_window_end IS NULL));
+ALTER TABLE devices ADD CONSTRAINT maint_window_valid_range
+    CHECK (
+        maint_window_start IS NULL
+        OR (maint_window_start >= 0 AND maint_window_start < 1440
+            AND maint_window_end >= 0 AND maint_window_end < 1440
+            AND maint_window_start != maint_window_end)
+    );
+
+DROP TABLE maintenance_windows;
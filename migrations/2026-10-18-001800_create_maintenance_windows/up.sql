@@ -0,0 +1,41 @@
+-- Maintenance windows of an account, replacing the single daily window per device: weekly rules
+-- ("every Tuesday 10:00-14:00") or one-off ranges, for one device or all of them. Weekly times are
+-- minutes from local midnight in the account's time zone, a window ending before it starts runs
+-- past midnight into the next day.
+CREATE TABLE maintenance_windows (
+  id uuid PRIMARY KEY,
+  user_id uuid REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  -- NULL: all devices of the account
+  device_id uuid REFERENCES devices (id) ON DELETE CASCADE,
+  label TEXT,
+  -- Weekly rule: days of the week as a bitmask, 1 = Monday ... 64 = Sunday
+  weekdays SMALLINT,
+  start_minute SMALLINT,
+  end_minute SMALLINT,
+  -- One-off range
+  starts_at TIMESTAMP,
+  ends_at TIMESTAMP,
+  created_at TIMESTAMP DEFAULT now() NOT NULL,
+  CONSTRAINT maintenance_window_weekly_or_one_off CHECK (
+    (weekdays IS NOT NULL AND start_minute IS NOT NULL AND end_minute IS NOT NULL
+      AND starts_at IS NULL AND ends_at IS NULL
+      AND weekdays BETWEEN 1 AND 127
+      AND start_minute BETWEEN 0 AND 1439 AND end_minute BETWEEN 0 AND 1439
+      AND start_minute != end_minute)
+    OR (weekdays IS NULL AND start_minute IS NULL AND end_minute IS NULL
+      AND starts_at IS NOT NULL AND ends_at IS NOT NULL AND starts_at < ends_at)
+  )
+);
+
+CREATE INDEX maintenance_windows_user_id ON maintenance_windows (user_id);
+
+-- Daily windows become weekly rules for every day of the week
+INSERT INTO maintenance_windows (id, user_id, device_id, weekdays, start_minute, end_minute)
+SELECT gen_random_uuid(), user_id, id, 127, maint_window_start, maint_window_end
+FROM devices
+WHERE maint_window_start IS NOT NULL;
+
+ALTER TABLE devices DROP CONSTRAINT maint_window_valid_range;
+ALTER TABLE devices DROP CONSTRAINT maint_window_both_or_neither;
+ALTER TABLE devices DROP COLUMN maint_window_end;
+ALTER TABLE devices DROP COLUMN maint_window_start;
@@ -0,0 +1,3 @@
+ALTER TABLE users DROP COLUMN outage_queue;
+
+DROP TABLE planned_outages;
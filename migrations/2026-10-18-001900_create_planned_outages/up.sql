@@ -0,0 +1,15 @@
+-- Planned outages (rolling blackout schedules) per queue, as published by the grid operator.
+-- Imports replace the slots of a queue over the time span they cover.
+CREATE TABLE planned_outages (
+  id uuid PRIMARY KEY,
+  queue TEXT NOT NULL,
+  starts_at TIMESTAMP NOT NULL,
+  ends_at TIMESTAMP NOT NULL,
+  created_at TIMESTAMP DEFAULT now() NOT NULL,
+  CONSTRAINT planned_outage_valid_range CHECK (starts_at < ends_at)
+);
+
+CREATE INDEX planned_outages_queue_starts_at ON planned_outages (queue, starts_at);
+
+-- Queue (group) of the account's address in the schedules, e.g. "3.1"
+ALTER TABLE users ADD COLUMN outage_queue TEXT DEFAULT NULL;
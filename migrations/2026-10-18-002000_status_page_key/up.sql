@@ -0,0 +1,6 @@
+-- Secret part of the URL of the account's status page (the ntfy click target). Only lets the page be
+-- viewed, so it is stored as is to build links with.
+ALTER TABLE users ADD COLUMN status_page_key TEXT;
+UPDATE users SET status_page_key = 'sp_' || replace(gen_random_uuid()::text, '-', '');
+ALTER TABLE users ALTER COLUMN status_page_key SET NOT NULL;
+ALTER TABLE users ADD CONSTRAINT users_status_page_key_unique UNIQUE (status_page_key);
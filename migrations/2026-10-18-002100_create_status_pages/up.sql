@@ -0,0 +1,27 @@
+-- Who can see a status page: nobody but its owner through the API (private), anyone with the link
+-- (unlisted), or also everyone browsing the directory of pages (public)
+CREATE TYPE page_visibility_enum AS ENUM ('private', 'unlisted', 'public');
+
+-- Shareable status pages grouping several devices, e.g. all apartments of a building
+CREATE TABLE status_pages (
+  id uuid PRIMARY KEY,
+  user_id uuid REFERENCES users (id) ON DELETE CASCADE NOT NULL,
+  -- Part of the page's URL, lowercase letters, digits and dashes
+  slug TEXT NOT NULL,
+  title TEXT NOT NULL,
+  visibility page_visibility_enum DEFAULT 'private' NOT NULL,
+  created_at TIMESTAMP DEFAULT now() NOT NULL,
+  CONSTRAINT status_pages_slug_unique UNIQUE (slug)
+);
+
+CREATE INDEX status_pages_user_id ON status_pages (user_id);
+
+-- Devices shown on a page, in `position` order and under the name the page gives them.
+-- Admins may add devices of other accounts.
+CREATE TABLE status_page_monitors (
+  page_id uuid REFERENCES status_pages (id) ON DELETE CASCADE NOT NULL,
+  device_id uuid REFERENCES devices (id) ON DELETE CASCADE NOT NULL,
+  display_name TEXT NOT NULL,
+  position SMALLINT NOT NULL,
+  PRIMARY KEY (page_id, device_id)
+);
@@ -1,7 +1,17 @@
+use crate::channels::{self, EmailChannel, Priority, TelegramChannel};
 use crate::context::Context;
-use crate::db::{self, Invite, User, UserState};
-use rocket::serde::Deserialize;
+use crate::db::{
+    self, ApiToken, Channel, ChannelKind, Device, DeviceChanges, DeviceState, EscalationStep, Invite, MaintenanceWindow, User,
+    UserChanges, UserState,
+};
+use crate::notifications::EventSettings;
+use crate::{email, notifications, prom, schedule, status_page, tokens};
+use rocket::serde::json::{self, Value, json};
+use rocket::serde::{Deserialize, Deserializer};
+use rocket::tokio;
 use rocket_db_pools::diesel::AsyncPgConnection as Conn;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
 
 #[derive(Debug, Deserialize)]
 #[serde(crate = "rocket::serde")]
@@ -10,6 +20,7 @@ pub struct NewUser {
     pub user_type: db::UserType,
     pub invites_limit: i64,
     pub up_delay: Option<u16>,
+    /// Initial state of the managed ntfy channel.
     pub ntfy_enabled: bool,
     /// Language code for notifications (e.g., "uk", "en")
     pub language_code: String,
@@ -27,7 +38,15 @@ pub fn validate_language_code(lang: &str) -> Result<(), String> {
     Ok(())
 }
 
-pub async fn create_user(opts: &NewUser, conn: &mut Conn, context: &Context) -> Result<UserState, String> {
+/// A freshly created account. The plaintext tokens are only available here, the DB keeps hashes.
+#[derive(Debug)]
+pub struct CreatedUser {
+    pub state: UserState,
+    pub access_token: String,
+    pub heartbeat_token: String,
+}
+
+pub async fn create_user(opts: &NewUser, conn: &mut Conn, context: &Context) -> Result<CreatedUser, String> {
     let mut invite_id: Option<db::ID> = None;
     let mut invite_token_key: Option<String> = None;
 
@@ -77,15 +96,32 @@ pub async fn create_user(opts: &NewUser, conn: &mut Conn, context: &Context) ->
 
     validate_language_code(&opts.language_code)?;
 
-    let ntfy = match context.ntfy.create_new_user(opts.ntfy_enabled).await {
+    let ntfy = match context.ntfy.create_new_user().await {
         Ok(new_ntfy_user) => new_ntfy_user,
         Err(err) => return Err(format!("{err:?}")),
     };
-    let new_user = User::new(user_type, invites_limit, opts.up_delay, opts.language_code.clone(), &ntfy);
+    let (access_token, heartbeat_token) = (tokens::new_account_token(), tokens::new_heartbeat_token());
+    let new_user = User::new(
+        user_type,
+        invites_limit,
+        opts.language_code.clone(),
+        &ntfy,
+        tokens::hash_token(&access_token),
+    );
+    let device = Device::new(
+        new_user.id,
+        DEFAULT_DEVICE_NAME.to_string(),
+        opts.up_delay,
+        tokens::hash_token(&heartbeat_token),
+    );
+    let ntfy_channel = Channel::new(new_user.id, ChannelKind::Ntfy, opts.ntfy_enabled, managed_ntfy_config(&ntfy));
     let new_state = db::UserState {
-        uptime: db::UptimeState::new(new_user.id),
         user: new_user,
         ntfy,
+        devices: vec![DeviceState::new(device)],
+        channels: vec![ntfy_channel],
+        escalation: Vec::new(),
+        maintenance: Vec::new(),
     };
 
     if let Err(err) = db::create_new_state(conn, &new_state, invite_id.as_ref()).await {
@@ -105,7 +141,11 @@ pub async fn create_user(opts: &NewUser, conn: &mut Conn, context: &Context) ->
     }
 
     context.add_state(new_state.clone()).await;
-    Ok(new_state)
+    Ok(CreatedUser {
+        state: new_state,
+        access_token,
+        heartbeat_token,
+    })
 }
 
 #[derive(Debug, Deserialize)]
@@ -135,3 +175,999 @@ pub async fn create_invite(opts: &NewInvite, conn: &mut Conn, context: &Context)
         Err(err) => Err(format!("DB Err: {err:?}")),
     }
 }
+
+// Devices
+
+/// Name of the device created together with an account.
+pub const DEFAULT_DEVICE_NAME: &str = "default";
+/// Upper bound on devices per account, each one costs a background check per loop iteration.
+pub const MAX_DEVICES_PER_USER: usize = 16;
+
+/// Validate the name of a device or token (`what`): 1-32 characters, no control characters or
+/// surrounding whitespace.
+pub fn validate_name(what: &str, name: &str) -> Result<(), String> {
+    if name.is_empty() || name.chars().count() > 32 {
+        return Err(format!("{what} name must be 1-32 characters long"));
+    }
+    if name.trim() != name || name.chars().any(char::is_control) {
+        return Err(format!(
+            "{what} name must not contain control characters or surrounding whitespace"
+        ));
+    }
+    Ok(())
+}
+
+pub fn validate_up_delay(up_delay: i64) -> Result<(), String> {
+    if !(10..=32767).contains(&up_delay) {
+        return Err("up_delay must be between 10 and 32767 seconds".to_string());
+    }
+    Ok(())
+}
+
+/// Distinguishes a missing field (None) from an explicit null (Some(None)).
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Partial update of a device. Missing fields are left as is, a null flap_threshold disables
+/// flap detection.
+#[derive(Debug, Default, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct DeviceSettings {
+    pub name: Option<String>,
+    // @NOTE: Numbers are taken as i64 and narrowed after `validate`, so an out of range
+    //  value gets a proper 400 instead of a bare 422 from the deserializer.
+    pub up_delay: Option<i64>,
+    pub min_outage_secs: Option<i64>,
+    /// null disables flap detection
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub flap_threshold: Option<Option<i64>>,
+    pub flap_window_minutes: Option<i64>,
+    pub flap_stable_minutes: Option<i64>,
+    pub recovery_heartbeats: Option<i64>,
+}
+
+impl DeviceSettings {
+    fn validate(&self) -> Result<DeviceChanges, String> {
+        if let Some(name) = &self.name {
+            validate_name("Device", name)?;
+        }
+        if let Some(delay) = self.up_delay {
+            validate_up_delay(delay)?;
+        }
+        if let Some(secs) = self.min_outage_secs
+            && !(0..=86400).contains(&secs)
+        {
+            return Err("min_outage_secs must be between 0 and 86400 seconds".to_string());
+        }
+        if let Some(Some(threshold)) = self.flap_threshold
+            && !(2..=100).contains(&threshold)
+        {
+            return Err("flap_threshold must be between 2 and 100 flips, or null to disable".to_string());
+        }
+        for (key, minutes) in [
+            ("flap_window_minutes", self.flap_window_minutes),
+            ("flap_stable_minutes", self.flap_stable_minutes),
+        ] {
+            if let Some(minutes) = minutes
+                && !(1..=1440).contains(&minutes)
+            {
+                return Err(format!("{key} must be between 1 and 1440 minutes"));
+            }
+        }
+        if let Some(heartbeats) = self.recovery_heartbeats
+            && !(1..=100).contains(&heartbeats)
+        {
+            return Err("recovery_heartbeats must be between 1 and 100".to_string());
+        }
+        // All of them are in range of the column types by now
+        Ok(DeviceChanges {
+            name: self.name.clone(),
+            up_delay: self.up_delay.map(|delay| delay as i16),
+            min_outage_secs: self.min_outage_secs.map(|secs| secs as i32),
+            flap_threshold: self.flap_threshold.map(|threshold| threshold.map(|flips| flips as i16)),
+            flap_window_minutes: self.flap_window_minutes.map(|minutes| minutes as i16),
+            flap_stable_minutes: self.flap_stable_minutes.map(|minutes| minutes as i16),
+            recovery_heartbeats: self.recovery_heartbeats.map(|heartbeats| heartbeats as i16),
+        })
+    }
+}
+
+/// A daily window like quiet hours, given as `<prefix>_start` and
+/// `<prefix>_end`: both present or both absent, both null or both minutes from local midnight.
+fn validate_daily_window(
+    (prefix, what): (&str, &str),
+    start: Option<Option<i16>>,
+    end: Option<Option<i16>>,
+) -> Result<(), String> {
+    if start.is_some() != end.is_some() {
+        return Err(format!("{prefix}_start and {prefix}_end must be set together"));
+    }
+    // Reject mixed null/value (e.g., start=60, end=null) — DB constraint would catch it as 500
+    if let (Some(a), Some(b)) = (start, end)
+        && a.is_some() != b.is_some()
+    {
+        return Err(format!("{what} start and end must both be set or both be null"));
+    }
+    if let (Some(Some(s)), Some(Some(e))) = (start, end) {
+        if !(0..1440).contains(&s) || !(0..1440).contains(&e) {
+            return Err(format!("{what} values must be 0-1439 (minutes from midnight)"));
+        }
+        if s == e {
+            return Err(format!("{what} start and end must differ"));
+        }
+    }
+    Ok(())
+}
+
+fn is_unique_violation(err: &diesel::result::Error) -> bool {
+    matches!(
+        err,
+        diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _)
+    )
+}
+
+pub async fn create_device(
+    uid: db::ID,
+    name: String,
+    up_delay: Option<i64>,
+    conn: &mut Conn,
+    context: &Context,
+) -> Result<(DeviceState, String), String> {
+    validate_name("Device", &name)?;
+    if let Some(up_delay) = up_delay {
+        validate_up_delay(up_delay)?;
+    }
+    match context.users.read().await.get(&uid) {
+        Some(state) if state.devices.len() >= MAX_DEVICES_PER_USER => {
+            return Err(format!("Device limit of {MAX_DEVICES_PER_USER} reached"));
+        }
+        Some(state) if state.devices.iter().any(|d| d.device.name == name) => {
+            return Err("Device name already in use".to_string());
+        }
+        None => return Err("User not found".to_string()),
+        _ => {}
+    }
+
+    let token = tokens::new_heartbeat_token();
+    let new_state = DeviceState::new(Device::new(
+        uid,
+        name,
+        up_delay.map(|delay| delay as u16),
+        tokens::hash_token(&token),
+    ));
+    match db::create_device(conn, &new_state).await {
+        Ok(_) => {}
+        Err(err) if is_unique_violation(&err) => return Err("Device name already in use".to_string()),
+        Err(err) => return Err(format!("{err:?}")),
+    }
+    prom::UPTIME_STATE
+        .with_label_values(&[&uid.to_string(), &new_state.device.id.to_string()])
+        .set(i64::from(&new_state.uptime.status));
+    context.add_device(new_state.clone()).await;
+    Ok((new_state, token))
+}
+
+/// Body of `PATCH /api/v1/me/settings`: settings of one device plus the account wide notification settings.
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct SettingsUpdate {
+    #[serde(flatten)]
+    pub device: DeviceSettings,
+    /// Merged into the notification settings per event kind, see `notifications::EventSettings::merge`.
+    pub notifications: Option<Value>,
+    /// IANA time zone of the account, e.g. "Europe/Kyiv". Daily windows are local to it.
+    pub timezone: Option<String>,
+    /// Quiet hours of the account, null (both start and end) turns them off.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub quiet_hours_start: Option<Option<i16>>,
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub quiet_hours_end: Option<Option<i16>>,
+    /// Queue of the planned outage schedule the account is in, e.g. "3.1", null removes it.
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub outage_queue: Option<Option<String>>,
+}
+
+/// Apply a settings update, both parts are validated before anything is written. Returns the device
+/// and the user's notification settings.
+pub async fn update_settings(
+    uid: db::ID,
+    device_id: db::ID,
+    update: &SettingsUpdate,
+    conn: &mut Conn,
+    context: &Context,
+) -> Result<(Device, db::User), String> {
+    let device_changes = update.device.validate()?;
+    let (quiet_start, quiet_end) = (update.quiet_hours_start, update.quiet_hours_end);
+    validate_daily_window(("quiet_hours", "Quiet hours"), quiet_start, quiet_end)?;
+    if let Some(timezone) = &update.timezone
+        && timezone.parse::<chrono_tz::Tz>().is_err()
+    {
+        return Err(format!(
+            "Unknown time zone '{timezone}', expected an IANA name like Europe/Kyiv"
+        ));
+    }
+    if let Some(Some(queue)) = &update.outage_queue {
+        schedule::validate_queue(queue)?;
+    }
+    let (current, notification_settings) = {
+        let users = context.users.read().await;
+        let Some(state) = users.get(&uid) else {
+            return Err("User not found".to_string());
+        };
+        let Some(current) = state.device(device_id).map(|d| d.device.clone()) else {
+            return Err("Device not found".to_string());
+        };
+        let settings = match &update.notifications {
+            Some(changes) => Some(EventSettings::merge(&state.user.notification_settings, changes)?),
+            None => None,
+        };
+        (current, settings)
+    };
+    let user_changes = UserChanges {
+        notification_settings,
+        quiet_hours_start: quiet_start,
+        quiet_hours_end: quiet_end,
+        timezone: update.timezone.clone(),
+        outage_queue: update.outage_queue.clone(),
+    };
+
+    let device = match db::update_settings(conn, uid, device_id, &device_changes, &user_changes).await {
+        Ok(device) => device.unwrap_or(current),
+        Err(err) if is_unique_violation(&err) => return Err("Device name already in use".to_string()),
+        Err(err) => return Err(format!("{err:?}")),
+    };
+    // @NOTE: Memory is only touched once the transaction has committed, so a failed
+    //  write never leaves the running state ahead of the DB.
+    let mut users = context.users.write().await;
+    let Some(state) = users.get_mut(&uid) else {
+        return Err("User not found".to_string());
+    };
+    if let Some(item) = state.device_mut(device_id) {
+        item.device = device.clone();
+    }
+    let user = &mut state.user;
+    if let Some(settings) = user_changes.notification_settings {
+        user.notification_settings = settings;
+    }
+    if let (Some(start), Some(end)) = (quiet_start, quiet_end) {
+        (user.quiet_hours_start, user.quiet_hours_end) = (start, end);
+    }
+    if let Some(timezone) = user_changes.timezone {
+        user.timezone = timezone;
+    }
+    if let Some(queue) = user_changes.outage_queue {
+        user.outage_queue = queue;
+    }
+    Ok((device, user.clone()))
+}
+
+/// Apply a partial settings update to one of the user's devices, in the DB and in memory.
+pub async fn update_device_settings(
+    uid: db::ID,
+    device_id: db::ID,
+    settings: &DeviceSettings,
+    conn: &mut Conn,
+    context: &Context,
+) -> Result<Device, String> {
+    let changes = settings.validate()?;
+    let current = match context.users.read().await.get(&uid).and_then(|s| s.device(device_id)) {
+        Some(d) => d.device.clone(),
+        None => return Err("Device not found".to_string()),
+    };
+    if changes.is_empty() {
+        return Ok(current);
+    }
+
+    let device = match db::update_device(conn, uid, device_id, &changes).await {
+        Ok(device) => device,
+        Err(err) if is_unique_violation(&err) => return Err("Device name already in use".to_string()),
+        Err(err) => return Err(format!("{err:?}")),
+    };
+    if let Some(state) = context.users.write().await.get_mut(&uid)
+        && let Some(item) = state.device_mut(device_id)
+    {
+        item.device = device.clone();
+    }
+    Ok(device)
+}
+
+// Named API tokens
+
+/// Upper bound on named tokens per account.
+pub const MAX_API_TOKENS_PER_USER: usize = 32;
+/// Longest allowed `expires_in`: 10 years.
+const MAX_TOKEN_LIFETIME_SECS: u64 = 10 * 365 * 86400;
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct NewApiToken {
+    pub name: String,
+    pub scope: db::TokenScope,
+    /// Device for heartbeat tokens, may be omitted for single-device accounts.
+    pub device: Option<db::ID>,
+    /// Lifetime in seconds, the token never expires if omitted.
+    pub expires_in: Option<u64>,
+}
+
+/// Create a named token. Returns it along with the plaintext, which is never available again.
+pub async fn create_api_token(
+    uid: db::ID,
+    opts: NewApiToken,
+    conn: &mut Conn,
+    context: &Context,
+) -> Result<(ApiToken, String), String> {
+    validate_name("Token", &opts.name)?;
+    let expires_at = match opts.expires_in {
+        Some(secs) if secs == 0 || secs > MAX_TOKEN_LIFETIME_SECS => {
+            return Err(format!("expires_in must be between 1 and {MAX_TOKEN_LIFETIME_SECS} seconds"));
+        }
+        Some(secs) => Some(SystemTime::now() + Duration::from_secs(secs)),
+        None => None,
+    };
+    let device_id = match (opts.scope, context.users.read().await.get(&uid)) {
+        (_, None) => return Err("User not found".to_string()),
+        (db::TokenScope::Account, Some(_)) if opts.device.is_some() => {
+            return Err("Account tokens can't be bound to a device".to_string());
+        }
+        (db::TokenScope::Account, Some(_)) => None,
+        (db::TokenScope::Heartbeat, Some(state)) => Some(state.resolve_device(opts.device)?.device.id),
+    };
+    let existing = context.api_tokens_for(uid).await;
+    if existing.len() >= MAX_API_TOKENS_PER_USER {
+        return Err(format!("Token limit of {MAX_API_TOKENS_PER_USER} reached"));
+    }
+    if existing.iter().any(|t| t.name == opts.name) {
+        return Err("Token name already in use".to_string());
+    }
+
+    let token = match opts.scope {
+        db::TokenScope::Account => tokens::new_account_token(),
+        db::TokenScope::Heartbeat => tokens::new_heartbeat_token(),
+    };
+    let api_token = ApiToken::new(uid, device_id, opts.name, opts.scope, tokens::hash_token(&token), expires_at);
+    match db::create_api_token(conn, &api_token).await {
+        Ok(_) => {}
+        Err(err) if is_unique_violation(&err) => return Err("Token name already in use".to_string()),
+        Err(err) => return Err(format!("{err:?}")),
+    }
+    context.add_api_token(api_token.clone()).await;
+    Ok((api_token, token))
+}
+
+// Notification channels
+
+/// Upper bound on channels per account, every notification is sent to each enabled one.
+pub const MAX_CHANNELS_PER_USER: usize = 16;
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct NewChannel {
+    pub kind: ChannelKind,
+    /// Defaults to true.
+    pub enabled: Option<bool>,
+    /// Kind specific settings, must be omitted for channels set up by the server (`ChannelKind::is_managed`).
+    pub config: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ChannelChanges {
+    pub enabled: Option<bool>,
+    /// Merged into the current config, keys set to null are removed.
+    pub config: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ChannelUrl {
+    /// Apprise style URL, see `channels::parse_url`.
+    pub url: String,
+    pub enabled: Option<bool>,
+}
+
+/// Config of the channel publishing to the user's own topic on the managed ntfy instance.
+fn managed_ntfy_config(ntfy: &db::NtfyUser) -> Value {
+    json!({"topic": ntfy.topic})
+}
+
+/// Reject a config the channel can't be built from, or one targeting the same place as another channel.
+fn check_channel_config(state: &UserState, channel_id: db::ID, kind: ChannelKind, config: &Value) -> Result<(), String> {
+    let channel = channels::build(kind, config)?;
+    let target = channel.target();
+    let duplicate = state
+        .channels
+        .iter()
+        .filter(|c| c.id != channel_id && c.kind == kind)
+        .filter_map(|c| channels::build(c.kind, &c.config).ok())
+        .any(|c| c.target() == target);
+    if duplicate {
+        return Err(format!(
+            "A {} channel for {} already exists",
+            kind.label(),
+            channel.describe()
+        ));
+    }
+    Ok(())
+}
+
+/// Mail the verification links of newly added email recipients, in the background.
+fn send_verification_emails(context: &Context, language: &str, pending: Vec<(String, String)>) {
+    for (address, token) in pending {
+        let link = email::verification_link(&token);
+        let args = [("address", address.as_str()), ("link", link.as_str())];
+        let subject = notifications::localize(language, "email-verify-subject");
+        let body = notifications::localize_with_args(language, "email-verify-body", &args);
+        let context = context.clone();
+        tokio::spawn(async move {
+            if let Err(err) = context.mailer.send(&address, &subject, body).await {
+                warn!("Failed to send verification email to {address}: {err}");
+            }
+        });
+    }
+}
+
+pub async fn create_channel(uid: db::ID, opts: NewChannel, conn: &mut Conn, context: &Context) -> Result<Channel, String> {
+    let mut pending = Vec::new();
+    let (channel, language) = {
+        let users = context.users.read().await;
+        let Some(state) = users.get(&uid) else {
+            return Err("User not found".to_string());
+        };
+        if state.channels.len() >= MAX_CHANNELS_PER_USER {
+            return Err(format!("Channel limit of {MAX_CHANNELS_PER_USER} reached"));
+        }
+        let (config, enabled) = match (opts.kind, opts.config) {
+            // @NOTE: Only the server decides which ntfy topic or Telegram chat is used, the admin token
+            //  and the bot can write to any of them.
+            (ChannelKind::Ntfy, Some(config)) if ChannelKind::Ntfy.is_managed(Some(&config)) => {
+                return Err("ntfy channels take no config unless they point to your own server (server=...)".to_string());
+            }
+            (kind, Some(config)) if kind.is_managed(Some(&config)) => {
+                return Err(format!("{} channels take no config, the server sets them up", kind.label()));
+            }
+            (ChannelKind::Ntfy, None) => (managed_ntfy_config(&state.ntfy), opts.enabled.unwrap_or(true)),
+            (ChannelKind::Telegram, None) if !context.telegram.is_configured() => {
+                return Err("Telegram is not configured on this server".to_string());
+            }
+            // Switched on once the chat is linked
+            (ChannelKind::Telegram, None) => (TelegramChannel::new_link(), false),
+            (ChannelKind::Email, _) if !context.mailer.is_configured() => {
+                return Err("Email is not configured on this server".to_string());
+            }
+            (ChannelKind::Email, config) => {
+                let (email, to_verify) = EmailChannel::from_input(&config.unwrap_or_else(|| json!({})), None)?;
+                pending = to_verify;
+                (json!(email), opts.enabled.unwrap_or(true))
+            }
+            (_, config) => (config.unwrap_or_else(|| json!({})), opts.enabled.unwrap_or(true)),
+        };
+        let channel = Channel::new(uid, opts.kind, enabled, config);
+        check_channel_config(state, channel.id, channel.kind, &channel.config)?;
+        (channel, state.user.language_code.clone())
+    };
+
+    db::create_channel(conn, &channel).await.map_err(|err| format!("{err:?}"))?;
+    context.add_channel(channel.clone()).await;
+    send_verification_emails(context, &language, pending);
+    Ok(channel)
+}
+
+pub async fn update_channel(
+    mut channel: Channel,
+    opts: ChannelChanges,
+    conn: &mut Conn,
+    context: &Context,
+) -> Result<Channel, String> {
+    let mut pending = Vec::new();
+    if let Some(changes) = opts.config {
+        if channel.kind.is_managed(Some(&channel.config)) {
+            return Err(format!(
+                "{} channels are set up by the server, only 'enabled' can be changed",
+                channel.kind.label()
+            ));
+        }
+        if channel.kind == ChannelKind::Email {
+            // The recipient list is replaced, verified addresses that are kept stay verified
+            let existing: Option<EmailChannel> = json::from_value(channel.config.clone()).ok();
+            let (email, to_verify) = EmailChannel::from_input(&changes, existing.as_ref())?;
+            (channel.config, pending) = (json!(email), to_verify);
+        } else {
+            let (Value::Object(config), Value::Object(changes)) = (&mut channel.config, changes) else {
+                return Err("config must be a JSON object".to_string());
+            };
+            for (key, value) in changes {
+                match value {
+                    Value::Null => config.remove(&key),
+                    value => config.insert(key, value),
+                };
+            }
+            if channel.kind.is_managed(Some(&channel.config)) {
+                return Err(format!("{} channels on your own server need a server", channel.kind.label()));
+            }
+        }
+        match context.users.read().await.get(&channel.user_id) {
+            Some(state) => check_channel_config(state, channel.id, channel.kind, &channel.config)?,
+            None => return Err("User not found".to_string()),
+        }
+    }
+    if let Some(enabled) = opts.enabled {
+        channel.enabled = enabled;
+    }
+
+    db::update_channel(conn, &channel).await.map_err(|err| format!("{err:?}"))?;
+    context.update_channel(channel.clone()).await;
+    if !pending.is_empty() {
+        let language = context
+            .users
+            .read()
+            .await
+            .get(&channel.user_id)
+            .map(|s| s.user.language_code.clone());
+        send_verification_emails(context, &language.unwrap_or_default(), pending);
+    }
+    Ok(channel)
+}
+
+/// Whether `config` (as submitted, before it is stored) points to the same place as `channel`.
+fn same_target(channel: &Channel, kind: ChannelKind, config: &Value) -> bool {
+    if channel.kind != kind {
+        return false;
+    }
+    if kind == ChannelKind::Email {
+        let normalize = |addresses: Vec<String>| {
+            let mut addresses: Vec<String> = addresses.iter().map(|a| a.to_lowercase()).collect();
+            addresses.sort();
+            addresses
+        };
+        let existing = json::from_value::<EmailChannel>(channel.config.clone())
+            .map(|e| e.recipients.into_iter().map(|r| r.address).collect())
+            .unwrap_or_default();
+        return EmailChannel::input_addresses(config).is_ok_and(|new| normalize(new) == normalize(existing));
+    }
+    match (channels::build(kind, config), channels::build(channel.kind, &channel.config)) {
+        (Ok(new), Ok(existing)) => new.target() == existing.target(),
+        _ => false,
+    }
+}
+
+/// Add a channel from an Apprise style URL (see `channels::parse_url`), or replace the config of
+/// the channel that already points to the same place. Returns the channel and whether it is new.
+pub async fn apply_channel_url(
+    uid: db::ID,
+    opts: ChannelUrl,
+    conn: &mut Conn,
+    context: &Context,
+) -> Result<(Channel, bool), String> {
+    let (kind, config) = channels::parse_url(&opts.url)?;
+    let enabled = opts.enabled;
+    let existing = match context.users.read().await.get(&uid) {
+        Some(state) => state.channels.iter().find(|c| same_target(c, kind, &config)).cloned(),
+        None => return Err("User not found".to_string()),
+    };
+    let Some(existing) = existing else {
+        let opts = NewChannel {
+            kind,
+            enabled,
+            config: Some(config),
+        };
+        return Ok((create_channel(uid, opts, conn, context).await?, true));
+    };
+
+    // Replace the config as a whole, keys the URL doesn't set are removed
+    let mut changes = config;
+    if kind != ChannelKind::Email
+        && let (Value::Object(old), Value::Object(new)) = (&existing.config, &mut changes)
+    {
+        for key in old.keys() {
+            new.entry(key.clone()).or_insert(Value::Null);
+        }
+    }
+    let opts = ChannelChanges {
+        enabled,
+        config: Some(changes),
+    };
+    Ok((update_channel(existing, opts, conn, context).await?, false))
+}
+
+/// Confirm the email recipient the verification `token` was sent to. Returns the address and the
+/// owner's language, or None for unknown and expired links.
+pub async fn verify_email(token: &str, conn: &mut Conn, context: &Context) -> Result<Option<(String, String)>, String> {
+    let token_hash = tokens::hash_token(token);
+    let found = context.users.read().await.values().find_map(|state| {
+        state.channels.iter().filter(|c| c.kind == ChannelKind::Email).find_map(|c| {
+            let mut email: EmailChannel = json::from_value(c.config.clone()).ok()?;
+            let index = email
+                .recipients
+                .iter()
+                .position(|r| r.verify_hash.as_deref() == Some(token_hash.as_str()))?;
+            let verified = email.verify(&token_hash, SystemTime::now());
+            Some((c.clone(), email, index, verified, state.user.language_code.clone()))
+        })
+    });
+    let Some((mut channel, email, index, true, language)) = found else {
+        return Ok(None);
+    };
+
+    let address = email.recipients[index].address.clone();
+    channel.config = json!(email);
+    db::update_channel(conn, &channel).await.map_err(|err| format!("{err:?}"))?;
+    context.update_channel(channel).await;
+    Ok(Some((address, language)))
+}
+
+// Escalation steps
+
+/// Upper bound on escalation steps per account.
+pub const MAX_ESCALATION_STEPS: usize = 10;
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct NewEscalationStep {
+    /// Minutes since the outage started, 1 minute to 7 days.
+    pub delay_minutes: i32,
+    /// Defaults to high.
+    pub priority: Option<Priority>,
+    /// One of the user's channels to notify on top of the enabled ones.
+    pub channel_id: Option<db::ID>,
+}
+
+pub async fn create_escalation_step(
+    uid: db::ID,
+    opts: NewEscalationStep,
+    conn: &mut Conn,
+    context: &Context,
+) -> Result<EscalationStep, String> {
+    if !(1..=7 * 1440).contains(&opts.delay_minutes) {
+        return Err("delay_minutes must be between 1 and 10080 (7 days)".to_string());
+    }
+    match context.users.read().await.get(&uid) {
+        Some(state) if state.escalation.len() >= MAX_ESCALATION_STEPS => {
+            return Err(format!("Escalation step limit of {MAX_ESCALATION_STEPS} reached"));
+        }
+        Some(state) if state.escalation.iter().any(|s| s.delay_minutes == opts.delay_minutes) => {
+            return Err(format!("There already is a step after {} minutes", opts.delay_minutes));
+        }
+        Some(state) if opts.channel_id.is_some_and(|id| !state.channels.iter().any(|c| c.id == id)) => {
+            return Err("Channel not found".to_string());
+        }
+        None => return Err("User not found".to_string()),
+        _ => {}
+    }
+
+    let step = EscalationStep::new(
+        uid,
+        opts.delay_minutes,
+        opts.priority.unwrap_or(Priority::High),
+        opts.channel_id,
+    );
+    match db::create_escalation_step(conn, &step).await {
+        Ok(()) => {}
+        Err(err) if is_unique_violation(&err) => {
+            return Err(format!("There already is a step after {} minutes", opts.delay_minutes));
+        }
+        Err(err) => return Err(format!("{err:?}")),
+    }
+    context.add_escalation_step(step.clone()).await;
+    Ok(step)
+}
+
+// Maintenance windows
+
+/// Upper bound on maintenance windows per account, all of them are checked on every heartbeat.
+pub const MAX_MAINTENANCE_WINDOWS: usize = 50;
+/// Longest one-off window, longer breaks are what pausing is for.
+const MAX_ONE_OFF_DAYS: u64 = 30;
+/// Day names as the API returns them, index 0 is Monday (bit 1 of `weekdays`).
+pub const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+/// Format of one-off window times, local to the account's time zone.
+pub const LOCAL_DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+/// Either a weekly rule (`weekdays`, `start_minute`, `end_minute`) or a one-off range (`starts_at`,
+/// `ends_at`), all in the account's time zone. Without `device_id` it covers all devices.
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct MaintenanceWindowSpec {
+    pub device_id: Option<db::ID>,
+    pub label: Option<String>,
+    /// Day names, e.g. ["tue", "thu"].
+    pub weekdays: Option<Vec<String>>,
+    /// Minutes from local midnight, an end before the start runs into the next day.
+    pub start_minute: Option<i16>,
+    pub end_minute: Option<i16>,
+    /// Local date and time, e.g. "2026-10-20T09:00".
+    pub starts_at: Option<String>,
+    pub ends_at: Option<String>,
+}
+
+impl MaintenanceWindowSpec {
+    fn validate(self, state: &UserState, now: SystemTime) -> Result<MaintenanceWindow, String> {
+        let uid = state.user.id;
+        if let Some(label) = &self.label
+            && (label.trim().is_empty() || label.chars().count() > 64 || label.chars().any(char::is_control))
+        {
+            return Err("Label must be 1-64 characters long, without control characters".to_string());
+        }
+        if let Some(device_id) = self.device_id
+            && state.device(device_id).is_none()
+        {
+            return Err("Device not found".to_string());
+        }
+        match (
+            self.weekdays,
+            self.start_minute,
+            self.end_minute,
+            self.starts_at,
+            self.ends_at,
+        ) {
+            (Some(days), Some(start), Some(end), None, None) => {
+                let mut weekdays = 0;
+                for day in &days {
+                    match day.parse::<chrono::Weekday>() {
+                        Ok(day) => weekdays |= 1 << day.num_days_from_monday(),
+                        Err(_) => return Err(format!("Unknown day '{day}', expected one of {}", WEEKDAYS.join(", "))),
+                    }
+                }
+                if weekdays == 0 {
+                    return Err("weekdays must name at least one day".to_string());
+                }
+                if !(0..1440).contains(&start) || !(0..1440).contains(&end) {
+                    return Err("start_minute and end_minute must be 0-1439 (minutes from midnight)".to_string());
+                }
+                if start == end {
+                    return Err("start_minute and end_minute must differ".to_string());
+                }
+                Ok(MaintenanceWindow::weekly(
+                    uid,
+                    self.device_id,
+                    self.label,
+                    weekdays,
+                    (start, end),
+                ))
+            }
+            (None, None, None, Some(starts_at), Some(ends_at)) => {
+                let tz = state.user.tz();
+                let (starts_at, ends_at) = (parse_local_datetime(&starts_at, tz)?, parse_local_datetime(&ends_at, tz)?);
+                if ends_at <= starts_at {
+                    return Err("ends_at must be after starts_at".to_string());
+                }
+                if ends_at <= now {
+                    return Err("The window is already over".to_string());
+                }
+                if ends_at.duration_since(starts_at).unwrap_or_default() > Duration::from_secs(MAX_ONE_OFF_DAYS * 86400) {
+                    return Err(format!(
+                        "One-off windows can last at most {MAX_ONE_OFF_DAYS} days, pause monitoring instead"
+                    ));
+                }
+                Ok(MaintenanceWindow::one_off(
+                    uid,
+                    self.device_id,
+                    self.label,
+                    (starts_at, ends_at),
+                ))
+            }
+            _ => Err("Give either weekdays, start_minute and end_minute, or starts_at and ends_at".to_string()),
+        }
+    }
+}
+
+/// Parse a local date and time (see `LOCAL_DATETIME_FORMAT`) in `tz`. Of a time repeated when
+/// daylight saving time ends, the first occurrence is taken.
+fn parse_local_datetime(value: &str, tz: chrono_tz::Tz) -> Result<SystemTime, String> {
+    use chrono::TimeZone;
+    let naive = chrono::NaiveDateTime::parse_from_str(value, LOCAL_DATETIME_FORMAT)
+        .map_err(|_| format!("Invalid time '{value}', expected local YYYY-MM-DDTHH:MM"))?;
+    match tz.from_local_datetime(&naive).earliest() {
+        Some(local) => Ok(local.with_timezone(&chrono::Utc).into()),
+        None => Err(format!("{value} doesn't exist in {tz}, the clocks skip it")),
+    }
+}
+
+/// Add a maintenance window. One-off windows that are over are deleted along the way.
+pub async fn create_maintenance_window(
+    uid: db::ID,
+    spec: MaintenanceWindowSpec,
+    conn: &mut Conn,
+    context: &Context,
+) -> Result<MaintenanceWindow, String> {
+    let now = SystemTime::now();
+    let window = match context.users.read().await.get(&uid) {
+        Some(state) if state.maintenance.iter().filter(|w| !w.has_ended(now)).count() >= MAX_MAINTENANCE_WINDOWS => {
+            return Err(format!("Maintenance window limit of {MAX_MAINTENANCE_WINDOWS} reached"));
+        }
+        Some(state) => spec.validate(state, now)?,
+        None => return Err("User not found".to_string()),
+    };
+    db::delete_ended_maintenance_windows(conn, uid, now)
+        .await
+        .map_err(|err| format!("{err:?}"))?;
+    db::create_maintenance_window(conn, &window)
+        .await
+        .map_err(|err| format!("{err:?}"))?;
+    context.add_maintenance_window(window.clone(), now).await;
+    Ok(window)
+}
+
+/// Replace a maintenance window with `spec`, keeping its id.
+pub async fn update_maintenance_window(
+    current: MaintenanceWindow,
+    spec: MaintenanceWindowSpec,
+    conn: &mut Conn,
+    context: &Context,
+) -> Result<MaintenanceWindow, String> {
+    let window = match context.users.read().await.get(&current.user_id) {
+        Some(state) => MaintenanceWindow {
+            id: current.id,
+            created_at: current.created_at,
+            ..spec.validate(state, SystemTime::now())?
+        },
+        None => return Err("User not found".to_string()),
+    };
+    match db::update_maintenance_window(conn, &window).await {
+        Ok(updated) if updated > 0 => {}
+        Ok(_) => return Err("Maintenance window not found".to_string()),
+        Err(err) => return Err(format!("{err:?}")),
+    }
+    context.update_maintenance_window(window.clone()).await;
+    Ok(window)
+}
+
+// Status pages
+
+/// Upper bound on status pages per account.
+pub const MAX_STATUS_PAGES: usize = 10;
+/// Upper bound on devices per page, each one costs a query whenever the page is viewed.
+pub const MAX_PAGE_MONITORS: usize = 50;
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct StatusPageMonitorSpec {
+    pub device_id: db::ID,
+    /// Shown instead of the device name, e.g. "Apt. 12"
+    pub name: Option<String>,
+}
+
+/// A page listing `monitors` in the given order. Without `visibility`, new pages are private and
+/// updated ones keep theirs.
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct StatusPageSpec {
+    pub slug: String,
+    pub title: String,
+    pub visibility: Option<db::PageVisibility>,
+    pub monitors: Vec<StatusPageMonitorSpec>,
+}
+
+impl StatusPageSpec {
+    /// Admins may list devices of any account, everyone else only their own.
+    fn validate(
+        self,
+        uid: db::ID,
+        current: Option<&db::StatusPage>,
+        users: &HashMap<db::ID, UserState>,
+    ) -> Result<(db::StatusPage, Vec<db::StatusPageMonitor>), String> {
+        let Some(owner) = users.get(&uid) else {
+            return Err("User not found".to_string());
+        };
+        status_page::validate_slug(&self.slug)?;
+        let title = self.title.trim().to_string();
+        if title.is_empty() || title.chars().count() > 100 || title.chars().any(char::is_control) {
+            return Err("Title must be 1-100 characters long, without control characters".to_string());
+        }
+        if self.monitors.is_empty() || self.monitors.len() > MAX_PAGE_MONITORS {
+            return Err(format!("A page lists 1-{MAX_PAGE_MONITORS} devices"));
+        }
+        let visibility = self
+            .visibility
+            .or(current.map(|page| page.visibility))
+            .unwrap_or(db::PageVisibility::Private);
+        let page = match current {
+            Some(current) => db::StatusPage {
+                slug: self.slug,
+                title,
+                visibility,
+                ..current.clone()
+            },
+            None => db::StatusPage::new(uid, self.slug, title, visibility),
+        };
+
+        let mut monitors: Vec<db::StatusPageMonitor> = Vec::new();
+        for (position, spec) in self.monitors.into_iter().enumerate() {
+            let device = match owner.user.user_type {
+                db::UserType::Admin => users.values().find_map(|state| state.device(spec.device_id)),
+                db::UserType::Normal => owner.device(spec.device_id),
+            };
+            let Some(device) = device else {
+                return Err(format!("Device {} not found", spec.device_id));
+            };
+            if monitors.iter().any(|m| m.device_id == spec.device_id) {
+                return Err(format!("Device {} is listed twice", spec.device_id));
+            }
+            let display_name = match spec.name {
+                Some(name) => name.trim().to_string(),
+                None => device.device.name.clone(),
+            };
+            if display_name.is_empty() || display_name.chars().count() > 64 || display_name.chars().any(char::is_control) {
+                return Err("Names must be 1-64 characters long, without control characters".to_string());
+            }
+            monitors.push(db::StatusPageMonitor {
+                page_id: page.id,
+                device_id: spec.device_id,
+                display_name,
+                position: position as i16,
+            });
+        }
+        Ok((page, monitors))
+    }
+}
+
+/// Create a status page, see `StatusPageSpec`.
+pub async fn create_status_page(
+    uid: db::ID,
+    spec: StatusPageSpec,
+    conn: &mut Conn,
+    context: &Context,
+) -> Result<(db::StatusPage, Vec<db::StatusPageMonitor>), String> {
+    let pages = db::get_status_pages_for_user(conn, uid)
+        .await
+        .map_err(|err| format!("{err:?}"))?;
+    if pages.len() >= MAX_STATUS_PAGES {
+        return Err(format!("Status page limit of {MAX_STATUS_PAGES} reached"));
+    }
+    let (page, monitors) = spec.validate(uid, None, &*context.users.read().await)?;
+    match db::create_status_page(conn, &page, &monitors).await {
+        Ok(()) => Ok((page, monitors)),
+        Err(err) if is_unique_violation(&err) => Err("Slug is already taken".to_string()),
+        Err(err) => Err(format!("{err:?}")),
+    }
+}
+
+/// Replace the settings and devices of a status page, keeping its id.
+pub async fn update_status_page(
+    current: db::StatusPage,
+    spec: StatusPageSpec,
+    conn: &mut Conn,
+    context: &Context,
+) -> Result<(db::StatusPage, Vec<db::StatusPageMonitor>), String> {
+    let (page, monitors) = spec.validate(current.user_id, Some(&current), &*context.users.read().await)?;
+    match db::update_status_page(conn, &page, &monitors).await {
+        Ok(updated) if updated > 0 => Ok((page, monitors)),
+        Ok(_) => Err("Status page not found".to_string()),
+        Err(err) if is_unique_violation(&err) => Err("Slug is already taken".to_string()),
+        Err(err) => Err(format!("{err:?}")),
+    }
+}
+
+// Outage schedules
+
+/// Import a planned outage schedule, replacing what is stored for the time it covers per queue.
+/// Returns the number of imported slots by queue.
+pub async fn import_schedule(
+    format: schedule::Format,
+    body: &str,
+    queue: Option<&str>,
+    conn: &mut Conn,
+    context: &Context,
+) -> Result<std::collections::BTreeMap<String, usize>, String> {
+    let outages = schedule::parse(format, body, queue, *schedule::SCHEDULE_TZ)?;
+    if outages.is_empty() {
+        return Err("The schedule has no planned outages".to_string());
+    }
+    let prune_before = SystemTime::now() - schedule::RETENTION;
+    // @NOTE: Held across the DB write, so concurrent imports end up in memory in the order they
+    //  were stored.
+    let mut current = context.schedule.write().await;
+    db::replace_planned_outages(conn, &outages, prune_before)
+        .await
+        .map_err(|err| format!("{err:?}"))?;
+    current.replace(&outages, prune_before);
+    let mut counts = std::collections::BTreeMap::new();
+    for outage in &outages {
+        *counts.entry(outage.queue.clone()).or_insert(0) += 1;
+    }
+    Ok(counts)
+}
@@ -55,7 +55,7 @@ pub async fn delete_user(
     if admin.uid == user_id {
         return json!({"status": 400, "error": "Cannot delete yourself"});
     }
-    // Collect data needed for cleanup before deletion (DB will cascade-delete invites)
+    // Collect data needed for cleanup before deletion (DB will cascade-delete invites and devices)
     let invite_ids: Vec<uuid::Uuid> = match db::get_invites_for_user(&mut conn, user_id).await {
         Ok(invites) => invites.iter().filter(|i| !i.is_used).map(|i| i.id).collect(),
         Err(err) => {
@@ -63,15 +63,21 @@ pub async fn delete_user(
             vec![]
         }
     };
-    let ntfy_username = context.users.read().await.get(&user_id).map(|s| s.ntfy.username.clone());
+    let (ntfy_username, device_ids) = match context.users.read().await.get(&user_id) {
+        Some(s) => (Some(s.ntfy.username.clone()), s.devices.iter().map(|d| d.device.id).collect()),
+        None => (None, vec![]),
+    };
     match db::delete_user(&mut conn, user_id).await {
         Ok(deleted) if deleted > 0 => {
             context.remove_user(user_id).await;
             context.remove_invite_ids(&invite_ids).await;
             // Clean up per-user metrics
             let uid_str = user_id.to_string();
-            let _ = prom::UPTIME_STATE.remove_label_values(&[&uid_str]);
-            let _ = prom::LAST_SEEN_TIMESTAMP.remove_label_values(&[&uid_str]);
+            for device_id in device_ids {
+                let device_str = device_id.to_string();
+                let _ = prom::UPTIME_STATE.remove_label_values(&[&uid_str, &device_str]);
+                let _ = prom::LAST_SEEN_TIMESTAMP.remove_label_values(&[&uid_str, &device_str]);
+            }
             prom::ACTIVE_USERS.dec();
             // Clean up ntfy.sh server user
             if let Some(username) = ntfy_username
@@ -87,8 +93,8 @@ pub async fn delete_user(
 }
 
 /// List all users (admin only).
-/// @NOTE: Intentionally exposes full user data including access_token and ntfy
-///  credentials — admins have explicit access to manage and impersonate any user.
+/// @NOTE: Intentionally exposes ntfy credentials — admins have explicit access to manage
+///  any user. Tokens are never included, only their hashes are stored (see `tokens`).
 #[get("/api/v1/admin/users")]
 pub async fn admin_list_users(_admin: bauth::AdminAuth, context: &State<Context>) -> Value {
     let users: Vec<db::UserState> = context.users.read().await.values().cloned().collect();
@@ -103,3 +109,31 @@ pub async fn admin_get_user(_admin: bauth::AdminAuth, uid: uuid::Uuid, context:
         None => json!({"status": 404, "error": "User not found"}),
     }
 }
+
+/// Notifications waiting for delivery and undeliverable ones, oldest first (admin only).
+/// `?failed=true` only lists the items that ran out of attempts.
+#[get("/api/v1/admin/outbox?<failed>")]
+pub async fn admin_list_outbox(_admin: bauth::AdminAuth, failed: Option<bool>, mut conn: Connection<DB>) -> Value {
+    match db::get_outbox_items(&mut conn, failed.unwrap_or(false), 500).await {
+        Ok(items) => json!({"status": 200, "outbox": items}),
+        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+    }
+}
+
+/// Queue an undeliverable notification again, with a fresh set of attempts (admin only)
+#[post("/api/v1/admin/outbox/<item_id>/retry")]
+pub async fn admin_retry_outbox_item(
+    _admin: bauth::AdminAuth,
+    item_id: uuid::Uuid,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    match db::retry_outbox_item(&mut conn, item_id, std::time::SystemTime::now()).await {
+        Ok(updated) if updated > 0 => {
+            context.outbox_wake.notify_one();
+            json!({"status": 200, "message": "Notification queued again"})
+        }
+        Ok(_) => json!({"status": 404, "error": "No undeliverable notification with this ID"}),
+        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+    }
+}
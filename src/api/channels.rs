@@ -0,0 +1,132 @@
+use crate::actions::{self, ChannelChanges, ChannelUrl, NewChannel};
+use crate::channels::{self, TelegramChannel};
+use crate::{DB, bauth, context::Context, db, notifications};
+use rocket::State;
+use rocket::http::Status;
+use rocket::serde::json::{self, Json, Value, json};
+use rocket_db_pools::Connection;
+
+/// A channel as returned by the API: the config is replaced with its description, as it may hold secrets.
+/// Telegram channels waiting to be linked also get the code to send to the bot.
+fn channel_json(channel: &db::Channel, context: &Context) -> Value {
+    let mut value = json!(channel);
+    value["description"] = match channels::build(channel.kind, &channel.config) {
+        Ok(sender) => json!(sender.describe()),
+        Err(err) => json!(format!("invalid config: {err}")),
+    };
+    if channel.kind == db::ChannelKind::Telegram
+        && let Ok(TelegramChannel {
+            chat_id: None,
+            link_code: Some(code),
+            link_expires_at,
+            ..
+        }) = json::from_value::<TelegramChannel>(channel.config.clone())
+    {
+        let bot = context.telegram.bot_username();
+        value["link"] = json!({
+            "code": code,
+            "expires_at": link_expires_at,
+            "bot": bot,
+            "url": bot.map(|bot| format!("https://t.me/{bot}?start={code}")),
+        });
+    }
+    value
+}
+
+/// List the account's notification channels
+#[get("/api/v1/me/channels")]
+pub async fn list_channels(bauth: bauth::BAuth, context: &State<Context>) -> Value {
+    match context.users.read().await.get(&bauth.uid) {
+        Some(state) => {
+            json!({"status": 200, "channels": state.channels.iter().map(|c| channel_json(c, context)).collect::<Vec<_>>()})
+        }
+        None => json!({"status": 404, "error": "User not found"}),
+    }
+}
+
+/// Add a notification channel, e.g. `{"kind": "Webhook", "config": {"url": "...", "secret": "..."}}`
+#[post("/api/v1/me/channels", data = "<opts>")]
+pub async fn create_channel(
+    bauth: bauth::BAuth,
+    opts: Json<NewChannel>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    match actions::create_channel(bauth.uid, opts.into_inner(), &mut conn, context).await {
+        Ok(channel) => json!({"status": 200, "channel": channel_json(&channel, context)}),
+        Err(err) => json!({"status": 400, "error": err}),
+    }
+}
+
+/// Add a channel from a URL like `gotifys://host/token`, or update the one already pointing there
+#[patch("/api/v1/me/channels", data = "<opts>")]
+pub async fn apply_channel_url(
+    bauth: bauth::BAuth,
+    opts: Json<ChannelUrl>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    match actions::apply_channel_url(bauth.uid, opts.into_inner(), &mut conn, context).await {
+        Ok((channel, created)) => json!({"status": 200, "created": created, "channel": channel_json(&channel, context)}),
+        Err(err) => json!({"status": 400, "error": err}),
+    }
+}
+
+/// Enable/disable a channel and/or change its config
+#[patch("/api/v1/me/channels/<channel_id>", data = "<opts>")]
+pub async fn update_channel(
+    bauth: bauth::BAuth,
+    channel_id: uuid::Uuid,
+    opts: Json<ChannelChanges>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    let channel = match context.users.read().await.get(&bauth.uid) {
+        Some(state) => match state.channels.iter().find(|c| c.id == channel_id) {
+            Some(channel) => channel.clone(),
+            None => return json!({"status": 404, "error": "Channel not found"}),
+        },
+        None => return json!({"status": 404, "error": "User not found"}),
+    };
+    match actions::update_channel(channel, opts.into_inner(), &mut conn, context).await {
+        Ok(channel) => json!({"status": 200, "channel": channel_json(&channel, context)}),
+        Err(err) => json!({"status": 400, "error": err}),
+    }
+}
+
+/// Remove a notification channel
+#[delete("/api/v1/me/channels/<channel_id>")]
+pub async fn delete_channel(
+    bauth: bauth::BAuth,
+    channel_id: uuid::Uuid,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    match db::delete_channel(&mut conn, bauth.uid, channel_id).await {
+        Ok(deleted) if deleted > 0 => {
+            context.remove_channel(bauth.uid, channel_id).await;
+            json!({"status": 200, "message": "Channel deleted"})
+        }
+        Ok(_) => json!({"status": 404, "error": "Channel not found"}),
+        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+    }
+}
+
+/// Target of the link mailed to new email recipients, opened in a browser so it answers in plain text
+#[get("/api/v1/email/verify?<token>")]
+pub async fn verify_email(
+    _rl: bauth::RateLimitGuard,
+    token: &str,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> (Status, String) {
+    match actions::verify_email(token, &mut conn, context).await {
+        Ok(Some((address, language))) => (
+            Status::Ok,
+            notifications::localize_with_args(&language, "email-verified", &[("address", &address)]),
+        ),
+        // @NOTE: The owner is unknown here, so there is no language to answer in.
+        Ok(None) => (Status::NotFound, notifications::localize("en", "email-verify-invalid")),
+        Err(err) => (Status::InternalServerError, format!("{err:?}")),
+    }
+}
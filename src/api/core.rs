@@ -2,7 +2,6 @@ use crate::{DB, bauth, context::Context, db, notifications, prom};
 use rocket::State;
 use rocket::http::Status;
 use rocket::serde::json::{Value, json};
-use rocket::tokio;
 use rocket_db_pools::Connection;
 
 #[get("/api/v1/health")]
@@ -12,12 +11,17 @@ pub async fn api_health(_rl: bauth::RateLimitGuard) -> Value {
 }
 
 #[get("/api/v1/up")]
-pub async fn api_up(bauth: bauth::BAuth, mut conn: Connection<DB>, context: &State<Context>) -> Status {
-    let uid_str = bauth.uid.to_string();
-    let uptime_snapshot = {
+pub async fn api_up(auth: bauth::DeviceAuth, mut conn: Connection<DB>, context: &State<Context>) -> Status {
+    let (uid_str, device_str) = (auth.uid.to_string(), auth.device_id.to_string());
+    let (uptime_snapshot, event, outbox) = {
+        let schedule = context.schedule.read().await;
         let mut guard = context.users.write().await;
-        let Some(item) = guard.get_mut(&bauth.uid) else {
-            // User was deleted between BAuth validation and here (race with delete_user)
+        let Some(item) = guard.get_mut(&auth.uid) else {
+            // User was deleted between DeviceAuth validation and here (race with delete_user)
+            return Status::Unauthorized;
+        };
+        let Some(idx) = item.devices.iter().position(|d| d.device.id == auth.device_id) else {
+            // Same race, with delete_device
             return Status::Unauthorized;
         };
         // Update last-seen metric
@@ -25,40 +29,64 @@ pub async fn api_up(bauth: bauth::BAuth, mut conn: Connection<DB>, context: &Sta
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap_or_default()
             .as_secs_f64();
-        prom::LAST_SEEN_TIMESTAMP.with_label_values(&[&uid_str]).set(now_ts);
+        prom::LAST_SEEN_TIMESTAMP
+            .with_label_values(&[&uid_str, &device_str])
+            .set(now_ts);
+
+        let local = item.user.local_time(std::time::SystemTime::now());
+        let in_maint = item.maintenance.iter().any(|w| w.covers(auth.device_id, &local));
+        let device = &mut item.devices[idx];
 
-        let in_maint = item
-            .user
-            .is_in_maintenance_window(notifications::utc_minute_of_day(now_ts as u64));
+        let (prev_status, prev_changed_at) = (device.uptime.status, device.uptime.state_changed_at);
+        let touch_result = device.uptime.touch(std::time::SystemTime::now(), &device.device);
+        let mut event = device.transition_event(prev_status, prev_changed_at, db::EventSource::Heartbeat);
+        // First ping after a maintenance-suppressed silence closes that period in the history
+        if let Some(since) = device.maint_suppressed_since.take()
+            && event.is_none()
+        {
+            let now = device.uptime.touched_at;
+            event = Some(device.event(
+                device.uptime.status,
+                now,
+                now.duration_since(since).unwrap_or_default(),
+                db::EventSource::Heartbeat,
+            ));
+        }
+        // Update uptime state metric
+        prom::UPTIME_STATE
+            .with_label_values(&[&uid_str, &device_str])
+            .set(i64::from(&device.uptime.status));
+        // An outage shorter than min_outage_secs was never notified, so neither is its end
+        let held_back = matches!(touch_result, db::TouchResult::Restored(_)) && device.uptime.held_outage_secs.take().is_some();
+        let started_flapping =
+            matches!(touch_result, db::TouchResult::Restored(_)) && !in_maint && device.record_flip(device.uptime.touched_at);
+        let device = device.clone();
 
-        match item.uptime.touch() {
+        let outbox = match touch_result {
+            _ if started_flapping => notifications::queue_flapping_notice(item, &device, db::UpStatus::Down),
+            db::TouchResult::Restored(_) if held_back || device.uptime.flapping_since.is_some() => Vec::new(),
             db::TouchResult::Connected if !in_maint => {
-                // Clone with Uninitialized status so dispatch uses "device connected" title
-                let mut notification_state = item.clone();
-                notification_state.uptime.status = db::UpStatus::Uninitialized;
-                tokio::spawn(notifications::dispatch_notifications(
-                    notification_state,
-                    context.inner().clone(),
-                    None,
-                ));
+                // Clone with Uninitialized status so the notification uses the "device connected" title
+                let mut notification_device = device.clone();
+                notification_device.uptime.status = db::UpStatus::Uninitialized;
+                notifications::queue_notifications(item, &notification_device, None, &schedule)
             }
             db::TouchResult::Restored(duration) if !in_maint => {
-                tokio::spawn(notifications::dispatch_notifications(
-                    item.clone(),
-                    context.inner().clone(),
-                    Some(duration),
-                ));
+                notifications::queue_notifications(item, &device, Some(duration), &schedule)
             }
-            _ => {} // NoChange, or suppressed by maintenance window
-        }
-        // Update uptime state metric
-        prom::UPTIME_STATE
-            .with_label_values(&[&uid_str])
-            .set(i64::from(&item.uptime.status));
-        item.uptime.clone()
+            _ => Vec::new(), // NoChange, or suppressed by maintenance window
+        };
+        (device.uptime, event, outbox)
     };
     // Persist uptime state to DB (outside the write lock to avoid blocking)
-    if let Err(err) = db::update_uptime_state(&mut conn, &uptime_snapshot).await {
+    let result = match &event {
+        Some(event) => db::record_transition(&mut conn, &uptime_snapshot, event, &outbox).await,
+        None => db::update_uptime_state(&mut conn, &uptime_snapshot).await,
+    };
+    if !outbox.is_empty() {
+        context.outbox_wake.notify_one();
+    }
+    if let Err(err) = result {
         warn!("Failed to persist uptime state: {err:?}");
     }
     Status::Ok
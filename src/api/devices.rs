@@ -0,0 +1,95 @@
+use crate::actions::{self, DeviceSettings};
+use crate::{DB, bauth, context::Context, db, prom};
+use rocket::State;
+use rocket::serde::json::{Json, Value, json};
+use rocket_db_pools::Connection;
+
+/// List the account's devices with their current uptime state
+#[get("/api/v1/me/devices")]
+pub async fn list_devices(bauth: bauth::BAuth, context: &State<Context>) -> Value {
+    match context.users.read().await.get(&bauth.uid) {
+        Some(state) => json!({"status": 200, "devices": state.devices}),
+        None => json!({"status": 404, "error": "User not found"}),
+    }
+}
+
+/// Register a new device (returns its heartbeat token)
+#[derive(rocket::serde::Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct NewDevice {
+    pub name: String,
+    pub up_delay: Option<i64>,
+}
+
+#[post("/api/v1/me/devices", data = "<opts>")]
+pub async fn create_device(
+    bauth: bauth::BAuth,
+    opts: Json<NewDevice>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    let opts = opts.into_inner();
+    match actions::create_device(bauth.uid, opts.name, opts.up_delay, &mut conn, context).await {
+        // Heartbeat token in plaintext, shown only this once
+        Ok((device, token)) => json!({"status": 200, "device": device, "access_token": token}),
+        Err(err) => json!({"status": 400, "error": err}),
+    }
+}
+
+/// Get a single device
+#[get("/api/v1/me/devices/<device_id>")]
+pub async fn get_device(bauth: bauth::BAuth, device_id: uuid::Uuid, context: &State<Context>) -> Value {
+    match context.users.read().await.get(&bauth.uid).and_then(|s| s.device(device_id)) {
+        Some(device) => json!({"status": 200, "device": device}),
+        None => json!({"status": 404, "error": "Device not found"}),
+    }
+}
+
+/// Rename a device or change its up_delay / outage settings
+#[patch("/api/v1/me/devices/<device_id>", data = "<opts>")]
+pub async fn update_device(
+    bauth: bauth::BAuth,
+    device_id: uuid::Uuid,
+    opts: Json<DeviceSettings>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    if context
+        .users
+        .read()
+        .await
+        .get(&bauth.uid)
+        .and_then(|s| s.device(device_id))
+        .is_none()
+    {
+        return json!({"status": 404, "error": "Device not found"});
+    }
+    match actions::update_device_settings(bauth.uid, device_id, &opts, &mut conn, context).await {
+        Ok(_) => match context.users.read().await.get(&bauth.uid).and_then(|s| s.device(device_id)) {
+            Some(device) => json!({"status": 200, "device": device}),
+            None => json!({"status": 404, "error": "Device not found"}),
+        },
+        Err(err) => json!({"status": 400, "error": err}),
+    }
+}
+
+/// Delete a device together with its uptime history
+#[delete("/api/v1/me/devices/<device_id>")]
+pub async fn delete_device(
+    bauth: bauth::BAuth,
+    device_id: uuid::Uuid,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    match db::delete_device(&mut conn, bauth.uid, device_id).await {
+        Ok(deleted) if deleted > 0 => {
+            context.remove_device(bauth.uid, device_id).await;
+            let (uid_str, device_str) = (bauth.uid.to_string(), device_id.to_string());
+            let _ = prom::UPTIME_STATE.remove_label_values(&[&uid_str, &device_str]);
+            let _ = prom::LAST_SEEN_TIMESTAMP.remove_label_values(&[&uid_str, &device_str]);
+            json!({"status": 200, "message": "Device deleted"})
+        }
+        Ok(_) => json!({"status": 404, "error": "Device not found"}),
+        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+    }
+}
@@ -0,0 +1,46 @@
+use crate::actions::{self, NewEscalationStep};
+use crate::{DB, bauth, context::Context, db};
+use rocket::State;
+use rocket::serde::json::{Json, Value, json};
+use rocket_db_pools::Connection;
+
+/// List the account's escalation steps, ordered by delay
+#[get("/api/v1/me/escalation")]
+pub async fn list_escalation_steps(bauth: bauth::BAuth, context: &State<Context>) -> Value {
+    match context.users.read().await.get(&bauth.uid) {
+        Some(state) => json!({"status": 200, "steps": state.escalation}),
+        None => json!({"status": 404, "error": "User not found"}),
+    }
+}
+
+/// Add a reminder for long outages, e.g. `{"delay_minutes": 60, "priority": "max", "channel_id": "..."}`
+#[post("/api/v1/me/escalation", data = "<opts>")]
+pub async fn create_escalation_step(
+    bauth: bauth::BAuth,
+    opts: Json<NewEscalationStep>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    match actions::create_escalation_step(bauth.uid, opts.into_inner(), &mut conn, context).await {
+        Ok(step) => json!({"status": 200, "step": step}),
+        Err(err) => json!({"status": 400, "error": err}),
+    }
+}
+
+/// Remove an escalation step
+#[delete("/api/v1/me/escalation/<step_id>")]
+pub async fn delete_escalation_step(
+    bauth: bauth::BAuth,
+    step_id: uuid::Uuid,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    match db::delete_escalation_step(&mut conn, bauth.uid, step_id).await {
+        Ok(deleted) if deleted > 0 => {
+            context.remove_escalation_step(bauth.uid, step_id).await;
+            json!({"status": 200, "message": "Escalation step deleted"})
+        }
+        Ok(_) => json!({"status": 404, "error": "Escalation step not found"}),
+        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{DB, bauth, context::Context, db, graph, ical, notifications, stats};
+use rocket::State;
+use rocket::http::ContentType;
+use rocket::serde::json::{Value, json};
+use rocket_db_pools::Connection;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Page size bounds for /api/v1/me/events
+const EVENTS_DEFAULT_LIMIT: i64 = 100;
+const EVENTS_MAX_LIMIT: i64 = 1000;
+/// Most recent transitions the calendar feed is built from, older ones are left out.
+const FEED_MAX_EVENTS: i64 = 10_000;
+
+/// Latest timestamp accepted in queries (9999-12-31), later ones overflow the database's microseconds.
+const MAX_EPOCH_SECS: u64 = 253_402_300_799;
+
+fn from_epoch_secs(secs: u64) -> Option<SystemTime> {
+    UNIX_EPOCH
+        .checked_add(Duration::from_secs(secs))
+        .filter(|_| secs <= MAX_EPOCH_SECS)
+}
+
+/// List recorded state transitions, newest first. `since`/`until` are Unix timestamps (seconds).
+/// Pass the returned `next_cursor` as `cursor` to fetch the next (older) page.
+/// Covers all devices of the account unless `device` is given.
+#[get("/api/v1/me/events?<device>&<since>&<until>&<limit>&<cursor>")]
+pub async fn get_events(
+    bauth: bauth::BAuth,
+    device: Option<uuid::Uuid>,
+    since: Option<u64>,
+    until: Option<u64>,
+    limit: Option<i64>,
+    cursor: Option<uuid::Uuid>,
+    mut conn: Connection<DB>,
+) -> Value {
+    let limit = limit.unwrap_or(EVENTS_DEFAULT_LIMIT);
+    if !(1..=EVENTS_MAX_LIMIT).contains(&limit) {
+        return json!({"status": 400, "error": format!("limit must be between 1 and {EVENTS_MAX_LIMIT}")});
+    }
+
+    let (since, until) = match (since.map(from_epoch_secs), until.map(from_epoch_secs)) {
+        (Some(None), _) | (_, Some(None)) => {
+            return json!({"status": 400, "error": format!("since and until must be Unix timestamps up to {MAX_EPOCH_SECS}")});
+        }
+        (since, until) => (since.flatten(), until.flatten()),
+    };
+
+    // Fetch one extra row to know whether another page exists
+    match db::get_uptime_events(&mut conn, bauth.uid, device, since, until, cursor, limit + 1).await {
+        Ok(mut events) => {
+            let next_cursor = if events.len() as i64 > limit {
+                events.truncate(limit as usize);
+                events.last().map(|e| e.id)
+            } else {
+                None
+            };
+            json!({"status": 200, "events": events, "next_cursor": next_cursor})
+        }
+        Err(diesel::result::Error::NotFound) => json!({"status": 400, "error": "Unknown cursor"}),
+        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+    }
+}
+
+/// Up/down/paused/maintenance time, outage count, longest outage, MTTR and MTBF over a window.
+/// `device` may be omitted for single-device accounts.
+#[get("/api/v1/me/stats?<device>&<window>")]
+pub async fn get_stats(
+    bauth: bauth::BAuth,
+    device: Option<uuid::Uuid>,
+    window: Option<&str>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    let window = window.unwrap_or("24h");
+    let Some(length) = stats::parse_window(window) else {
+        return json!({"status": 400, "error": "window must be one of: 24h, 7d, 30d"});
+    };
+    let current = match context.users.read().await.get(&bauth.uid).map(|s| s.resolve_device(device)) {
+        Some(Ok(item)) => item.uptime.clone(),
+        Some(Err(err)) => return json!({"status": 400, "error": err}),
+        None => return json!({"status": 404, "error": "User not found"}),
+    };
+
+    let end = SystemTime::now();
+    let start = end - length;
+    match db::get_uptime_window(&mut conn, current.device_id, start, end).await {
+        Ok((previous, events)) => {
+            let stats = stats::compute(start, end, previous.as_ref(), &events, &current);
+            json!({"status": 200, "device_id": current.device_id, "window": window, "stats": stats})
+        }
+        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+    }
+}
+
+/// Uptime graph over a window: a timeline bar of up/down/paused/maintenance time and the availability
+/// per hour (24h) or day (7d, 30d) in the account's time zone. `format` is svg (default) or png.
+#[get("/api/v1/me/graph?<device>&<window>&<format>")]
+pub async fn get_graph(
+    bauth: bauth::BAuth,
+    device: Option<uuid::Uuid>,
+    window: Option<&str>,
+    format: Option<&str>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Result<(ContentType, Vec<u8>), Value> {
+    let window = window.unwrap_or("24h");
+    let Some(length) = stats::parse_window(window) else {
+        return Err(json!({"status": 400, "error": "window must be one of: 24h, 7d, 30d"}));
+    };
+    let Some(format) = graph::Format::from_name(format.unwrap_or("svg")) else {
+        return Err(json!({"status": 400, "error": "format must be one of: svg, png"}));
+    };
+    let (current, tz) = match context.users.read().await.get(&bauth.uid) {
+        Some(state) => match state.resolve_device(device) {
+            Ok(item) => (item.uptime.clone(), state.user.tz()),
+            Err(err) => return Err(json!({"status": 400, "error": err})),
+        },
+        None => return Err(json!({"status": 404, "error": "User not found"})),
+    };
+
+    let end = SystemTime::now();
+    let start = end - length;
+    let (previous, events) = db::get_uptime_window(&mut conn, current.device_id, start, end)
+        .await
+        .map_err(|err| json!({"status": 500, "error": format!("{err:?}")}))?;
+    let spans = stats::timeline(start, end, previous.as_ref(), &events, &current);
+    let svg = graph::svg(window, start, end, &spans, tz);
+    match format {
+        graph::Format::Svg => Ok((ContentType::SVG, svg.into_bytes())),
+        graph::Format::Png => match graph::png(&svg) {
+            Ok(png) => Ok((ContentType::PNG, png)),
+            Err(err) => Err(json!({"status": 500, "error": err})),
+        },
+    }
+}
+
+/// Recorded outages of the last 90 days and the maintenance windows as an iCalendar feed, to
+/// subscribe to in calendar apps. Calendar apps can't send headers, so the token may also be given
+/// as `?token=`.
+#[get("/api/v1/me/outages.ics")]
+pub async fn get_outages_ics(
+    auth: bauth::FeedAuth,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Result<(ContentType, String), Value> {
+    let now = SystemTime::now();
+    let mut events = db::get_uptime_events(
+        &mut conn,
+        auth.uid,
+        None,
+        Some(now - ical::FEED_HISTORY),
+        None,
+        None,
+        FEED_MAX_EVENTS,
+    )
+    .await
+    .map_err(|err| json!({"status": 500, "error": format!("{err:?}")}))?;
+    events.reverse();
+    match context.users.read().await.get(&auth.uid) {
+        Some(state) => {
+            // @NOTE: RFC 5545 defaults to UTF-8, browsers and HTTP libraries don't.
+            let content_type = ContentType::new("text", "calendar").with_params(("charset", "utf-8"));
+            Ok((content_type, notifications::outages_calendar(state, &events, now)))
+        }
+        None => Err(json!({"status": 404, "error": "User not found"})),
+    }
+}
@@ -0,0 +1,115 @@
+use crate::actions::{self, LOCAL_DATETIME_FORMAT, MaintenanceWindowSpec, WEEKDAYS};
+use crate::{DB, bauth, context::Context, db};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use rocket::State;
+use rocket::serde::json::{Json, Value, json};
+use rocket_db_pools::Connection;
+use std::time::SystemTime;
+
+/// A window with its times local to the account's time zone, and whether it is in effect right now.
+fn window_json(window: &db::MaintenanceWindow, local: &DateTime<Tz>) -> Value {
+    let weekdays = window.weekdays.map(|days| {
+        (0..7)
+            .filter(|day| days & (1 << day) != 0)
+            .map(|day| WEEKDAYS[day])
+            .collect::<Vec<_>>()
+    });
+    let format = |t: Option<SystemTime>| {
+        t.map(|t| {
+            DateTime::<Utc>::from(t)
+                .with_timezone(&local.timezone())
+                .format(LOCAL_DATETIME_FORMAT)
+                .to_string()
+        })
+    };
+    json!({
+        "id": window.id,
+        "device_id": window.device_id,
+        "label": window.label,
+        "weekdays": weekdays,
+        "start_minute": window.start_minute,
+        "end_minute": window.end_minute,
+        "starts_at": format(window.starts_at),
+        "ends_at": format(window.ends_at),
+        "active": window.is_active(local),
+    })
+}
+
+/// Response with one window, or the error of the action that produced it
+fn window_response(result: Result<db::MaintenanceWindow, String>, user: Option<db::User>) -> Value {
+    match (result, user) {
+        (Ok(window), Some(user)) => {
+            let local = user.local_time(SystemTime::now());
+            json!({"status": 200, "timezone": user.timezone, "window": window_json(&window, &local)})
+        }
+        (Ok(_), None) => json!({"status": 404, "error": "User not found"}),
+        (Err(err), _) => json!({"status": 400, "error": err}),
+    }
+}
+
+/// List the account's maintenance windows, times are local to the returned time zone
+#[get("/api/v1/me/maintenance")]
+pub async fn list_maintenance_windows(bauth: bauth::BAuth, context: &State<Context>) -> Value {
+    match context.users.read().await.get(&bauth.uid) {
+        Some(state) => {
+            let local = state.user.local_time(SystemTime::now());
+            let windows: Vec<Value> = state.maintenance.iter().map(|w| window_json(w, &local)).collect();
+            json!({"status": 200, "timezone": state.user.timezone, "windows": windows})
+        }
+        None => json!({"status": 404, "error": "User not found"}),
+    }
+}
+
+/// Add a maintenance window, e.g. `{"weekdays": ["tue"], "start_minute": 600, "end_minute": 840, "label": "grid works"}`
+/// or `{"starts_at": "2026-10-20T09:00", "ends_at": "2026-10-20T17:00", "device_id": "..."}`
+#[post("/api/v1/me/maintenance", data = "<spec>")]
+pub async fn create_maintenance_window(
+    bauth: bauth::BAuth,
+    spec: Json<MaintenanceWindowSpec>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    let result = actions::create_maintenance_window(bauth.uid, spec.into_inner(), &mut conn, context).await;
+    let user = context.users.read().await.get(&bauth.uid).map(|s| s.user.clone());
+    window_response(result, user)
+}
+
+/// Replace a maintenance window (same body as when adding one)
+#[patch("/api/v1/me/maintenance/<window_id>", data = "<spec>")]
+pub async fn update_maintenance_window(
+    bauth: bauth::BAuth,
+    window_id: uuid::Uuid,
+    spec: Json<MaintenanceWindowSpec>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    let current = match context.users.read().await.get(&bauth.uid) {
+        Some(state) => match state.maintenance.iter().find(|w| w.id == window_id) {
+            Some(window) => window.clone(),
+            None => return json!({"status": 404, "error": "Maintenance window not found"}),
+        },
+        None => return json!({"status": 404, "error": "User not found"}),
+    };
+    let result = actions::update_maintenance_window(current, spec.into_inner(), &mut conn, context).await;
+    let user = context.users.read().await.get(&bauth.uid).map(|s| s.user.clone());
+    window_response(result, user)
+}
+
+/// Remove a maintenance window
+#[delete("/api/v1/me/maintenance/<window_id>")]
+pub async fn delete_maintenance_window(
+    bauth: bauth::BAuth,
+    window_id: uuid::Uuid,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    match db::delete_maintenance_window(&mut conn, bauth.uid, window_id).await {
+        Ok(deleted) if deleted > 0 => {
+            context.remove_maintenance_window(bauth.uid, window_id).await;
+            json!({"status": 200, "message": "Maintenance window deleted"})
+        }
+        Ok(_) => json!({"status": 404, "error": "Maintenance window not found"}),
+        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+    }
+}
@@ -1,7 +1,25 @@
 mod admin;
+mod channels;
 mod core;
+mod devices;
+mod escalation;
+mod history;
+mod maintenance;
+mod pages;
+mod schedule;
+mod status;
+mod tokens;
 mod user;
 
 pub use admin::*;
+pub use channels::*;
 pub use core::*;
+pub use devices::*;
+pub use escalation::*;
+pub use history::*;
+pub use maintenance::*;
+pub use pages::*;
+pub use schedule::*;
+pub use status::*;
+pub use tokens::*;
 pub use user::*;
@@ -0,0 +1,297 @@
+use crate::actions::{self, StatusPageSpec};
+use crate::status_page::{self, Monitor, Page};
+use crate::{DB, bauth, context::Context, db, graph, ical, notifications, stats};
+use chrono_tz::Tz;
+use rocket::State;
+use rocket::http::{Header, Status};
+use rocket::response::content::RawHtml;
+use rocket::serde::json::{Json, Value, json};
+use rocket_db_pools::Connection;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn epoch_secs(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// A page as its owner sees it, with the ids of the devices it lists.
+fn page_json(page: &db::StatusPage, monitors: &[db::StatusPageMonitor]) -> Value {
+    let monitors: Vec<Value> = monitors
+        .iter()
+        .filter(|m| m.page_id == page.id)
+        .map(|m| json!({"device_id": m.device_id, "name": m.display_name}))
+        .collect();
+    json!({
+        "id": page.id,
+        "slug": page.slug,
+        "title": page.title,
+        "visibility": page.visibility,
+        "path": status_page::page_path(&page.slug),
+        "url": status_page::page_url(&page.slug),
+        "monitors": monitors,
+    })
+}
+
+/// Response with one page, or the error of the action that produced it
+fn page_response(result: Result<(db::StatusPage, Vec<db::StatusPageMonitor>), String>) -> Value {
+    match result {
+        Ok((page, monitors)) => json!({"status": 200, "page": page_json(&page, &monitors)}),
+        Err(err) => json!({"status": 400, "error": err}),
+    }
+}
+
+/// List the account's status pages
+#[get("/api/v1/me/pages")]
+pub async fn list_pages(bauth: bauth::BAuth, mut conn: Connection<DB>) -> Value {
+    let pages = match db::get_status_pages_for_user(&mut conn, bauth.uid).await {
+        Ok(pages) => pages,
+        Err(err) => return json!({"status": 500, "error": format!("{err:?}")}),
+    };
+    let ids: Vec<db::ID> = pages.iter().map(|p| p.id).collect();
+    match db::get_status_page_monitors(&mut conn, &ids).await {
+        Ok(monitors) => {
+            let pages: Vec<Value> = pages.iter().map(|p| page_json(p, &monitors)).collect();
+            json!({"status": 200, "pages": pages})
+        }
+        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+    }
+}
+
+/// Add a status page, e.g. `{"slug": "shevchenka-12", "title": "Shevchenka St. 12", "visibility": "unlisted",
+/// "monitors": [{"device_id": "...", "name": "Apt. 1"}, {"device_id": "..."}]}`
+#[post("/api/v1/me/pages", data = "<spec>")]
+pub async fn create_page(
+    bauth: bauth::BAuth,
+    spec: Json<StatusPageSpec>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    page_response(actions::create_status_page(bauth.uid, spec.into_inner(), &mut conn, context).await)
+}
+
+/// Replace a status page (same body as when adding one)
+#[patch("/api/v1/me/pages/<page_id>", data = "<spec>")]
+pub async fn update_page(
+    bauth: bauth::BAuth,
+    page_id: uuid::Uuid,
+    spec: Json<StatusPageSpec>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    let current = match db::get_status_pages_for_user(&mut conn, bauth.uid).await {
+        Ok(pages) => match pages.into_iter().find(|p| p.id == page_id) {
+            Some(page) => page,
+            None => return json!({"status": 404, "error": "Status page not found"}),
+        },
+        Err(err) => return json!({"status": 500, "error": format!("{err:?}")}),
+    };
+    page_response(actions::update_status_page(current, spec.into_inner(), &mut conn, context).await)
+}
+
+/// Remove a status page
+#[delete("/api/v1/me/pages/<page_id>")]
+pub async fn delete_page(bauth: bauth::BAuth, page_id: uuid::Uuid, mut conn: Connection<DB>) -> Value {
+    match db::delete_status_page(&mut conn, bauth.uid, page_id).await {
+        Ok(deleted) if deleted > 0 => json!({"status": 200, "message": "Status page deleted"}),
+        Ok(_) => json!({"status": 404, "error": "Status page not found"}),
+        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+    }
+}
+
+/// Directory of public status pages
+#[get("/api/v1/pages")]
+pub async fn list_public_pages(_rl: bauth::RateLimitGuard, mut conn: Connection<DB>) -> Value {
+    match db::get_public_status_pages(&mut conn).await {
+        Ok(pages) => {
+            let pages: Vec<Value> = pages
+                .iter()
+                .map(|p| json!({"slug": p.slug, "title": p.title, "path": status_page::page_path(&p.slug)}))
+                .collect();
+            json!({"status": 200, "pages": pages})
+        }
+        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+    }
+}
+
+/// What visitors of a page get to see: its title, the owner's language and time zone, and the
+/// devices with their display names. Nothing that identifies the owner or the devices.
+struct Shared {
+    title: String,
+    language: String,
+    tz: Tz,
+    devices: Vec<(String, db::UptimeState)>,
+}
+
+/// Everything a visitor sees over `[start, end)` of one device.
+struct Window {
+    spans: Vec<stats::Span>,
+    stats: stats::UptimeStats,
+    outages: Vec<ical::Outage>,
+}
+
+impl Shared {
+    /// The page at `slug`, None unless it exists and isn't private.
+    async fn load(conn: &mut Connection<DB>, context: &Context, slug: &str) -> Result<Option<Shared>, String> {
+        let page = match db::get_status_page_by_slug(conn, slug).await {
+            Ok(Some(page)) if page.visibility != db::PageVisibility::Private => page,
+            Ok(_) => return Ok(None),
+            Err(err) => return Err(format!("{err:?}")),
+        };
+        let monitors = db::get_status_page_monitors(conn, &[page.id])
+            .await
+            .map_err(|err| format!("{err:?}"))?;
+        let users = context.users.read().await;
+        let Some(owner) = users.get(&page.user_id) else {
+            return Ok(None);
+        };
+        // @NOTE: Admins may list devices of other accounts, so they are looked up everywhere.
+        let devices = monitors
+            .into_iter()
+            .filter_map(|m| {
+                users
+                    .values()
+                    .find_map(|state| state.device(m.device_id))
+                    .map(|device| (m.display_name, device.uptime.clone()))
+            })
+            .collect();
+        Ok(Some(Shared {
+            title: page.title,
+            language: owner.user.language_code.clone(),
+            tz: owner.user.tz(),
+            devices,
+        }))
+    }
+
+    async fn window(
+        conn: &mut Connection<DB>,
+        uptime: &db::UptimeState,
+        start: SystemTime,
+        end: SystemTime,
+    ) -> Result<Window, String> {
+        let (previous, events) = db::get_uptime_window(conn, uptime.device_id, start, end)
+            .await
+            .map_err(|err| format!("{err:?}"))?;
+        Ok(Window {
+            spans: stats::timeline(start, end, previous.as_ref(), &events, uptime),
+            stats: stats::compute(start, end, previous.as_ref(), &events, uptime),
+            outages: ical::outages(&events, &[uptime]),
+        })
+    }
+}
+
+/// A status page: current status of its devices under their display names, their uptime graphs and
+/// recent outages over `window` (24h, 7d or 30d). Same layout as the account's status page, in the
+/// language and time zone of the page's owner. Private pages are not found.
+#[get("/api/v1/pages/<slug>?<window>")]
+pub async fn view_page(
+    _rl: bauth::RateLimitGuard,
+    slug: &str,
+    window: Option<&str>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> (Status, RawHtml<String>) {
+    let shared = match Shared::load(&mut conn, context, slug).await {
+        Ok(Some(shared)) => shared,
+        Ok(None) => {
+            let message = notifications::localize("en", "status-page-not-found");
+            return (Status::NotFound, RawHtml(graph::escape(&message)));
+        }
+        Err(err) => return (Status::InternalServerError, RawHtml(graph::escape(&err))),
+    };
+    let window = window.filter(|w| stats::parse_window(w).is_some()).unwrap_or("24h");
+    let now = SystemTime::now();
+    let start = now - stats::parse_window(window).unwrap_or_default();
+
+    let mut monitors = Vec::new();
+    for (name, uptime) in shared.devices {
+        let data = match Shared::window(&mut conn, &uptime, start, now).await {
+            Ok(data) => data,
+            Err(err) => return (Status::InternalServerError, RawHtml(graph::escape(&err))),
+        };
+        monitors.push(Monitor {
+            name: Some(name),
+            graph: graph::svg(window, start, now, &data.spans, shared.tz),
+            outages: data.outages,
+            uptime,
+        });
+    }
+    let page = Page {
+        title: &shared.title,
+        language: &shared.language,
+        tz: shared.tz,
+        window,
+        now,
+    };
+    (Status::Ok, RawHtml(page.render(&monitors)))
+}
+
+/// JSON that other sites may fetch from their own pages.
+#[derive(Responder)]
+pub struct Embeddable {
+    inner: Value,
+    cors: Header<'static>,
+}
+
+impl From<Value> for Embeddable {
+    fn from(inner: Value) -> Self {
+        Embeddable {
+            inner,
+            cors: Header::new("Access-Control-Allow-Origin", "*"),
+        }
+    }
+}
+
+/// JSON variant of a status page for embedding. Times are Unix timestamps (seconds), `since` is
+/// when the current status began, `availability` is the up time percentage over `window` and
+/// `outages` are the most recent ones in it, newest first.
+#[get("/api/v1/pages/<slug>/json?<window>")]
+pub async fn view_page_json(
+    _rl: bauth::RateLimitGuard,
+    slug: &str,
+    window: Option<&str>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Embeddable {
+    let shared = match Shared::load(&mut conn, context, slug).await {
+        Ok(Some(shared)) => shared,
+        Ok(None) => return json!({"status": 404, "error": "Status page not found"}).into(),
+        Err(err) => return json!({"status": 500, "error": err}).into(),
+    };
+    let window = window.unwrap_or("24h");
+    let Some(length) = stats::parse_window(window) else {
+        return json!({"status": 400, "error": "window must be one of: 24h, 7d, 30d"}).into();
+    };
+    let now = SystemTime::now();
+    let start = now - length;
+
+    let mut monitors = Vec::new();
+    for (name, uptime) in shared.devices {
+        let data = match Shared::window(&mut conn, &uptime, start, now).await {
+            Ok(data) => data,
+            Err(err) => return json!({"status": 500, "error": err}).into(),
+        };
+        let known = uptime.status != db::UpStatus::Uninitialized;
+        let outages: Vec<Value> = data
+            .outages
+            .iter()
+            .rev()
+            .take(status_page::MAX_OUTAGES)
+            .map(|o| json!({"start": epoch_secs(o.start), "end": o.end.map(epoch_secs)}))
+            .collect();
+        monitors.push(json!({
+            "name": name,
+            "status": status_page::status_name(uptime.status),
+            "since": known.then(|| epoch_secs(uptime.state_changed_at)),
+            "last_heartbeat": known.then(|| epoch_secs(uptime.touched_at)),
+            "availability": data.stats.availability,
+            "outages": outages,
+        }));
+    }
+    json!({
+        "status": 200,
+        "title": shared.title,
+        "window": window,
+        "updated_at": epoch_secs(now),
+        "monitors": monitors,
+    })
+    .into()
+}
@@ -0,0 +1,72 @@
+use crate::actions::{self, LOCAL_DATETIME_FORMAT};
+use crate::schedule::Format;
+use crate::{DB, bauth, context::Context};
+use chrono::{DateTime, Utc};
+use rocket::State;
+use rocket::data::{Data, ToByteUnit};
+use rocket::serde::json::{Value, json};
+use rocket_db_pools::Connection;
+use std::time::SystemTime;
+
+/// Import a planned outage schedule from the request body (admin only). `format` is json, csv or ics,
+/// `queue` names the queue of ICS events without CATEGORIES. Slots already stored for the time the
+/// import covers are replaced, per queue.
+#[post("/api/v1/admin/schedule?<format>&<queue>", data = "<body>")]
+pub async fn import_schedule(
+    _admin: bauth::AdminAuth,
+    format: &str,
+    queue: Option<&str>,
+    body: Data<'_>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    let Some(format) = Format::from_name(format) else {
+        return json!({"status": 400, "error": format!("Unknown format '{format}', expected json, csv or ics")});
+    };
+    let body = match body.open(1.mebibytes()).into_string().await {
+        Ok(body) if body.is_complete() => body.into_inner(),
+        Ok(_) => return json!({"status": 400, "error": "The schedule is larger than 1 MiB"}),
+        Err(err) => return json!({"status": 400, "error": format!("Failed to read the schedule: {err}")}),
+    };
+    match actions::import_schedule(format, &body, queue, &mut conn, context).await {
+        Ok(queues) => json!({"status": 200, "imported": queues.values().sum::<usize>(), "queues": queues}),
+        Err(err) => json!({"status": 400, "error": err}),
+    }
+}
+
+/// The account's outage queue with its upcoming planned outages, local to the account's time zone.
+/// Also lists the queues the schedule knows, to pick one from.
+#[get("/api/v1/me/schedule")]
+pub async fn get_schedule(bauth: bauth::BAuth, context: &State<Context>) -> Value {
+    let schedule = context.schedule.read().await;
+    let Some(user) = context.users.read().await.get(&bauth.uid).map(|s| s.user.clone()) else {
+        return json!({"status": 404, "error": "User not found"});
+    };
+    let now = SystemTime::now();
+    let tz = user.tz();
+    let format = |t: SystemTime| {
+        DateTime::<Utc>::from(t)
+            .with_timezone(&tz)
+            .format(LOCAL_DATETIME_FORMAT)
+            .to_string()
+    };
+    let (planned_until, outages) = match &user.outage_queue {
+        Some(queue) => {
+            let outages: Vec<Value> = schedule
+                .upcoming(queue, now)
+                .iter()
+                .map(|(start, end)| json!({"starts_at": format(*start), "ends_at": format(*end)}))
+                .collect();
+            (schedule.planned_until(queue, now).map(format), outages)
+        }
+        None => (None, Vec::new()),
+    };
+    json!({
+        "status": 200,
+        "queue": user.outage_queue,
+        "timezone": user.timezone,
+        "planned_until": planned_until,
+        "outages": outages,
+        "queues": schedule.queues().collect::<Vec<_>>(),
+    })
+}
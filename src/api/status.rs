@@ -0,0 +1,76 @@
+use crate::status_page::{self, Monitor, Page};
+use crate::{DB, bauth, context::Context, db, graph, ical, notifications, stats};
+use rocket::State;
+use rocket::http::Status;
+use rocket::response::content::RawHtml;
+use rocket::serde::json::{Value, json};
+use rocket_db_pools::Connection;
+use std::time::SystemTime;
+
+/// Link to the account's status page. `url` is null unless the server knows its public address
+/// (OUBOT_PUBLIC_URL), notifications then link to it when clicked.
+#[get("/api/v1/me/status-page")]
+pub async fn get_status_page(bauth: bauth::BAuth, context: &State<Context>) -> Value {
+    match context.users.read().await.get(&bauth.uid) {
+        Some(state) => json!({
+            "status": 200,
+            "path": status_page::path(&state.user.status_page_key),
+            "url": status_page::url(&state.user),
+        }),
+        None => json!({"status": 404, "error": "User not found"}),
+    }
+}
+
+/// The account's status page: current status of every device, its uptime graph and recent outages
+/// over `window` (24h, 7d or 30d). Server-rendered HTML without scripts, in the account's language.
+/// @NOTE: Anyone with the link can see the page, replace it with
+///  `POST /api/v1/me/regenerate-token?kind=status_page`.
+#[get("/api/v1/status/<key>?<window>")]
+pub async fn view_status_page(
+    _rl: bauth::RateLimitGuard,
+    key: &str,
+    window: Option<&str>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> (Status, RawHtml<String>) {
+    let Some((user, devices)) = context
+        .users
+        .read()
+        .await
+        .values()
+        .find(|s| s.user.status_page_key == key)
+        .map(|s| (s.user.clone(), s.devices.clone()))
+    else {
+        // @NOTE: The owner is unknown here, so there is no language to answer in.
+        let message = notifications::localize("en", "status-page-not-found");
+        return (Status::NotFound, RawHtml(graph::escape(&message)));
+    };
+    let window = window.filter(|w| stats::parse_window(w).is_some()).unwrap_or("24h");
+    let length = stats::parse_window(window).unwrap_or_default();
+
+    let tz = user.tz();
+    let now = SystemTime::now();
+    let start = now - length;
+    let mut monitors = Vec::new();
+    for device in &devices {
+        let (previous, events) = match db::get_uptime_window(&mut conn, device.device.id, start, now).await {
+            Ok(window) => window,
+            Err(err) => return (Status::InternalServerError, RawHtml(graph::escape(&format!("{err:?}")))),
+        };
+        let spans = stats::timeline(start, now, previous.as_ref(), &events, &device.uptime);
+        monitors.push(Monitor {
+            name: (devices.len() > 1).then(|| device.device.name.clone()),
+            uptime: device.uptime.clone(),
+            graph: graph::svg(window, start, now, &spans, tz),
+            outages: ical::outages(&events, &[&device.uptime]),
+        });
+    }
+    let page = Page {
+        title: &notifications::localize(&user.language_code, "status-page-title"),
+        language: &user.language_code,
+        tz,
+        window,
+        now,
+    };
+    (Status::Ok, RawHtml(page.render(&monitors)))
+}
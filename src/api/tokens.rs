@@ -0,0 +1,39 @@
+use crate::actions::{self, NewApiToken};
+use crate::{DB, bauth, context::Context, db};
+use rocket::State;
+use rocket::serde::json::{Json, Value, json};
+use rocket_db_pools::Connection;
+
+/// List the account's named tokens (never the tokens themselves)
+#[get("/api/v1/me/tokens")]
+pub async fn list_tokens(bauth: bauth::BAuth, context: &State<Context>) -> Value {
+    json!({"status": 200, "tokens": context.api_tokens_for(bauth.uid).await})
+}
+
+/// Create a named account or heartbeat token, optionally expiring
+#[post("/api/v1/me/tokens", data = "<opts>")]
+pub async fn create_token(
+    bauth: bauth::BAuth,
+    opts: Json<NewApiToken>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    match actions::create_api_token(bauth.uid, opts.into_inner(), &mut conn, context).await {
+        // Plaintext token, shown only this once
+        Ok((token, plaintext)) => json!({"status": 200, "token": token, "access_token": plaintext}),
+        Err(err) => json!({"status": 400, "error": err}),
+    }
+}
+
+/// Revoke a named token, it stops working immediately
+#[delete("/api/v1/me/tokens/<token_id>")]
+pub async fn delete_token(bauth: bauth::BAuth, token_id: uuid::Uuid, mut conn: Connection<DB>, context: &State<Context>) -> Value {
+    match db::delete_api_token(&mut conn, bauth.uid, token_id).await {
+        Ok(deleted) if deleted > 0 => {
+            context.remove_api_token(bauth.uid, token_id).await;
+            json!({"status": 200, "message": "Token revoked"})
+        }
+        Ok(_) => json!({"status": 404, "error": "Token not found"}),
+        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+    }
+}
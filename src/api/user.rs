@@ -1,5 +1,5 @@
 use crate::actions::{self, NewUser};
-use crate::{DB, bauth, context::Context, db, prom};
+use crate::{DB, bauth, context::Context, db, notifications, prom, status_page, tokens};
 use rocket::State;
 use rocket::serde::json::{Json, Value, json};
 use rocket_db_pools::Connection;
@@ -13,14 +13,24 @@ pub async fn create_user(
     context: &State<Context>,
 ) -> Value {
     match actions::create_user(&opts, &mut conn, context).await {
-        Ok(state) => {
+        Ok(created) => {
+            let state = created.state;
             let uid_str = state.user.id.to_string();
             prom::ACTIVE_USERS.inc();
-            prom::UPTIME_STATE
-                .with_label_values(&[&uid_str])
-                .set(i64::from(&state.uptime.status));
-            prom::LAST_SEEN_TIMESTAMP.with_label_values(&[&uid_str]).set(0.0);
-            json!({"status": 200, "state": state})
+            for device in &state.devices {
+                let device_str = device.device.id.to_string();
+                prom::UPTIME_STATE
+                    .with_label_values(&[&uid_str, &device_str])
+                    .set(i64::from(&device.uptime.status));
+                prom::LAST_SEEN_TIMESTAMP.with_label_values(&[&uid_str, &device_str]).set(0.0);
+            }
+            // @NOTE: The only time these tokens are ever returned, the DB only keeps their hashes.
+            json!({
+                "status": 200,
+                "state": state,
+                "access_token": created.access_token,
+                "heartbeat_token": created.heartbeat_token,
+            })
         }
         Err(err) => json!({"status": 400, "error": err}),
     }
@@ -35,18 +45,37 @@ pub async fn get_me(bauth: bauth::BAuth, context: &State<Context>) -> Value {
     }
 }
 
-/// Regenerate access token (for Pico W client)
-#[post("/api/v1/me/regenerate-token")]
-pub async fn regenerate_token(bauth: bauth::BAuth, mut conn: Connection<DB>, context: &State<Context>) -> Value {
-    match db::regenerate_user_token(&mut conn, bauth.uid).await {
+/// Regenerate a token. `kind` is "account" (default, the management API token), "heartbeat"
+/// (the token a device pings with; `device` may be omitted for single-device accounts) or
+/// "status_page" (the key in the status page link, the old link stops working).
+/// New tokens are returned in plaintext this once, only their hashes are stored.
+/// @NOTE: Rotating one kind never touches the other. Accounts migrated from a single shared
+///  token get them split this way: the old value stays valid for the other kind.
+#[post("/api/v1/me/regenerate-token?<kind>&<device>")]
+pub async fn regenerate_token(
+    bauth: bauth::BAuth,
+    kind: Option<&str>,
+    device: Option<uuid::Uuid>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    match kind.unwrap_or("account") {
+        "account" => regenerate_account_token(bauth.uid, &mut conn, context).await,
+        "heartbeat" => regenerate_heartbeat_token(bauth.uid, device, &mut conn, context).await,
+        "status_page" => regenerate_status_page_key(bauth.uid, &mut conn, context).await,
+        _ => json!({"status": 400, "error": "kind must be one of: account, heartbeat, status_page"}),
+    }
+}
+
+async fn regenerate_account_token(uid: db::ID, conn: &mut Connection<DB>, context: &Context) -> Value {
+    match db::regenerate_user_token(conn, uid).await {
         Ok(new_token) => {
             // Update in-memory state
-            let old_token = {
+            let new_hash = tokens::hash_token(&new_token);
+            let old_hash = {
                 let mut users = context.users.write().await;
-                if let Some(state) = users.get_mut(&bauth.uid) {
-                    let old = state.user.access_token.clone();
-                    state.user.access_token = new_token.clone();
-                    old
+                if let Some(state) = users.get_mut(&uid) {
+                    std::mem::replace(&mut state.user.token_hash, new_hash.clone())
                 } else {
                     return json!({"status": 404, "error": "User not found"});
                 }
@@ -54,60 +83,82 @@ pub async fn regenerate_token(bauth: bauth::BAuth, mut conn: Connection<DB>, con
             // Update tokens map
             {
                 let mut tokens = context.tokens.write().await;
-                tokens.remove(&old_token);
-                tokens.insert(new_token.clone(), bauth.uid);
+                tokens.remove(&old_hash);
+                tokens.insert(new_hash, uid);
             }
-            json!({"status": 200, "access_token": new_token})
+            json!({"status": 200, "kind": "account", "access_token": new_token})
         }
         Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
     }
 }
 
-/// Get ntfy notification settings
-#[get("/api/v1/me/ntfy")]
-pub async fn get_ntfy_settings(bauth: bauth::BAuth, context: &State<Context>) -> Value {
-    match context.users.read().await.get(&bauth.uid) {
-        Some(state) => json!({
-            "status": 200,
-            "ntfy": {
-                "enabled": state.ntfy.enabled,
-                "topic": state.ntfy.topic,
-                "username": state.ntfy.username,
-                "password": state.ntfy.password,
+async fn regenerate_heartbeat_token(uid: db::ID, device: Option<db::ID>, conn: &mut Connection<DB>, context: &Context) -> Value {
+    let device_id = match context.users.read().await.get(&uid).map(|s| s.resolve_device(device)) {
+        Some(Ok(item)) => item.device.id,
+        Some(Err(err)) => return json!({"status": 400, "error": err}),
+        None => return json!({"status": 404, "error": "User not found"}),
+    };
+    match db::regenerate_device_token(conn, uid, device_id).await {
+        Ok(new_token) => {
+            let new_hash = tokens::hash_token(&new_token);
+            let old_hash = {
+                let mut users = context.users.write().await;
+                match users.get_mut(&uid).and_then(|s| s.device_mut(device_id)) {
+                    Some(item) => std::mem::replace(&mut item.device.token_hash, new_hash.clone()),
+                    None => return json!({"status": 404, "error": "Device not found"}),
+                }
+            };
+            {
+                let mut device_tokens = context.device_tokens.write().await;
+                device_tokens.remove(&old_hash);
+                device_tokens.insert(new_hash, (uid, device_id));
             }
-        }),
-        None => json!({"status": 404, "error": "User not found"}),
+            json!({"status": 200, "kind": "heartbeat", "device_id": device_id, "access_token": new_token})
+        }
+        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
     }
 }
 
-/// Update ntfy notification settings (enable/disable)
-#[derive(rocket::serde::Deserialize)]
-#[serde(crate = "rocket::serde")]
-pub struct UpdateNtfy {
-    pub enabled: bool,
+async fn regenerate_status_page_key(uid: db::ID, conn: &mut Connection<DB>, context: &Context) -> Value {
+    match db::regenerate_status_page_key(conn, uid).await {
+        Ok(new_key) => match context.users.write().await.get_mut(&uid) {
+            Some(state) => {
+                state.user.status_page_key = new_key;
+                json!({
+                    "status": 200,
+                    "kind": "status_page",
+                    "path": status_page::path(&state.user.status_page_key),
+                    "url": status_page::url(&state.user),
+                })
+            }
+            None => json!({"status": 404, "error": "User not found"}),
+        },
+        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+    }
 }
 
-#[patch("/api/v1/me/ntfy", data = "<opts>")]
-pub async fn update_ntfy_settings(
-    bauth: bauth::BAuth,
-    opts: Json<UpdateNtfy>,
-    mut conn: Connection<DB>,
-    context: &State<Context>,
-) -> Value {
-    let ntfy_id = match context.users.read().await.get(&bauth.uid) {
-        Some(state) => state.ntfy.id,
-        None => return json!({"status": 404, "error": "User not found"}),
-    };
-
-    match db::update_ntfy_enabled(&mut conn, ntfy_id, opts.enabled).await {
-        Ok(_) => {
-            // Update in-memory state
-            if let Some(state) = context.users.write().await.get_mut(&bauth.uid) {
-                state.ntfy.enabled = opts.enabled;
-            }
-            json!({"status": 200, "enabled": opts.enabled})
+/// Get the managed ntfy account, `enabled` reflects its channel (toggled via /api/v1/me/channels/<id>)
+#[get("/api/v1/me/ntfy")]
+pub async fn get_ntfy_settings(bauth: bauth::BAuth, context: &State<Context>) -> Value {
+    match context.users.read().await.get(&bauth.uid) {
+        Some(state) => {
+            // The channel of the managed account, not one on the user's own ntfy server
+            let channel = state
+                .channels
+                .iter()
+                .find(|c| c.kind == db::ChannelKind::Ntfy && c.kind.is_managed(Some(&c.config)));
+            json!({
+                "status": 200,
+                "ntfy": {
+                    "enabled": channel.is_some_and(|c| c.enabled),
+                    "channel_id": channel.map(|c| c.id),
+                    "topic": state.ntfy.topic,
+                    "username": state.ntfy.username,
+                    "password": state.ntfy.password,
+                }
+            })
         }
-        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+        None => json!({"status": 404, "error": "User not found"}),
     }
 }
 
@@ -156,127 +207,126 @@ pub async fn update_language(
 
 // Monitoring control endpoints
 
-/// Pause monitoring — freezes state, suppresses all notifications.
+/// Pause or unpause the given device, or every device of the account if none is given.
 /// @NOTE: Persists to DB inside the write lock to prevent the race where
 ///  background_handle_down's deferred DB write could overwrite Paused with Down.
-#[post("/api/v1/me/pause")]
-pub async fn pause_monitoring(bauth: bauth::BAuth, mut conn: Connection<DB>, context: &State<Context>) -> Value {
+async fn set_paused(uid: db::ID, device_id: Option<db::ID>, pause: bool, conn: &mut Connection<DB>, context: &Context) -> Value {
     let mut guard = context.users.write().await;
-    let Some(item) = guard.get_mut(&bauth.uid) else {
+    let Some(item) = guard.get_mut(&uid) else {
         return json!({"status": 404, "error": "User not found"});
     };
-    if let Err(err) = item.uptime.pause() {
-        return json!({"status": 400, "error": err});
+    if let Some(id) = device_id
+        && item.device(id).is_none()
+    {
+        return json!({"status": 404, "error": "Device not found"});
     }
-    prom::UPTIME_STATE
-        .with_label_values(&[&bauth.uid.to_string()])
-        .set(i64::from(&item.uptime.status));
-    if let Err(err) = db::update_uptime_state(&mut conn, &item.uptime).await {
-        warn!("Failed to persist pause state: {err:?}");
+    let mut changed = 0;
+    let mut last_err = "No devices registered";
+    for device in item
+        .devices
+        .iter_mut()
+        .filter(|d| device_id.is_none_or(|id| d.device.id == id))
+    {
+        let (prev_status, prev_changed_at) = (device.uptime.status, device.uptime.state_changed_at);
+        let (result, source) = if pause {
+            (device.uptime.pause(), db::EventSource::Pause)
+        } else {
+            (device.uptime.unpause(), db::EventSource::Unpause)
+        };
+        if let Err(err) = result {
+            last_err = err;
+            continue;
+        }
+        changed += 1;
+        (device.maint_suppressed_since, device.uptime.held_outage_secs) = (None, None);
+        prom::UPTIME_STATE
+            .with_label_values(&[&uid.to_string(), &device.device.id.to_string()])
+            .set(i64::from(&device.uptime.status));
+        let result = match device.transition_event(prev_status, prev_changed_at, source) {
+            Some(event) => db::record_transition(conn, &device.uptime, &event, &[]).await,
+            None => db::update_uptime_state(conn, &device.uptime).await,
+        };
+        if let Err(err) = result {
+            warn!("Failed to persist pause state: {err:?}");
+        }
+    }
+    match (changed, pause) {
+        (0, _) => json!({"status": 400, "error": last_err}),
+        (_, true) => json!({"status": 200, "message": "Monitoring paused", "devices": changed}),
+        (_, false) => json!({"status": 200, "message": "Monitoring resumed", "devices": changed}),
     }
-    json!({"status": 200, "message": "Monitoring paused"})
+}
+
+/// Pause monitoring — freezes state, suppresses all notifications.
+#[post("/api/v1/me/pause?<device>")]
+pub async fn pause_monitoring(
+    bauth: bauth::BAuth,
+    device: Option<uuid::Uuid>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    set_paused(bauth.uid, device, true, &mut conn, context).await
 }
 
 /// Resume monitoring — restores pre-pause state, refreshes touched_at.
-#[post("/api/v1/me/unpause")]
-pub async fn unpause_monitoring(bauth: bauth::BAuth, mut conn: Connection<DB>, context: &State<Context>) -> Value {
-    let mut guard = context.users.write().await;
-    let Some(item) = guard.get_mut(&bauth.uid) else {
-        return json!({"status": 404, "error": "User not found"});
-    };
-    if let Err(err) = item.uptime.unpause() {
-        return json!({"status": 400, "error": err});
-    }
-    prom::UPTIME_STATE
-        .with_label_values(&[&bauth.uid.to_string()])
-        .set(i64::from(&item.uptime.status));
-    if let Err(err) = db::update_uptime_state(&mut conn, &item.uptime).await {
-        warn!("Failed to persist unpause state: {err:?}");
-    }
-    json!({"status": 200, "message": "Monitoring resumed"})
+#[post("/api/v1/me/unpause?<device>")]
+pub async fn unpause_monitoring(
+    bauth: bauth::BAuth,
+    device: Option<uuid::Uuid>,
+    mut conn: Connection<DB>,
+    context: &State<Context>,
+) -> Value {
+    set_paused(bauth.uid, device, false, &mut conn, context).await
 }
 
-/// Get user settings (up_delay, maintenance window)
-#[get("/api/v1/me/settings")]
-pub async fn get_settings(bauth: bauth::BAuth, context: &State<Context>) -> Value {
+fn settings_json(device: &db::Device, user: &db::User) -> Value {
+    json!({
+        "status": 200,
+        "device_id": device.id,
+        "up_delay": device.up_delay,
+        "min_outage_secs": device.min_outage_secs,
+        "flap_threshold": device.flap_threshold,
+        "flap_window_minutes": device.flap_window_minutes,
+        "flap_stable_minutes": device.flap_stable_minutes,
+        "recovery_heartbeats": device.recovery_heartbeats,
+        "timezone": user.timezone,
+        "quiet_hours_start": user.quiet_hours_start,
+        "quiet_hours_end": user.quiet_hours_end,
+        "outage_queue": user.outage_queue,
+        "notifications": notifications::effective_settings(user),
+    })
+}
+
+/// Get device settings (up_delay, outage debouncing), the account's time zone, quiet
+/// hours, outage queue and notification settings per event kind. `device` may be omitted for single-device accounts.
+#[get("/api/v1/me/settings?<device>")]
+pub async fn get_settings(bauth: bauth::BAuth, device: Option<uuid::Uuid>, context: &State<Context>) -> Value {
     match context.users.read().await.get(&bauth.uid) {
-        Some(state) => json!({
-            "status": 200,
-            "up_delay": state.user.up_delay,
-            "maint_window_start_utc": state.user.maint_window_start_utc,
-            "maint_window_end_utc": state.user.maint_window_end_utc,
-        }),
+        Some(state) => match state.resolve_device(device) {
+            Ok(item) => settings_json(&item.device, &state.user),
+            Err(err) => json!({"status": 400, "error": err}),
+        },
         None => json!({"status": 404, "error": "User not found"}),
     }
 }
 
-/// Update user settings (up_delay and/or maintenance window)
-#[derive(rocket::serde::Deserialize)]
-#[serde(crate = "rocket::serde")]
-pub struct UpdateSettings {
-    pub up_delay: Option<i16>,
-    pub maint_window_start_utc: Option<Option<i16>>,
-    pub maint_window_end_utc: Option<Option<i16>>,
-}
-
-#[patch("/api/v1/me/settings", data = "<opts>")]
+/// Update device settings (up_delay and/or outage debouncing), time zone, quiet hours,
+/// outage queue and/or notification settings, e.g. `{"notifications": {"down": {"priority": "max", "tags": ["rotating_light"]}}}`
+#[patch("/api/v1/me/settings?<device>", data = "<opts>")]
 pub async fn update_settings(
     bauth: bauth::BAuth,
-    opts: Json<UpdateSettings>,
+    device: Option<uuid::Uuid>,
+    opts: Json<actions::SettingsUpdate>,
     mut conn: Connection<DB>,
     context: &State<Context>,
 ) -> Value {
-    // Validate up_delay
-    if let Some(delay) = opts.up_delay
-        && (!(10..=32767).contains(&delay))
-    {
-        return json!({"status": 400, "error": "up_delay must be between 10 and 32767 seconds"});
-    }
-    // Validate maintenance window: both present or both absent
-    let maint_start = opts.maint_window_start_utc;
-    let maint_end = opts.maint_window_end_utc;
-    if maint_start.is_some() != maint_end.is_some() {
-        return json!({"status": 400, "error": "maint_window_start_utc and maint_window_end_utc must be set together"});
-    }
-    // Reject mixed null/value (e.g., start=60, end=null) — DB constraint would catch it as 500
-    if let (Some(a), Some(b)) = (maint_start, maint_end)
-        && a.is_some() != b.is_some()
-    {
-        return json!({"status": 400, "error": "Maintenance window start and end must both be set or both be null"});
-    }
-    if let (Some(Some(s)), Some(Some(e))) = (maint_start, maint_end) {
-        if !(0..1440).contains(&s) || !(0..1440).contains(&e) {
-            return json!({"status": 400, "error": "Maintenance window values must be 0-1439 (minutes from midnight UTC)"});
-        }
-        if s == e {
-            return json!({"status": 400, "error": "Maintenance window start and end must differ"});
-        }
-    }
-
-    match db::update_user_settings(&mut conn, bauth.uid, opts.up_delay, maint_start, maint_end).await {
-        Ok(_) => {
-            // Update in-memory state
-            let mut users = context.users.write().await;
-            if let Some(state) = users.get_mut(&bauth.uid) {
-                if let Some(delay) = opts.up_delay {
-                    state.user.up_delay = delay;
-                }
-                if let Some(start) = maint_start {
-                    state.user.maint_window_start_utc = start;
-                }
-                if let Some(end) = maint_end {
-                    state.user.maint_window_end_utc = end;
-                }
-                json!({
-                    "status": 200,
-                    "up_delay": state.user.up_delay,
-                    "maint_window_start_utc": state.user.maint_window_start_utc,
-                    "maint_window_end_utc": state.user.maint_window_end_utc,
-                })
-            } else {
-                json!({"status": 404, "error": "User not found"})
-            }
-        }
-        Err(err) => json!({"status": 500, "error": format!("{err:?}")}),
+    let device_id = match context.users.read().await.get(&bauth.uid).map(|s| s.resolve_device(device)) {
+        Some(Ok(item)) => item.device.id,
+        Some(Err(err)) => return json!({"status": 400, "error": err}),
+        None => return json!({"status": 404, "error": "User not found"}),
+    };
+    match actions::update_settings(bauth.uid, device_id, &opts, &mut conn, context).await {
+        Ok((device, user)) => settings_json(&device, &user),
+        Err(err) => json!({"status": 400, "error": err}),
     }
 }
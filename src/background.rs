@@ -1,49 +1,129 @@
-use crate::{context, db, notifications, prom};
+use crate::channels::{SendError, TelegramChannel};
+use crate::schedule::ScheduleSource;
+use crate::{actions, channels, context, db, notifications, prom, telegram};
+use rocket::serde::json;
 use rocket::tokio;
-use rocket_db_pools::diesel::PgPool;
-use std::time::{Duration, SystemTime};
+use rocket_db_pools::diesel::{AsyncPgConnection, PgPool};
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Notification decided on while looping over a user's devices, queued once the loop lets go of them.
+enum Notice {
+    /// Regular Up/Down notification, with how long the previous state lasted
+    Change(Duration),
+    /// The device started flapping, with its status before the last flip
+    Flapping(db::UpStatus),
+    /// A flapping device settled
+    Stable,
+    /// The device is still down, with the escalation step that is due and how long the outage lasts
+    StillDown(db::EscalationStep, Duration),
+    /// Only the transition is recorded (flapping, or the outage is held back)
+    Silent,
+}
 
 pub async fn background_handle_down(context: context::Context, db_pool: PgPool) {
     loop {
         let mut sleep_for = Duration::new(5, 0);
-        let mut states_to_persist = Vec::new();
+        let mut transitions = Vec::new();
         {
             // @NOTE: Single write lock to atomically check thresholds and transition
             //  states, preventing TOCTOU race with api_up's touch().
+            let schedule = context.schedule.read().await;
             let mut guard = context.users.write().await;
             let now = SystemTime::now();
-            let now_utc_minutes =
-                notifications::utc_minute_of_day(now.duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_secs());
             for (_, item) in guard.iter_mut() {
-                let query_at = item.uptime.touched_at + Duration::new(item.user.up_delay as u64, 0);
-                if let Ok(remaining) = query_at.duration_since(now) {
-                    sleep_for = sleep_for.min(remaining);
-                } else if item.uptime.status == db::UpStatus::Paused {
-                    // Paused: skip entirely — no down transition while frozen
-                } else if item.user.is_in_maintenance_window(now_utc_minutes) {
-                    // Maintenance window: suppress down transition
-                } else if let Some(duration) = item.uptime.go_down() {
-                    prom::UPTIME_STATE
-                        .with_label_values(&[&item.user.id.to_string()])
-                        .set(i64::from(&item.uptime.status));
-                    states_to_persist.push(item.uptime.clone());
-                    tokio::spawn(notifications::dispatch_notifications(
-                        item.clone(),
-                        context.clone(),
-                        Some(duration),
-                    ));
+                let mut notify = Vec::new();
+                let local = item.user.local_time(now);
+                for device in item.devices.iter_mut() {
+                    let in_maint = item.maintenance.iter().any(|w| w.covers(device.device.id, &local));
+                    if device.uptime.status != db::UpStatus::Paused {
+                        // Outage lasted min_outage_secs, notify it after all (unless it's maintenance by now)
+                        if let Some((due, duration)) = device.pending_outage()
+                            && due <= now
+                        {
+                            device.uptime.held_outage_secs = None;
+                            let notice = match device.uptime.status == db::UpStatus::Down && !in_maint {
+                                true => Notice::Change(duration),
+                                false => Notice::Silent,
+                            };
+                            notify.push((device.clone(), None, notice));
+                        }
+                        if device.settle_flapping(now) {
+                            notify.push((device.clone(), None, Notice::Stable));
+                        }
+                        if !in_maint && let Some((step, elapsed)) = device.due_escalation(&item.escalation, now) {
+                            device.uptime.escalated_minutes = step.delay_minutes;
+                            notify.push((device.clone(), None, Notice::StillDown(step.clone(), elapsed)));
+                        }
+                    }
+                    let query_at = device.uptime.touched_at + Duration::new(device.device.up_delay as u64, 0);
+                    if let Ok(remaining) = query_at.duration_since(now) {
+                        sleep_for = sleep_for.min(remaining);
+                    } else if device.uptime.status == db::UpStatus::Paused {
+                        // Paused: skip entirely — no down transition while frozen
+                    } else if in_maint {
+                        // Maintenance window: suppress down transition, but record the silence once
+                        if device.uptime.status == db::UpStatus::Up && device.maint_suppressed_since.is_none() {
+                            device.maint_suppressed_since = Some(now);
+                            let event = device.event(
+                                device.uptime.status,
+                                now,
+                                now.duration_since(device.uptime.state_changed_at).unwrap_or_default(),
+                                db::EventSource::Maintenance,
+                            );
+                            transitions.push((device.uptime.clone(), Some(event), Vec::new()));
+                        }
+                    } else {
+                        let (prev_status, prev_changed_at) = (device.uptime.status, device.uptime.state_changed_at);
+                        if let Some(duration) = device.uptime.go_down() {
+                            device.maint_suppressed_since = None;
+                            prom::UPTIME_STATE
+                                .with_label_values(&[&device.device.user_id.to_string(), &device.device.id.to_string()])
+                                .set(i64::from(&device.uptime.status));
+                            if let Some(event) = device.transition_event(prev_status, prev_changed_at, db::EventSource::Timeout) {
+                                let notice = if device.record_flip(now) {
+                                    Notice::Flapping(prev_status)
+                                } else if device.uptime.flapping_since.is_some() {
+                                    Notice::Silent
+                                } else if device.device.min_outage_secs > 0 {
+                                    device.uptime.held_outage_secs = Some(duration.as_secs() as i64);
+                                    Notice::Silent
+                                } else {
+                                    Notice::Change(duration)
+                                };
+                                notify.push((device.clone(), Some(event), notice));
+                            }
+                        }
+                    }
+                }
+                for (device, event, notice) in notify {
+                    let outbox = match notice {
+                        Notice::Change(duration) => notifications::queue_notifications(item, &device, Some(duration), &schedule),
+                        Notice::Flapping(from) => notifications::queue_flapping_notice(item, &device, from),
+                        Notice::Stable => notifications::queue_stable_notice(item, &device),
+                        Notice::StillDown(step, elapsed) => {
+                            notifications::queue_escalation(item, &device, (&step, elapsed), &schedule)
+                        }
+                        Notice::Silent => Vec::new(),
+                    };
+                    transitions.push((device.uptime, event, outbox));
                 }
             }
         }
         // Persist state changes to DB
-        if !states_to_persist.is_empty() {
+        if !transitions.is_empty() {
             match db_pool.get().await {
                 Ok(mut conn) => {
-                    for state in &states_to_persist {
-                        if let Err(err) = db::update_uptime_state(&mut conn, state).await {
+                    for (state, event, outbox) in &transitions {
+                        let result = match event {
+                            Some(event) => db::record_transition(&mut conn, state, event, outbox).await,
+                            None => db::record_notice(&mut conn, state, outbox).await,
+                        };
+                        if let Err(err) = result {
                             warn!("Failed to persist uptime state: {err:?}");
                         }
                     }
+                    context.outbox_wake.notify_one();
                 }
                 Err(err) => warn!("Failed to get DB connection for state persistence: {err:?}"),
             }
@@ -54,3 +134,351 @@ pub async fn background_handle_down(context: context::Context, db_pool: PgPool)
         tokio::time::sleep(sleep_for).await;
     }
 }
+
+/// How often the outbox is checked for due items when nothing wakes the worker up.
+const OUTBOX_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Items claimed per round, the rest is picked up by the next one.
+const OUTBOX_BATCH_SIZE: i64 = 100;
+/// How long a claimed item may take to deliver before another round picks it up again. Channels
+/// make a single attempt with a 10s timeout per request, email one per recipient.
+const OUTBOX_LEASE: Duration = Duration::from_secs(2 * 60);
+/// How long undeliverable items are kept for inspection (`GET /api/v1/admin/outbox`).
+const OUTBOX_FAILED_RETENTION: Duration = Duration::from_secs(30 * 86400);
+
+/// Delivers the notification outbox: every due item gets an attempt through its channel, failures
+/// are retried with exponential backoff (see `notifications::outbox_retry_delay`) and marked as
+/// failed after `notifications::OUTBOX_MAX_ATTEMPTS`. Also keeps the outbox metrics up to date.
+/// @NOTE: Items live in the DB until delivered, so they survive restarts. Delivery is at least
+///  once: an item sent right before a crash is sent again once its lease runs out.
+pub async fn background_deliver_outbox(context: context::Context, db_pool: PgPool) {
+    let mut last_cleanup: Option<Instant> = None;
+    loop {
+        match db_pool.get().await {
+            Ok(mut conn) => {
+                let now = SystemTime::now();
+                match db::claim_due_outbox_items(&mut conn, now, now + OUTBOX_LEASE, OUTBOX_BATCH_SIZE).await {
+                    Ok(items) => {
+                        for item in merge_deferred(&context, &mut conn, items).await {
+                            tokio::spawn(deliver_outbox_item(context.clone(), db_pool.clone(), item));
+                        }
+                    }
+                    Err(err) => warn!("Failed to load the notification outbox: {err:?}"),
+                }
+                if last_cleanup.is_none_or(|at| at.elapsed() > Duration::from_secs(3600)) {
+                    last_cleanup = Some(Instant::now());
+                    match db::delete_failed_outbox_items(&mut conn, now - OUTBOX_FAILED_RETENTION).await {
+                        Ok(0) => {}
+                        Ok(deleted) => info!("Forgot {deleted} undeliverable notification(s)"),
+                        Err(err) => warn!("Failed to clean up the notification outbox: {err:?}"),
+                    }
+                }
+                match db::get_outbox_stats(&mut conn).await {
+                    Ok((pending, oldest, failed)) => {
+                        prom::OUTBOX_PENDING.set(pending);
+                        let age = oldest.and_then(|at| now.duration_since(at).ok()).unwrap_or_default();
+                        prom::OUTBOX_OLDEST_AGE.set(age.as_secs_f64());
+                        prom::OUTBOX_FAILED.set(failed);
+                    }
+                    Err(err) => warn!("Failed to count the notification outbox: {err:?}"),
+                }
+            }
+            Err(err) => warn!("Failed to get DB connection for the notification outbox: {err:?}"),
+        }
+        tokio::select! {
+            _ = context.outbox_wake.notified() => {}
+            _ = tokio::time::sleep(OUTBOX_POLL_INTERVAL) => {}
+        }
+    }
+}
+
+/// Items held back during quiet hours become due together once they end: the ones for the same
+/// channel are merged into a digest (see `notifications::digest`), the others are returned as is.
+async fn merge_deferred(
+    context: &context::Context,
+    conn: &mut AsyncPgConnection,
+    items: Vec<db::OutboxItem>,
+) -> Vec<db::OutboxItem> {
+    let mut due = Vec::new();
+    let mut deferred: HashMap<db::ID, Vec<(db::OutboxItem, channels::Notification)>> = HashMap::new();
+    for item in items {
+        match item.notification() {
+            Ok(notification) if notification.deferred && item.attempts == 0 => {
+                deferred.entry(item.channel_id).or_default().push((item, notification));
+            }
+            _ => due.push(item),
+        }
+    }
+    for (_, mut group) in deferred {
+        let notifications: Vec<channels::Notification> = group.iter().map(|(_, n)| n.clone()).collect();
+        let (mut item, _) = group.remove(0);
+        let tz = context
+            .users
+            .read()
+            .await
+            .get(&item.user_id)
+            .map(|s| s.user.tz())
+            .unwrap_or_default();
+        let Some(digest) = notifications::digest(&notifications, tz).filter(|_| !group.is_empty()) else {
+            due.push(item);
+            continue;
+        };
+        (item.event, item.payload) = (digest.event.clone(), digest.to_payload());
+        let merged: Vec<db::ID> = group.iter().map(|(item, _)| item.id).collect();
+        match db::merge_outbox_items(conn, &item, &merged).await {
+            Ok(()) => due.push(item),
+            // Still claimed, they are merged on the next try once the lease runs out
+            Err(err) => warn!("Failed to merge {} deferred notification(s): {err:?}", merged.len() + 1),
+        }
+    }
+    due
+}
+
+/// One delivery attempt of a claimed outbox item, storing its outcome.
+async fn deliver_outbox_item(context: context::Context, db_pool: PgPool, mut item: db::OutboxItem) {
+    // Disabled channels only take reminders of the escalation steps they are the extra channel of
+    let channel = context.users.read().await.get(&item.user_id).and_then(|state| {
+        state
+            .channels
+            .iter()
+            .find(|c| c.id == item.channel_id)
+            .filter(|c| c.enabled || (item.event == "still_down" && state.escalation.iter().any(|s| s.channel_id == Some(c.id))))
+            .cloned()
+    });
+    // The channel was removed or switched off after the notification was queued
+    let Some(channel) = channel else {
+        info!(
+            "Dropping {event} for channel {id}, it is disabled or gone",
+            event = item.event,
+            id = item.channel_id
+        );
+        match db_pool.get().await {
+            Ok(mut conn) => {
+                if let Err(err) = db::delete_outbox_item(&mut conn, item.id).await {
+                    warn!("Failed to delete notification {id} from the outbox: {err:?}", id = item.id);
+                }
+            }
+            Err(err) => warn!("Failed to get DB connection to drop notification {id}: {err:?}", id = item.id),
+        }
+        return;
+    };
+
+    let kind = channel.kind.label();
+    item.attempts += 1;
+    // Invalid configs or payloads won't get any better with retries
+    let result = match (channels::build(channel.kind, &channel.config), item.notification()) {
+        (Ok(sender), Ok(notification)) => {
+            info!(
+                "Sending {event} via {kind} channel {id} (attempt {attempt})",
+                event = item.event,
+                id = channel.id,
+                attempt = item.attempts
+            );
+            sender.send(&context, &notification).await
+        }
+        (Err(err), _) | (_, Err(err)) => Err(SendError::Permanent(err)),
+    };
+    // @NOTE: The connection is taken once the outcome is known, so slow endpoints don't hold on
+    //  to the pool while they are being waited for.
+    let Ok(mut conn) = db_pool.get().await else {
+        // Stays claimed, the lease running out makes it due again
+        warn!(
+            "Failed to get DB connection to store the outcome of notification {id}",
+            id = item.id
+        );
+        return;
+    };
+    let outcome = match result {
+        Ok(_) => {
+            prom::NOTIFICATIONS.with_label_values(&[&item.event, "success", kind]).inc();
+            db::delete_outbox_item(&mut conn, item.id).await.map(|_| ())
+        }
+        Err(err) => {
+            let now = SystemTime::now();
+            if err.is_permanent() || item.attempts >= notifications::OUTBOX_MAX_ATTEMPTS {
+                warn!(
+                    "Giving up notifying via {kind} channel {id} after {attempts} attempt(s): {err}",
+                    id = channel.id,
+                    attempts = item.attempts
+                );
+                prom::NOTIFICATIONS.with_label_values(&[&item.event, "failure", kind]).inc();
+                item.failed_at = Some(now);
+            } else {
+                let delay = notifications::outbox_retry_delay(item.attempts);
+                warn!(
+                    "Failed attempting to notify via {kind} channel {id}, retrying in {delay:?}: {err}",
+                    id = channel.id
+                );
+                prom::NOTIFICATIONS.with_label_values(&[&item.event, "retry", kind]).inc();
+                item.next_attempt_at = now + delay;
+            }
+            item.last_error = Some(err.to_string());
+            db::update_outbox_item(&mut conn, &item).await
+        }
+    };
+    if let Err(err) = outcome {
+        warn!("Failed to store the outcome of notification {id}: {err:?}", id = item.id);
+    }
+}
+
+/// How often recorded token uses are written to `api_tokens.last_used_*`.
+const TOKEN_USAGE_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Writes the last use of named API tokens to the DB in batches, so authenticating
+/// a request (e.g. every heartbeat) never costs a DB round trip.
+/// @NOTE: Uses recorded since the last flush are lost on shutdown, which only makes
+///  last_used_at lag behind by at most one interval.
+pub async fn background_flush_token_usage(context: context::Context, db_pool: PgPool) {
+    loop {
+        tokio::time::sleep(TOKEN_USAGE_FLUSH_INTERVAL).await;
+        let usage = std::mem::take(&mut *context.token_usage.lock().await);
+        if usage.is_empty() {
+            continue;
+        }
+        {
+            let mut api_tokens = context.api_tokens.write().await;
+            for token in api_tokens.values_mut() {
+                if let Some((at, ip)) = usage.get(&token.id) {
+                    (token.last_used_at, token.last_used_ip) = (Some(*at), ip.clone());
+                }
+            }
+        }
+        match db_pool.get().await {
+            Ok(mut conn) => {
+                for (token_id, (at, ip)) in &usage {
+                    if let Err(err) = db::update_api_token_last_used(&mut conn, *token_id, *at, ip.as_deref()).await {
+                        warn!("Failed to persist token usage: {err:?}");
+                    }
+                }
+            }
+            Err(err) => warn!("Failed to get DB connection for token usage flush: {err:?}"),
+        }
+    }
+}
+
+/// Pause after a failed getUpdates call before polling again.
+const TELEGRAM_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Long polls the Telegram bot for link codes: a chat sending `/start <code>` (or just the code)
+/// gets linked to the channel that was created with it.
+/// @NOTE: Only started when TELEGRAM_BOT_TOKEN is set. Updates are acknowledged by the
+///  next getUpdates offset, so a restart may handle the last batch twice (harmless, codes are one-time).
+pub async fn background_telegram_updates(context: context::Context, db_pool: PgPool) {
+    let mut offset = 0;
+    loop {
+        // The bot name is only cosmetic (t.me links), getUpdates errors are logged below
+        if context.telegram.bot_username().is_none()
+            && let Ok(username) = context.telegram.get_me().await
+        {
+            info!("Telegram bot @{username} is listening for link codes");
+        }
+        let updates = match context.telegram.get_updates(offset).await {
+            Ok(updates) => updates,
+            Err(err) => {
+                warn!("Telegram getUpdates failed: {err}");
+                tokio::time::sleep(TELEGRAM_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        for update in updates {
+            offset = offset.max(update.update_id + 1);
+            if let Some(message) = update.message {
+                handle_telegram_message(&context, &db_pool, message).await;
+            }
+        }
+    }
+}
+
+async fn handle_telegram_message(context: &context::Context, db_pool: &PgPool, message: telegram::Message) {
+    let text = message.text.unwrap_or_default();
+    // "/start <code>", "/start@bot_name <code>" in groups, or the bare code
+    let code = match text.trim().split_once(char::is_whitespace) {
+        Some((command, rest)) if command.starts_with("/start") => rest.trim(),
+        _ => text.trim(),
+    };
+    let now = SystemTime::now();
+    let found = {
+        let users = context.users.read().await;
+        users.values().find_map(|state| {
+            state.channels.iter().find_map(|channel| {
+                let config = json::from_value::<TelegramChannel>(channel.config.clone()).ok()?;
+                (channel.kind == db::ChannelKind::Telegram && config.accepts_code(code, now))
+                    .then(|| (channel.clone(), state.user.language_code.clone()))
+            })
+        })
+    };
+    let Some((mut channel, language_code)) = found else {
+        let language_code = message.from.and_then(|f| f.language_code).unwrap_or_default();
+        let reply = notifications::localize(&language_code, "telegram-link-invalid");
+        if let Err(err) = context
+            .telegram
+            .send_message(message.chat.id, &telegram::escape_html(&reply))
+            .await
+        {
+            warn!("Failed to answer Telegram chat: {err}");
+        }
+        return;
+    };
+
+    channel.config = TelegramChannel::linked(message.chat.id, message.chat.display_name());
+    channel.enabled = true;
+    match db_pool.get().await {
+        Ok(mut conn) => {
+            if let Err(err) = db::update_channel(&mut conn, &channel).await {
+                warn!("Failed to persist linked Telegram chat: {err:?}");
+                return;
+            }
+        }
+        Err(err) => {
+            warn!("Failed to get DB connection for Telegram link: {err:?}");
+            return;
+        }
+    }
+    info!(
+        "Linked Telegram chat to channel {id} of user {uid}",
+        id = channel.id,
+        uid = channel.user_id
+    );
+    context.update_channel(channel).await;
+    let reply = notifications::localize(&language_code, "telegram-linked");
+    if let Err(err) = context
+        .telegram
+        .send_message(message.chat.id, &telegram::escape_html(&reply))
+        .await
+    {
+        warn!("Failed to answer Telegram chat: {err}");
+    }
+}
+
+/// Pause after a failed schedule fetch before trying again.
+const SCHEDULE_RETRY_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// Imports the planned outage schedule from `source` every refresh interval, the same way an admin
+/// upload does (see `actions::import_schedule`).
+/// @NOTE: Only started when SCHEDULE_URL is set. A failed fetch keeps the schedule imported last.
+pub async fn background_import_schedule(context: context::Context, db_pool: PgPool, source: ScheduleSource) {
+    loop {
+        let body = match source.fetch().await {
+            Ok(body) => body,
+            Err(err) => {
+                warn!("Failed to fetch the outage schedule from {url}: {err}", url = source.url);
+                tokio::time::sleep(SCHEDULE_RETRY_DELAY.min(source.refresh)).await;
+                continue;
+            }
+        };
+        match db_pool.get().await {
+            Ok(mut conn) => {
+                match actions::import_schedule(source.format, &body, source.queue.as_deref(), &mut conn, &context).await {
+                    Ok(queues) => info!(
+                        "Imported {n} planned outage(s) of {q} queue(s) from {url}",
+                        n = queues.values().sum::<usize>(),
+                        q = queues.len(),
+                        url = source.url
+                    ),
+                    Err(err) => warn!("Failed to import the outage schedule from {url}: {err}", url = source.url),
+                }
+            }
+            Err(err) => warn!("Failed to get DB connection for the outage schedule: {err:?}"),
+        }
+        tokio::time::sleep(source.refresh).await;
+    }
+}
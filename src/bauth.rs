@@ -1,4 +1,5 @@
-use crate::{context, db, prom};
+use crate::db::TokenScope;
+use crate::{context, db, prom, tokens};
 use governor::{Quota, RateLimiter, clock::QuantaClock, state::InMemoryState};
 use rocket::fairing::{Fairing, Info, Kind};
 use rocket::http::Status;
@@ -7,6 +8,7 @@ use rocket::{Data, Request, State};
 use std::net::IpAddr;
 use std::num::NonZero;
 use std::sync::Arc;
+use std::time::SystemTime;
 
 type IpLimiter = Arc<RateLimiter<IpAddr, dashmap::DashMap<IpAddr, InMemoryState>, QuantaClock>>;
 
@@ -38,7 +40,7 @@ impl Fairing for IpRateLimitFairing {
                 if limiter.check_key(&ip).is_err() {
                     warn!("[AUTH] ip={ip} result=rate_limited prefix=none");
                     prom::AUTH_FAILURES.with_label_values(&["rate_limited"]).inc();
-                    // @NOTE: We set a flag in the request-local cache so BAuth/AdminAuth
+                    // @NOTE: We set a flag in the request-local cache so BAuth/AdminAuth/DeviceAuth
                     //  guards can detect the rate limit and return 429. The fairing itself
                     //  can't short-circuit the request in Rocket 0.5.
                     request.local_cache(|| RateLimited(true));
@@ -57,10 +59,12 @@ struct RateLimited(bool);
 /// Extract a safe token prefix for logging. Never logs the full token.
 fn token_prefix(raw: &str) -> &'static str {
     let token = raw.strip_prefix("token ").unwrap_or(raw);
+    // @NOTE: We return a static str to avoid lifetime issues. The prefix is only
+    //  used for log categorization, not identification.
     if token.starts_with("tk_") {
-        // @NOTE: We return a static str to avoid lifetime issues. The prefix is only
-        //  used for log categorization, not identification.
         if token.len() >= 9 { "tk_..." } else { "tk_short" }
+    } else if token.starts_with("hb_") {
+        if token.len() >= 9 { "hb_..." } else { "hb_short" }
     } else {
         "malformed"
     }
@@ -92,11 +96,28 @@ impl<'r> FromRequest<'r> for RateLimitGuard {
     }
 }
 
+/// Account auth (TokenScope::Account), also granted by named account tokens.
 #[derive(Debug)]
 pub struct BAuth {
     pub uid: db::ID,
 }
 
+/// Heartbeat auth (TokenScope::Heartbeat): the token identifies a single device.
+#[derive(Debug)]
+pub struct DeviceAuth {
+    pub uid: db::ID,
+    pub device_id: db::ID,
+}
+
+/// Account auth that also takes the token from a `token` query parameter, for calendar apps
+/// and the like that can't send headers.
+/// @NOTE: Query strings end up in access logs and app settings, hand out a named token that
+///  can be revoked on its own.
+#[derive(Debug)]
+pub struct FeedAuth {
+    pub uid: db::ID,
+}
+
 #[derive(Debug)]
 pub struct AdminAuth {
     pub uid: db::ID,
@@ -108,26 +129,69 @@ pub enum BAuthError {
     Invalid,
     RateLimited,
     NotAdmin,
+    WrongScope,
+    Expired,
 }
 
-/// Shared token resolution: rate limit check, header extraction, token lookup, failure logging.
-/// Returns the authenticated user ID or an error outcome.
-async fn resolve_token(req: &Request<'_>) -> Result<db::ID, (Status, BAuthError)> {
+/// Shared token resolution: rate limit check, token lookup, failure logging. `raw` is the
+/// Authorization header (or whatever else carries the token).
+/// Returns the user ID, plus the device ID for heartbeat tokens, or an error outcome.
+/// @NOTE: A valid token of the other scope is rejected with 403 (and logged as wrong_scope),
+///  so e.g. a token extracted from a flashed board can't be used to manage the account.
+///  Uses of named tokens are only recorded in memory, see background::background_flush_token_usage.
+async fn resolve_token(
+    req: &Request<'_>,
+    raw: Option<&str>,
+    scope: TokenScope,
+) -> Result<(db::ID, Option<db::ID>), (Status, BAuthError)> {
     // Check if the IP rate limiter already rejected this request
     if req.local_cache(|| RateLimited(false)).0 {
         return Err((Status::TooManyRequests, BAuthError::RateLimited));
     }
 
-    match req.headers().get_one("Authorization") {
+    match raw {
         Some(raw) => {
-            let tokens = &req.guard::<&State<context::Context>>().await.unwrap().tokens;
-            match tokens.read().await.get(raw.strip_prefix("token ").unwrap_or(raw)) {
-                Some(uid) => Ok(*uid),
-                None => {
+            let context = req.guard::<&State<context::Context>>().await.unwrap();
+            // @NOTE: Maps are keyed by the token hash, plaintext tokens are never kept around.
+            let hash = tokens::hash_token(raw.strip_prefix("token ").unwrap_or(raw));
+            let (mut account, mut heartbeat) = (
+                context.tokens.read().await.get(&hash).copied(),
+                context.device_tokens.read().await.get(&hash).copied(),
+            );
+            let mut named_id = None;
+            if account.is_none() && heartbeat.is_none() {
+                let named = context.api_tokens.read().await.get(&hash).cloned();
+                if let Some(named) = named {
+                    let now = SystemTime::now();
+                    if named.is_expired(now) {
+                        log_auth_failure(req, "expired_token", Some(raw));
+                        return Err((Status::Unauthorized, BAuthError::Expired));
+                    }
+                    match (named.scope, named.device_id) {
+                        (TokenScope::Account, _) => account = Some(named.user_id),
+                        (TokenScope::Heartbeat, Some(device_id)) => heartbeat = Some((named.user_id, device_id)),
+                        (TokenScope::Heartbeat, None) => {}
+                    }
+                    named_id = Some(named.id);
+                }
+            }
+            let result = match (scope, account, heartbeat) {
+                (TokenScope::Account, Some(uid), _) => Ok((uid, None)),
+                (TokenScope::Heartbeat, _, Some((uid, device_id))) => Ok((uid, Some(device_id))),
+                (_, None, None) => {
                     log_auth_failure(req, "invalid_token", Some(raw));
                     Err((Status::Unauthorized, BAuthError::Invalid))
                 }
+                _ => {
+                    log_auth_failure(req, "wrong_scope", Some(raw));
+                    Err((Status::Forbidden, BAuthError::WrongScope))
+                }
+            };
+            if let (Ok(_), Some(token_id)) = (&result, named_id) {
+                let ip = req.client_ip().map(|ip| ip.to_string());
+                context.token_usage.lock().await.insert(token_id, (SystemTime::now(), ip));
             }
+            result
         }
         None => {
             log_auth_failure(req, "missing_header", None);
@@ -141,8 +205,37 @@ impl<'r> FromRequest<'r> for BAuth {
     type Error = BAuthError;
 
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        match resolve_token(req).await {
-            Ok(uid) => Outcome::Success(BAuth { uid }),
+        match resolve_token(req, req.headers().get_one("Authorization"), TokenScope::Account).await {
+            Ok((uid, _)) => Outcome::Success(BAuth { uid }),
+            Err(e) => Outcome::Error(e),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for DeviceAuth {
+    type Error = BAuthError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match resolve_token(req, req.headers().get_one("Authorization"), TokenScope::Heartbeat).await {
+            Ok((uid, Some(device_id))) => Outcome::Success(DeviceAuth { uid, device_id }),
+            Ok((_, None)) => Outcome::Error((Status::Unauthorized, BAuthError::Invalid)),
+            Err(e) => Outcome::Error(e),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for FeedAuth {
+    type Error = BAuthError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let raw = req
+            .headers()
+            .get_one("Authorization")
+            .or_else(|| req.query_value::<&str>("token").and_then(Result::ok));
+        match resolve_token(req, raw, TokenScope::Account).await {
+            Ok((uid, _)) => Outcome::Success(FeedAuth { uid }),
             Err(e) => Outcome::Error(e),
         }
     }
@@ -153,8 +246,8 @@ impl<'r> FromRequest<'r> for AdminAuth {
     type Error = BAuthError;
 
     async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        let uid = match resolve_token(req).await {
-            Ok(uid) => uid,
+        let uid = match resolve_token(req, req.headers().get_one("Authorization"), TokenScope::Account).await {
+            Ok((uid, _)) => uid,
             Err(e) => return Outcome::Error(e),
         };
 
@@ -0,0 +1,64 @@
+use super::webhook::{masked_url, validate_url};
+use super::{Notification, NotificationChannel, SendError};
+use crate::context::Context;
+use rocket::serde::Deserialize;
+use rocket::serde::json::json;
+
+/// Discord incoming webhook (Server Settings > Integrations > Webhooks).
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct DiscordChannel {
+    /// Webhook URL, its last path segment is the secret token.
+    pub url: String,
+}
+
+/// Backslash-escape Discord markdown, so device names are shown as typed.
+fn escape_markdown(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| match c {
+            '\\' | '*' | '_' | '~' | '`' | '|' | '>' | '#' => vec!['\\', c],
+            c => vec![c],
+        })
+        .collect()
+}
+
+#[rocket::async_trait]
+impl NotificationChannel for DiscordChannel {
+    fn describe(&self) -> String {
+        format!("Discord webhook {}", masked_url(&self.url))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.url.len() > 2048 {
+            return Err("Discord webhook URL must be at most 2048 characters long".to_string());
+        }
+        validate_url(&self.url).map_err(|_| "Discord webhook URL must be a valid http(s) URL".to_string())
+    }
+
+    async fn send(&self, context: &Context, notification: &Notification) -> Result<(), SendError> {
+        let mut content = format!("**{}**", escape_markdown(&notification.title));
+        if !notification.message.is_empty() {
+            content = format!("{content}\n{}", escape_markdown(&notification.message));
+        }
+        // @NOTE: No pings, a device named "@everyone" must not notify the whole server.
+        let payload = json!({"content": content, "allowed_mentions": {"parse": []}});
+        context.webhook.deliver(|client| client.post(&self.url).json(&payload)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::channels::build;
+    use crate::db::ChannelKind;
+    use rocket::serde::json::json;
+
+    #[test]
+    fn test_hides_token() {
+        let discord = json!({"url": "https://discord.com/api/webhooks/123/secret-token"});
+        assert_eq!(
+            build(ChannelKind::Discord, &discord).unwrap().describe(),
+            "Discord webhook discord.com/api/webhooks/123/***"
+        );
+        assert!(build(ChannelKind::Discord, &json!({"url": "ftp://discord.com/x"})).is_err());
+    }
+}
@@ -0,0 +1,224 @@
+use super::{Notification, NotificationChannel, SendError};
+use crate::context::Context;
+use crate::tokens;
+use lettre::Address;
+use rocket::serde::json::Value;
+use rocket::serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long a verification link can be used.
+pub const VERIFY_LINK_LIFETIME: Duration = Duration::from_secs(48 * 3600);
+pub const MAX_RECIPIENTS: usize = 5;
+
+/// Plain text email to a list of recipients, sent through the server's SMTP relay (see `email::Mailer`).
+/// @NOTE: Every address has to be confirmed through a link mailed to it (double opt-in), otherwise
+///  the server could be used to mail anyone. Unverified recipients are skipped when sending.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct EmailChannel {
+    pub recipients: Vec<Recipient>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct Recipient {
+    pub address: String,
+    pub verified: bool,
+    /// Hash of the token in the verification link (see `tokens::hash_token`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_hash: Option<String>,
+    /// Unix seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_expires_at: Option<u64>,
+}
+
+impl EmailChannel {
+    /// Build the channel from user input `{"to": ["a@example.com", ...]}` (or a comma separated
+    /// string), keeping the state of addresses `existing` already has. Returns the channel and the
+    /// (address, token) pairs that need a verification email.
+    pub fn from_input(input: &Value, existing: Option<&EmailChannel>) -> Result<(EmailChannel, Vec<(String, String)>), String> {
+        let addresses = Self::input_addresses(input)?;
+        let recipients = addresses
+            .into_iter()
+            .map(|address| {
+                let known = existing.and_then(|e| e.recipients.iter().find(|r| r.address.eq_ignore_ascii_case(&address)));
+                match known {
+                    Some(r) if r.verified => r.clone(),
+                    _ => Recipient {
+                        address,
+                        verified: false,
+                        verify_hash: None,
+                        verify_expires_at: None,
+                    },
+                }
+            })
+            .collect();
+        let mut channel = EmailChannel { recipients };
+        channel.validate()?;
+
+        // New or still unverified, (re)send the link
+        let expires_at = (SystemTime::now() + VERIFY_LINK_LIFETIME)
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut pending = Vec::new();
+        for recipient in channel.recipients.iter_mut().filter(|r| !r.verified) {
+            let token = tokens::new_email_token();
+            recipient.verify_hash = Some(tokens::hash_token(&token));
+            recipient.verify_expires_at = Some(expires_at);
+            pending.push((recipient.address.clone(), token));
+        }
+        Ok((channel, pending))
+    }
+
+    /// The recipients of user input, `{"to": [...]}` or `{"to": "a@example.com,b@example.com"}`.
+    pub fn input_addresses(input: &Value) -> Result<Vec<String>, String> {
+        let addresses: Vec<String> = match input.get("to") {
+            Some(Value::String(list)) => list
+                .split(',')
+                .map(|a| a.trim().to_string())
+                .filter(|a| !a.is_empty())
+                .collect(),
+            Some(Value::Array(list)) => list
+                .iter()
+                .map(|a| a.as_str().map(|a| a.trim().to_string()).ok_or("Recipients must be strings"))
+                .collect::<Result<_, _>>()?,
+            _ => return Err("Email channel needs recipients, e.g. {\"to\": [\"me@example.com\"]}".to_string()),
+        };
+        if let Some(key) = input.as_object().and_then(|o| o.keys().find(|k| *k != "to")) {
+            return Err(format!("Invalid email config: unknown field `{key}`, expected `to`"));
+        }
+        Ok(addresses)
+    }
+
+    /// Mark the recipient with this verification token hash as verified. Returns false if there
+    /// is none or the link expired.
+    pub fn verify(&mut self, token_hash: &str, now: SystemTime) -> bool {
+        let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let Some(recipient) = self
+            .recipients
+            .iter_mut()
+            .find(|r| r.verify_hash.as_deref() == Some(token_hash))
+        else {
+            return false;
+        };
+        if recipient.verify_expires_at.is_none_or(|t| now >= t) {
+            return false;
+        }
+        recipient.verified = true;
+        recipient.verify_hash = None;
+        recipient.verify_expires_at = None;
+        true
+    }
+}
+
+#[rocket::async_trait]
+impl NotificationChannel for EmailChannel {
+    fn describe(&self) -> String {
+        let addresses: Vec<String> = self
+            .recipients
+            .iter()
+            .map(|r| match r.verified {
+                true => r.address.clone(),
+                false => format!("{} (unverified)", r.address),
+            })
+            .collect();
+        format!("email to {}", addresses.join(", "))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.recipients.is_empty() || self.recipients.len() > MAX_RECIPIENTS {
+            return Err(format!("Email channel needs 1 to {MAX_RECIPIENTS} recipients"));
+        }
+        for (i, recipient) in self.recipients.iter().enumerate() {
+            recipient
+                .address
+                .parse::<Address>()
+                .map_err(|_| format!("Invalid email address: {}", recipient.address))?;
+            if self.recipients[..i]
+                .iter()
+                .any(|r| r.address.eq_ignore_ascii_case(&recipient.address))
+            {
+                return Err(format!("Duplicate email address: {}", recipient.address));
+            }
+        }
+        Ok(())
+    }
+
+    async fn send(&self, context: &Context, notification: &Notification) -> Result<(), SendError> {
+        let verified: Vec<&Recipient> = self.recipients.iter().filter(|r| r.verified).collect();
+        if verified.is_empty() {
+            return Err(SendError::Permanent("No verified email recipients".to_string()));
+        }
+        let mut body = notification.title.clone();
+        if !notification.message.is_empty() {
+            body = format!("{body}\n{}", notification.message);
+        }
+        let (mut delivered, mut permanent) = (false, false);
+        let mut errors = Vec::new();
+        for recipient in verified {
+            match context
+                .mailer
+                .send(&recipient.address, &notification.title, body.clone())
+                .await
+            {
+                Ok(()) => delivered = true,
+                Err(err) => {
+                    permanent |= err.is_permanent();
+                    errors.push(format!("{}: {err}", recipient.address));
+                }
+            }
+        }
+        // @NOTE: Retries go to every recipient again. Once someone got the mail, a retry would
+        //  send it to them twice, so only failures where nobody got it are retried.
+        let err = errors.join("; ");
+        match (errors.is_empty(), delivered || permanent) {
+            (true, _) => Ok(()),
+            (false, true) => Err(SendError::Permanent(err)),
+            (false, false) => Err(SendError::Transient(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::build;
+    use crate::db::ChannelKind;
+    use rocket::serde::json::{from_value, json};
+
+    #[test]
+    fn test_verification() {
+        let mut channel: EmailChannel = from_value(json!({"recipients": [
+            {"address": "a@example.com", "verified": false, "verify_hash": "hash-a", "verify_expires_at": u64::MAX},
+            {"address": "b@example.com", "verified": false, "verify_hash": "hash-b", "verify_expires_at": 0},
+        ]}))
+        .unwrap();
+        assert_eq!(
+            channel.describe(),
+            "email to a@example.com (unverified), b@example.com (unverified)"
+        );
+
+        let now = SystemTime::now();
+        assert!(!channel.verify("hash-b", now), "expired");
+        assert!(!channel.verify("hash-c", now));
+        assert!(channel.verify("hash-a", now));
+        assert!(!channel.verify("hash-a", now), "links are single use");
+
+        // Verified addresses are kept as they are
+        let (channel, pending) = EmailChannel::from_input(&json!({"to": ["A@example.com"]}), Some(&channel)).unwrap();
+        assert_eq!(channel.describe(), "email to a@example.com");
+        assert!(pending.is_empty());
+        assert!(build(ChannelKind::Email, &json!(channel)).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_invalid_recipients() {
+        assert!(EmailChannel::from_input(&json!({}), None).is_err());
+        assert!(EmailChannel::from_input(&json!({"to": []}), None).is_err());
+        assert!(EmailChannel::from_input(&json!({"to": "not-an-address"}), None).is_err());
+        assert!(EmailChannel::from_input(&json!({"to": "a@example.com,A@example.com"}), None).is_err());
+        assert!(EmailChannel::from_input(&json!({"to": "a@example.com", "cc": "b@example.com"}), None).is_err());
+        assert!(EmailChannel::from_input(&json!({"to": "a@x.com,b@x.com,c@x.com,d@x.com,e@x.com,f@x.com"}), None).is_err());
+    }
+}
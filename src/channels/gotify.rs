@@ -0,0 +1,72 @@
+use super::webhook::validate_url;
+use super::{Notification, NotificationChannel, Priority, SendError};
+use crate::context::Context;
+use crate::tokens;
+use rocket::serde::Deserialize;
+use rocket::serde::json::json;
+
+/// Message from a Gotify application, created under Apps in the Gotify web UI.
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct GotifyChannel {
+    /// Base URL of the Gotify server, e.g. https://gotify.example.com
+    pub server: String,
+    /// Application token.
+    pub token: String,
+    /// Overrides the priority of every notification (min, low, default, high or max).
+    pub priority: Option<Priority>,
+}
+
+#[rocket::async_trait]
+impl NotificationChannel for GotifyChannel {
+    fn describe(&self) -> String {
+        // @NOTE: A fingerprint tells apps on the same server apart without showing the token.
+        let host = reqwest::Url::parse(&self.server)
+            .ok()
+            .and_then(|u| u.host_str().map(String::from));
+        let fingerprint = &tokens::hmac_sha256_hex(b"gotify", &self.token)[..8];
+        format!("Gotify app {fingerprint} on {}", host.unwrap_or_default())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_url(&self.server).map_err(|_| "Gotify server must be a valid http(s) URL".to_string())?;
+        if self.token.is_empty() || self.token.len() > 256 {
+            return Err("Gotify token must be 1-256 characters long".to_string());
+        }
+        Ok(())
+    }
+
+    async fn send(&self, context: &Context, notification: &Notification) -> Result<(), SendError> {
+        let mut payload = json!({
+            "title": notification.title,
+            "message": notification.message,
+            "priority": self.priority.unwrap_or(notification.priority).gotify_level(),
+        });
+        if let Some(click) = &notification.click {
+            payload["extras"] = json!({"client::notification": {"click": {"url": click}}});
+        }
+        let url = format!("{}/message", self.server.trim_end_matches('/'));
+        context
+            .webhook
+            .deliver(|client| client.post(&url).header("X-Gotify-Key", &self.token).json(&payload))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::channels::build;
+    use crate::db::ChannelKind;
+    use rocket::serde::json::json;
+
+    #[test]
+    fn test_rejects_numeric_priority() {
+        assert!(
+            build(
+                ChannelKind::Gotify,
+                &json!({"server": "https://g.example.com", "token": "t", "priority": 5})
+            )
+            .is_err()
+        );
+    }
+}
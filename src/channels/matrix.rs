@@ -0,0 +1,103 @@
+use super::webhook::validate_url;
+use super::{Notification, NotificationChannel, SendError};
+use crate::context::Context;
+use crate::telegram::escape_html;
+use rocket::serde::Deserialize;
+use rocket::serde::json::json;
+
+/// Message in a Matrix room, posted with the client-server API as the user the token belongs to
+/// (usually a dedicated bot account that joined the room).
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct MatrixChannel {
+    /// Base URL of the homeserver, e.g. https://matrix.org
+    pub homeserver: String,
+    pub access_token: String,
+    /// Internal room ID (`!abc:example.org`), not an alias.
+    pub room_id: String,
+}
+
+#[rocket::async_trait]
+impl NotificationChannel for MatrixChannel {
+    fn describe(&self) -> String {
+        let host = reqwest::Url::parse(&self.homeserver)
+            .ok()
+            .and_then(|u| u.host_str().map(String::from));
+        format!("Matrix room {} on {}", self.room_id, host.unwrap_or_default())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        validate_url(&self.homeserver).map_err(|_| "Matrix homeserver must be a valid http(s) URL".to_string())?;
+        if self.access_token.is_empty() || self.access_token.len() > 1024 {
+            return Err("Matrix access token must be 1-1024 characters long".to_string());
+        }
+        if !self.room_id.starts_with('!') || self.room_id.len() > 255 {
+            return Err("Matrix room must be a room ID like !abc:example.org (not an #alias)".to_string());
+        }
+        Ok(())
+    }
+
+    async fn send(&self, context: &Context, notification: &Notification) -> Result<(), SendError> {
+        let (mut body, mut html) = (
+            notification.title.clone(),
+            format!("<b>{}</b>", escape_html(&notification.title)),
+        );
+        if !notification.message.is_empty() {
+            body = format!("{body}\n{}", notification.message);
+            html = format!("{html}<br>{}", escape_html(&notification.message));
+        }
+        let content = json!({
+            "msgtype": "m.notice",
+            "body": body,
+            "format": "org.matrix.custom.html",
+            "formatted_body": html,
+        });
+        // @NOTE: The transaction ID is derived from the notification, so it stays the same when the
+        //  outbox retries it and the homeserver drops duplicates.
+        let mut url = reqwest::Url::parse(&self.homeserver).map_err(|err| format!("{err}"))?;
+        url.path_segments_mut()
+            .map_err(|_| "Invalid Matrix homeserver".to_string())?
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v3", "rooms", &self.room_id, "send", "m.room.message"])
+            .push(&format!(
+                "oubot-{}-{}-{}",
+                notification.device_id, notification.event, notification.timestamp
+            ));
+
+        context
+            .webhook
+            .deliver(|client| client.put(url).bearer_auth(&self.access_token).json(&content))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::channels::build;
+    use crate::db::ChannelKind;
+    use rocket::serde::json::json;
+
+    #[test]
+    fn test_hides_token() {
+        let matrix = json!({"homeserver": "https://matrix.org", "access_token": "syt_secret", "room_id": "!abc:matrix.org"});
+        assert_eq!(
+            build(ChannelKind::Matrix, &matrix).unwrap().describe(),
+            "Matrix room !abc:matrix.org on matrix.org"
+        );
+
+        assert!(
+            build(
+                ChannelKind::Matrix,
+                &json!({"homeserver": "https://matrix.org", "access_token": "t", "room_id": "#alias:matrix.org"})
+            )
+            .is_err()
+        );
+        assert!(
+            build(
+                ChannelKind::Matrix,
+                &json!({"homeserver": "https://matrix.org", "access_token": "", "room_id": "!abc"})
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Everything a notification can be delivered through. Users have any number of channels
+//! (see `db::Channel`), each stored as a kind plus a JSON config which is turned into one of
+//! the `NotificationChannel` implementations below.
+mod apprise;
+mod discord;
+mod email;
+mod gotify;
+mod matrix;
+mod ntfy;
+mod slack;
+mod telegram;
+mod webhook;
+
+pub use apprise::parse_url;
+pub use discord::DiscordChannel;
+pub use email::EmailChannel;
+pub use gotify::GotifyChannel;
+pub use matrix::MatrixChannel;
+pub use ntfy::NtfyChannel;
+pub use slack::SlackChannel;
+pub use telegram::TelegramChannel;
+pub use webhook::{WebhookChannel, WebhookClient, validate_url};
+
+use crate::context::Context;
+use crate::db::{ChannelKind, ID, UpStatus};
+use rocket::serde::json::{self, Value};
+use rocket::serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+/// A status change of a device, the same for every channel. Webhooks get it as their JSON
+/// body, documented in docs/WEBHOOKS.md.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Notification {
+    /// "connected", "up", "down", "flapping", "stable", "still_down" or "digest", same as the `type` label
+    /// of oubot_notifications_total
+    pub event: String,
+    pub user_id: ID,
+    pub device_id: ID,
+    pub device_name: String,
+    pub old_status: UpStatus,
+    pub new_status: UpStatus,
+    /// Seconds spent in old_status, null for the first heartbeat of a device.
+    pub duration: Option<u64>,
+    /// Unix seconds when the notification was generated.
+    pub timestamp: u64,
+    pub language: String,
+    /// Localized title and message, ready to be shown to the user.
+    pub title: String,
+    pub message: String,
+    /// Not part of the webhook body, only kept in the outbox payload (see `to_payload`).
+    #[serde(skip_serializing)]
+    pub priority: Priority,
+    /// ntfy tags (emojis), empty for items queued before they were configurable.
+    #[serde(skip_serializing, default)]
+    pub tags: Vec<String>,
+    /// URL of an icon shown by ntfy.
+    #[serde(skip_serializing, default)]
+    pub icon: Option<String>,
+    /// URL opened when the notification is clicked (ntfy and Gotify).
+    #[serde(skip_serializing, default)]
+    pub click: Option<String>,
+    /// Held back until the end of quiet hours, merged with the others held back for the same channel.
+    #[serde(skip_serializing, default)]
+    pub deferred: bool,
+    /// Whether an outage is a planned one of the account's queue, null without a queue or for
+    /// notifications about anything but outages.
+    #[serde(default)]
+    pub schedule: Option<OutagePlan>,
+}
+
+/// An outage checked against the planned outage schedule of the account's queue (see `schedule`).
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct OutagePlan {
+    pub queue: String,
+    pub planned: bool,
+    /// Unix seconds the planned outage ends, when power should be back. Null for unplanned outages.
+    pub until: Option<u64>,
+}
+
+impl Notification {
+    /// JSON stored in the notification outbox, the webhook body plus how it is shown.
+    pub fn to_payload(&self) -> Value {
+        let mut payload = json::to_value(self).unwrap_or_default();
+        payload["priority"] = json::to_value(self.priority).unwrap_or_default();
+        payload["tags"] = json::to_value(&self.tags).unwrap_or_default();
+        payload["icon"] = json::to_value(&self.icon).unwrap_or_default();
+        payload["click"] = json::to_value(&self.click).unwrap_or_default();
+        payload["deferred"] = self.deferred.into();
+        payload
+    }
+}
+
+/// How urgent a notification is, mapped onto the scale of each service. Ordered from Min to Max.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Deserialize, Serialize, diesel_derive_enum::DbEnum)]
+#[ExistingTypePath = "crate::schema::sql_types::PriorityEnum"]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum Priority {
+    Min,
+    Low,
+    Default,
+    High,
+    Max,
+}
+
+impl Priority {
+    /// Name for ntfy's X-Priority header.
+    pub fn ntfy_name(&self) -> &'static str {
+        match self {
+            Priority::Min => "min",
+            Priority::Low => "low",
+            Priority::Default => "default",
+            Priority::High => "high",
+            Priority::Max => "max",
+        }
+    }
+
+    /// ntfy's numeric priority (1-5), used when publishing JSON.
+    pub fn ntfy_level(&self) -> u8 {
+        match self {
+            Priority::Min => 1,
+            Priority::Low => 2,
+            Priority::Default => 3,
+            Priority::High => 4,
+            Priority::Max => 5,
+        }
+    }
+
+    /// Gotify's 0-10 scale, where 1-3 is silent, 4-7 makes a sound and 8-10 also pops up.
+    pub fn gotify_level(&self) -> u8 {
+        match self {
+            Priority::Min => 1,
+            Priority::Low => 3,
+            Priority::Default => 5,
+            Priority::High => 8,
+            Priority::Max => 10,
+        }
+    }
+}
+
+/// Why a notification wasn't delivered, which tells the outbox whether to try again.
+#[derive(Debug)]
+pub enum SendError {
+    /// May go through later: connection errors, timeouts, 429 and 5xx responses
+    Transient(String),
+    /// Won't get any better with retries, e.g. a 404 from a deleted webhook or a 401 from a revoked token
+    Permanent(String),
+}
+
+impl SendError {
+    pub fn is_permanent(&self) -> bool {
+        matches!(self, SendError::Permanent(_))
+    }
+}
+
+impl From<String> for SendError {
+    fn from(err: String) -> Self {
+        SendError::Transient(err)
+    }
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendError::Transient(err) | SendError::Permanent(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+#[rocket::async_trait]
+pub trait NotificationChannel: Send + Sync {
+    /// One line summary for listings, must not contain secrets.
+    fn describe(&self) -> String;
+    /// Where notifications end up, two channels with the same target are duplicates.
+    fn target(&self) -> String {
+        self.describe()
+    }
+    /// Reject settings that can never work, checked before a config is stored.
+    fn validate(&self) -> Result<(), String>;
+    async fn send(&self, context: &Context, notification: &Notification) -> Result<(), SendError>;
+}
+
+/// Turn a stored (or submitted) config into the channel implementation of its kind.
+pub fn build(kind: ChannelKind, config: &Value) -> Result<Box<dyn NotificationChannel>, String> {
+    match kind {
+        ChannelKind::Ntfy => parse::<NtfyChannel>(kind, config),
+        ChannelKind::Webhook => parse::<WebhookChannel>(kind, config),
+        ChannelKind::Telegram => parse::<TelegramChannel>(kind, config),
+        ChannelKind::Email => parse::<EmailChannel>(kind, config),
+        ChannelKind::Matrix => parse::<MatrixChannel>(kind, config),
+        ChannelKind::Discord => parse::<DiscordChannel>(kind, config),
+        ChannelKind::Slack => parse::<SlackChannel>(kind, config),
+        ChannelKind::Gotify => parse::<GotifyChannel>(kind, config),
+    }
+}
+
+fn parse<T: NotificationChannel + DeserializeOwned + 'static>(
+    kind: ChannelKind,
+    config: &Value,
+) -> Result<Box<dyn NotificationChannel>, String> {
+    let channel: T = json::from_value(config.clone()).map_err(|err| format!("Invalid {} config: {err}", kind.label()))?;
+    channel.validate()?;
+    Ok(Box::new(channel))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::serde::json::json;
+
+    #[test]
+    fn test_build_webhook() {
+        let channel = build(
+            ChannelKind::Webhook,
+            &json!({"url": "https://example.com/hook", "secret": "s"}),
+        )
+        .unwrap();
+        assert_eq!(channel.describe(), "example.com/***");
+        assert_eq!(channel.target(), "https://example.com/hook");
+    }
+
+    #[test]
+    fn test_build_rejects_invalid_config() {
+        assert!(build(ChannelKind::Webhook, &json!({})).is_err());
+        assert!(build(ChannelKind::Webhook, &json!({"url": "file:///etc/passwd"})).is_err());
+        assert!(build(ChannelKind::Webhook, &json!({"url": "https://example.com", "secret": ""})).is_err());
+        assert!(build(ChannelKind::Webhook, &json!({"url": "https://example.com", "token": "x"})).is_err());
+        assert!(build(ChannelKind::Ntfy, &json!({"topic": ""})).is_err());
+        assert!(build(ChannelKind::Telegram, &json!({})).is_err());
+    }
+
+    #[test]
+    fn test_outbox_payload_keeps_priority() {
+        let notification = Notification {
+            event: "down".to_string(),
+            user_id: ID::nil(),
+            device_id: ID::nil(),
+            device_name: "Home".to_string(),
+            old_status: UpStatus::Up,
+            new_status: UpStatus::Down,
+            duration: Some(60),
+            timestamp: 1,
+            language: "en".to_string(),
+            title: "Power outage!".to_string(),
+            message: String::new(),
+            priority: Priority::Max,
+            tags: vec!["rotating_light".to_string()],
+            icon: None,
+            click: Some("https://example.com/status".to_string()),
+            deferred: true,
+            schedule: None,
+        };
+        // Webhooks never see the priority, the outbox does
+        let body = json::to_value(&notification).unwrap();
+        assert!(body.get("priority").is_none() && body.get("tags").is_none() && body.get("click").is_none());
+        assert!(body.get("deferred").is_none());
+        let restored: Notification = json::from_value(notification.to_payload()).unwrap();
+        assert_eq!(restored.priority, Priority::Max);
+        assert_eq!(restored.event, "down");
+        assert_eq!(restored.title, "Power outage!");
+        assert_eq!(restored.tags, ["rotating_light"]);
+        assert_eq!(restored.click.as_deref(), Some("https://example.com/status"));
+        assert!(restored.deferred);
+
+        // Items queued before tags, click and quiet hours existed
+        let mut old = notification.to_payload();
+        for key in ["tags", "icon", "click", "deferred"] {
+            old.as_object_mut().unwrap().remove(key);
+        }
+        let restored: Notification = json::from_value(old).unwrap();
+        assert!(restored.tags.is_empty() && restored.click.is_none() && !restored.deferred);
+    }
+}
@@ -0,0 +1,137 @@
+use super::webhook::validate_url;
+use super::{Notification, NotificationChannel, Priority, SendError};
+use crate::context::Context;
+use crate::notifications;
+use crate::ntfy::NtfyNotification;
+use rocket::serde::Deserialize;
+use rocket::serde::json::json;
+
+/// Topic of the user's managed ntfy account, published to with the admin token. With `server` set
+/// it is a topic on another ntfy server instead, published to with the credentials given here.
+/// @NOTE: The managed topic is filled in by the server when the channel is created, users can't
+///  pick it (any topic of the managed instance is writable with the admin token).
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct NtfyChannel {
+    pub topic: String,
+    /// Base URL of the user's own ntfy server, e.g. https://ntfy.sh
+    pub server: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Access token (tk_...), instead of username and password.
+    pub token: Option<String>,
+    /// Overrides the priority of every notification, only on the user's own server.
+    pub priority: Option<Priority>,
+}
+
+impl NtfyChannel {
+    /// Items queued before tags were configurable have none, they get the status emoji.
+    fn tags(notification: &Notification) -> Vec<String> {
+        if notification.tags.is_empty() {
+            vec![notifications::default_tag(notification.new_status).to_string()]
+        } else {
+            notification.tags.clone()
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl NotificationChannel for NtfyChannel {
+    fn describe(&self) -> String {
+        match self.server.as_deref().map(reqwest::Url::parse) {
+            Some(Ok(url)) => format!("ntfy topic {} on {}", self.topic, url.host_str().unwrap_or_default()),
+            Some(Err(_)) => format!("ntfy topic {} on invalid server", self.topic),
+            None => format!("ntfy topic {}", self.topic),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.topic.is_empty() || self.topic.len() > 64 {
+            return Err("ntfy topic must be 1-64 characters long".to_string());
+        }
+        if !self.topic.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err("ntfy topic may only contain letters, digits, - and _".to_string());
+        }
+        let Some(server) = &self.server else {
+            if self.username.is_some() || self.password.is_some() || self.token.is_some() || self.priority.is_some() {
+                return Err("ntfy credentials and priority need a server".to_string());
+            }
+            return Ok(());
+        };
+        validate_url(server).map_err(|_| "ntfy server must be a valid http(s) URL".to_string())?;
+        if self.username.is_some() != self.password.is_some() {
+            return Err("ntfy username and password go together".to_string());
+        }
+        if self.token.is_some() && self.username.is_some() {
+            return Err("Use either an ntfy token or username and password".to_string());
+        }
+        Ok(())
+    }
+
+    async fn send(&self, context: &Context, notification: &Notification) -> Result<(), SendError> {
+        let Some(server) = &self.server else {
+            let data = NtfyNotification {
+                topic: self.topic.clone(),
+                title: notification.title.clone(),
+                message: notification.message.clone(),
+                tags: Self::tags(notification).join(","),
+                priority: notification.priority.ntfy_name().to_string(),
+                icon: notification.icon.clone(),
+                click: notification.click.clone(),
+            };
+            return context
+                .ntfy
+                .send_notification(data)
+                .await
+                .map_err(|err| format!("{err:?}").into());
+        };
+
+        // Published as JSON to the server root, which takes the topic from the body
+        let mut payload = json!({
+            "topic": self.topic,
+            "title": notification.title,
+            "message": notification.message,
+            "tags": Self::tags(notification),
+            "priority": self.priority.unwrap_or(notification.priority).ntfy_level(),
+        });
+        if let Some(icon) = &notification.icon {
+            payload["icon"] = json!(icon);
+        }
+        if let Some(click) = &notification.click {
+            payload["click"] = json!(click);
+        }
+        context
+            .webhook
+            .deliver(|client| {
+                let request = client.post(server).json(&payload);
+                match (&self.token, &self.username) {
+                    (Some(token), _) => request.bearer_auth(token),
+                    (None, Some(username)) => request.basic_auth(username, self.password.as_ref()),
+                    (None, None) => request,
+                }
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::channels::build;
+    use crate::db::ChannelKind;
+    use rocket::serde::json::json;
+
+    #[test]
+    fn test_managed_takes_no_credentials() {
+        assert!(ChannelKind::Ntfy.is_managed(Some(&json!({"topic": "t"}))));
+        assert!(!ChannelKind::Ntfy.is_managed(Some(&json!({"topic": "t", "server": "https://ntfy.sh"}))));
+        assert!(build(ChannelKind::Ntfy, &json!({"topic": "t", "token": "tk_x"})).is_err());
+        assert!(
+            build(
+                ChannelKind::Ntfy,
+                &json!({"topic": "t", "server": "https://ntfy.sh", "username": "me"})
+            )
+            .is_err()
+        );
+        assert!(build(ChannelKind::Ntfy, &json!({"topic": "a/b", "server": "https://ntfy.sh"})).is_err());
+    }
+}
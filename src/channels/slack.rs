@@ -0,0 +1,57 @@
+use super::webhook::{masked_url, validate_url};
+use super::{Notification, NotificationChannel, SendError};
+use crate::context::Context;
+use rocket::serde::Deserialize;
+use rocket::serde::json::json;
+
+/// Slack incoming webhook, also accepted by Slack compatible chats (Mattermost, Rocket.Chat, Zulip).
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct SlackChannel {
+    /// Webhook URL, its last path segment is the secret token.
+    pub url: String,
+}
+
+/// Slack only needs &, < and > escaped in message text.
+fn escape_mrkdwn(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[rocket::async_trait]
+impl NotificationChannel for SlackChannel {
+    fn describe(&self) -> String {
+        format!("Slack webhook {}", masked_url(&self.url))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.url.len() > 2048 {
+            return Err("Slack webhook URL must be at most 2048 characters long".to_string());
+        }
+        validate_url(&self.url).map_err(|_| "Slack webhook URL must be a valid http(s) URL".to_string())
+    }
+
+    async fn send(&self, context: &Context, notification: &Notification) -> Result<(), SendError> {
+        let mut text = format!("*{}*", escape_mrkdwn(&notification.title));
+        if !notification.message.is_empty() {
+            text = format!("{text}\n{}", escape_mrkdwn(&notification.message));
+        }
+        let payload = json!({"text": text});
+        context.webhook.deliver(|client| client.post(&self.url).json(&payload)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::channels::build;
+    use crate::db::ChannelKind;
+    use rocket::serde::json::json;
+
+    #[test]
+    fn test_hides_token() {
+        let slack = json!({"url": "http://127.0.0.1:8097/services/T0/B0/secret-token?x=1"});
+        assert_eq!(
+            build(ChannelKind::Slack, &slack).unwrap().describe(),
+            "Slack webhook 127.0.0.1:8097/services/T0/B0/***"
+        );
+    }
+}
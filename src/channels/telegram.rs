@@ -0,0 +1,111 @@
+use super::{Notification, NotificationChannel, SendError};
+use crate::context::Context;
+use crate::telegram::escape_html;
+use rand::{Rng, distributions::Alphanumeric};
+use rocket::serde::json::{Value, json};
+use rocket::serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long a link code can be sent to the bot.
+pub const LINK_CODE_LIFETIME: Duration = Duration::from_secs(3600);
+
+/// Telegram chat linked through the bot. Created with a one-time `link_code`, which is replaced
+/// by the chat once the user sends `/start <code>` to the bot (see `background_telegram_updates`).
+/// @NOTE: Managed by the server like the ntfy topic, otherwise users could point the bot at any chat.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct TelegramChannel {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chat_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chat_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_code: Option<String>,
+    /// Unix seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_expires_at: Option<u64>,
+}
+
+impl TelegramChannel {
+    /// Config of a new, not yet linked channel.
+    pub fn new_link() -> Value {
+        let code: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(12)
+            .map(char::from)
+            .collect();
+        let expires_at = SystemTime::now() + LINK_CODE_LIFETIME;
+        json!(TelegramChannel {
+            link_code: Some(code),
+            link_expires_at: Some(expires_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()),
+            ..Default::default()
+        })
+    }
+
+    /// Config once the code was sent from `chat_id`.
+    pub fn linked(chat_id: i64, chat_name: String) -> Value {
+        json!(TelegramChannel {
+            chat_id: Some(chat_id),
+            chat_name: Some(chat_name),
+            ..Default::default()
+        })
+    }
+
+    /// Whether this channel waits for `code` and it hasn't expired.
+    pub fn accepts_code(&self, code: &str, now: SystemTime) -> bool {
+        let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        self.chat_id.is_none() && self.link_code.as_deref() == Some(code) && self.link_expires_at.is_some_and(|t| now < t)
+    }
+}
+
+#[rocket::async_trait]
+impl NotificationChannel for TelegramChannel {
+    fn describe(&self) -> String {
+        match (&self.chat_name, &self.link_code) {
+            (Some(name), _) => format!("Telegram chat {name}"),
+            (None, Some(code)) => format!("Telegram, waiting for /start {code}"),
+            (None, None) => "Telegram, not linked".to_string(),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.chat_id.is_none() && self.link_code.is_none() {
+            return Err("Telegram channel needs a chat or a link code".to_string());
+        }
+        Ok(())
+    }
+
+    async fn send(&self, context: &Context, notification: &Notification) -> Result<(), SendError> {
+        let Some(chat_id) = self.chat_id else {
+            return Err(SendError::Permanent("Telegram chat not linked yet".to_string()));
+        };
+        let mut text = format!("<b>{}</b>", escape_html(&notification.title));
+        if !notification.message.is_empty() {
+            text = format!("{text}\n{}", escape_html(&notification.message));
+        }
+        Ok(context.telegram.send_message(chat_id, &text).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::build;
+    use crate::db::ChannelKind;
+    use rocket::serde::json::from_value;
+
+    #[test]
+    fn test_link_code() {
+        let config = TelegramChannel::new_link();
+        let channel: TelegramChannel = from_value(config.clone()).unwrap();
+        let code = channel.link_code.clone().unwrap();
+        let now = SystemTime::now();
+        assert!(channel.accepts_code(&code, now));
+        assert!(!channel.accepts_code("wrong", now));
+        assert!(!channel.accepts_code(&code, now + LINK_CODE_LIFETIME));
+        assert!(build(ChannelKind::Telegram, &config).unwrap().describe().contains(&code));
+
+        let linked = build(ChannelKind::Telegram, &TelegramChannel::linked(42, "@someone".to_string())).unwrap();
+        assert_eq!(linked.describe(), "Telegram chat @someone");
+    }
+}
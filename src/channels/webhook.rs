@@ -0,0 +1,119 @@
+use super::{Notification, NotificationChannel, SendError};
+use crate::context::Context;
+use crate::tokens;
+use rocket::serde::Deserialize;
+use std::time::Duration;
+
+/// HTTP client shared by all channels posting to user supplied URLs (webhooks, chat services).
+#[derive(Debug, Clone)]
+pub struct WebhookClient {
+    client: reqwest::Client,
+}
+
+impl WebhookClient {
+    pub fn new() -> WebhookClient {
+        // @NOTE: Redirects are not followed, so a webhook can't bounce requests somewhere else.
+        let client = reqwest::Client::builder()
+            .user_agent("OpenUptimeBot/v0")
+            .timeout(Duration::from_secs(10))
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .expect("RIP");
+        WebhookClient { client }
+    }
+
+    /// Send the request made by `build`, once: failed notifications are retried from the outbox
+    /// (see `background::deliver_outbox_item`). Connection errors, timeouts, 429 and 5xx responses
+    /// are transient, any other response permanent.
+    pub async fn deliver(&self, build: impl FnOnce(&reqwest::Client) -> reqwest::RequestBuilder) -> Result<(), SendError> {
+        match build(&self.client).send().await {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response) if response.status().is_server_error() || response.status().as_u16() == 429 => {
+                Err(SendError::Transient(format!("HTTP {}", response.status())))
+            }
+            Ok(response) => Err(SendError::Permanent(format!("HTTP {}", response.status()))),
+            // @NOTE: The URL may carry a secret (chat webhook tokens), keep it out of the logs.
+            Err(err) => Err(SendError::Transient(format!("{:?}", err.without_url()))),
+        }
+    }
+}
+
+/// JSON POST of the notification to a user supplied URL, optionally signed.
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct WebhookChannel {
+    pub url: String,
+    /// Payloads are signed with HMAC-SHA256 using this secret when set.
+    pub secret: Option<String>,
+}
+
+#[rocket::async_trait]
+impl NotificationChannel for WebhookChannel {
+    fn describe(&self) -> String {
+        masked_url(&self.url)
+    }
+
+    fn target(&self) -> String {
+        self.url.clone()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.url.len() > 2048 {
+            return Err("Webhook URL must be at most 2048 characters long".to_string());
+        }
+        validate_url(&self.url)?;
+        if let Some(secret) = &self.secret
+            && (secret.is_empty() || secret.len() > 256)
+        {
+            return Err("Webhook secret must be 1-256 characters long".to_string());
+        }
+        Ok(())
+    }
+
+    /// POST the payload (see `WebhookClient::deliver`).
+    async fn send(&self, context: &Context, notification: &Notification) -> Result<(), SendError> {
+        let body = rocket::serde::json::to_string(notification).map_err(|err| SendError::Permanent(format!("{err:?}")))?;
+        let signature = self
+            .secret
+            .as_ref()
+            .map(|secret| tokens::hmac_sha256_hex(secret.as_bytes(), &body));
+        context
+            .webhook
+            .deliver(|client| {
+                let request = client
+                    .post(&self.url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .header("X-Oubot-Event", &notification.event)
+                    .body(body);
+                match &signature {
+                    Some(signature) => request.header("X-Oubot-Signature", format!("sha256={signature}")),
+                    None => request,
+                }
+            })
+            .await
+    }
+}
+
+/// Only plain http(s) URLs with a host are accepted.
+pub fn validate_url(url: &str) -> Result<(), String> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.host_str().is_some() => Ok(()),
+        _ => Err("Webhook URL must be a valid http(s) URL".to_string()),
+    }
+}
+
+/// `url` without its last path segment and query, where chat services put the webhook token.
+pub fn masked_url(url: &str) -> String {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return "invalid URL".to_string();
+    };
+    let mut segments: Vec<&str> = parsed.path_segments().map(|s| s.collect()).unwrap_or_default();
+    if let Some(last) = segments.last_mut() {
+        *last = "***";
+    }
+    let host = match parsed.port() {
+        Some(port) => format!("{}:{port}", parsed.host_str().unwrap_or_default()),
+        None => parsed.host_str().unwrap_or_default().to_string(),
+    };
+    format!("{host}/{}", segments.join("/"))
+}
@@ -1,17 +1,39 @@
-use crate::db::{ID, Invite, UserState};
+use crate::channels::WebhookClient;
+use crate::db::{ApiToken, Channel, DeviceState, EscalationStep, ID, Invite, MaintenanceWindow, UserState};
+use crate::email::Mailer;
 use crate::ntfy::NtfyClient;
-use rocket::tokio::sync::{Mutex, RwLock};
+use crate::schedule::Schedule;
+use crate::telegram::TelegramClient;
+use rocket::tokio::sync::{Mutex, Notify, RwLock};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Last use of named tokens by token_id: (at, ip).
+pub type TokenUsage = HashMap<ID, (SystemTime, Option<String>)>;
 
 #[derive(Debug, Clone)]
 pub struct Context {
     pub users: Arc<RwLock<HashMap<ID, UserState>>>,
+    /// Account token hashes (see `tokens::hash_token`), mapping to user_id.
     pub tokens: Arc<RwLock<HashMap<String, ID>>>,
+    /// Device heartbeat token hashes, mapping to (user_id, device_id).
+    pub device_tokens: Arc<RwLock<HashMap<String, (ID, ID)>>>,
+    /// Named API tokens by hash, checked after the account and device tokens.
+    pub api_tokens: Arc<RwLock<HashMap<String, ApiToken>>>,
+    /// Uses of named tokens not written to the DB yet.
+    pub token_usage: Arc<Mutex<TokenUsage>>,
     pub invite_tokens: Arc<RwLock<HashMap<String, ID>>>,
     /// Serializes first-user (admin init) creation to prevent TOCTOU race.
     pub init_lock: Arc<Mutex<()>>,
     pub ntfy: NtfyClient,
+    pub webhook: WebhookClient,
+    pub telegram: TelegramClient,
+    pub mailer: Mailer,
+    /// Wakes the outbox worker up when notifications were queued.
+    pub outbox_wake: Arc<Notify>,
+    /// Planned outages by queue. Taken before `users` when both are needed.
+    pub schedule: Arc<RwLock<Schedule>>,
 }
 
 impl Context {
@@ -19,14 +41,28 @@ impl Context {
         Context {
             users: Default::default(),
             tokens: Default::default(),
+            device_tokens: Default::default(),
+            api_tokens: Default::default(),
+            token_usage: Default::default(),
             invite_tokens: Default::default(),
             init_lock: Default::default(),
             ntfy: NtfyClient::new(),
+            webhook: WebhookClient::new(),
+            telegram: TelegramClient::new(),
+            mailer: Mailer::new(),
+            outbox_wake: Default::default(),
+            schedule: Default::default(),
         }
     }
 
     pub async fn add_state(&self, v: UserState) {
-        self.tokens.write().await.insert(v.user.access_token.clone(), v.user.id);
+        self.tokens.write().await.insert(v.user.token_hash.clone(), v.user.id);
+        {
+            let mut device_tokens = self.device_tokens.write().await;
+            for d in &v.devices {
+                device_tokens.insert(d.device.token_hash.clone(), (v.user.id, d.device.id));
+            }
+        }
 
         if let Some(old) = self.users.write().await.insert(v.user.id, v) {
             warn!("Creating new user state, but one already existed: {old:?}");
@@ -56,10 +92,134 @@ impl Context {
     /// Remove a user from in-memory state
     pub async fn remove_user(&self, user_id: ID) {
         if let Some(state) = self.users.write().await.remove(&user_id) {
-            self.tokens.write().await.remove(&state.user.access_token);
+            self.tokens.write().await.remove(&state.user.token_hash);
+            let mut device_tokens = self.device_tokens.write().await;
+            for d in &state.devices {
+                device_tokens.remove(&d.device.token_hash);
+            }
+            self.api_tokens.write().await.retain(|_, t| t.user_id != user_id);
         }
     }
 
+    /// Attach a new device to an existing user's state
+    pub async fn add_device(&self, v: DeviceState) {
+        let (user_id, device_id) = (v.device.user_id, v.device.id);
+        let token = v.device.token_hash.clone();
+        let mut users = self.users.write().await;
+        if let Some(state) = users.get_mut(&user_id) {
+            state.devices.push(v);
+            self.device_tokens.write().await.insert(token, (user_id, device_id));
+        }
+    }
+
+    /// Remove a device from in-memory state, with its maintenance windows (like ON DELETE CASCADE)
+    pub async fn remove_device(&self, user_id: ID, device_id: ID) {
+        let mut users = self.users.write().await;
+        if let Some(state) = users.get_mut(&user_id)
+            && let Some(pos) = state.devices.iter().position(|d| d.device.id == device_id)
+        {
+            let removed = state.devices.remove(pos);
+            self.device_tokens.write().await.remove(&removed.device.token_hash);
+            self.api_tokens.write().await.retain(|_, t| t.device_id != Some(device_id));
+            state.maintenance.retain(|w| w.device_id != Some(device_id));
+        }
+    }
+
+    pub async fn add_channel(&self, v: Channel) {
+        if let Some(state) = self.users.write().await.get_mut(&v.user_id) {
+            state.channels.push(v);
+        }
+    }
+
+    /// Replace a channel with its updated version, keeping its position.
+    pub async fn update_channel(&self, v: Channel) {
+        if let Some(state) = self.users.write().await.get_mut(&v.user_id)
+            && let Some(channel) = state.channels.iter_mut().find(|c| c.id == v.id)
+        {
+            *channel = v;
+        }
+    }
+
+    /// Remove a channel, escalation steps it was the extra channel of lose it (like ON DELETE SET NULL).
+    pub async fn remove_channel(&self, user_id: ID, channel_id: ID) {
+        if let Some(state) = self.users.write().await.get_mut(&user_id) {
+            state.channels.retain(|c| c.id != channel_id);
+            for step in state.escalation.iter_mut().filter(|s| s.channel_id == Some(channel_id)) {
+                step.channel_id = None;
+            }
+        }
+    }
+
+    /// Insert an escalation step, keeping the steps ordered by delay.
+    pub async fn add_escalation_step(&self, v: EscalationStep) {
+        if let Some(state) = self.users.write().await.get_mut(&v.user_id) {
+            let pos = state.escalation.partition_point(|s| s.delay_minutes < v.delay_minutes);
+            state.escalation.insert(pos, v);
+        }
+    }
+
+    pub async fn remove_escalation_step(&self, user_id: ID, step_id: ID) {
+        if let Some(state) = self.users.write().await.get_mut(&user_id) {
+            state.escalation.retain(|s| s.id != step_id);
+        }
+    }
+
+    /// Add a maintenance window, dropping one-off windows that ended before `now` (already deleted in the DB).
+    pub async fn add_maintenance_window(&self, v: MaintenanceWindow, now: SystemTime) {
+        if let Some(state) = self.users.write().await.get_mut(&v.user_id) {
+            state.maintenance.retain(|w| !w.has_ended(now));
+            state.maintenance.push(v);
+        }
+    }
+
+    /// Replace a maintenance window with its updated version, keeping its position.
+    pub async fn update_maintenance_window(&self, v: MaintenanceWindow) {
+        if let Some(state) = self.users.write().await.get_mut(&v.user_id)
+            && let Some(window) = state.maintenance.iter_mut().find(|w| w.id == v.id)
+        {
+            *window = v;
+        }
+    }
+
+    pub async fn remove_maintenance_window(&self, user_id: ID, window_id: ID) {
+        if let Some(state) = self.users.write().await.get_mut(&user_id) {
+            state.maintenance.retain(|w| w.id != window_id);
+        }
+    }
+
+    pub async fn add_api_token(&self, v: ApiToken) {
+        self.api_tokens.write().await.insert(v.token_hash.clone(), v);
+    }
+
+    /// Remove a named token. Returns false if the user has no such token.
+    pub async fn remove_api_token(&self, user_id: ID, token_id: ID) -> bool {
+        let mut api_tokens = self.api_tokens.write().await;
+        let before = api_tokens.len();
+        api_tokens.retain(|_, t| !(t.user_id == user_id && t.id == token_id));
+        api_tokens.len() < before
+    }
+
+    /// A user's named tokens, including uses that haven't been flushed to the DB yet.
+    pub async fn api_tokens_for(&self, user_id: ID) -> Vec<ApiToken> {
+        let usage = self.token_usage.lock().await;
+        let mut tokens: Vec<ApiToken> = self
+            .api_tokens
+            .read()
+            .await
+            .values()
+            .filter(|t| t.user_id == user_id)
+            .cloned()
+            .map(|mut t| {
+                if let Some((at, ip)) = usage.get(&t.id) {
+                    (t.last_used_at, t.last_used_ip) = (Some(*at), ip.clone());
+                }
+                t
+            })
+            .collect();
+        tokens.sort_by_key(|t| t.created_at);
+        tokens
+    }
+
     /// Remove invite tokens by invite IDs (used when cascade-deleting a user's invites)
     pub async fn remove_invite_ids(&self, invite_ids: &[ID]) {
         if invite_ids.is_empty() {
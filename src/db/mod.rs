@@ -1,11 +1,15 @@
 mod models;
 pub use models::*;
 
-use crate::schema::{invites, ntfy_users, uptime_states, users};
-use rand::{Rng, distributions::Alphanumeric};
+use crate::schema::{
+    api_tokens, devices, escalation_steps, invites, maintenance_windows, notification_channels, notification_outbox, ntfy_users,
+    planned_outages, status_page_monitors, status_pages, uptime_events, uptime_states, users,
+};
+use crate::tokens;
 use rocket_db_pools::diesel::AsyncPgConnection;
 use rocket_db_pools::diesel::prelude::*;
 use rocket_db_pools::diesel::scoped_futures::ScopedFutureExt;
+use std::time::SystemTime;
 use uuid::Uuid;
 
 type R<T> = std::result::Result<T, Box<dyn std::error::Error>>;
@@ -41,10 +45,19 @@ pub async fn create_new_state(
                     .returning(users::dsl::id)
                     .get_result::<ID>(tconn)
                     .await?;
-                diesel::insert_into(uptime_states::dsl::uptime_states)
-                    .values(&user_state.uptime)
-                    .returning(uptime_states::dsl::id)
-                    .get_result::<ID>(tconn)
+                for device_state in &user_state.devices {
+                    diesel::insert_into(devices::dsl::devices)
+                        .values(&device_state.device)
+                        .execute(tconn)
+                        .await?;
+                    diesel::insert_into(uptime_states::dsl::uptime_states)
+                        .values(&device_state.uptime)
+                        .execute(tconn)
+                        .await?;
+                }
+                diesel::insert_into(notification_channels::dsl::notification_channels)
+                    .values(&user_state.channels)
+                    .execute(tconn)
                     .await?;
 
                 // Consume the invite (after user insert, since user_id has FK to users)
@@ -78,18 +91,90 @@ pub async fn get_all_states(conn: &mut AsyncPgConnection) -> R<Vec<UserState>> {
 
     let mut all_states = Vec::new();
     for (user, ntfy) in user_items {
-        let uptime = uptime_states::dsl::uptime_states
-            .filter(uptime_states::dsl::user_id.eq(user.id))
-            .order(uptime_states::dsl::created_at.desc())
-            .select(UptimeState::as_select())
-            .first::<UptimeState>(conn)
-            .await?;
-        all_states.push(UserState { user, ntfy, uptime });
+        let devices = get_devices_for_user(conn, user.id).await?;
+        let mut device_states = Vec::with_capacity(devices.len());
+        for device in devices {
+            let uptime = uptime_states::dsl::uptime_states
+                .filter(uptime_states::dsl::device_id.eq(device.id))
+                .order(uptime_states::dsl::created_at.desc())
+                .select(UptimeState::as_select())
+                .first::<UptimeState>(conn)
+                .await?;
+            device_states.push(DeviceState::load(uptime, device));
+        }
+        let channels = get_channels_for_user(conn, user.id).await?;
+        let escalation = get_escalation_steps_for_user(conn, user.id).await?;
+        let maintenance = get_maintenance_windows_for_user(conn, user.id).await?;
+        all_states.push(UserState {
+            user,
+            ntfy,
+            devices: device_states,
+            channels,
+            escalation,
+            maintenance,
+        });
     }
 
     Ok(all_states)
 }
 
+// Devices
+
+pub async fn get_devices_for_user(conn: &mut AsyncPgConnection, user_id: ID) -> Result<Vec<Device>, diesel::result::Error> {
+    devices::dsl::devices
+        .filter(devices::dsl::user_id.eq(user_id))
+        .order((devices::dsl::created_at.asc(), devices::dsl::id.asc()))
+        .select(Device::as_select())
+        .load::<Device>(conn)
+        .await
+}
+
+pub async fn create_device(conn: &mut AsyncPgConnection, device_state: &DeviceState) -> Result<(), diesel::result::Error> {
+    conn.transaction::<_, diesel::result::Error, _>(|tconn| {
+        async move {
+            diesel::insert_into(devices::dsl::devices)
+                .values(&device_state.device)
+                .execute(tconn)
+                .await?;
+            diesel::insert_into(uptime_states::dsl::uptime_states)
+                .values(&device_state.uptime)
+                .execute(tconn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+pub async fn update_device(
+    conn: &mut AsyncPgConnection,
+    user_id: ID,
+    device_id: ID,
+    changes: &DeviceChanges,
+) -> Result<Device, diesel::result::Error> {
+    diesel::update(
+        devices::dsl::devices
+            .filter(devices::dsl::id.eq(device_id))
+            .filter(devices::dsl::user_id.eq(user_id)),
+    )
+    .set(changes)
+    .returning(Device::as_returning())
+    .get_result::<Device>(conn)
+    .await
+}
+
+/// Delete a device along with its uptime state and history (ON DELETE CASCADE).
+pub async fn delete_device(conn: &mut AsyncPgConnection, user_id: ID, device_id: ID) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        devices::dsl::devices
+            .filter(devices::dsl::id.eq(device_id))
+            .filter(devices::dsl::user_id.eq(user_id)),
+    )
+    .execute(conn)
+    .await
+}
+
 pub async fn create_new_invite(conn: &mut AsyncPgConnection, invite: &Invite) -> Result<(), String> {
     let owner_id = invite.owner_id.ok_or_else(|| "Invite must have an owner".to_string())?;
     let result = conn
@@ -178,7 +263,7 @@ pub async fn delete_user(conn: &mut AsyncPgConnection, user_id: ID) -> Result<us
                 .first(tconn)
                 .await?;
 
-            // Delete user (cascades uptime_states and invites via ON DELETE CASCADE)
+            // Delete user (cascades devices, uptime_states and invites via ON DELETE CASCADE)
             let deleted = diesel::delete(users::dsl::users.filter(users::dsl::id.eq(user_id)))
                 .execute(tconn)
                 .await?;
@@ -195,24 +280,459 @@ pub async fn delete_user(conn: &mut AsyncPgConnection, user_id: ID) -> Result<us
     .await
 }
 
+/// Hash tokens still stored in plaintext (rows written before token hashing). Returns how many were updated.
+pub async fn rehash_plaintext_tokens(conn: &mut AsyncPgConnection) -> Result<usize, diesel::result::Error> {
+    let mut updated = 0;
+
+    let user_tokens: Vec<(ID, String)> = users::dsl::users
+        .select((users::dsl::id, users::dsl::token_hash))
+        .load(conn)
+        .await?;
+    for (id, value) in user_tokens.into_iter().filter(|(_, v)| !tokens::is_hashed(v)) {
+        updated += diesel::update(users::dsl::users.filter(users::dsl::id.eq(id)))
+            .set(users::dsl::token_hash.eq(tokens::hash_token(&value)))
+            .execute(conn)
+            .await?;
+    }
+
+    let device_tokens: Vec<(ID, String)> = devices::dsl::devices
+        .select((devices::dsl::id, devices::dsl::token_hash))
+        .load(conn)
+        .await?;
+    for (id, value) in device_tokens.into_iter().filter(|(_, v)| !tokens::is_hashed(v)) {
+        updated += diesel::update(devices::dsl::devices.filter(devices::dsl::id.eq(id)))
+            .set(devices::dsl::token_hash.eq(tokens::hash_token(&value)))
+            .execute(conn)
+            .await?;
+    }
+
+    Ok(updated)
+}
+
+/// Give devices that still ping with their account's token (the `default` device of accounts created
+/// before heartbeat tokens existed) a new heartbeat token, so a token flashed into a board can't manage
+/// the account. The new token isn't shown anywhere, the board has to be re-flashed after regenerating
+/// it. Returns the affected devices.
+pub async fn split_shared_device_tokens(conn: &mut AsyncPgConnection) -> Result<Vec<(ID, ID)>, diesel::result::Error> {
+    let shared: Vec<(ID, ID)> = devices::dsl::devices
+        .inner_join(users::dsl::users)
+        .filter(devices::dsl::token_hash.eq(users::dsl::token_hash))
+        .select((devices::dsl::user_id, devices::dsl::id))
+        .load(conn)
+        .await?;
+    for (_, id) in &shared {
+        diesel::update(devices::dsl::devices.filter(devices::dsl::id.eq(id)))
+            .set(devices::dsl::token_hash.eq(tokens::hash_token(&tokens::new_heartbeat_token())))
+            .execute(conn)
+            .await?;
+    }
+    Ok(shared)
+}
+
 // User settings management
 
+/// Rotate the account token. Only the hash is stored, the plaintext is returned to be shown once.
 pub async fn regenerate_user_token(conn: &mut AsyncPgConnection, user_id: ID) -> Result<String, diesel::result::Error> {
-    let rng = rand::thread_rng();
-    let secret_part: String = rng.sample_iter(&Alphanumeric).take(16).map(char::from).collect();
-    let new_token = format!("tk_{secret_part}");
+    let new_token = tokens::new_account_token();
 
     diesel::update(users::dsl::users.filter(users::dsl::id.eq(user_id)))
-        .set(users::dsl::access_token.eq(&new_token))
+        .set(users::dsl::token_hash.eq(tokens::hash_token(&new_token)))
         .execute(conn)
         .await?;
 
     Ok(new_token)
 }
 
-pub async fn update_ntfy_enabled(conn: &mut AsyncPgConnection, ntfy_id: ID, enabled: bool) -> Result<(), diesel::result::Error> {
-    diesel::update(ntfy_users::dsl::ntfy_users.filter(ntfy_users::dsl::id.eq(ntfy_id)))
-        .set(ntfy_users::dsl::enabled.eq(enabled))
+/// Replace the status page key, the old URL stops working.
+pub async fn regenerate_status_page_key(conn: &mut AsyncPgConnection, user_id: ID) -> Result<String, diesel::result::Error> {
+    let new_key = tokens::new_status_page_key();
+
+    diesel::update(users::dsl::users.filter(users::dsl::id.eq(user_id)))
+        .set(users::dsl::status_page_key.eq(&new_key))
+        .execute(conn)
+        .await?;
+
+    Ok(new_key)
+}
+
+pub async fn regenerate_device_token(
+    conn: &mut AsyncPgConnection,
+    user_id: ID,
+    device_id: ID,
+) -> Result<String, diesel::result::Error> {
+    let new_token = tokens::new_heartbeat_token();
+
+    diesel::update(
+        devices::dsl::devices
+            .filter(devices::dsl::id.eq(device_id))
+            .filter(devices::dsl::user_id.eq(user_id)),
+    )
+    .set(devices::dsl::token_hash.eq(tokens::hash_token(&new_token)))
+    .execute(conn)
+    .await?;
+
+    Ok(new_token)
+}
+
+// Notification channels
+
+pub async fn get_channels_for_user(conn: &mut AsyncPgConnection, user_id: ID) -> Result<Vec<Channel>, diesel::result::Error> {
+    notification_channels::dsl::notification_channels
+        .filter(notification_channels::dsl::user_id.eq(user_id))
+        .order((
+            notification_channels::dsl::created_at.asc(),
+            notification_channels::dsl::id.asc(),
+        ))
+        .select(Channel::as_select())
+        .load(conn)
+        .await
+}
+
+pub async fn create_channel(conn: &mut AsyncPgConnection, channel: &Channel) -> Result<(), diesel::result::Error> {
+    diesel::insert_into(notification_channels::dsl::notification_channels)
+        .values(channel)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Persist the enabled flag and config of a channel (both are always written).
+pub async fn update_channel(conn: &mut AsyncPgConnection, channel: &Channel) -> Result<(), diesel::result::Error> {
+    diesel::update(
+        notification_channels::dsl::notification_channels
+            .filter(notification_channels::dsl::id.eq(channel.id))
+            .filter(notification_channels::dsl::user_id.eq(channel.user_id)),
+    )
+    .set((
+        notification_channels::dsl::enabled.eq(channel.enabled),
+        notification_channels::dsl::config.eq(&channel.config),
+    ))
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_channel(conn: &mut AsyncPgConnection, user_id: ID, channel_id: ID) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        notification_channels::dsl::notification_channels
+            .filter(notification_channels::dsl::id.eq(channel_id))
+            .filter(notification_channels::dsl::user_id.eq(user_id)),
+    )
+    .execute(conn)
+    .await
+}
+
+// Escalation steps
+
+pub async fn get_escalation_steps_for_user(
+    conn: &mut AsyncPgConnection,
+    user_id: ID,
+) -> Result<Vec<EscalationStep>, diesel::result::Error> {
+    escalation_steps::dsl::escalation_steps
+        .filter(escalation_steps::dsl::user_id.eq(user_id))
+        .order(escalation_steps::dsl::delay_minutes.asc())
+        .select(EscalationStep::as_select())
+        .load(conn)
+        .await
+}
+
+pub async fn create_escalation_step(conn: &mut AsyncPgConnection, step: &EscalationStep) -> Result<(), diesel::result::Error> {
+    diesel::insert_into(escalation_steps::dsl::escalation_steps)
+        .values(step)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_escalation_step(
+    conn: &mut AsyncPgConnection,
+    user_id: ID,
+    step_id: ID,
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        escalation_steps::dsl::escalation_steps
+            .filter(escalation_steps::dsl::id.eq(step_id))
+            .filter(escalation_steps::dsl::user_id.eq(user_id)),
+    )
+    .execute(conn)
+    .await
+}
+
+// Maintenance windows
+
+pub async fn get_maintenance_windows_for_user(
+    conn: &mut AsyncPgConnection,
+    user_id: ID,
+) -> Result<Vec<MaintenanceWindow>, diesel::result::Error> {
+    maintenance_windows::dsl::maintenance_windows
+        .filter(maintenance_windows::dsl::user_id.eq(user_id))
+        .order((maintenance_windows::dsl::created_at.asc(), maintenance_windows::dsl::id.asc()))
+        .select(MaintenanceWindow::as_select())
+        .load(conn)
+        .await
+}
+
+pub async fn create_maintenance_window(
+    conn: &mut AsyncPgConnection,
+    window: &MaintenanceWindow,
+) -> Result<(), diesel::result::Error> {
+    diesel::insert_into(maintenance_windows::dsl::maintenance_windows)
+        .values(window)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Replace everything but the id, owner and creation time of a window.
+pub async fn update_maintenance_window(
+    conn: &mut AsyncPgConnection,
+    window: &MaintenanceWindow,
+) -> Result<usize, diesel::result::Error> {
+    use maintenance_windows::dsl;
+    diesel::update(
+        dsl::maintenance_windows
+            .filter(dsl::id.eq(window.id))
+            .filter(dsl::user_id.eq(window.user_id)),
+    )
+    .set((
+        dsl::device_id.eq(window.device_id),
+        dsl::label.eq(&window.label),
+        dsl::weekdays.eq(window.weekdays),
+        dsl::start_minute.eq(window.start_minute),
+        dsl::end_minute.eq(window.end_minute),
+        dsl::starts_at.eq(window.starts_at),
+        dsl::ends_at.eq(window.ends_at),
+    ))
+    .execute(conn)
+    .await
+}
+
+pub async fn delete_maintenance_window(
+    conn: &mut AsyncPgConnection,
+    user_id: ID,
+    window_id: ID,
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        maintenance_windows::dsl::maintenance_windows
+            .filter(maintenance_windows::dsl::id.eq(window_id))
+            .filter(maintenance_windows::dsl::user_id.eq(user_id)),
+    )
+    .execute(conn)
+    .await
+}
+
+/// Delete the user's one-off windows that ended before `now`.
+pub async fn delete_ended_maintenance_windows(
+    conn: &mut AsyncPgConnection,
+    user_id: ID,
+    now: SystemTime,
+) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        maintenance_windows::dsl::maintenance_windows
+            .filter(maintenance_windows::dsl::user_id.eq(user_id))
+            .filter(maintenance_windows::dsl::ends_at.le(now)),
+    )
+    .execute(conn)
+    .await
+}
+
+// Status pages
+
+pub async fn get_status_pages_for_user(
+    conn: &mut AsyncPgConnection,
+    user_id: ID,
+) -> Result<Vec<StatusPage>, diesel::result::Error> {
+    status_pages::dsl::status_pages
+        .filter(status_pages::dsl::user_id.eq(user_id))
+        .order((status_pages::dsl::created_at.asc(), status_pages::dsl::id.asc()))
+        .select(StatusPage::as_select())
+        .load(conn)
+        .await
+}
+
+/// Pages listed in the directory, by title.
+pub async fn get_public_status_pages(conn: &mut AsyncPgConnection) -> Result<Vec<StatusPage>, diesel::result::Error> {
+    status_pages::dsl::status_pages
+        .filter(status_pages::dsl::visibility.eq(PageVisibility::Public))
+        .order((status_pages::dsl::title.asc(), status_pages::dsl::slug.asc()))
+        .select(StatusPage::as_select())
+        .load(conn)
+        .await
+}
+
+pub async fn get_status_page_by_slug(
+    conn: &mut AsyncPgConnection,
+    slug: &str,
+) -> Result<Option<StatusPage>, diesel::result::Error> {
+    status_pages::dsl::status_pages
+        .filter(status_pages::dsl::slug.eq(slug))
+        .select(StatusPage::as_select())
+        .first(conn)
+        .await
+        .optional()
+}
+
+/// Monitors of the given pages, in page order.
+pub async fn get_status_page_monitors(
+    conn: &mut AsyncPgConnection,
+    page_ids: &[ID],
+) -> Result<Vec<StatusPageMonitor>, diesel::result::Error> {
+    status_page_monitors::dsl::status_page_monitors
+        .filter(status_page_monitors::dsl::page_id.eq_any(page_ids))
+        .order(status_page_monitors::dsl::position.asc())
+        .select(StatusPageMonitor::as_select())
+        .load(conn)
+        .await
+}
+
+pub async fn create_status_page(
+    conn: &mut AsyncPgConnection,
+    page: &StatusPage,
+    monitors: &[StatusPageMonitor],
+) -> Result<(), diesel::result::Error> {
+    conn.transaction::<_, diesel::result::Error, _>(|tconn| {
+        async move {
+            diesel::insert_into(status_pages::dsl::status_pages)
+                .values(page)
+                .execute(tconn)
+                .await?;
+            diesel::insert_into(status_page_monitors::dsl::status_page_monitors)
+                .values(monitors)
+                .execute(tconn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Replace the slug, title, visibility and monitors of a page, returns the number of pages updated.
+pub async fn update_status_page(
+    conn: &mut AsyncPgConnection,
+    page: &StatusPage,
+    monitors: &[StatusPageMonitor],
+) -> Result<usize, diesel::result::Error> {
+    conn.transaction::<_, diesel::result::Error, _>(|tconn| {
+        async move {
+            use status_pages::dsl;
+            let updated = diesel::update(
+                dsl::status_pages
+                    .filter(dsl::id.eq(page.id))
+                    .filter(dsl::user_id.eq(page.user_id)),
+            )
+            .set((
+                dsl::slug.eq(&page.slug),
+                dsl::title.eq(&page.title),
+                dsl::visibility.eq(page.visibility),
+            ))
+            .execute(tconn)
+            .await?;
+            if updated > 0 {
+                diesel::delete(
+                    status_page_monitors::dsl::status_page_monitors.filter(status_page_monitors::dsl::page_id.eq(page.id)),
+                )
+                .execute(tconn)
+                .await?;
+                diesel::insert_into(status_page_monitors::dsl::status_page_monitors)
+                    .values(monitors)
+                    .execute(tconn)
+                    .await?;
+            }
+            Ok(updated)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+pub async fn delete_status_page(conn: &mut AsyncPgConnection, user_id: ID, page_id: ID) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        status_pages::dsl::status_pages
+            .filter(status_pages::dsl::id.eq(page_id))
+            .filter(status_pages::dsl::user_id.eq(user_id)),
+    )
+    .execute(conn)
+    .await
+}
+
+// Planned outages
+
+pub async fn get_planned_outages(conn: &mut AsyncPgConnection) -> Result<Vec<PlannedOutage>, diesel::result::Error> {
+    planned_outages::dsl::planned_outages
+        .order((planned_outages::dsl::queue.asc(), planned_outages::dsl::starts_at.asc()))
+        .select(PlannedOutage::as_select())
+        .load(conn)
+        .await
+}
+
+/// Store an imported schedule: per queue, the slots overlapping the span the import covers are
+/// replaced (see `schedule::import_spans`), slots that ended before `prune_before` are deleted.
+pub async fn replace_planned_outages(
+    conn: &mut AsyncPgConnection,
+    outages: &[PlannedOutage],
+    prune_before: SystemTime,
+) -> Result<(), diesel::result::Error> {
+    use planned_outages::dsl;
+    conn.transaction::<_, diesel::result::Error, _>(|tconn| {
+        async move {
+            for (queue, (from, until)) in crate::schedule::import_spans(outages) {
+                diesel::delete(
+                    dsl::planned_outages
+                        .filter(dsl::queue.eq(queue))
+                        .filter(dsl::starts_at.lt(until))
+                        .filter(dsl::ends_at.gt(from)),
+                )
+                .execute(tconn)
+                .await?;
+            }
+            diesel::delete(dsl::planned_outages.filter(dsl::ends_at.lt(prune_before)))
+                .execute(tconn)
+                .await?;
+            // @NOTE: Postgres takes at most 65535 bind parameters per statement, 5 per row.
+            for chunk in outages.chunks(1000) {
+                diesel::insert_into(dsl::planned_outages).values(chunk).execute(tconn).await?;
+            }
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+// Named API tokens
+
+pub async fn get_all_api_tokens(conn: &mut AsyncPgConnection) -> Result<Vec<ApiToken>, diesel::result::Error> {
+    api_tokens::dsl::api_tokens.select(ApiToken::as_select()).load(conn).await
+}
+
+pub async fn create_api_token(conn: &mut AsyncPgConnection, token: &ApiToken) -> Result<(), diesel::result::Error> {
+    diesel::insert_into(api_tokens::dsl::api_tokens)
+        .values(token)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_api_token(conn: &mut AsyncPgConnection, user_id: ID, token_id: ID) -> Result<usize, diesel::result::Error> {
+    diesel::delete(
+        api_tokens::dsl::api_tokens
+            .filter(api_tokens::dsl::id.eq(token_id))
+            .filter(api_tokens::dsl::user_id.eq(user_id)),
+    )
+    .execute(conn)
+    .await
+}
+
+/// Persist the last use of a token (called from the batched flush, never per request).
+pub async fn update_api_token_last_used(
+    conn: &mut AsyncPgConnection,
+    token_id: ID,
+    at: SystemTime,
+    ip: Option<&str>,
+) -> Result<(), diesel::result::Error> {
+    diesel::update(api_tokens::dsl::api_tokens.filter(api_tokens::dsl::id.eq(token_id)))
+        .set((api_tokens::dsl::last_used_at.eq(at), api_tokens::dsl::last_used_ip.eq(ip)))
         .execute(conn)
         .await?;
     Ok(())
@@ -230,6 +750,35 @@ pub async fn update_user_language(
     Ok(())
 }
 
+/// Write the device and account parts of a settings update together, either both land or neither.
+/// Returns the updated device, None if its part was empty.
+pub async fn update_settings(
+    conn: &mut AsyncPgConnection,
+    user_id: ID,
+    device_id: ID,
+    device: &DeviceChanges,
+    user: &UserChanges,
+) -> Result<Option<Device>, diesel::result::Error> {
+    conn.transaction::<_, diesel::result::Error, _>(|tconn| {
+        async move {
+            let updated = if device.is_empty() {
+                None
+            } else {
+                Some(update_device(tconn, user_id, device_id, device).await?)
+            };
+            if !user.is_empty() {
+                diesel::update(users::dsl::users.filter(users::dsl::id.eq(user_id)))
+                    .set(user)
+                    .execute(tconn)
+                    .await?;
+            }
+            Ok(updated)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
 pub async fn update_uptime_state(conn: &mut AsyncPgConnection, state: &UptimeState) -> Result<(), diesel::result::Error> {
     diesel::update(uptime_states::dsl::uptime_states.filter(uptime_states::dsl::id.eq(state.id)))
         .set((
@@ -237,52 +786,299 @@ pub async fn update_uptime_state(conn: &mut AsyncPgConnection, state: &UptimeSta
             uptime_states::dsl::status.eq(state.status),
             uptime_states::dsl::state_changed_at.eq(state.state_changed_at),
             uptime_states::dsl::pre_pause_status.eq(state.pre_pause_status),
+            uptime_states::dsl::flapping_since.eq(state.flapping_since),
+            uptime_states::dsl::held_outage_secs.eq(state.held_outage_secs),
+            uptime_states::dsl::recovery_started_at.eq(state.recovery_started_at),
+            uptime_states::dsl::recovery_count.eq(state.recovery_count),
+            uptime_states::dsl::escalated_minutes.eq(state.escalated_minutes),
         ))
         .execute(conn)
         .await?;
     Ok(())
 }
 
-pub async fn update_user_settings(
+/// Persist a state transition together with its history entry and the notifications it
+/// triggered, so the three never diverge.
+pub async fn record_transition(
     conn: &mut AsyncPgConnection,
-    user_id: ID,
-    up_delay: Option<i16>,
-    maint_start: Option<Option<i16>>,
-    maint_end: Option<Option<i16>>,
+    state: &UptimeState,
+    event: &UptimeEvent,
+    outbox: &[OutboxItem],
 ) -> Result<(), diesel::result::Error> {
-    let maint = match (maint_start, maint_end) {
-        (Some(s), Some(e)) => Some((s, e)),
-        _ => None,
-    };
-    let target = users::dsl::users.filter(users::dsl::id.eq(user_id));
-    match (up_delay, maint) {
-        (Some(d), Some((s, e))) => {
-            diesel::update(target)
-                .set((
-                    users::dsl::up_delay.eq(d),
-                    users::dsl::maint_window_start_utc.eq(s),
-                    users::dsl::maint_window_end_utc.eq(e),
-                ))
-                .execute(conn)
+    conn.transaction::<_, diesel::result::Error, _>(|tconn| {
+        async move {
+            update_uptime_state(tconn, state).await?;
+            diesel::insert_into(uptime_events::dsl::uptime_events)
+                .values(event)
+                .execute(tconn)
                 .await?;
+            if !outbox.is_empty() {
+                diesel::insert_into(notification_outbox::dsl::notification_outbox)
+                    .values(outbox)
+                    .execute(tconn)
+                    .await?;
+            }
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Persist a state change that has no history entry (a device settling after flapping, a held back
+/// outage becoming due) together with the notifications it triggered.
+pub async fn record_notice(
+    conn: &mut AsyncPgConnection,
+    state: &UptimeState,
+    outbox: &[OutboxItem],
+) -> Result<(), diesel::result::Error> {
+    conn.transaction::<_, diesel::result::Error, _>(|tconn| {
+        async move {
+            update_uptime_state(tconn, state).await?;
+            if !outbox.is_empty() {
+                diesel::insert_into(notification_outbox::dsl::notification_outbox)
+                    .values(outbox)
+                    .execute(tconn)
+                    .await?;
+            }
+            Ok(())
         }
-        (Some(d), None) => {
-            diesel::update(target).set(users::dsl::up_delay.eq(d)).execute(conn).await?;
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Outbox items due for a delivery attempt at `now`, oldest first. They are leased until
+/// `lease_until`: if the outcome is never stored (e.g. a crash mid-delivery) they become due again then.
+pub async fn claim_due_outbox_items(
+    conn: &mut AsyncPgConnection,
+    now: SystemTime,
+    lease_until: SystemTime,
+    limit: i64,
+) -> Result<Vec<OutboxItem>, diesel::result::Error> {
+    conn.transaction::<_, diesel::result::Error, _>(|tconn| {
+        async move {
+            let items: Vec<OutboxItem> = notification_outbox::dsl::notification_outbox
+                .filter(notification_outbox::dsl::failed_at.is_null())
+                .filter(notification_outbox::dsl::next_attempt_at.le(now))
+                .order(notification_outbox::dsl::next_attempt_at.asc())
+                .limit(limit)
+                .for_update()
+                .skip_locked()
+                .select(OutboxItem::as_select())
+                .load(tconn)
+                .await?;
+            let ids: Vec<ID> = items.iter().map(|item| item.id).collect();
+            diesel::update(notification_outbox::dsl::notification_outbox.filter(notification_outbox::dsl::id.eq_any(&ids)))
+                .set(notification_outbox::dsl::next_attempt_at.eq(lease_until))
+                .execute(tconn)
+                .await?;
+            Ok(items)
         }
-        (None, Some((s, e))) => {
-            diesel::update(target)
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Replace the payload of `item` by a digest of it and the items with `merged_ids`, which are removed.
+pub async fn merge_outbox_items(
+    conn: &mut AsyncPgConnection,
+    item: &OutboxItem,
+    merged_ids: &[ID],
+) -> Result<(), diesel::result::Error> {
+    conn.transaction::<_, diesel::result::Error, _>(|tconn| {
+        async move {
+            diesel::update(notification_outbox::dsl::notification_outbox.filter(notification_outbox::dsl::id.eq(item.id)))
                 .set((
-                    users::dsl::maint_window_start_utc.eq(s),
-                    users::dsl::maint_window_end_utc.eq(e),
+                    notification_outbox::dsl::event.eq(&item.event),
+                    notification_outbox::dsl::payload.eq(&item.payload),
                 ))
-                .execute(conn)
+                .execute(tconn)
+                .await?;
+            diesel::delete(notification_outbox::dsl::notification_outbox.filter(notification_outbox::dsl::id.eq_any(merged_ids)))
+                .execute(tconn)
                 .await?;
+            Ok(())
         }
-        (None, None) => {}
-    }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Store the outcome of a failed delivery attempt (attempts, next_attempt_at, last_error, failed_at).
+pub async fn update_outbox_item(conn: &mut AsyncPgConnection, item: &OutboxItem) -> Result<(), diesel::result::Error> {
+    diesel::update(notification_outbox::dsl::notification_outbox.filter(notification_outbox::dsl::id.eq(item.id)))
+        .set((
+            notification_outbox::dsl::attempts.eq(item.attempts),
+            notification_outbox::dsl::next_attempt_at.eq(item.next_attempt_at),
+            notification_outbox::dsl::last_error.eq(&item.last_error),
+            notification_outbox::dsl::failed_at.eq(item.failed_at),
+        ))
+        .execute(conn)
+        .await?;
     Ok(())
 }
 
+pub async fn delete_outbox_item(conn: &mut AsyncPgConnection, item_id: ID) -> Result<usize, diesel::result::Error> {
+    diesel::delete(notification_outbox::dsl::notification_outbox.filter(notification_outbox::dsl::id.eq(item_id)))
+        .execute(conn)
+        .await
+}
+
+/// Queued notifications oldest first, only the undeliverable ones if `failed_only`.
+pub async fn get_outbox_items(
+    conn: &mut AsyncPgConnection,
+    failed_only: bool,
+    limit: i64,
+) -> Result<Vec<OutboxItem>, diesel::result::Error> {
+    let mut query = notification_outbox::dsl::notification_outbox
+        .order(notification_outbox::dsl::created_at.asc())
+        .limit(limit)
+        .select(OutboxItem::as_select())
+        .into_boxed();
+    if failed_only {
+        query = query.filter(notification_outbox::dsl::failed_at.is_not_null());
+    }
+    query.load(conn).await
+}
+
+/// Queue an undeliverable item again with a fresh set of attempts. Returns 0 if there is no such failed item.
+pub async fn retry_outbox_item(
+    conn: &mut AsyncPgConnection,
+    item_id: ID,
+    now: SystemTime,
+) -> Result<usize, diesel::result::Error> {
+    diesel::update(
+        notification_outbox::dsl::notification_outbox
+            .filter(notification_outbox::dsl::id.eq(item_id))
+            .filter(notification_outbox::dsl::failed_at.is_not_null()),
+    )
+    .set((
+        notification_outbox::dsl::attempts.eq(0),
+        notification_outbox::dsl::next_attempt_at.eq(now),
+        notification_outbox::dsl::failed_at.eq(None::<SystemTime>),
+    ))
+    .execute(conn)
+    .await
+}
+
+/// Forget undeliverable items that failed before `before`.
+pub async fn delete_failed_outbox_items(conn: &mut AsyncPgConnection, before: SystemTime) -> Result<usize, diesel::result::Error> {
+    diesel::delete(notification_outbox::dsl::notification_outbox.filter(notification_outbox::dsl::failed_at.lt(before)))
+        .execute(conn)
+        .await
+}
+
+/// (pending items, creation time of the oldest pending one, failed items), for the outbox metrics.
+pub async fn get_outbox_stats(conn: &mut AsyncPgConnection) -> Result<(i64, Option<SystemTime>, i64), diesel::result::Error> {
+    use diesel::dsl::count_star;
+    let (pending, oldest) = notification_outbox::dsl::notification_outbox
+        .filter(notification_outbox::dsl::failed_at.is_null())
+        .select((count_star(), diesel::dsl::min(notification_outbox::dsl::created_at)))
+        .first::<(i64, Option<SystemTime>)>(conn)
+        .await?;
+    let failed = notification_outbox::dsl::notification_outbox
+        .filter(notification_outbox::dsl::failed_at.is_not_null())
+        .count()
+        .get_result(conn)
+        .await?;
+    Ok((pending, oldest, failed))
+}
+
+/// Newest-first page of a user's uptime history, optionally for a single device. `cursor` is the
+/// id of the last event of the previous page; events strictly older than it (by `at`, then id) are returned.
+/// Entries that keep the status, like the start and end of a maintenance silence, are left out, stats
+/// read those through `get_uptime_window`.
+pub async fn get_uptime_events(
+    conn: &mut AsyncPgConnection,
+    user_id: ID,
+    device_id: Option<ID>,
+    since: Option<SystemTime>,
+    until: Option<SystemTime>,
+    cursor: Option<ID>,
+    limit: i64,
+) -> Result<Vec<UptimeEvent>, diesel::result::Error> {
+    use uptime_events::dsl;
+
+    let mut query = dsl::uptime_events
+        .filter(dsl::user_id.eq(user_id))
+        .filter(dsl::from_status.ne(dsl::to_status))
+        .into_boxed();
+    if let Some(device_id) = device_id {
+        query = query.filter(dsl::device_id.eq(device_id));
+    }
+    if let Some(since) = since {
+        query = query.filter(dsl::at.ge(since));
+    }
+    if let Some(until) = until {
+        query = query.filter(dsl::at.lt(until));
+    }
+    if let Some(cursor) = cursor {
+        let (c_at, c_id) = dsl::uptime_events
+            .filter(dsl::id.eq(cursor))
+            .filter(dsl::user_id.eq(user_id))
+            .select((dsl::at, dsl::id))
+            .first::<(SystemTime, ID)>(conn)
+            .await?;
+        query = query.filter(dsl::at.lt(c_at).or(dsl::at.eq(c_at).and(dsl::id.lt(c_id))));
+    }
+    query
+        .order((dsl::at.desc(), dsl::id.desc()))
+        .limit(limit)
+        .select(UptimeEvent::as_select())
+        .load::<UptimeEvent>(conn)
+        .await
+}
+
+/// All of a device's events in `[since, until)`, oldest first (for stats over a window).
+pub async fn get_uptime_events_between(
+    conn: &mut AsyncPgConnection,
+    device_id: ID,
+    since: SystemTime,
+    until: SystemTime,
+) -> Result<Vec<UptimeEvent>, diesel::result::Error> {
+    use uptime_events::dsl;
+
+    dsl::uptime_events
+        .filter(dsl::device_id.eq(device_id))
+        .filter(dsl::at.ge(since))
+        .filter(dsl::at.lt(until))
+        .order((dsl::at.asc(), dsl::id.asc()))
+        .select(UptimeEvent::as_select())
+        .load::<UptimeEvent>(conn)
+        .await
+}
+
+/// The most recent event strictly before `before`, i.e. the state a window starts in.
+pub async fn get_last_uptime_event_before(
+    conn: &mut AsyncPgConnection,
+    device_id: ID,
+    before: SystemTime,
+) -> Result<Option<UptimeEvent>, diesel::result::Error> {
+    use uptime_events::dsl;
+
+    dsl::uptime_events
+        .filter(dsl::device_id.eq(device_id))
+        .filter(dsl::at.lt(before))
+        .order((dsl::at.desc(), dsl::id.desc()))
+        .select(UptimeEvent::as_select())
+        .first::<UptimeEvent>(conn)
+        .await
+        .optional()
+}
+
+/// The last event before `start` and the events in `[start, end)` of a device, as `stats::compute` takes them.
+pub async fn get_uptime_window(
+    conn: &mut AsyncPgConnection,
+    device_id: ID,
+    start: SystemTime,
+    end: SystemTime,
+) -> Result<(Option<UptimeEvent>, Vec<UptimeEvent>), diesel::result::Error> {
+    let previous = get_last_uptime_event_before(conn, device_id, start).await?;
+    let events = get_uptime_events_between(conn, device_id, start, end).await?;
+    Ok((previous, events))
+}
+
 pub async fn get_all_unused_invites(conn: &mut AsyncPgConnection) -> Result<Vec<Invite>, diesel::result::Error> {
     invites::dsl::invites
         .filter(invites::dsl::is_used.eq(false))
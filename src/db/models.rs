@@ -1,12 +1,32 @@
-use crate::schema::{invites, ntfy_users, uptime_states, users};
+use crate::channels::{Notification, Priority};
+use crate::schema::{
+    api_tokens, devices, escalation_steps, invites, maintenance_windows, notification_channels, notification_outbox, ntfy_users,
+    planned_outages, status_page_monitors, status_pages, uptime_events, uptime_states, users,
+};
+use crate::tokens;
+use chrono::{DateTime, Datelike, NaiveTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
 use rand::{Rng, distributions::Alphanumeric};
-use rocket::serde::{Deserialize, Serialize};
+use rocket::serde::{Deserialize, Serialize, Serializer, json::Value};
 use rocket_db_pools::diesel::prelude::*;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
 pub type ID = Uuid;
 
+/// Serializes a timestamp as whole seconds since the Unix epoch (same unit the API accepts in queries).
+pub fn serialize_epoch_secs<S: Serializer>(t: &SystemTime, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_u64(t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs())
+}
+
+/// Same as `serialize_epoch_secs`, with `None` as null.
+pub fn serialize_opt_epoch_secs<S: Serializer>(t: &Option<SystemTime>, s: S) -> Result<S::Ok, S::Error> {
+    match t {
+        Some(t) => serialize_epoch_secs(t, s),
+        None => s.serialize_none(),
+    }
+}
+
 /// Ntfy.sh instance 'managed' user for whoever is using the service,
 /// which is created with read-only permissions for their own topic.
 /// A lot of this information is provided to the user and can be reset
@@ -16,7 +36,6 @@ pub type ID = Uuid;
 #[serde(crate = "rocket::serde")]
 pub struct NtfyUser {
     pub id: ID,
-    pub enabled: bool,
     pub topic: String,
     #[serde(rename(serialize = "permission"))]
     pub topic_permission: String,
@@ -42,46 +61,183 @@ pub struct User {
     pub created_at: SystemTime,
     pub invites_limit: i64,
     pub invites_used: i64,
-    pub access_token: String,
-    pub up_delay: i16,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
     pub ntfy_id: ID,
     pub language_code: String,
-    pub maint_window_start_utc: Option<i16>,
-    pub maint_window_end_utc: Option<i16>,
+    /// Overrides per event kind, see `notifications::EventSettings`.
+    #[serde(skip_serializing)]
+    pub notification_settings: Value,
+    /// Daily quiet hours in minutes from local midnight, both set or both null.
+    pub quiet_hours_start: Option<i16>,
+    pub quiet_hours_end: Option<i16>,
+    /// IANA time zone, daily windows and times in notifications are local to it.
+    pub timezone: String,
+    /// Queue (group) of the account in the planned outage schedules, see `schedule`.
+    pub outage_queue: Option<String>,
+    /// Secret part of the status page URL, see `status_page`.
+    #[serde(skip_serializing)]
+    pub status_page_key: String,
 }
 
 impl User {
-    pub fn new(user_type: UserType, invites_limit: i64, up_delay: Option<u16>, language_code: String, ntfy: &NtfyUser) -> User {
-        let rng = rand::thread_rng();
-        let secret_part: String = rng.sample_iter(&Alphanumeric).take(16).map(char::from).collect();
+    pub fn new(user_type: UserType, invites_limit: i64, language_code: String, ntfy: &NtfyUser, token_hash: String) -> User {
         User {
             id: Uuid::new_v4(),
             user_type,
             created_at: SystemTime::now(),
             invites_limit,
             invites_used: 0,
-            access_token: format!("tk_{secret_part}"),
-            up_delay: up_delay.unwrap_or(60) as i16,
+            token_hash,
             ntfy_id: ntfy.id,
             language_code,
-            maint_window_start_utc: None,
-            maint_window_end_utc: None,
+            notification_settings: Value::Object(Default::default()),
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+            timezone: "UTC".to_string(),
+            outage_queue: None,
+            status_page_key: tokens::new_status_page_key(),
         }
     }
 
-    /// Check if the current UTC time-of-day falls within the maintenance window.
-    /// Handles midnight-spanning windows (e.g., 23:50-00:10).
-    pub fn is_in_maintenance_window(&self, now_utc_minutes: i32) -> bool {
-        match (self.maint_window_start_utc, self.maint_window_end_utc) {
-            (Some(start), Some(end)) => {
-                let (s, e, n) = (start as i32, end as i32, now_utc_minutes);
-                if s < e { n >= s && n < e } else { n >= s || n < e }
-            }
-            _ => false,
+    /// The account's time zone, UTC if the stored one is unknown (e.g. dropped from the tz database).
+    pub fn tz(&self) -> Tz {
+        self.timezone.parse().unwrap_or(Tz::UTC)
+    }
+
+    /// `now` in the account's time zone, for maintenance window checks.
+    pub fn local_time(&self, now: SystemTime) -> DateTime<Tz> {
+        DateTime::<Utc>::from(now).with_timezone(&self.tz())
+    }
+
+    /// End of the quiet hours `now` falls into, None outside of them (or without quiet hours).
+    pub fn quiet_hours_until(&self, now: SystemTime) -> Option<SystemTime> {
+        let (Some(start), Some(end)) = (self.quiet_hours_start, self.quiet_hours_end) else {
+            return None;
+        };
+        let tz = self.tz();
+        let local = self.local_time(now);
+        let minute = minute_of_day(&local);
+        if !in_daily_window(start, end, minute) {
+            return None;
+        }
+        let end_time = NaiveTime::from_hms_opt(end as u32 / 60, end as u32 % 60, 0)?;
+        let mut end_at = local.date_naive().and_time(end_time);
+        if end as i32 <= minute {
+            end_at += chrono::Duration::days(1);
+        }
+        // @NOTE: An end inside the hour skipped when DST starts is moved past the jump, one inside
+        //  the hour repeated when it ends is its first occurrence.
+        let end_at = tz
+            .from_local_datetime(&end_at)
+            .earliest()
+            .or_else(|| tz.from_local_datetime(&(end_at + chrono::Duration::hours(1))).earliest())?;
+        Some(end_at.with_timezone(&Utc).into())
+    }
+}
+
+/// Local time of day in minutes (0-1439).
+fn minute_of_day(local: &DateTime<Tz>) -> i32 {
+    (local.hour() * 60 + local.minute()) as i32
+}
+
+/// Whether `minute` (0-1439) falls within the daily window from `start` to `end`, which may span
+/// midnight (e.g., 23:50-00:10).
+fn in_daily_window(start: i16, end: i16, minute: i32) -> bool {
+    let (s, e, n) = (start as i32, end as i32, minute);
+    if s < e { n >= s && n < e } else { n >= s || n < e }
+}
+
+/// A monitored board (ESP32, Pico W, ...). Each device pings with its own token and has
+/// its own timeout and uptime state.
+#[derive(Debug, Clone, Serialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = devices)]
+#[serde(crate = "rocket::serde")]
+pub struct Device {
+    pub id: ID,
+    pub user_id: ID,
+    pub created_at: SystemTime,
+    pub name: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    pub up_delay: i16,
+    /// Seconds the device has to stay down before the outage is notified, shorter ones stay silent.
+    pub min_outage_secs: i32,
+    /// Up/Down flips within flap_window_minutes that mark the device as flapping, None disables it.
+    pub flap_threshold: Option<i16>,
+    pub flap_window_minutes: i16,
+    /// Minutes without a flip before a flapping device counts as stable again.
+    pub flap_stable_minutes: i16,
+    /// Heartbeats in a row needed before a Down device counts as Up again.
+    pub recovery_heartbeats: i16,
+}
+
+impl Device {
+    pub fn new(user_id: ID, name: String, up_delay: Option<u16>, token_hash: String) -> Device {
+        Device {
+            id: Uuid::new_v4(),
+            user_id,
+            created_at: SystemTime::now(),
+            name,
+            token_hash,
+            up_delay: up_delay.unwrap_or(60) as i16,
+            min_outage_secs: 0,
+            flap_threshold: None,
+            flap_window_minutes: 10,
+            flap_stable_minutes: 15,
+            recovery_heartbeats: 1,
         }
     }
 }
 
+/// Partial update of a device's settings. `None` leaves a column untouched,
+/// `Some(None)` clears a nullable one.
+#[derive(Debug, Default, AsChangeset)]
+#[diesel(table_name = devices)]
+pub struct DeviceChanges {
+    pub name: Option<String>,
+    pub up_delay: Option<i16>,
+    pub min_outage_secs: Option<i32>,
+    pub flap_threshold: Option<Option<i16>>,
+    pub flap_window_minutes: Option<i16>,
+    pub flap_stable_minutes: Option<i16>,
+    pub recovery_heartbeats: Option<i16>,
+}
+
+impl DeviceChanges {
+    /// Diesel refuses an empty changeset, callers skip the update instead.
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.up_delay.is_none()
+            && self.min_outage_secs.is_none()
+            && self.flap_threshold.is_none()
+            && self.flap_window_minutes.is_none()
+            && self.flap_stable_minutes.is_none()
+            && self.recovery_heartbeats.is_none()
+    }
+}
+
+/// Partial update of the account wide settings, same rules as `DeviceChanges`.
+#[derive(Debug, Default, AsChangeset)]
+#[diesel(table_name = users)]
+pub struct UserChanges {
+    pub notification_settings: Option<Value>,
+    pub quiet_hours_start: Option<Option<i16>>,
+    pub quiet_hours_end: Option<Option<i16>>,
+    pub timezone: Option<String>,
+    pub outage_queue: Option<Option<String>>,
+}
+
+impl UserChanges {
+    pub fn is_empty(&self) -> bool {
+        self.notification_settings.is_none()
+            && self.quiet_hours_start.is_none()
+            && self.quiet_hours_end.is_none()
+            && self.timezone.is_none()
+            && self.outage_queue.is_none()
+    }
+}
+
 // @NOTE: This is the the second out of 2 diesel enum packages that I've tried,
 //  and the first one is even more broken. This one works as long as you go into
 //  the src/schema.rs file and remove autogenerated trair 'Clone' from there. I
@@ -94,8 +250,9 @@ impl User {
 //  This was written at 4:44 am and I refuse to spent any more time on fixing
 //  this hack. I do not understand why the fu*ck is this feature not in diesel
 //  standard library.                                       - andrew, Nov 2 2024
-#[derive(Debug, Copy, Clone, Eq, PartialEq, diesel_derive_enum::DbEnum)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize, diesel_derive_enum::DbEnum)]
 #[ExistingTypePath = "crate::schema::sql_types::StatusEnum"]
+#[serde(crate = "rocket::serde")]
 pub enum UpStatus {
     Uninitialized,
     Up,
@@ -115,21 +272,101 @@ impl From<&UpStatus> for i64 {
     }
 }
 
-#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[derive(Debug, Clone, Serialize, Queryable, Selectable, Insertable)]
 #[diesel(table_name = uptime_states)]
+#[serde(crate = "rocket::serde")]
 pub struct UptimeState {
+    #[serde(skip_serializing)]
     pub id: ID,
+    #[serde(skip_serializing)]
     pub created_at: SystemTime,
+    #[serde(serialize_with = "serialize_epoch_secs")]
     pub touched_at: SystemTime,
     pub status: UpStatus,
-    pub user_id: Option<ID>,
+    #[serde(skip_serializing)]
+    pub device_id: ID,
+    #[serde(serialize_with = "serialize_epoch_secs")]
     pub state_changed_at: SystemTime,
+    #[serde(skip_serializing)]
     pub pre_pause_status: Option<UpStatus>,
+    /// Set while the device flaps, see `DeviceState::record_flip`.
+    #[serde(serialize_with = "serialize_opt_epoch_secs")]
+    pub flapping_since: Option<SystemTime>,
+    /// Seconds power was on before the current outage, while its notification is held back (see
+    /// `DeviceState::pending_outage`).
+    #[serde(skip_serializing)]
+    pub held_outage_secs: Option<i64>,
+    /// First heartbeat of a recovery that isn't confirmed yet, see `touch`.
+    #[serde(skip_serializing)]
+    pub recovery_started_at: Option<SystemTime>,
+    /// Heartbeats seen since recovery_started_at.
+    #[serde(skip_serializing)]
+    pub recovery_count: i16,
+    /// delay_minutes of the last escalation step sent for the current outage, 0 if none was.
+    #[serde(skip_serializing)]
+    pub escalated_minutes: i32,
+}
+
+/// What caused a recorded state transition.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, diesel_derive_enum::DbEnum)]
+#[ExistingTypePath = "crate::schema::sql_types::EventSourceEnum"]
+#[serde(crate = "rocket::serde")]
+pub enum EventSource {
+    /// Device ping (Uninitialized/Down → Up)
+    Heartbeat,
+    /// up_delay expired without a ping (Up → Down)
+    Timeout,
+    Pause,
+    Unpause,
+    /// Timeout expired inside the maintenance window. The Down transition is suppressed,
+    /// so from_status == to_status; the next event marks the end of the suppressed period.
+    Maintenance,
+}
+
+/// Single entry of the uptime history. Rows are only ever inserted.
+#[derive(Debug, Clone, Serialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = uptime_events)]
+#[serde(crate = "rocket::serde")]
+pub struct UptimeEvent {
+    pub id: ID,
+    #[serde(skip_serializing)]
+    pub user_id: ID,
+    pub device_id: ID,
+    pub from_status: UpStatus,
+    pub to_status: UpStatus,
+    #[serde(serialize_with = "serialize_epoch_secs")]
+    pub at: SystemTime,
+    /// Seconds spent in from_status before this transition.
+    pub duration_in_previous_state: i64,
+    pub source: EventSource,
+}
+
+impl UptimeEvent {
+    pub fn new(
+        user_id: ID,
+        device_id: ID,
+        from: UpStatus,
+        to: UpStatus,
+        at: SystemTime,
+        previous: Duration,
+        source: EventSource,
+    ) -> Self {
+        UptimeEvent {
+            id: Uuid::new_v4(),
+            user_id,
+            device_id,
+            from_status: from,
+            to_status: to,
+            at,
+            duration_in_previous_state: previous.as_secs() as i64,
+            source,
+        }
+    }
 }
 
 /// Result of a touch() call indicating what state transition occurred.
 pub enum TouchResult {
-    /// No state change (device was already Up or Paused)
+    /// No state change (device was already Up or Paused, or its recovery isn't confirmed yet)
     NoChange,
     /// Device connected for the first time (Uninitialized → Up)
     Connected,
@@ -138,23 +375,29 @@ pub enum TouchResult {
 }
 
 impl UptimeState {
-    pub fn new(user_id: ID) -> UptimeState {
+    pub fn new(device_id: ID) -> UptimeState {
         let now = SystemTime::now();
         UptimeState {
             id: Uuid::new_v4(),
             created_at: now,
             touched_at: now,
             status: UpStatus::Uninitialized,
-            user_id: Some(user_id),
+            device_id,
             state_changed_at: now,
             pre_pause_status: None,
+            flapping_since: None,
+            held_outage_secs: None,
+            recovery_started_at: None,
+            recovery_count: 0,
+            escalated_minutes: 0,
         }
     }
 
-    /// Called when device pings. Returns the state transition that occurred.
-    pub fn touch(&mut self) -> TouchResult {
-        let now = SystemTime::now();
-        self.touched_at = now;
+    /// Called when device pings at `now`. Returns the state transition that occurred.
+    /// A Down device only comes back after `device.recovery_heartbeats` heartbeats in a row, each
+    /// within up_delay of the previous one. The outage then ends at the first of them.
+    pub fn touch(&mut self, now: SystemTime, device: &Device) -> TouchResult {
+        let last_touch = std::mem::replace(&mut self.touched_at, now);
 
         if self.status == UpStatus::Uninitialized {
             self.status = UpStatus::Up;
@@ -163,10 +406,25 @@ impl UptimeState {
         }
 
         if self.status == UpStatus::Down {
+            // A gap longer than up_delay means power went away again, the recovery starts over
+            let window = Duration::from_secs(device.up_delay as u64);
+            let recovered_at = match self.recovery_started_at {
+                Some(started) if now.duration_since(last_touch).unwrap_or_default() <= window => started,
+                _ => {
+                    self.recovery_count = 0;
+                    now
+                }
+            };
+            self.recovery_count = self.recovery_count.saturating_add(1);
+            if self.recovery_count < device.recovery_heartbeats {
+                self.recovery_started_at = Some(recovered_at);
+                return TouchResult::NoChange;
+            }
             // Calculate how long power was off (since last state change to Down)
-            let duration = now.duration_since(self.state_changed_at).unwrap_or_default();
+            let duration = recovered_at.duration_since(self.state_changed_at).unwrap_or_default();
             self.status = UpStatus::Up;
-            self.state_changed_at = now;
+            self.state_changed_at = recovered_at;
+            (self.recovery_started_at, self.recovery_count) = (None, 0);
             return TouchResult::Restored(duration);
         }
         TouchResult::NoChange
@@ -181,6 +439,7 @@ impl UptimeState {
             let duration = now.duration_since(self.state_changed_at).ok();
             self.status = UpStatus::Down;
             self.state_changed_at = now;
+            self.escalated_minutes = 0;
             return duration;
         }
         None
@@ -192,6 +451,8 @@ impl UptimeState {
             UpStatus::Up | UpStatus::Down => {
                 self.pre_pause_status = Some(self.status);
                 self.status = UpStatus::Paused;
+                (self.recovery_started_at, self.recovery_count) = (None, 0);
+                self.state_changed_at = SystemTime::now();
                 Ok(())
             }
             UpStatus::Paused => Err("Already paused"),
@@ -213,13 +474,155 @@ impl UptimeState {
     }
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct DeviceState {
+    pub device: Device,
+    pub uptime: UptimeState,
+    /// When a Maintenance event was recorded for the current silence, so the background loop
+    /// records it only once. Cleared by the next event (heartbeat, timeout, pause).
+    #[serde(skip_serializing)]
+    pub maint_suppressed_since: Option<SystemTime>,
+    /// Up/Down flips within the flap window, oldest first.
+    #[serde(skip_serializing)]
+    pub recent_flips: Vec<SystemTime>,
+}
+
+impl DeviceState {
+    pub fn new(device: Device) -> DeviceState {
+        DeviceState::load(UptimeState::new(device.id), device)
+    }
+
+    /// Device with its persisted uptime state.
+    /// @NOTE: Flips are only tracked in memory, after a restart flap detection starts counting again.
+    pub fn load(uptime: UptimeState, device: Device) -> DeviceState {
+        DeviceState {
+            uptime,
+            device,
+            maint_suppressed_since: None,
+            recent_flips: Vec::new(),
+        }
+    }
+
+    /// Outage notification held back until min_outage_secs passed: (due at, how long power was on).
+    /// Dropped if the device comes back before that.
+    pub fn pending_outage(&self) -> Option<(SystemTime, Duration)> {
+        let up_for = Duration::from_secs(self.uptime.held_outage_secs?.max(0) as u64);
+        let hold = Duration::from_secs(self.device.min_outage_secs.max(0) as u64);
+        Some((self.uptime.state_changed_at + hold, up_for))
+    }
+
+    /// Note an Up/Down flip at `now` for flap detection. Returns true if it made the device flap.
+    pub fn record_flip(&mut self, now: SystemTime) -> bool {
+        let Some(threshold) = self.device.flap_threshold else {
+            self.recent_flips.clear();
+            return false;
+        };
+        let window = Duration::from_secs(self.device.flap_window_minutes as u64 * 60);
+        self.recent_flips
+            .retain(|at| now.duration_since(*at).unwrap_or_default() < window);
+        self.recent_flips.push(now);
+        if self.uptime.flapping_since.is_none() && self.recent_flips.len() >= threshold as usize {
+            self.uptime.flapping_since = Some(now);
+            return true;
+        }
+        false
+    }
+
+    /// Clear the flapping flag once the last flip is flap_stable_minutes old, or flap detection
+    /// got disabled. Returns true if the device just settled.
+    pub fn settle_flapping(&mut self, now: SystemTime) -> bool {
+        if self.uptime.flapping_since.is_none() {
+            return false;
+        }
+        // Every flip moves state_changed_at, which unlike recent_flips survives restarts
+        let stable = Duration::from_secs(self.device.flap_stable_minutes as u64 * 60);
+        let quiet_for = now.duration_since(self.uptime.state_changed_at).unwrap_or_default();
+        if self.device.flap_threshold.is_some() && quiet_for < stable {
+            return false;
+        }
+        self.uptime.flapping_since = None;
+        self.recent_flips.clear();
+        true
+    }
+
+    /// The last of `steps` (ordered by delay) that is due and wasn't sent during the current outage,
+    /// with how long the outage lasts. Outages that weren't notified (yet) get no reminders either.
+    pub fn due_escalation<'a>(&self, steps: &'a [EscalationStep], now: SystemTime) -> Option<(&'a EscalationStep, Duration)> {
+        if self.uptime.status != UpStatus::Down || self.uptime.held_outage_secs.is_some() || self.uptime.flapping_since.is_some() {
+            return None;
+        }
+        let elapsed = now.duration_since(self.uptime.state_changed_at).unwrap_or_default();
+        let minutes = (elapsed.as_secs() / 60) as i32;
+        steps
+            .iter()
+            .rev()
+            .find(|s| s.delay_minutes <= minutes && s.delay_minutes > self.uptime.escalated_minutes)
+            .map(|step| (step, elapsed))
+    }
+
+    /// Build the history entry for a transition that just happened, given the status and
+    /// state_changed_at from before it. Returns None if the status didn't actually change.
+    pub fn transition_event(&self, from: UpStatus, from_changed_at: SystemTime, source: EventSource) -> Option<UptimeEvent> {
+        if from == self.uptime.status {
+            return None;
+        }
+        let previous = self
+            .uptime
+            .state_changed_at
+            .duration_since(from_changed_at)
+            .unwrap_or_default();
+        Some(self.event(from, self.uptime.state_changed_at, previous, source))
+    }
+
+    /// History entry moving from `from` to the current status at `at`.
+    pub fn event(&self, from: UpStatus, at: SystemTime, previous: Duration, source: EventSource) -> UptimeEvent {
+        UptimeEvent::new(
+            self.device.user_id,
+            self.device.id,
+            from,
+            self.uptime.status,
+            at,
+            previous,
+            source,
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(crate = "rocket::serde")]
 pub struct UserState {
     pub user: User,
     pub ntfy: NtfyUser,
-    #[serde(skip_serializing)]
-    pub uptime: UptimeState,
+    /// Ordered by creation time, so the first one is the device created with the account.
+    pub devices: Vec<DeviceState>,
+    /// Ordered by creation time, the managed ntfy channel comes first unless the user removed it.
+    pub channels: Vec<Channel>,
+    /// Ordered by delay_minutes.
+    pub escalation: Vec<EscalationStep>,
+    /// Ordered by creation time.
+    pub maintenance: Vec<MaintenanceWindow>,
+}
+
+impl UserState {
+    pub fn device(&self, device_id: ID) -> Option<&DeviceState> {
+        self.devices.iter().find(|d| d.device.id == device_id)
+    }
+
+    pub fn device_mut(&mut self, device_id: ID) -> Option<&mut DeviceState> {
+        self.devices.iter_mut().find(|d| d.device.id == device_id)
+    }
+
+    /// Pick the device an endpoint should act on: the requested one, or the only one
+    /// if the account has a single device.
+    pub fn resolve_device(&self, device_id: Option<ID>) -> Result<&DeviceState, &'static str> {
+        match (device_id, self.devices.as_slice()) {
+            (Some(id), _) => self.device(id).ok_or("Device not found"),
+            (None, [only]) => Ok(only),
+            (None, []) => Err("No devices registered"),
+            (None, _) => Err("Account has several devices, pass 'device' to pick one"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Queryable, Selectable, Insertable)]
@@ -247,3 +650,628 @@ impl Invite {
         }
     }
 }
+
+/// What a token is allowed to do.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize, diesel_derive_enum::DbEnum)]
+#[ExistingTypePath = "crate::schema::sql_types::TokenScopeEnum"]
+#[serde(crate = "rocket::serde")]
+pub enum TokenScope {
+    /// Management API: /api/v1/me/*, invites, admin endpoints
+    Account,
+    /// Heartbeats only: /api/v1/up
+    Heartbeat,
+}
+
+/// Named token on top of the account and device tokens, optionally expiring.
+#[derive(Debug, Clone, Serialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = api_tokens)]
+#[serde(crate = "rocket::serde")]
+pub struct ApiToken {
+    pub id: ID,
+    #[serde(skip_serializing)]
+    pub user_id: ID,
+    /// Set for heartbeat tokens only.
+    pub device_id: Option<ID>,
+    pub name: String,
+    pub scope: TokenScope,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    #[serde(serialize_with = "serialize_epoch_secs")]
+    pub created_at: SystemTime,
+    #[serde(serialize_with = "serialize_opt_epoch_secs")]
+    pub expires_at: Option<SystemTime>,
+    #[serde(serialize_with = "serialize_opt_epoch_secs")]
+    pub last_used_at: Option<SystemTime>,
+    pub last_used_ip: Option<String>,
+}
+
+impl ApiToken {
+    pub fn new(
+        user_id: ID,
+        device_id: Option<ID>,
+        name: String,
+        scope: TokenScope,
+        token_hash: String,
+        expires_at: Option<SystemTime>,
+    ) -> ApiToken {
+        ApiToken {
+            id: Uuid::new_v4(),
+            user_id,
+            device_id,
+            name,
+            scope,
+            token_hash,
+            created_at: SystemTime::now(),
+            expires_at,
+            last_used_at: None,
+            last_used_ip: None,
+        }
+    }
+
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at.is_some_and(|t| t <= now)
+    }
+}
+
+/// Kinds of notification channels, each implemented in src/channels.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize, diesel_derive_enum::DbEnum)]
+#[ExistingTypePath = "crate::schema::sql_types::ChannelKindEnum"]
+#[serde(crate = "rocket::serde")]
+pub enum ChannelKind {
+    /// Topic of the ntfy user managed by the server, or a topic on the user's own ntfy server
+    Ntfy,
+    /// JSON POST to a user supplied URL (see docs/WEBHOOKS.md)
+    Webhook,
+    /// Chat linked to the Telegram bot with a one-time code
+    Telegram,
+    /// Email to verified addresses through the server's SMTP relay
+    Email,
+    /// Message in a Matrix room, with the user's own access token
+    Matrix,
+    /// Discord incoming webhook
+    Discord,
+    /// Slack (or compatible) incoming webhook
+    Slack,
+    /// Application on a Gotify server
+    Gotify,
+}
+
+impl ChannelKind {
+    /// Lowercase name, used as the `channel` label of oubot_notifications_total.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChannelKind::Ntfy => "ntfy",
+            ChannelKind::Webhook => "webhook",
+            ChannelKind::Telegram => "telegram",
+            ChannelKind::Email => "email",
+            ChannelKind::Matrix => "matrix",
+            ChannelKind::Discord => "discord",
+            ChannelKind::Slack => "slack",
+            ChannelKind::Gotify => "gotify",
+        }
+    }
+
+    /// Config is set by the server only, users can merely switch these channels on and off. That is
+    /// the case for Telegram chats and ntfy channels on the managed instance (without a `server`).
+    pub fn is_managed(&self, config: Option<&Value>) -> bool {
+        match self {
+            ChannelKind::Telegram => true,
+            ChannelKind::Ntfy => config.is_none_or(|c| c.get("server").is_none()),
+            _ => false,
+        }
+    }
+}
+
+/// One way of notifying a user, each with its own on/off switch.
+#[derive(Debug, Clone, Serialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = notification_channels)]
+#[serde(crate = "rocket::serde")]
+pub struct Channel {
+    pub id: ID,
+    #[serde(skip_serializing)]
+    pub user_id: ID,
+    pub kind: ChannelKind,
+    pub enabled: bool,
+    /// Kind specific settings, may hold secrets so it is never returned as is.
+    #[serde(skip_serializing)]
+    pub config: Value,
+    #[serde(serialize_with = "serialize_epoch_secs")]
+    pub created_at: SystemTime,
+}
+
+impl Channel {
+    pub fn new(user_id: ID, kind: ChannelKind, enabled: bool, config: Value) -> Channel {
+        Channel {
+            id: Uuid::new_v4(),
+            user_id,
+            kind,
+            enabled,
+            config,
+            created_at: SystemTime::now(),
+        }
+    }
+}
+
+/// Reminder sent while a device stays down, `delay_minutes` after the outage started.
+#[derive(Debug, Clone, Serialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = escalation_steps)]
+#[serde(crate = "rocket::serde")]
+pub struct EscalationStep {
+    pub id: ID,
+    #[serde(skip_serializing)]
+    pub user_id: ID,
+    pub delay_minutes: i32,
+    pub priority: Priority,
+    /// Notified on top of the enabled channels, even while it is disabled itself.
+    pub channel_id: Option<ID>,
+    #[serde(serialize_with = "serialize_epoch_secs")]
+    pub created_at: SystemTime,
+}
+
+impl EscalationStep {
+    pub fn new(user_id: ID, delay_minutes: i32, priority: Priority, channel_id: Option<ID>) -> EscalationStep {
+        EscalationStep {
+            id: Uuid::new_v4(),
+            user_id,
+            delay_minutes,
+            priority,
+            channel_id,
+            created_at: SystemTime::now(),
+        }
+    }
+}
+
+/// Time notifications of a device (or all devices of the account when `device_id` is None) are
+/// suppressed in: a weekly rule when `weekdays` is set, a one-off range otherwise.
+#[derive(Debug, Clone, Serialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = maintenance_windows)]
+#[serde(crate = "rocket::serde")]
+pub struct MaintenanceWindow {
+    pub id: ID,
+    #[serde(skip_serializing)]
+    pub user_id: ID,
+    pub device_id: Option<ID>,
+    pub label: Option<String>,
+    /// Days of the week as a bitmask, 1 = Monday ... 64 = Sunday.
+    pub weekdays: Option<i16>,
+    /// Minutes from local midnight, a window ending before it starts runs into the next day.
+    pub start_minute: Option<i16>,
+    pub end_minute: Option<i16>,
+    #[serde(serialize_with = "serialize_opt_epoch_secs")]
+    pub starts_at: Option<SystemTime>,
+    #[serde(serialize_with = "serialize_opt_epoch_secs")]
+    pub ends_at: Option<SystemTime>,
+    #[serde(serialize_with = "serialize_epoch_secs")]
+    pub created_at: SystemTime,
+}
+
+impl MaintenanceWindow {
+    pub fn weekly(user_id: ID, device_id: Option<ID>, label: Option<String>, weekdays: i16, (start, end): (i16, i16)) -> Self {
+        MaintenanceWindow {
+            weekdays: Some(weekdays),
+            start_minute: Some(start),
+            end_minute: Some(end),
+            ..MaintenanceWindow::new(user_id, device_id, label)
+        }
+    }
+
+    pub fn one_off(
+        user_id: ID,
+        device_id: Option<ID>,
+        label: Option<String>,
+        (starts_at, ends_at): (SystemTime, SystemTime),
+    ) -> Self {
+        MaintenanceWindow {
+            starts_at: Some(starts_at),
+            ends_at: Some(ends_at),
+            ..MaintenanceWindow::new(user_id, device_id, label)
+        }
+    }
+
+    fn new(user_id: ID, device_id: Option<ID>, label: Option<String>) -> Self {
+        MaintenanceWindow {
+            id: Uuid::new_v4(),
+            user_id,
+            device_id,
+            label,
+            weekdays: None,
+            start_minute: None,
+            end_minute: None,
+            starts_at: None,
+            ends_at: None,
+            created_at: SystemTime::now(),
+        }
+    }
+
+    /// Whether the window applies to `device_id` at `local` (see `User::local_time`).
+    pub fn covers(&self, device_id: ID, local: &DateTime<Tz>) -> bool {
+        self.device_id.is_none_or(|id| id == device_id) && self.is_active(local)
+    }
+
+    /// Whether the window is in effect at `local`. A weekly rule running past midnight belongs to the
+    /// day it starts on, so "Tue 22:00-02:00" also covers Wednesday 01:00.
+    pub fn is_active(&self, local: &DateTime<Tz>) -> bool {
+        match (
+            self.weekdays,
+            self.start_minute,
+            self.end_minute,
+            self.starts_at,
+            self.ends_at,
+        ) {
+            (Some(days), Some(start), Some(end), _, _) => {
+                let minute = minute_of_day(local);
+                let today = local.weekday().num_days_from_monday();
+                let on = |day: u32| days & (1 << day) != 0;
+                if !in_daily_window(start, end, minute) {
+                    false
+                } else if start < end || minute >= start as i32 {
+                    on(today)
+                } else {
+                    on((today + 6) % 7)
+                }
+            }
+            (_, _, _, Some(starts_at), Some(ends_at)) => {
+                let now = SystemTime::from(local.with_timezone(&Utc));
+                starts_at <= now && now < ends_at
+            }
+            _ => false,
+        }
+    }
+
+    /// A one-off window that is over, nothing to keep it for.
+    pub fn has_ended(&self, now: SystemTime) -> bool {
+        self.ends_at.is_some_and(|ends_at| ends_at <= now)
+    }
+}
+
+/// One slot of a rolling blackout schedule, see `schedule::Schedule`.
+#[derive(Debug, Clone, PartialEq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = planned_outages)]
+pub struct PlannedOutage {
+    pub id: ID,
+    pub queue: String,
+    pub starts_at: SystemTime,
+    pub ends_at: SystemTime,
+    pub created_at: SystemTime,
+}
+
+impl PlannedOutage {
+    pub fn new(queue: String, starts_at: SystemTime, ends_at: SystemTime) -> PlannedOutage {
+        PlannedOutage {
+            id: Uuid::new_v4(),
+            queue,
+            starts_at,
+            ends_at,
+            created_at: SystemTime::now(),
+        }
+    }
+}
+
+/// Who can see a status page.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize, Serialize, diesel_derive_enum::DbEnum)]
+#[ExistingTypePath = "crate::schema::sql_types::PageVisibilityEnum"]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum PageVisibility {
+    /// Not served, only its owner can see the settings
+    Private,
+    /// Served to anyone with the link
+    Unlisted,
+    /// Served and listed at /api/v1/pages
+    Public,
+}
+
+/// Shareable status page grouping several devices, served at `/api/v1/pages/<slug>`.
+#[derive(Debug, Clone, Serialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = status_pages)]
+#[serde(crate = "rocket::serde")]
+pub struct StatusPage {
+    pub id: ID,
+    #[serde(skip_serializing)]
+    pub user_id: ID,
+    pub slug: String,
+    pub title: String,
+    pub visibility: PageVisibility,
+    #[serde(serialize_with = "serialize_epoch_secs")]
+    pub created_at: SystemTime,
+}
+
+impl StatusPage {
+    pub fn new(user_id: ID, slug: String, title: String, visibility: PageVisibility) -> StatusPage {
+        StatusPage {
+            id: Uuid::new_v4(),
+            user_id,
+            slug,
+            title,
+            visibility,
+            created_at: SystemTime::now(),
+        }
+    }
+}
+
+/// A device shown on a status page, under the name the page gives it.
+#[derive(Debug, Clone, Serialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = status_page_monitors)]
+#[serde(crate = "rocket::serde")]
+pub struct StatusPageMonitor {
+    #[serde(skip_serializing)]
+    pub page_id: ID,
+    pub device_id: ID,
+    pub display_name: String,
+    #[serde(skip_serializing)]
+    pub position: i16,
+}
+
+/// A notification waiting for delivery through one channel (see `background::background_deliver_outbox`).
+/// Delivered rows are deleted, rows that ran out of attempts stay with `failed_at` set.
+#[derive(Debug, Clone, Serialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = notification_outbox)]
+#[serde(crate = "rocket::serde")]
+pub struct OutboxItem {
+    pub id: ID,
+    pub user_id: ID,
+    pub channel_id: ID,
+    pub event: String,
+    /// The `Notification` as JSON, including its priority.
+    pub payload: Value,
+    pub attempts: i32,
+    #[serde(serialize_with = "serialize_epoch_secs")]
+    pub next_attempt_at: SystemTime,
+    pub last_error: Option<String>,
+    #[serde(serialize_with = "serialize_epoch_secs")]
+    pub created_at: SystemTime,
+    #[serde(serialize_with = "serialize_opt_epoch_secs")]
+    pub failed_at: Option<SystemTime>,
+}
+
+impl OutboxItem {
+    /// Queue `notification` for `channel`, due right away.
+    pub fn new(channel: &Channel, notification: &Notification) -> OutboxItem {
+        let now = SystemTime::now();
+        OutboxItem {
+            id: Uuid::new_v4(),
+            user_id: channel.user_id,
+            channel_id: channel.id,
+            event: notification.event.clone(),
+            payload: notification.to_payload(),
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+            created_at: now,
+            failed_at: None,
+        }
+    }
+
+    pub fn notification(&self) -> Result<Notification, String> {
+        rocket::serde::json::from_value(self.payload.clone()).map_err(|err| format!("Invalid outbox payload: {err}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000 + secs)
+    }
+
+    fn flaky_device(threshold: Option<i16>) -> DeviceState {
+        let mut device = Device::new(Uuid::nil(), "default".to_string(), None, String::new());
+        (device.flap_threshold, device.flap_window_minutes, device.flap_stable_minutes) = (threshold, 10, 15);
+        DeviceState::new(device)
+    }
+
+    #[test]
+    fn test_flapping_needs_threshold_flips_within_window() {
+        let mut device = flaky_device(Some(3));
+        assert!(!device.record_flip(t(0)));
+        assert!(!device.record_flip(t(5 * 60)));
+        // The first flip left the 10 minute window
+        assert!(!device.record_flip(t(11 * 60)));
+        assert!(device.uptime.flapping_since.is_none());
+        assert!(device.record_flip(t(12 * 60)));
+        assert_eq!(device.uptime.flapping_since, Some(t(12 * 60)));
+        // Only the first flip over the threshold starts flapping
+        assert!(!device.record_flip(t(13 * 60)));
+    }
+
+    #[test]
+    fn test_flap_detection_disabled() {
+        let mut device = flaky_device(None);
+        for minute in 0..10 {
+            assert!(!device.record_flip(t(minute * 60)));
+        }
+        assert!(device.recent_flips.is_empty());
+    }
+
+    #[test]
+    fn test_flapping_settles_after_stable_minutes() {
+        let mut device = flaky_device(Some(2));
+        device.record_flip(t(0));
+        assert!(device.record_flip(t(60)));
+        device.uptime.state_changed_at = t(60);
+        assert!(!device.settle_flapping(t(60 + 14 * 60)));
+        assert!(device.settle_flapping(t(60 + 15 * 60)));
+        assert!(device.uptime.flapping_since.is_none() && device.recent_flips.is_empty());
+        assert!(!device.settle_flapping(t(60 + 16 * 60)));
+
+        // Switching flap detection off settles right away
+        device.record_flip(t(2000));
+        device.record_flip(t(2001));
+        device.uptime.state_changed_at = t(2001);
+        device.device.flap_threshold = None;
+        assert!(device.settle_flapping(t(2002)));
+    }
+
+    fn down_since(secs: u64) -> UptimeState {
+        let mut state = UptimeState::new(Uuid::nil());
+        (state.status, state.state_changed_at, state.touched_at) = (UpStatus::Down, t(secs), t(secs));
+        state
+    }
+
+    #[test]
+    fn test_recovery_needs_consecutive_heartbeats() {
+        let mut device = Device::new(Uuid::nil(), "default".to_string(), Some(60), String::new());
+        device.recovery_heartbeats = 3;
+        let mut state = down_since(0);
+        // A flicker: one heartbeat, then nothing for longer than up_delay
+        assert!(matches!(state.touch(t(1000), &device), TouchResult::NoChange));
+        assert!(matches!(state.touch(t(1100), &device), TouchResult::NoChange));
+        assert!(matches!(state.touch(t(1150), &device), TouchResult::NoChange));
+        assert_eq!(state.status, UpStatus::Down);
+        // The outage ends at the first heartbeat of the confirmed recovery, not the flicker
+        match state.touch(t(1200), &device) {
+            TouchResult::Restored(duration) => assert_eq!(duration, Duration::from_secs(1100)),
+            _ => panic!("expected the recovery to be confirmed"),
+        }
+        assert_eq!((state.status, state.state_changed_at), (UpStatus::Up, t(1100)));
+        assert_eq!((state.recovery_started_at, state.recovery_count), (None, 0));
+    }
+
+    #[test]
+    fn test_escalation_sends_each_step_once() {
+        let steps: Vec<EscalationStep> = [60, 240]
+            .into_iter()
+            .map(|minutes| EscalationStep::new(Uuid::nil(), minutes, Priority::High, None))
+            .collect();
+        let mut device = flaky_device(None);
+        device.uptime = down_since(0);
+        assert!(device.due_escalation(&steps, t(59 * 60)).is_none());
+        let (step, elapsed) = device.due_escalation(&steps, t(61 * 60)).unwrap();
+        assert_eq!((step.delay_minutes, elapsed), (60, Duration::from_secs(61 * 60)));
+        device.uptime.escalated_minutes = step.delay_minutes;
+        assert!(device.due_escalation(&steps, t(62 * 60)).is_none());
+
+        // After a restart only the last step that is due gets sent
+        device.uptime.escalated_minutes = 0;
+        assert_eq!(device.due_escalation(&steps, t(300 * 60)).unwrap().0.delay_minutes, 240);
+
+        // A held back outage isn't escalated, neither is a device that is up again
+        device.uptime.held_outage_secs = Some(0);
+        assert!(device.due_escalation(&steps, t(300 * 60)).is_none());
+        device.uptime.held_outage_secs = None;
+        device.uptime.status = UpStatus::Up;
+        assert!(device.due_escalation(&steps, t(300 * 60)).is_none());
+    }
+
+    #[test]
+    fn test_held_back_outage_survives_restart() {
+        let mut device = flaky_device(None);
+        device.device.min_outage_secs = 120;
+        device.uptime = down_since(1000);
+        device.uptime.held_outage_secs = Some(3600);
+
+        // The server restarts during the hold, state is loaded from the database again
+        let reloaded = DeviceState::load(device.uptime.clone(), device.device.clone());
+        assert_eq!(reloaded.pending_outage(), Some((t(1120), Duration::from_secs(3600))));
+
+        // Without a held back outage there is nothing to notify after a restart
+        device.uptime.held_outage_secs = None;
+        let reloaded = DeviceState::load(device.uptime.clone(), device.device.clone());
+        assert_eq!(reloaded.pending_outage(), None);
+    }
+
+    #[test]
+    fn test_single_heartbeat_recovers_by_default() {
+        let device = Device::new(Uuid::nil(), "default".to_string(), None, String::new());
+        let mut state = down_since(0);
+        assert!(matches!(state.touch(t(500), &device), TouchResult::Restored(d) if d == Duration::from_secs(500)));
+        assert_eq!(state.state_changed_at, t(500));
+    }
+
+    #[test]
+    fn test_quiet_hours_span_midnight() {
+        let ntfy = NtfyUser {
+            id: Uuid::nil(),
+            topic: String::new(),
+            topic_permission: String::new(),
+            username: String::new(),
+            password: String::new(),
+            tier: String::new(),
+        };
+        let mut user = User::new(UserType::Normal, 0, "en".to_string(), &ntfy, String::new());
+        let at = |day: u64, minute: u64, secs: u64| UNIX_EPOCH + Duration::from_secs(day * 86400 + minute * 60 + secs);
+        assert_eq!(user.quiet_hours_until(at(20_000, 0, 0)), None);
+
+        // 22:00-07:00 UTC
+        (user.quiet_hours_start, user.quiet_hours_end) = (Some(22 * 60), Some(7 * 60));
+        assert_eq!(user.quiet_hours_until(at(20_000, 21 * 60 + 59, 30)), None);
+        assert_eq!(user.quiet_hours_until(at(20_000, 22 * 60, 0)), Some(at(20_001, 7 * 60, 0)));
+        assert_eq!(
+            user.quiet_hours_until(at(20_001, 6 * 60 + 59, 59)),
+            Some(at(20_001, 7 * 60, 0))
+        );
+        assert_eq!(user.quiet_hours_until(at(20_001, 7 * 60, 0)), None);
+
+        // 13:00-14:00 UTC
+        (user.quiet_hours_start, user.quiet_hours_end) = (Some(13 * 60), Some(14 * 60));
+        assert_eq!(
+            user.quiet_hours_until(at(20_000, 13 * 60 + 30, 10)),
+            Some(at(20_000, 14 * 60, 0))
+        );
+        assert_eq!(user.quiet_hours_until(at(20_000, 23 * 60, 0)), None);
+    }
+
+    #[test]
+    fn test_quiet_hours_follow_local_time_across_dst() {
+        let ntfy = NtfyUser {
+            id: Uuid::nil(),
+            topic: String::new(),
+            topic_permission: String::new(),
+            username: String::new(),
+            password: String::new(),
+            tier: String::new(),
+        };
+        let mut user = User::new(UserType::Normal, 0, "uk".to_string(), &ntfy, String::new());
+        user.timezone = "Europe/Kyiv".to_string();
+        let utc = |m: u32, d: u32, h: u32, min: u32| -> SystemTime { Utc.with_ymd_and_hms(2026, m, d, h, min, 0).unwrap().into() };
+        // UTC+2 in winter, UTC+3 in summer
+        assert_eq!(minute_of_day(&user.local_time(utc(1, 15, 12, 0))), 14 * 60);
+        assert_eq!(minute_of_day(&user.local_time(utc(7, 15, 12, 0))), 15 * 60);
+
+        // 22:00-07:00 local, the clocks go forward during the night of March 29
+        (user.quiet_hours_start, user.quiet_hours_end) = (Some(22 * 60), Some(7 * 60));
+        assert_eq!(user.quiet_hours_until(utc(3, 28, 19, 59)), None);
+        assert_eq!(user.quiet_hours_until(utc(3, 28, 20, 30)), Some(utc(3, 29, 4, 0)));
+        // and back during the night of October 25
+        assert_eq!(user.quiet_hours_until(utc(10, 24, 19, 30)), Some(utc(10, 25, 5, 0)));
+
+        // An end within the skipped hour is moved past the jump
+        user.quiet_hours_end = Some(3 * 60 + 30);
+        assert_eq!(user.quiet_hours_until(utc(3, 28, 22, 0)), Some(utc(3, 29, 1, 30)));
+
+        // Unknown zones fall back to UTC
+        user.timezone = "Mars/Olympus_Mons".to_string();
+        assert_eq!(minute_of_day(&user.local_time(utc(1, 15, 12, 0))), 12 * 60);
+    }
+
+    #[test]
+    fn test_maintenance_window_weekdays_and_one_off() {
+        let (device, other) = (Uuid::new_v4(), Uuid::new_v4());
+        // 2026-10-20 is a Tuesday
+        let kyiv = |d: u32, h: u32, min: u32| chrono_tz::Europe::Kyiv.with_ymd_and_hms(2026, 10, d, h, min, 0).unwrap();
+
+        // Every Tuesday 10:00-14:00 local, for all devices
+        let tuesdays = MaintenanceWindow::weekly(Uuid::nil(), None, None, 1 << 1, (10 * 60, 14 * 60));
+        assert!(tuesdays.covers(device, &kyiv(20, 10, 0)));
+        assert!(tuesdays.covers(other, &kyiv(20, 13, 59)));
+        assert!(!tuesdays.covers(device, &kyiv(20, 14, 0)));
+        assert!(!tuesdays.covers(device, &kyiv(21, 12, 0)));
+
+        // Friday 22:00-02:00 of one device runs into Saturday, but not into Friday morning
+        let friday_nights = MaintenanceWindow::weekly(Uuid::nil(), Some(device), None, 1 << 4, (22 * 60, 2 * 60));
+        assert!(friday_nights.covers(device, &kyiv(23, 23, 0)));
+        assert!(friday_nights.covers(device, &kyiv(24, 1, 30)));
+        assert!(!friday_nights.covers(device, &kyiv(23, 1, 30)));
+        assert!(!friday_nights.covers(other, &kyiv(23, 23, 0)));
+
+        let from: SystemTime = kyiv(25, 9, 0).with_timezone(&Utc).into();
+        let until: SystemTime = kyiv(25, 17, 0).with_timezone(&Utc).into();
+        let one_off = MaintenanceWindow::one_off(Uuid::nil(), None, Some("grid works".to_string()), (from, until));
+        assert!(!one_off.covers(device, &kyiv(25, 8, 59)));
+        assert!(one_off.covers(device, &kyiv(25, 9, 0)));
+        assert!(!one_off.covers(device, &kyiv(25, 17, 0)));
+        assert!(!one_off.has_ended(from));
+        assert!(one_off.has_ended(until));
+        assert!(!tuesdays.has_ended(until));
+    }
+}
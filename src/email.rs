@@ -0,0 +1,105 @@
+use crate::channels::SendError;
+use lazy_static::lazy_static;
+use lettre::message::{Mailbox, header::ContentType};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Address, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::env::var;
+use std::time::Duration;
+
+lazy_static! {
+    /// Base URL the server is reachable at from a browser, used for email verification links.
+    pub static ref OUBOT_PUBLIC_URL: Option<String> = var("OUBOT_PUBLIC_URL").ok().map(|url| url.trim_end_matches('/').to_string());
+}
+
+/// SMTP relay for the email channel, only usable when SMTP_HOST is set.
+#[derive(Clone)]
+pub struct Mailer {
+    transport: Option<AsyncSmtpTransport<Tokio1Executor>>,
+    from: Option<Mailbox>,
+}
+
+impl std::fmt::Debug for Mailer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mailer").field("from", &self.from).finish_non_exhaustive()
+    }
+}
+
+impl Mailer {
+    /// Reads SMTP_HOST, SMTP_PORT, SMTP_TLS (starttls, tls or none), SMTP_USERNAME, SMTP_PASSWORD
+    /// and SMTP_FROM. Panics on an invalid config, so typos show up at startup.
+    pub fn new() -> Mailer {
+        let Some(host) = var("SMTP_HOST").ok().filter(|h| !h.is_empty()) else {
+            return Mailer {
+                transport: None,
+                from: None,
+            };
+        };
+        let from: Mailbox = var("SMTP_FROM")
+            .expect("SMTP_FROM required with SMTP_HOST")
+            .parse()
+            .expect("SMTP_FROM must be an email address, e.g. \"Uptime Bot <bot@example.com>\"");
+        assert!(
+            OUBOT_PUBLIC_URL.is_some(),
+            "OUBOT_PUBLIC_URL required with SMTP_HOST (verification links)"
+        );
+
+        let tls = var("SMTP_TLS").unwrap_or_else(|_| "starttls".to_string());
+        let (builder, default_port) = match tls.as_str() {
+            "starttls" => (
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host).expect("Invalid SMTP_HOST"),
+                587,
+            ),
+            "tls" => (
+                AsyncSmtpTransport::<Tokio1Executor>::relay(&host).expect("Invalid SMTP_HOST"),
+                465,
+            ),
+            // @WARNING: Plaintext, only meant for a relay on the same host or a test sink.
+            "none" => (AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host), 25),
+            _ => panic!("SMTP_TLS must be one of: starttls, tls, none"),
+        };
+        let port = var("SMTP_PORT").map_or(default_port, |p| p.parse().expect("SMTP_PORT must be a port number"));
+        let mut builder = builder.port(port).timeout(Some(Duration::from_secs(10)));
+        if let (Ok(username), Ok(password)) = (var("SMTP_USERNAME"), var("SMTP_PASSWORD")) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+        Mailer {
+            transport: Some(builder.build()),
+            from: Some(from),
+        }
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.transport.is_some()
+    }
+
+    /// Send a plain text email to a single recipient. Bad addresses and permanent (5xx) SMTP
+    /// replies, e.g. an unknown mailbox, are permanent errors.
+    pub async fn send(&self, to: &str, subject: &str, body: String) -> Result<(), SendError> {
+        let (Some(transport), Some(from)) = (&self.transport, &self.from) else {
+            return Err("Email is not configured on this server".to_string().into());
+        };
+        let to: Address = to
+            .parse()
+            .map_err(|err| SendError::Permanent(format!("Invalid address {to}: {err}")))?;
+        let message = Message::builder()
+            .from(from.clone())
+            .to(Mailbox::new(None, to))
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(body)
+            .map_err(|err| SendError::Permanent(format!("{err:?}")))?;
+        match transport.send(message).await {
+            Ok(_) => Ok(()),
+            Err(err) if err.is_permanent() => Err(SendError::Permanent(format!("{err}"))),
+            Err(err) => Err(SendError::Transient(format!("{err}"))),
+        }
+    }
+}
+
+/// Link that confirms an email recipient, see `api::verify_email`.
+pub fn verification_link(token: &str) -> String {
+    format!(
+        "{base}/api/v1/email/verify?token={token}",
+        base = OUBOT_PUBLIC_URL.as_deref().unwrap_or_default()
+    )
+}
@@ -0,0 +1,311 @@
+use crate::stats::{Segment, Span};
+use chrono::{DateTime, Duration as ChronoDuration, NaiveTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use lazy_static::lazy_static;
+use resvg::{tiny_skia, usvg};
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+const WIDTH: f64 = 720.0;
+const HEIGHT: f64 = 192.0;
+/// Left edge of the plots, the availability axis labels go before it.
+const LEFT: f64 = 40.0;
+const RIGHT: f64 = 700.0;
+const TIMELINE: (f64, f64) = (28.0, 24.0);
+const CHART: (f64, f64) = (80.0, 90.0);
+/// PNGs are rendered at twice the SVG size to stay sharp on phone screens.
+const PNG_SCALE: f32 = 2.0;
+const FONT: &str = "DejaVu Sans, Verdana, sans-serif";
+
+lazy_static! {
+    /// Loading system fonts scans the disk, so it's done once. Labels are left out of PNGs when
+    /// there are none (e.g. in a bare container).
+    static ref FONTS: Arc<usvg::fontdb::Database> = {
+        let mut fonts = usvg::fontdb::Database::new();
+        fonts.load_system_fonts();
+        Arc::new(fonts)
+    };
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Format {
+    Svg,
+    Png,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "svg" => Some(Format::Svg),
+            "png" => Some(Format::Png),
+            _ => None,
+        }
+    }
+}
+
+fn color(segment: Segment) -> &'static str {
+    match segment {
+        Segment::Up => "#2e9e44",
+        Segment::Down => "#d93025",
+        Segment::Paused => "#9aa0a6",
+        Segment::Maintenance => "#bdc1c6",
+        Segment::Unknown => "#ebedf0",
+    }
+}
+
+/// Up and down seconds of `spans` inside `[start, end)`.
+fn up_down(spans: &[Span], start: SystemTime, end: SystemTime) -> (u64, u64) {
+    spans.iter().fold((0, 0), |(up, down), span| {
+        let secs = span
+            .end
+            .min(end)
+            .duration_since(span.start.max(start))
+            .unwrap_or_default()
+            .as_secs();
+        match span.segment {
+            Segment::Up => (up + secs, down),
+            Segment::Down => (up, down + secs),
+            _ => (up, down),
+        }
+    })
+}
+
+/// Bar boundaries of the availability chart, whole local hours or days. The first and last bars may
+/// be partial.
+fn buckets(start: SystemTime, end: SystemTime, tz: Tz, hourly: bool) -> Vec<(SystemTime, SystemTime)> {
+    let mut buckets = Vec::new();
+    let mut cursor = start;
+    while cursor < end {
+        let local = DateTime::<Utc>::from(cursor).with_timezone(&tz).naive_local();
+        let next = if hourly {
+            local.date().and_time(NaiveTime::MIN) + ChronoDuration::hours(local.hour() as i64 + 1)
+        } else {
+            local.date().and_time(NaiveTime::MIN) + ChronoDuration::days(1)
+        };
+        // @NOTE: Midnight may be skipped by a daylight saving time change, the bar then ends an hour
+        //  later.
+        let next: SystemTime = tz
+            .from_local_datetime(&next)
+            .earliest()
+            .or_else(|| tz.from_local_datetime(&(next + ChronoDuration::hours(1))).earliest())
+            .map(Into::into)
+            .filter(|next| *next > cursor)
+            .unwrap_or(cursor + Duration::from_secs(3600));
+        buckets.push((cursor, next.min(end)));
+        cursor = next;
+    }
+    buckets
+}
+
+fn x_at(time: SystemTime, start: SystemTime, end: SystemTime) -> f64 {
+    let total = end.duration_since(start).unwrap_or_default().as_secs_f64().max(1.0);
+    let offset = time.duration_since(start).unwrap_or_default().as_secs_f64();
+    LEFT + (RIGHT - LEFT) * (offset / total).min(1.0)
+}
+
+fn percent(up: u64, down: u64) -> Option<f64> {
+    (up + down > 0).then(|| up as f64 * 100.0 / (up + down) as f64)
+}
+
+/// Render `spans` (covering `[start, end)`, see `stats::timeline`) as an SVG: the availability over
+/// the whole window titled with `label`, a timeline bar colored by state and an availability bar per
+/// local hour or day. Texts are numbers and times only, so there is nothing to localize.
+pub fn svg(label: &str, start: SystemTime, end: SystemTime, spans: &[Span], tz: Tz) -> String {
+    let local = |t: SystemTime| DateTime::<Utc>::from(t).with_timezone(&tz);
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}" font-family="{FONT}" fill="#3c4043">"##
+    );
+    out.push_str(r##"<rect width="100%" height="100%" fill="#ffffff"/>"##);
+
+    let (up, down) = up_down(spans, start, end);
+    let availability = percent(up, down).map_or("–".to_string(), |p| format!("{p:.2}%"));
+    let _ = write!(
+        out,
+        r#"<text x="{LEFT}" y="18" font-size="13">{}: {availability}</text>"#,
+        escape(label)
+    );
+
+    let (top, height) = TIMELINE;
+    let _ = write!(
+        out,
+        r#"<rect x="{LEFT}" y="{top}" width="{}" height="{height}" fill="{}"/>"#,
+        RIGHT - LEFT,
+        color(Segment::Unknown)
+    );
+    for span in spans.iter().filter(|s| s.segment != Segment::Unknown) {
+        let x = x_at(span.start, start, end);
+        let width = x_at(span.end, start, end) - x;
+        let _ = write!(
+            out,
+            r#"<rect x="{x:.2}" y="{top}" width="{width:.2}" height="{height}" fill="{}"/>"#,
+            color(span.segment)
+        );
+    }
+    let y = top + height + 14.0;
+    let _ = write!(
+        out,
+        r#"<text x="{LEFT}" y="{y}" font-size="10">{}</text>"#,
+        local(start).format("%d.%m %H:%M")
+    );
+    let _ = write!(
+        out,
+        r#"<text x="{RIGHT}" y="{y}" font-size="10" text-anchor="end">{}</text>"#,
+        local(end).format("%d.%m %H:%M")
+    );
+
+    let (top, height) = CHART;
+    for (fraction, text) in [(0.0, "100%"), (0.5, "50%"), (1.0, "0%")] {
+        let y = top + height * fraction;
+        let _ = write!(
+            out,
+            r##"<line x1="{LEFT}" y1="{y}" x2="{RIGHT}" y2="{y}" stroke="#dadce0"/>"##
+        );
+        let _ = write!(
+            out,
+            r#"<text x="{}" y="{}" font-size="9" text-anchor="end">{text}</text>"#,
+            LEFT - 4.0,
+            y + 3.0
+        );
+    }
+    // @NOTE: Bars per hour for windows up to a day, per day for longer ones.
+    let hourly = end.duration_since(start).unwrap_or_default() <= Duration::from_secs(86400);
+    let buckets = buckets(start, end, tz, hourly);
+    let slot = (RIGHT - LEFT) / buckets.len().max(1) as f64;
+    for (i, (from, to)) in buckets.iter().enumerate() {
+        let x = LEFT + slot * i as f64 + 1.0;
+        let width = (slot - 2.0).max(1.0);
+        let (up, down) = up_down(spans, *from, *to);
+        match percent(up, down) {
+            Some(p) => {
+                // Up time from the bottom, over the rest of the bar colored as down time
+                let up_height = height * p / 100.0;
+                let _ = write!(out, "<g><title>{p:.2}%</title>");
+                if up_height < height {
+                    let _ = write!(
+                        out,
+                        r#"<rect x="{x:.2}" y="{top}" width="{width:.2}" height="{height}" fill="{}"/>"#,
+                        color(Segment::Down)
+                    );
+                }
+                if up_height > 0.0 {
+                    let _ = write!(
+                        out,
+                        r#"<rect x="{x:.2}" y="{:.2}" width="{width:.2}" height="{up_height:.2}" fill="{}"/>"#,
+                        top + height - up_height,
+                        color(Segment::Up)
+                    );
+                }
+                out.push_str("</g>");
+            }
+            None => {
+                let _ = write!(
+                    out,
+                    r#"<rect x="{x:.2}" y="{top}" width="{width:.2}" height="{height}" fill="{}"/>"#,
+                    color(Segment::Unknown)
+                );
+            }
+        }
+        let text = local(*from).format(if hourly { "%H" } else { "%d" });
+        let _ = write!(
+            out,
+            r#"<text x="{:.2}" y="{}" font-size="9" text-anchor="middle">{text}</text>"#,
+            x + width / 2.0,
+            top + height + 12.0
+        );
+    }
+    out.push_str("</svg>");
+    out
+}
+
+/// Rasterize an SVG made by `svg`.
+pub fn png(svg: &str) -> Result<Vec<u8>, String> {
+    let options = usvg::Options {
+        fontdb: FONTS.clone(),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &options).map_err(|err| format!("Failed to parse the graph: {err}"))?;
+    let size = tree.size().to_int_size().scale_by(PNG_SCALE).ok_or("Graph too large")?;
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height()).ok_or("Graph too large")?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(PNG_SCALE, PNG_SCALE),
+        &mut pixmap.as_mut(),
+    );
+    pixmap
+        .encode_png()
+        .map_err(|err| format!("Failed to encode the graph: {err}"))
+}
+
+/// Escape text and attribute values of SVG and HTML documents.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 3600;
+
+    fn t(secs: u64) -> SystemTime {
+        // 2026-10-18 00:00 in Kyiv
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_792_270_800 + secs)
+    }
+
+    fn span(start: u64, end: u64, segment: Segment) -> Span {
+        Span {
+            start: t(start * HOUR),
+            end: t(end * HOUR),
+            segment,
+        }
+    }
+
+    #[test]
+    fn test_buckets_follow_local_time() {
+        let tz: Tz = "Europe/Kyiv".parse().unwrap();
+        let hours = buckets(t(1800), t(24 * HOUR), tz, true);
+        assert_eq!(hours.len(), 24);
+        assert_eq!(hours[0], (t(1800), t(HOUR)));
+        // Summer time ends on 2026-10-25, that day has 25 hours
+        let days = buckets(t(12 * HOUR), t(8 * 24 * HOUR + HOUR), tz, false);
+        assert_eq!(days.len(), 8);
+        assert_eq!(days[0], (t(12 * HOUR), t(24 * HOUR)));
+        assert_eq!(days[7], (t(7 * 24 * HOUR), t(8 * 24 * HOUR + HOUR)));
+    }
+
+    #[test]
+    fn test_svg_and_png() {
+        let tz: Tz = "Europe/Kyiv".parse().unwrap();
+        let spans = [
+            span(0, 6, Segment::Unknown),
+            span(6, 12, Segment::Up),
+            span(12, 18, Segment::Down),
+            span(18, 24, Segment::Paused),
+        ];
+        let svg = svg("24h <test>", t(0), t(24 * HOUR), &spans, tz);
+        assert!(svg.contains(">24h &lt;test&gt;: 50.00%</text>"), "{svg}");
+        assert!(
+            svg.contains(r##"<rect x="370.00" y="28" width="165.00" height="24" fill="#d93025"/>"##),
+            "{svg}"
+        );
+        assert!(
+            svg.contains(">18.10 00:00</text>") && svg.contains(">19.10 00:00</text>"),
+            "{svg}"
+        );
+        // Hourly bars, an hour without monitoring is grey
+        assert_eq!(svg.matches("<title>100.00%</title>").count(), 6);
+        assert_eq!(svg.matches("<title>0.00%</title>").count(), 6);
+        assert_eq!(svg.matches(r##"fill="#d93025"/>"##).count(), 1 + 6);
+        assert!(svg.contains(r#"text-anchor="middle">23</text>"#), "{svg}");
+        let png = png(&svg).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+        // Width 1440 in the IHDR chunk
+        assert_eq!(png[16..20], 1440u32.to_be_bytes());
+    }
+}
@@ -0,0 +1,290 @@
+use crate::db::{ID, MaintenanceWindow, UpStatus, UptimeEvent, UptimeState};
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+/// How far back recorded outages are listed in the feed.
+pub const FEED_HISTORY: Duration = Duration::from_secs(90 * 86400);
+/// RRULE day names, index 0 is Monday (bit 1 of `MaintenanceWindow::weekdays`).
+const BYDAY: [&str; 7] = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+
+/// An outage of one device, `end` is None while it goes on.
+#[derive(Debug, PartialEq)]
+pub struct Outage {
+    pub device_id: ID,
+    pub start: SystemTime,
+    pub end: Option<SystemTime>,
+}
+
+/// Pair recorded transitions (chronological, of any number of devices) into outages. An outage
+/// that began before the first event starts `duration_in_previous_state` before its end, the ones
+/// still going on are taken from the live `current` states. Ordered by start.
+pub fn outages(events: &[UptimeEvent], current: &[&UptimeState]) -> Vec<Outage> {
+    let mut outages = Vec::new();
+    let mut open: HashMap<ID, SystemTime> = HashMap::new();
+    for event in events {
+        if event.from_status == UpStatus::Down && event.to_status != UpStatus::Down {
+            let start = open
+                .remove(&event.device_id)
+                .unwrap_or(event.at - Duration::from_secs(event.duration_in_previous_state.max(0) as u64));
+            outages.push(Outage {
+                device_id: event.device_id,
+                start,
+                end: Some(event.at),
+            });
+        } else if event.to_status == UpStatus::Down && event.from_status != UpStatus::Down {
+            open.insert(event.device_id, event.at);
+        }
+    }
+    for state in current.iter().filter(|s| s.status == UpStatus::Down) {
+        outages.push(Outage {
+            device_id: state.device_id,
+            start: open.get(&state.device_id).copied().unwrap_or(state.state_changed_at),
+            end: None,
+        });
+    }
+    outages.sort_by_key(|o| o.start);
+    outages
+}
+
+/// Start or end of an event: a point in time, or a local time following the zone's daylight saving
+/// time (for recurring events).
+#[derive(Debug, Clone, Copy)]
+pub enum When {
+    At(SystemTime),
+    Local(NaiveDateTime, Tz),
+}
+
+impl When {
+    /// `DTSTART`-like property, e.g. `DTSTART:20261020T050000Z` or `DTSTART;TZID=Europe/Kyiv:20261020T080000`.
+    fn property(&self, name: &str) -> String {
+        match self {
+            When::At(at) => format!("{name}:{}", DateTime::<Utc>::from(*at).format("%Y%m%dT%H%M%SZ")),
+            When::Local(local, Tz::UTC) => format!("{name}:{}", local.format("%Y%m%dT%H%M%SZ")),
+            When::Local(local, tz) => format!("{name};TZID={tz}:{}", local.format("%Y%m%dT%H%M%S")),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Event {
+    /// Stays the same for the same outage or window, so calendar apps update instead of duplicating it.
+    pub uid: String,
+    pub start: When,
+    pub end: When,
+    pub summary: String,
+    pub description: Option<String>,
+    /// e.g. `FREQ=WEEKLY;BYDAY=TU,TH`
+    pub rrule: Option<String>,
+}
+
+impl Event {
+    /// A maintenance window, weekly ones recur from the first of their days on or after they were created.
+    pub fn maintenance(window: &MaintenanceWindow, tz: Tz, summary: String) -> Option<Event> {
+        let uid = format!("maintenance-{}@open-uptime-bot", window.id);
+        let description = window.label.clone();
+        match (
+            window.weekdays,
+            window.start_minute,
+            window.end_minute,
+            window.starts_at,
+            window.ends_at,
+        ) {
+            (Some(weekdays), Some(start_minute), Some(end_minute), _, _) if weekdays & 0x7f != 0 => {
+                let mut day = DateTime::<Utc>::from(window.created_at).with_timezone(&tz).date_naive();
+                while weekdays & (1 << day.weekday().num_days_from_monday()) == 0 {
+                    day = day.succ_opt()?;
+                }
+                let start = day.and_hms_opt(0, 0, 0)? + ChronoDuration::minutes(start_minute.into());
+                let length = (end_minute - start_minute).rem_euclid(1440);
+                let days: Vec<&str> = (0..7).filter(|d| weekdays & (1 << d) != 0).map(|d| BYDAY[d]).collect();
+                Some(Event {
+                    uid,
+                    start: When::Local(start, tz),
+                    end: When::Local(start + ChronoDuration::minutes(length.into()), tz),
+                    summary,
+                    description,
+                    rrule: Some(format!("FREQ=WEEKLY;BYDAY={}", days.join(","))),
+                })
+            }
+            (_, _, _, Some(starts_at), Some(ends_at)) => Some(Event {
+                uid,
+                start: When::At(starts_at),
+                end: When::At(ends_at),
+                summary,
+                description,
+                rrule: None,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// An iCalendar (RFC 5545) document, built event by event.
+pub struct Calendar {
+    lines: Vec<String>,
+    stamp: String,
+}
+
+impl Calendar {
+    pub fn new(name: &str, now: SystemTime) -> Calendar {
+        let mut calendar = Calendar {
+            lines: Vec::new(),
+            stamp: When::At(now).property("DTSTAMP"),
+        };
+        calendar.lines.extend(
+            [
+                "BEGIN:VCALENDAR",
+                "VERSION:2.0",
+                "PRODID:-//Open Uptime Bot//Outages//EN",
+                "CALSCALE:GREGORIAN",
+                "METHOD:PUBLISH",
+            ]
+            .map(String::from),
+        );
+        calendar.lines.push(format!("X-WR-CALNAME:{}", escape(name)));
+        calendar
+    }
+
+    pub fn add(&mut self, event: &Event) {
+        self.lines.push("BEGIN:VEVENT".to_string());
+        self.lines.push(format!("UID:{}", event.uid));
+        self.lines.push(self.stamp.clone());
+        self.lines.push(event.start.property("DTSTART"));
+        self.lines.push(event.end.property("DTEND"));
+        if let Some(rrule) = &event.rrule {
+            self.lines.push(format!("RRULE:{rrule}"));
+        }
+        self.lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+        if let Some(description) = &event.description {
+            self.lines.push(format!("DESCRIPTION:{}", escape(description)));
+        }
+        self.lines.push("END:VEVENT".to_string());
+    }
+
+    /// The document with CRLF line endings and lines folded at 75 octets.
+    pub fn finish(mut self) -> String {
+        self.lines.push("END:VCALENDAR".to_string());
+        let mut out = String::new();
+        for line in &self.lines {
+            let mut width = 0;
+            for c in line.chars() {
+                // @NOTE: Continuation lines start with a space, which counts towards their 75 octets.
+                if width + c.len_utf8() > 75 {
+                    out.push_str("\r\n ");
+                    width = 1;
+                }
+                out.push(c);
+                width += c.len_utf8();
+            }
+            out.push_str("\r\n");
+        }
+        out
+    }
+}
+
+/// Escape a TEXT value.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::EventSource;
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    fn t(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_792_000_000 + secs)
+    }
+
+    fn event(device: ID, at: u64, from: UpStatus, to: UpStatus, previous: u64) -> UptimeEvent {
+        UptimeEvent::new(
+            Uuid::nil(),
+            device,
+            from,
+            to,
+            t(at),
+            Duration::from_secs(previous),
+            EventSource::Timeout,
+        )
+    }
+
+    #[test]
+    fn test_outages_from_events() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let events = [
+            // Outage of b that started before the first event
+            event(b, 100, UpStatus::Down, UpStatus::Up, 50),
+            event(a, 200, UpStatus::Up, UpStatus::Down, 0),
+            event(a, 500, UpStatus::Down, UpStatus::Paused, 300),
+            event(a, 900, UpStatus::Paused, UpStatus::Up, 400),
+            event(b, 1000, UpStatus::Up, UpStatus::Down, 900),
+        ];
+        let mut current = UptimeState::new(b);
+        (current.status, current.state_changed_at) = (UpStatus::Down, t(1000));
+        assert_eq!(
+            outages(&events, &[&current]),
+            vec![
+                Outage {
+                    device_id: b,
+                    start: t(50),
+                    end: Some(t(100))
+                },
+                Outage {
+                    device_id: a,
+                    start: t(200),
+                    end: Some(t(500))
+                },
+                Outage {
+                    device_id: b,
+                    start: t(1000),
+                    end: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_calendar_events() {
+        let tz: Tz = "Europe/Kyiv".parse().unwrap();
+        // Created on Sunday 2026-10-18, the first Tuesday is the 20th
+        let mut window = MaintenanceWindow::weekly(Uuid::nil(), None, Some("Grid works".to_string()), 0b1010, (1380, 60));
+        window.created_at = tz.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap().into();
+        let mut calendar = Calendar::new("Power, outages", t(0));
+        calendar.add(&Event::maintenance(&window, tz, "Maintenance".to_string()).unwrap());
+        calendar.add(&Event {
+            uid: "outage-1@open-uptime-bot".to_string(),
+            start: When::At(t(0)),
+            end: When::At(t(3600)),
+            summary: "No power".to_string(),
+            description: Some(
+                "Power was off for 1 hr; a very long description that has to be folded onto the next line".to_string(),
+            ),
+            rrule: None,
+        });
+        let ics = calendar.finish();
+        assert!(ics.contains("X-WR-CALNAME:Power\\, outages\r\n"), "{ics}");
+        assert!(
+            ics.contains("DTSTART;TZID=Europe/Kyiv:20261020T230000\r\nDTEND;TZID=Europe/Kyiv:20261021T010000\r\n"),
+            "{ics}"
+        );
+        assert!(ics.contains("RRULE:FREQ=WEEKLY;BYDAY=TU,TH\r\n"), "{ics}");
+        assert!(
+            ics.contains("DTSTART:20261014T174640Z\r\nDTEND:20261014T184640Z\r\n"),
+            "{ics}"
+        );
+        assert!(
+            ics.contains("DESCRIPTION:Power was off for 1 hr\\; a very long description that has to be\r\n  folded"),
+            "{ics}"
+        );
+        assert!(ics.lines().all(|line| line.len() <= 75), "{ics}");
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n") && ics.ends_with("END:VCALENDAR\r\n"));
+    }
+}
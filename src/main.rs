@@ -12,12 +12,21 @@ mod actions;
 mod api;
 mod background;
 mod bauth;
+mod channels;
 mod context;
 mod db;
+mod email;
+mod graph;
+mod ical;
 mod notifications;
 mod ntfy;
 mod prom;
+mod schedule;
 mod schema;
+mod stats;
+mod status_page;
+mod telegram;
+mod tokens;
 
 #[derive(Database)]
 #[database("open-uptime-bot")]
@@ -33,6 +42,8 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
     prom::TOTAL_REQUESTS_SERVED.reset();
     prom::ENDPOINTS_REQUESTS_SERVED.reset();
     prom::ACTIVE_USERS.set(0);
+    // Fail on startup rather than on the first authenticated request.
+    lazy_static::initialize(&tokens::TOKEN_SECRET);
 
     let figment = rocket::Config::figment().merge((
         "databases.open-uptime-bot",
@@ -46,7 +57,7 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         },
     ));
 
-    // @WARNING: Every route handler MUST use BAuth, AdminAuth, or RateLimitGuard
+    // @WARNING: Every route handler MUST use BAuth, FeedAuth, AdminAuth, DeviceAuth, or RateLimitGuard
     //  to ensure IP rate limiting coverage. The IpRateLimitFairing sets a flag but
     //  can't reject requests in Rocket 0.5 — guards must check the flag.
     //  The route-guard-lint check in flake.nix enforces this at build time.
@@ -61,16 +72,53 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
                 api::delete_invite,
                 api::get_me,
                 api::regenerate_token,
+                api::list_tokens,
+                api::create_token,
+                api::delete_token,
+                api::list_channels,
+                api::create_channel,
+                api::update_channel,
+                api::apply_channel_url,
+                api::delete_channel,
+                api::verify_email,
+                api::list_escalation_steps,
+                api::create_escalation_step,
+                api::delete_escalation_step,
+                api::list_maintenance_windows,
+                api::create_maintenance_window,
+                api::update_maintenance_window,
+                api::delete_maintenance_window,
+                api::get_schedule,
                 api::get_ntfy_settings,
-                api::update_ntfy_settings,
                 api::get_language,
                 api::update_language,
                 api::pause_monitoring,
                 api::unpause_monitoring,
                 api::get_settings,
                 api::update_settings,
+                api::list_devices,
+                api::create_device,
+                api::get_device,
+                api::update_device,
+                api::delete_device,
+                api::get_events,
+                api::get_stats,
+                api::get_graph,
+                api::get_outages_ics,
+                api::get_status_page,
+                api::view_status_page,
+                api::list_pages,
+                api::create_page,
+                api::update_page,
+                api::delete_page,
+                api::list_public_pages,
+                api::view_page,
+                api::view_page_json,
                 api::admin_list_users,
                 api::admin_get_user,
+                api::admin_list_outbox,
+                api::admin_retry_outbox_item,
+                api::import_schedule,
                 api::delete_user,
                 api::api_up,
                 api::api_health,
@@ -86,29 +134,49 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         .attach(AdHoc::try_on_ignite("init db load", |rocket| async {
             // Populating users/tokens from the database.
             let mut conn = DB::fetch(&rocket).unwrap().0.clone().get().await.unwrap();
+            let rehashed = db::rehash_plaintext_tokens(&mut conn).await.unwrap();
+            if rehashed > 0 {
+                info!("Hashed {rehashed} plaintext token(s) left over from before token hashing");
+            }
+            for (uid, device_id) in db::split_shared_device_tokens(&mut conn).await.unwrap() {
+                warn!(
+                    "Device {device_id} of user {uid} pinged with the account token and got a new heartbeat token, regenerate it and re-flash the board"
+                );
+            }
             let items = db::get_all_states(&mut conn).await.unwrap();
             info!("Loading {n} users from the database!", n = items.len());
 
             let context = rocket.state::<context::Context>().unwrap();
             for state in &items {
-                // Initialize per-user metrics from DB state
+                // Initialize per-device metrics from DB state
                 let uid_str = state.user.id.to_string();
-                prom::UPTIME_STATE
-                    .with_label_values(&[&uid_str])
-                    .set(i64::from(&state.uptime.status));
-                let touched_ts = state
-                    .uptime
-                    .touched_at
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs_f64();
-                prom::LAST_SEEN_TIMESTAMP.with_label_values(&[&uid_str]).set(touched_ts);
+                for device in &state.devices {
+                    let device_str = device.device.id.to_string();
+                    prom::UPTIME_STATE
+                        .with_label_values(&[&uid_str, &device_str])
+                        .set(i64::from(&device.uptime.status));
+                    let touched_ts = device
+                        .uptime
+                        .touched_at
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs_f64();
+                    prom::LAST_SEEN_TIMESTAMP
+                        .with_label_values(&[&uid_str, &device_str])
+                        .set(touched_ts);
+                }
             }
             prom::ACTIVE_USERS.set(items.len() as i64);
             for state in items {
                 context.add_state(state).await;
             }
 
+            let api_tokens = db::get_all_api_tokens(&mut conn).await.unwrap();
+            info!("Loading {n} named API tokens from the database!", n = api_tokens.len());
+            for token in api_tokens {
+                context.add_api_token(token).await;
+            }
+
             // Load unused invites into memory
             let invites = db::get_all_unused_invites(&mut conn).await.unwrap();
             info!("Loading {n} invites from the database!", n = invites.len());
@@ -116,12 +184,29 @@ async fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
                 context.invite_tokens.write().await.insert(invite.token, invite.id);
             }
 
+            let outages = db::get_planned_outages(&mut conn).await.unwrap();
+            info!("Loading {n} planned outages from the database!", n = outages.len());
+            *context.schedule.write().await = schedule::Schedule::new(&outages);
+
             Ok(rocket)
         }))
         .attach(AdHoc::try_on_ignite("background handle down", |rocket| async {
             let context = rocket.state::<context::Context>().unwrap();
             let pool = DB::fetch(&rocket).expect("RIP").0.clone();
-            tokio::spawn(background::background_handle_down(context.clone(), pool));
+            tokio::spawn(background::background_handle_down(context.clone(), pool.clone()));
+            tokio::spawn(background::background_deliver_outbox(context.clone(), pool.clone()));
+            if context.telegram.is_configured() {
+                tokio::spawn(background::background_telegram_updates(context.clone(), pool.clone()));
+            }
+            if let Some(source) = schedule::ScheduleSource::from_env() {
+                info!(
+                    "Importing the outage schedule from {url} every {refresh:?}",
+                    url = source.url,
+                    refresh = source.refresh
+                );
+                tokio::spawn(background::background_import_schedule(context.clone(), pool.clone(), source));
+            }
+            tokio::spawn(background::background_flush_token_usage(context.clone(), pool));
             Ok(rocket)
         }))
         .ignite()
@@ -1,9 +1,13 @@
-use crate::{context, db, ntfy, prom};
+use crate::schedule::Schedule;
+use crate::{channels, db, ical, status_page};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use fluent::types::FluentValue;
 use fluent_templates::{Loader, static_loader};
-use rocket::tokio;
+use rocket::serde::json::{self, Value, json};
+use rocket::serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use unic_langid::LanguageIdentifier;
 
 static_loader! {
@@ -50,23 +54,207 @@ pub fn format_duration(lang: &LanguageIdentifier, duration: Duration) -> String
     parts.join(" ")
 }
 
-pub async fn dispatch_notifications(item: db::UserState, context: context::Context, duration: Option<Duration>) {
-    let lang: LanguageIdentifier = item.user.language_code.parse().unwrap_or_else(|_| {
-        warn!(
-            "Invalid language code '{}' for user {}, falling back to 'en'",
-            item.user.language_code, item.user.id
-        );
-        "en".parse().unwrap()
+/// Look up a message without arguments, unknown language codes fall back to English.
+pub fn localize(language_code: &str, key: &str) -> String {
+    let lang: LanguageIdentifier = language_code.parse().unwrap_or_else(|_| "en".parse().unwrap());
+    LOCALES.lookup(&lang, key)
+}
+
+/// Same as `localize`, for messages with string arguments.
+pub fn localize_with_args(language_code: &str, key: &str, args: &[(&str, &str)]) -> String {
+    let lang: LanguageIdentifier = language_code.parse().unwrap_or_else(|_| "en".parse().unwrap());
+    let args: HashMap<String, FluentValue> = args
+        .iter()
+        .map(|(name, value)| (name.to_string(), FluentValue::from(value.to_string())))
+        .collect();
+    LOCALES.lookup_with_args(&lang, key, &args)
+}
+
+/// Event kinds with their own settings, "escalation" covers the reminders of every step.
+pub const EVENT_KINDS: &[&str] = &["connected", "down", "up", "escalation"];
+
+/// What happens to a notification during the user's quiet hours.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum QuietAction {
+    /// Sent as usual
+    Send,
+    /// Sent with min priority: no sound or vibration
+    #[default]
+    Silent,
+    /// Held back until the quiet hours end, then sent as one digest per channel
+    Defer,
+    /// Not sent at all
+    Drop,
+}
+
+/// How notifications of one event kind are sent, stored per user in `users.notification_settings`.
+/// Fields left out use the defaults: sent, with high priority, the status emoji as tag, no icon, the status page
+/// as click URL (if the server has a public URL) and silently during quiet hours.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct EventSettings {
+    /// false stops notifications of this kind
+    pub enabled: Option<bool>,
+    /// Not for "escalation", each step has its own.
+    pub priority: Option<channels::Priority>,
+    /// ntfy tags, emoji shortcodes like "rotating_light" are shown as emojis.
+    pub tags: Option<Vec<String>>,
+    /// URL of an icon shown by ntfy.
+    pub icon: Option<String>,
+    /// URL opened when the notification is clicked, instead of the status page.
+    pub click: Option<String>,
+    /// What happens during quiet hours.
+    pub quiet: Option<QuietAction>,
+}
+
+impl EventSettings {
+    /// The user's settings for `kind`, defaults if none (or broken ones) are stored.
+    pub fn of(user: &db::User, kind: &str) -> EventSettings {
+        user.notification_settings
+            .get(kind)
+            .and_then(|v| json::from_value(v.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    fn validate(&self, kind: &str) -> Result<(), String> {
+        if kind == "escalation" && self.priority.is_some() {
+            return Err("Escalation priority is set per step".to_string());
+        }
+        if let Some(tags) = &self.tags {
+            if tags.len() > 5 {
+                return Err("At most 5 tags are allowed".to_string());
+            }
+            if tags
+                .iter()
+                .any(|t| t.is_empty() || t.len() > 32 || t.contains(',') || t.chars().any(char::is_control))
+            {
+                return Err("Tags must be 1-32 characters long, without commas".to_string());
+            }
+        }
+        for (key, url) in [("icon", &self.icon), ("click", &self.click)] {
+            if let Some(url) = url
+                && (url.len() > 2048 || channels::validate_url(url).is_err())
+            {
+                return Err(format!("{key} must be a valid http(s) URL of at most 2048 characters"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge `changes` like `{"down": {"priority": "max", "click": null}}` into the stored `settings`.
+    /// Keys set to null go back to their default, so does a whole kind set to null.
+    pub fn merge(settings: &Value, changes: &Value) -> Result<Value, String> {
+        let Value::Object(changes) = changes else {
+            return Err("notifications must be a JSON object".to_string());
+        };
+        let mut merged = match settings {
+            Value::Object(settings) => settings.clone(),
+            _ => Default::default(),
+        };
+        for (kind, change) in changes {
+            if !EVENT_KINDS.contains(&kind.as_str()) {
+                return Err(format!(
+                    "Unknown event kind '{kind}', expected one of: {}",
+                    EVENT_KINDS.join(", ")
+                ));
+            }
+            let mut current = match merged.remove(kind) {
+                Some(Value::Object(current)) => current,
+                _ => Default::default(),
+            };
+            match change {
+                Value::Null => continue,
+                Value::Object(change) => {
+                    for (key, value) in change {
+                        match value {
+                            Value::Null => current.remove(key),
+                            value => current.insert(key.clone(), value.clone()),
+                        };
+                    }
+                }
+                _ => return Err(format!("notifications.{kind} must be a JSON object or null")),
+            }
+            let parsed: EventSettings =
+                json::from_value(Value::Object(current.clone())).map_err(|err| format!("Invalid notifications.{kind}: {err}"))?;
+            parsed.validate(kind)?;
+            if !current.is_empty() {
+                merged.insert(kind.clone(), Value::Object(current));
+            }
+        }
+        Ok(Value::Object(merged))
+    }
+}
+
+/// Every event kind with the defaults filled in, as returned by the settings endpoint.
+pub fn effective_settings(user: &db::User) -> Value {
+    let kinds = EVENT_KINDS.iter().map(|kind| {
+        let settings = EventSettings::of(user, kind);
+        let status = if matches!(*kind, "down" | "escalation") {
+            db::UpStatus::Down
+        } else {
+            db::UpStatus::Up
+        };
+        let priority = match *kind {
+            "escalation" => Value::Null,
+            _ => json!(settings.priority.unwrap_or(channels::Priority::High)),
+        };
+        let value = json!({
+            "enabled": settings.enabled.unwrap_or(true),
+            "priority": priority,
+            "tags": settings.tags.unwrap_or_else(|| vec![default_tag(status).to_string()]),
+            "icon": settings.icon,
+            "click": settings.click,
+            "quiet": settings.quiet.unwrap_or_default(),
+        });
+        (kind.to_string(), value)
     });
-    if !SUPPORTED_LOCALES.contains(&lang.language.as_str()) {
-        warn!(
-            "Unsupported locale '{}' for user {}, notifications will use English fallback",
-            item.user.language_code, item.user.id
-        );
+    Value::Object(kinds.collect())
+}
+
+/// Settings kind of a notification event, None for the ones that can't be configured (flapping, stable).
+fn settings_kind(event: &str) -> Option<&'static str> {
+    match event {
+        "connected" => Some("connected"),
+        "down" => Some("down"),
+        "up" => Some("up"),
+        "still_down" => Some("escalation"),
+        _ => None,
     }
+}
+
+/// ntfy tag of notifications that leave a device in `status`, unless the user picked others.
+pub fn default_tag(status: db::UpStatus) -> &'static str {
+    match status {
+        db::UpStatus::Down => "warning",
+        _ => "white_check_mark",
+    }
+}
+
+/// Delivery attempts per outbox item before it is marked as failed.
+pub const OUTBOX_MAX_ATTEMPTS: i32 = 8;
+/// Delay after the first failed attempt, doubled after every further one (30s, 1m, 2m, ... 32m).
+const OUTBOX_BASE_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// How long to wait before the next attempt of an item that failed `attempts` times.
+pub fn outbox_retry_delay(attempts: i32) -> Duration {
+    OUTBOX_BASE_RETRY_DELAY * 2u32.pow(attempts.clamp(1, OUTBOX_MAX_ATTEMPTS) as u32 - 1)
+}
+
+/// Notifications about a status change of one of the user's devices, one outbox item per enabled
+/// channel. Callers store them with the transition (see `db::record_transition`), the outbox worker
+/// delivers them. `device` is passed separately so callers can tweak its status (e.g. Uninitialized
+/// for the "device connected" message). Outages are checked against the `schedule` of the account's queue.
+pub fn queue_notifications(
+    item: &db::UserState,
+    device: &db::DeviceState,
+    duration: Option<Duration>,
+    schedule: &Schedule,
+) -> Vec<db::OutboxItem> {
+    let lang = user_language(item);
 
     // Format the duration message based on status
-    let duration_message = match (item.uptime.status, duration) {
+    let duration_message = match (device.uptime.status, duration) {
         (db::UpStatus::Up, Some(d)) => {
             let mut args = HashMap::new();
             args.insert("duration".to_string(), FluentValue::from(format_duration(&lang, d)));
@@ -82,49 +270,312 @@ pub async fn dispatch_notifications(item: db::UserState, context: context::Conte
 
     // @NOTE: Paused devices never trigger notifications (silent freeze/thaw).
     //  Pause/unpause is intentionally silent — the user initiated it.
-    let title = match item.uptime.status {
-        db::UpStatus::Uninitialized => LOCALES.lookup(&lang, "notification-device-connected"),
-        db::UpStatus::Up => LOCALES.lookup(&lang, "notification-power-on"),
-        db::UpStatus::Down => LOCALES.lookup(&lang, "notification-power-off"),
-        db::UpStatus::Paused => return,
+    // (type label, title, status before the change), the device carries the status after it
+    let (event_type, title_key, old_status) = match device.uptime.status {
+        db::UpStatus::Uninitialized => ("connected", "notification-device-connected", db::UpStatus::Uninitialized),
+        db::UpStatus::Up => ("up", "notification-power-on", db::UpStatus::Down),
+        db::UpStatus::Down => ("down", "notification-power-off", db::UpStatus::Up),
+        db::UpStatus::Paused => return Vec::new(),
+    };
+    // "connected" is sent with the device still Uninitialized, it is Up by now
+    let new_status = match device.uptime.status {
+        db::UpStatus::Uninitialized => db::UpStatus::Up,
+        status => status,
     };
+    let title = LOCALES.lookup(&lang, title_key);
+    let mut notification = notification(
+        item,
+        device,
+        &lang,
+        event_type,
+        (old_status, new_status),
+        (title, duration_message),
+        duration,
+    );
+    if new_status == db::UpStatus::Down {
+        add_outage_plan(&mut notification, item, schedule, &lang);
+    }
+    queue(item, &notification, None)
+}
 
-    if item.ntfy.enabled {
-        let notification = ntfy::NtfyNotification {
-            topic: item.ntfy.topic,
-            title,
-            message: duration_message,
-            status: match item.uptime.status {
-                db::UpStatus::Uninitialized | db::UpStatus::Up => "white_check_mark".to_string(),
-                db::UpStatus::Down => "warning".to_string(),
-                db::UpStatus::Paused => unreachable!(),
-            },
-            priority: "high".to_string(), // Configurable for user.
-        };
+/// The device just started flapping: one notice instead of its Up/Down notifications, which are
+/// held back until it settles (see `db::DeviceState::record_flip`). `from` is the status before the last flip.
+pub fn queue_flapping_notice(item: &db::UserState, device: &db::DeviceState, from: db::UpStatus) -> Vec<db::OutboxItem> {
+    let lang = user_language(item);
+    let minutes = |m: i16| FluentValue::from(format_duration(&lang, Duration::from_secs(m as u64 * 60)));
+    let mut args = HashMap::new();
+    args.insert("count".to_string(), FluentValue::from(device.recent_flips.len()));
+    args.insert("window".to_string(), minutes(device.device.flap_window_minutes));
+    args.insert("stable".to_string(), minutes(device.device.flap_stable_minutes));
+    let message = LOCALES.lookup_with_args(&lang, "notification-flapping-message", &args);
+    let title = LOCALES.lookup(&lang, "notification-flapping");
+    let statuses = (from, device.uptime.status);
+    queue(
+        item,
+        &notification(item, device, &lang, "flapping", statuses, (title, message), None),
+        None,
+    )
+}
 
-        let ntfy_type = match item.uptime.status {
-            db::UpStatus::Uninitialized => "connected",
-            db::UpStatus::Up => "up",
-            db::UpStatus::Down => "down",
-            db::UpStatus::Paused => unreachable!(),
-        };
-        let ntfy_type = ntfy_type.to_string();
-        tokio::spawn(async move {
-            info!("Sending ntfy {notification:?} to {u:?}", u = item.ntfy.username);
-            match context.ntfy.send_notification(notification).await {
-                Ok(_) => prom::NOTIFICATIONS.with_label_values(&[&ntfy_type, "success"]).inc(),
-                Err(err) => {
-                    warn!("Failed attempting to send ntfy-cation: {err:?}");
-                    prom::NOTIFICATIONS.with_label_values(&[&ntfy_type, "failure"]).inc();
-                }
-            }
-        });
+/// A flapping device settled, tell the status it ended up in.
+pub fn queue_stable_notice(item: &db::UserState, device: &db::DeviceState) -> Vec<db::OutboxItem> {
+    let lang = user_language(item);
+    let message = match device.uptime.status {
+        db::UpStatus::Down => LOCALES.lookup(&lang, "notification-stable-down"),
+        _ => LOCALES.lookup(&lang, "notification-stable-up"),
+    };
+    let title = LOCALES.lookup(&lang, "notification-stable");
+    let statuses = (device.uptime.status, device.uptime.status);
+    queue(
+        item,
+        &notification(item, device, &lang, "stable", statuses, (title, message), None),
+        None,
+    )
+}
+
+/// Reminder that the device is still down, `elapsed` after the outage started. Sent with the step's
+/// priority, also to its extra channel.
+pub fn queue_escalation(
+    item: &db::UserState,
+    device: &db::DeviceState,
+    (step, elapsed): (&db::EscalationStep, Duration),
+    schedule: &Schedule,
+) -> Vec<db::OutboxItem> {
+    let lang = user_language(item);
+    let mut args = HashMap::new();
+    args.insert("duration".to_string(), FluentValue::from(format_duration(&lang, elapsed)));
+    let message = LOCALES.lookup_with_args(&lang, "notification-still-down-message", &args);
+    let title = LOCALES.lookup(&lang, "notification-still-down");
+    let statuses = (db::UpStatus::Down, db::UpStatus::Down);
+    let mut notification = notification(item, device, &lang, "still_down", statuses, (title, message), Some(elapsed));
+    notification.priority = step.priority;
+    add_outage_plan(&mut notification, item, schedule, &lang);
+    queue(item, &notification, step.channel_id)
+}
+
+/// Tell whether an outage is a planned one of the account's queue, and until when power is planned to
+/// be off. Nothing for accounts without a queue, or with one the schedule doesn't know.
+fn add_outage_plan(
+    notification: &mut channels::Notification,
+    item: &db::UserState,
+    schedule: &Schedule,
+    lang: &LanguageIdentifier,
+) {
+    let Some(queue) = item
+        .user
+        .outage_queue
+        .as_ref()
+        .filter(|q| schedule.queues().any(|known| known == q.as_str()))
+    else {
+        return;
+    };
+    let now = SystemTime::now();
+    let (plan, note) = match schedule.planned_until(queue, now) {
+        Some(until) => {
+            let local = item.user.local_time(until);
+            let format = if local.date_naive() == item.user.local_time(now).date_naive() {
+                "%H:%M"
+            } else {
+                "%d.%m %H:%M"
+            };
+            let mut args = HashMap::new();
+            args.insert("queue".to_string(), FluentValue::from(queue.clone()));
+            args.insert("until".to_string(), FluentValue::from(local.format(format).to_string()));
+            let plan = channels::OutagePlan {
+                queue: queue.clone(),
+                planned: true,
+                until: Some(until.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()),
+            };
+            (plan, LOCALES.lookup_with_args(lang, "notification-planned-outage", &args))
+        }
+        None => {
+            let plan = channels::OutagePlan {
+                queue: queue.clone(),
+                planned: false,
+                until: None,
+            };
+            (plan, LOCALES.lookup(lang, "notification-unplanned-outage"))
+        }
+    };
+    notification.message = match notification.message.as_str() {
+        "" => note,
+        message => format!("{message}. {note}"),
+    };
+    notification.schedule = Some(plan);
+}
+
+fn user_language(item: &db::UserState) -> LanguageIdentifier {
+    let lang: LanguageIdentifier = item.user.language_code.parse().unwrap_or_else(|_| {
+        warn!(
+            "Invalid language code '{}' for user {}, falling back to 'en'",
+            item.user.language_code, item.user.id
+        );
+        "en".parse().unwrap()
+    });
+    if !SUPPORTED_LOCALES.contains(&lang.language.as_str()) {
+        warn!(
+            "Unsupported locale '{}' for user {}, notifications will use English fallback",
+            item.user.language_code, item.user.id
+        );
+    }
+    lang
+}
+
+/// Notification about `device`. `statuses` is (old, new), `text` the localized (title, message).
+fn notification(
+    item: &db::UserState,
+    device: &db::DeviceState,
+    lang: &LanguageIdentifier,
+    event_type: &str,
+    statuses: (db::UpStatus, db::UpStatus),
+    (title, message): (String, String),
+    duration: Option<Duration>,
+) -> channels::Notification {
+    // Only name the device when there is more than one, single-device accounts keep the short title
+    let title = if item.devices.len() > 1 {
+        let mut args = HashMap::new();
+        args.insert("device".to_string(), FluentValue::from(device.device.name.clone()));
+        args.insert("title".to_string(), FluentValue::from(title));
+        LOCALES.lookup_with_args(lang, "notification-title-with-device", &args)
+    } else {
+        title
+    };
+
+    let settings = settings_kind(event_type)
+        .map(|kind| EventSettings::of(&item.user, kind))
+        .unwrap_or_default();
+    channels::Notification {
+        event: event_type.to_string(),
+        user_id: item.user.id,
+        device_id: device.device.id,
+        device_name: device.device.name.clone(),
+        old_status: statuses.0,
+        new_status: statuses.1,
+        duration: duration.map(|d| d.as_secs()),
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        language: item.user.language_code.clone(),
+        title,
+        message,
+        priority: settings.priority.unwrap_or(channels::Priority::High),
+        tags: settings.tags.unwrap_or_else(|| vec![default_tag(statuses.1).to_string()]),
+        icon: settings.icon,
+        click: settings.click.or_else(|| status_page::url(&item.user)),
+        deferred: false,
+        schedule: None,
     }
 }
 
-/// Current UTC time-of-day in minutes (0-1439), for maintenance window checks.
-pub fn utc_minute_of_day(epoch_secs: u64) -> i32 {
-    ((epoch_secs % 86400) / 60) as i32
+/// One outbox item per enabled channel, plus `extra_channel` even if it is disabled. Nothing if the
+/// user switched off this kind of notification. During quiet hours it is sent, silenced, held back or
+/// dropped depending on the kind.
+fn queue(item: &db::UserState, notification: &channels::Notification, extra_channel: Option<db::ID>) -> Vec<db::OutboxItem> {
+    let settings = settings_kind(&notification.event)
+        .map(|kind| EventSettings::of(&item.user, kind))
+        .unwrap_or_default();
+    if settings.enabled == Some(false) {
+        return Vec::new();
+    }
+    let mut notification = notification.clone();
+    let mut deliver_at = None;
+    if let Some(until) = item.user.quiet_hours_until(SystemTime::now()) {
+        match settings.quiet.unwrap_or_default() {
+            QuietAction::Send => {}
+            QuietAction::Silent => notification.priority = channels::Priority::Min,
+            QuietAction::Defer => (notification.deferred, deliver_at) = (true, Some(until)),
+            QuietAction::Drop => return Vec::new(),
+        }
+    }
+    item.channels
+        .iter()
+        .filter(|c| c.enabled || Some(c.id) == extra_channel)
+        .map(|channel| {
+            let mut outbox_item = db::OutboxItem::new(channel, &notification);
+            if let Some(at) = deliver_at {
+                outbox_item.next_attempt_at = at;
+            }
+            outbox_item
+        })
+        .collect()
+}
+
+/// One notification instead of the ones held back for a channel during quiet hours, listing them
+/// oldest first with their local time of day in `tz`. Takes the priority of the most urgent one.
+pub fn digest(notifications: &[channels::Notification], tz: Tz) -> Option<channels::Notification> {
+    let mut notifications = notifications.to_vec();
+    notifications.sort_by_key(|n| n.timestamp);
+    let (first, last) = (notifications.first()?, notifications.last()?);
+    let lang: LanguageIdentifier = last.language.parse().unwrap_or_else(|_| "en".parse().unwrap());
+    let mut args = HashMap::new();
+    args.insert("count".to_string(), FluentValue::from(notifications.len()));
+    let lines: Vec<String> = notifications
+        .iter()
+        .map(|n| {
+            let time = DateTime::<Utc>::from(UNIX_EPOCH + Duration::from_secs(n.timestamp)).with_timezone(&tz);
+            let line = format!("{} {} {}", time.format("%H:%M"), n.title, n.message);
+            line.trim_end().to_string()
+        })
+        .collect();
+    Some(channels::Notification {
+        event: "digest".to_string(),
+        old_status: first.old_status,
+        duration: None,
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        title: LOCALES.lookup_with_args(&lang, "notification-digest", &args),
+        message: lines.join("\n"),
+        priority: notifications
+            .iter()
+            .map(|n| n.priority)
+            .max()
+            .unwrap_or(channels::Priority::High),
+        deferred: false,
+        ..last.clone()
+    })
+}
+
+/// The account's outages (from its recorded `events`, chronological) and maintenance windows as an
+/// iCalendar feed in the account's language. Outages are named after their device on accounts with
+/// several devices.
+pub fn outages_calendar(item: &db::UserState, events: &[db::UptimeEvent], now: SystemTime) -> String {
+    let lang = user_language(item);
+    let duration_args = |d: Duration| {
+        let mut args = HashMap::new();
+        args.insert("duration".to_string(), FluentValue::from(format_duration(&lang, d)));
+        args
+    };
+    let mut calendar = ical::Calendar::new(&LOCALES.lookup(&lang, "calendar-name"), now);
+    let current: Vec<&db::UptimeState> = item.devices.iter().map(|d| &d.uptime).collect();
+    for outage in ical::outages(events, &current) {
+        let mut summary = LOCALES.lookup(&lang, "calendar-outage");
+        if item.devices.len() > 1
+            && let Some(device) = item.device(outage.device_id)
+        {
+            let mut args = HashMap::new();
+            args.insert("device".to_string(), FluentValue::from(device.device.name.clone()));
+            args.insert("title".to_string(), FluentValue::from(summary));
+            summary = LOCALES.lookup_with_args(&lang, "notification-title-with-device", &args);
+        }
+        let end = outage.end.unwrap_or(now);
+        let lasted = end.duration_since(outage.start).unwrap_or_default();
+        let description = match outage.end {
+            Some(_) => LOCALES.lookup_with_args(&lang, "duration-power-was-off", &duration_args(lasted)),
+            None => LOCALES.lookup_with_args(&lang, "notification-still-down-message", &duration_args(lasted)),
+        };
+        let start_secs = outage.start.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        calendar.add(&ical::Event {
+            uid: format!("outage-{}-{start_secs}@open-uptime-bot", outage.device_id),
+            start: ical::When::At(outage.start),
+            end: ical::When::At(end),
+            summary,
+            description: Some(description),
+            rrule: None,
+        });
+    }
+    for window in &item.maintenance {
+        if let Some(event) = ical::Event::maintenance(window, item.user.tz(), LOCALES.lookup(&lang, "calendar-maintenance")) {
+            calendar.add(&event);
+        }
+    }
+    calendar.finish()
 }
 
 #[cfg(test)]
@@ -242,4 +693,147 @@ mod tests {
         let duration = Duration::from_secs(238 * 86400 + 15 * 3600 + 13 * 60);
         assert_eq!(format_duration(&lang, duration), "238 days 15 hr 13 min");
     }
+
+    #[test]
+    fn test_outbox_retry_delay_doubles_up_to_last_attempt() {
+        assert_eq!(outbox_retry_delay(1), Duration::from_secs(30));
+        assert_eq!(outbox_retry_delay(2), Duration::from_secs(60));
+        assert_eq!(outbox_retry_delay(7), Duration::from_secs(32 * 60));
+        // Out of range values are clamped instead of overflowing
+        assert_eq!(outbox_retry_delay(0), Duration::from_secs(30));
+        assert_eq!(outbox_retry_delay(1000), outbox_retry_delay(OUTBOX_MAX_ATTEMPTS));
+    }
+
+    #[test]
+    fn test_flapping_message_plurals() {
+        let args: HashMap<String, FluentValue> = [
+            ("count".to_string(), FluentValue::from(4)),
+            ("window".to_string(), FluentValue::from("10 хв")),
+            ("stable".to_string(), FluentValue::from("15 хв")),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            LOCALES.lookup_with_args(&uk(), "notification-flapping-message", &args),
+            "4 зміни стану за 10 хв. Сповіщення призупинено, доки девайс не буде стабільним 15 хв."
+        );
+        assert!(
+            LOCALES
+                .lookup_with_args(&en(), "notification-flapping-message", &args)
+                .starts_with("4 ups and downs within")
+        );
+    }
+
+    #[test]
+    fn test_still_down_message_uses_duration() {
+        let lang = uk();
+        let mut args = HashMap::new();
+        args.insert(
+            "duration".to_string(),
+            FluentValue::from(format_duration(&lang, Duration::from_secs(4 * 3600 + 5 * 60))),
+        );
+        assert_eq!(
+            LOCALES.lookup_with_args(&lang, "notification-still-down-message", &args),
+            "Світла немає вже 4 год 5 хв"
+        );
+    }
+
+    #[test]
+    fn test_event_settings_merge() {
+        let stored = json!({"down": {"priority": "max", "click": "https://example.com/status"}});
+        let merged = EventSettings::merge(
+            &stored,
+            &json!({"down": {"click": null, "tags": ["rotating_light"]}, "up": {"enabled": false}}),
+        )
+        .unwrap();
+        assert_eq!(
+            merged,
+            json!({"down": {"priority": "max", "tags": ["rotating_light"]}, "up": {"enabled": false}})
+        );
+        // Resetting every key, or the kind as a whole, drops it
+        let merged = EventSettings::merge(&merged, &json!({"up": {"enabled": null}, "down": null})).unwrap();
+        assert_eq!(merged, json!({}));
+    }
+
+    #[test]
+    fn test_event_settings_merge_rejects_invalid() {
+        let stored = json!({});
+        for changes in [
+            json!({"restored": {}}),
+            json!({"down": {"priority": "urgent"}}),
+            json!({"down": {"colour": "red"}}),
+            json!({"down": {"tags": ["a,b"]}}),
+            json!({"down": {"click": "javascript:alert(1)"}}),
+            json!({"escalation": {"priority": "max"}}),
+            json!({"down": {"quiet": "later"}}),
+            json!(["down"]),
+        ] {
+            assert!(EventSettings::merge(&stored, &changes).is_err(), "{changes}");
+        }
+    }
+
+    fn held_back(timestamp: u64, title: &str, message: &str, priority: channels::Priority) -> channels::Notification {
+        channels::Notification {
+            event: "down".to_string(),
+            user_id: db::ID::nil(),
+            device_id: db::ID::nil(),
+            device_name: "default".to_string(),
+            old_status: db::UpStatus::Up,
+            new_status: db::UpStatus::Down,
+            duration: Some(60),
+            timestamp,
+            language: "uk".to_string(),
+            title: title.to_string(),
+            message: message.to_string(),
+            priority,
+            tags: vec!["warning".to_string()],
+            icon: None,
+            click: None,
+            deferred: true,
+            schedule: None,
+        }
+    }
+
+    #[test]
+    fn test_digest_lists_held_back_notifications() {
+        assert!(digest(&[], Tz::UTC).is_none());
+        let day = 20_000 * 86400;
+        let mut up = held_back(day + 3 * 3600 + 5 * 60, "Світло з'явилося!", "", channels::Priority::Min);
+        (up.old_status, up.new_status) = (db::UpStatus::Down, db::UpStatus::Up);
+        let down = held_back(
+            day + 23 * 60,
+            "Відключення світла!",
+            "Світло було 5 год",
+            channels::Priority::Max,
+        );
+        let digest = digest(&[up, down], Tz::Europe__Kyiv).unwrap();
+        assert_eq!(digest.event, "digest");
+        assert_eq!(digest.title, "2 сповіщення за тихі години");
+        assert_eq!(
+            digest.message,
+            "03:23 Відключення світла! Світло було 5 год\n06:05 Світло з'явилося!"
+        );
+        assert_eq!((digest.old_status, digest.new_status), (db::UpStatus::Up, db::UpStatus::Up));
+        assert_eq!(digest.priority, channels::Priority::Max);
+        assert!(!digest.deferred && digest.duration.is_none());
+    }
+
+    #[test]
+    fn test_outage_plan_messages() {
+        let mut args = HashMap::new();
+        args.insert("queue".to_string(), FluentValue::from("3.1"));
+        args.insert("until".to_string(), FluentValue::from("18:00"));
+        assert_eq!(
+            LOCALES.lookup_with_args(&uk(), "notification-planned-outage", &args),
+            "Планове відключення (черга 3.1, до 18:00)"
+        );
+        assert_eq!(
+            LOCALES.lookup_with_args(&en(), "notification-planned-outage", &args),
+            "Planned outage (queue 3.1, until 18:00)"
+        );
+        assert_eq!(
+            LOCALES.lookup(&uk(), "notification-unplanned-outage"),
+            "Позапланове відключення"
+        );
+    }
 }
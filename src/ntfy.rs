@@ -48,8 +48,11 @@ pub struct NtfyNotification {
     pub topic: String,
     pub title: String,
     pub message: String,
-    pub status: String,
+    /// Comma separated.
+    pub tags: String,
     pub priority: String,
+    pub icon: Option<String>,
+    pub click: Option<String>,
 }
 
 impl NtfyClient {
@@ -75,7 +78,7 @@ impl NtfyClient {
         }
     }
 
-    fn generate_new_user(&self, enabled: bool) -> NtfyUser {
+    fn generate_new_user(&self) -> NtfyUser {
         // @NOTE: Building custom passphrase gen configuration. I have no clue
         //  how secure this actually is and I did not evaluate this package for
         //  security. This basic passphrase config feels good enough for current
@@ -97,7 +100,6 @@ impl NtfyClient {
         let user_suffix: String = (&mut rng).sample_iter(&Alphanumeric).take(8).map(char::from).collect();
         NtfyUser {
             id: Uuid::new_v4(),
-            enabled,
             topic: format!("topic_{topic_suffix}"),
             topic_permission: "ro".to_string(),
             username: format!("user_{user_suffix}"),
@@ -106,8 +108,8 @@ impl NtfyClient {
         }
     }
 
-    pub async fn create_new_user(&self, enabled: bool) -> Result<NtfyUser> {
-        let user = self.generate_new_user(enabled);
+    pub async fn create_new_user(&self) -> Result<NtfyUser> {
+        let user = self.generate_new_user();
 
         let new_user_response = self
             .client
@@ -151,15 +153,20 @@ impl NtfyClient {
 
     pub async fn send_notification(&self, data: NtfyNotification) -> Result<()> {
         // Note we are still using admin authentication header here to write.
-        self.client
+        let mut request = self
+            .client
             .post(format!("{base}/{topic}", base = self.base_url, topic = data.topic))
             .body(data.message)
-            .header("X-Tags", data.status)
+            .header("X-Tags", data.tags)
             .header("X-Title", data.title)
-            .header("X-Priority", data.priority)
-            .send()
-            .await?
-            .error_for_status()?;
+            .header("X-Priority", data.priority);
+        if let Some(icon) = data.icon {
+            request = request.header("X-Icon", icon);
+        }
+        if let Some(click) = data.click {
+            request = request.header("X-Click", click);
+        }
+        request.send().await?.error_for_status()?;
 
         Ok(())
     }
@@ -1,5 +1,5 @@
 use lazy_static::lazy_static;
-use prometheus::{self, Encoder, GaugeVec, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder};
+use prometheus::{self, Encoder, Gauge, GaugeVec, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder};
 use rocket::fairing::{Fairing, Info, Kind};
 use rocket::{Data, Request, Response, State};
 use std::time::Instant;
@@ -28,14 +28,14 @@ lazy_static! {
     // @NOTE: State encoding: 0=Uninitialized, 1=Up, 2=Down, 3=Paused
     pub static ref UPTIME_STATE: IntGaugeVec = prometheus::register_int_gauge_vec!(
         "oubot_uptime_state",
-        "Current uptime state per user (0=uninit, 1=up, 2=down, 3=paused)",
-        &["user_id"]
+        "Current uptime state per device (0=uninit, 1=up, 2=down, 3=paused)",
+        &["user_id", "device_id"]
     )
     .unwrap();
     pub static ref LAST_SEEN_TIMESTAMP: GaugeVec = prometheus::register_gauge_vec!(
         "oubot_last_seen_timestamp",
-        "Unix timestamp of last heartbeat per user",
-        &["user_id"]
+        "Unix timestamp of last heartbeat per device",
+        &["user_id", "device_id"]
     )
     .unwrap();
     pub static ref AUTH_FAILURES: IntCounterVec = prometheus::register_int_counter_vec!(
@@ -46,8 +46,23 @@ lazy_static! {
     .unwrap();
     pub static ref NOTIFICATIONS: IntCounterVec = prometheus::register_int_counter_vec!(
         "oubot_notifications_total",
-        "Notifications sent by type and result",
-        &["type", "result"]
+        "Notification delivery attempts by type, result (success, retry or failure) and channel",
+        &["type", "result", "channel"]
+    )
+    .unwrap();
+    pub static ref OUTBOX_PENDING: IntGauge = prometheus::register_int_gauge!(
+        "oubot_notification_outbox_pending",
+        "Notifications waiting for delivery"
+    )
+    .unwrap();
+    pub static ref OUTBOX_OLDEST_AGE: Gauge = prometheus::register_gauge!(
+        "oubot_notification_outbox_oldest_age_seconds",
+        "Age of the oldest notification waiting for delivery, 0 if there is none"
+    )
+    .unwrap();
+    pub static ref OUTBOX_FAILED: IntGauge = prometheus::register_int_gauge!(
+        "oubot_notification_outbox_failed",
+        "Notifications that ran out of delivery attempts, kept for inspection"
     )
     .unwrap();
     pub static ref ACTIVE_USERS: IntGauge = prometheus::register_int_gauge!(
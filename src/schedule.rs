@@ -0,0 +1,456 @@
+use crate::db::PlannedOutage;
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use lazy_static::lazy_static;
+use rocket::serde::Deserialize;
+use rocket::serde::json::{self, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::env::var;
+use std::time::{Duration, SystemTime};
+
+lazy_static! {
+    /// Time zone of schedule times given without an offset, the grid operator's local time.
+    pub static ref SCHEDULE_TZ: Tz = var("SCHEDULE_TIMEZONE")
+        .map(|tz| tz.parse().expect("SCHEDULE_TIMEZONE must be an IANA time zone, e.g. Europe/Kyiv"))
+        .unwrap_or(chrono_tz::Europe::Kyiv);
+}
+
+/// Planned outages that ended longer ago than this are deleted on the next import.
+pub const RETENTION: Duration = Duration::from_secs(31 * 86400);
+/// Upper bound on the slots of one import.
+pub const MAX_IMPORT_OUTAGES: usize = 20_000;
+
+/// File formats schedules are imported from.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Format {
+    /// `[{"queue": "3.1", "start": "2026-10-20T08:00", "end": "2026-10-20T12:00"}, ...]`, or the
+    /// slots keyed by queue: `{"3.1": [{"start": ..., "end": ...}]}`
+    Json,
+    /// `queue,start,end` or `queue,date,start,end` rows like `3.1,2026-10-20,08:00,12:00`
+    Csv,
+    /// iCalendar events, the queue is taken from CATEGORIES or given with the import
+    Ics,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            "ics" | "ical" => Some(Format::Ics),
+            _ => None,
+        }
+    }
+
+    /// Format named by the file extension of a URL or path.
+    pub fn guess(path: &str) -> Option<Format> {
+        let path = path.split(['?', '#']).next().unwrap_or(path);
+        Format::from_name(path.rsplit_once('.')?.1)
+    }
+}
+
+/// Queue names are short, like "3.1" or "4-2".
+pub fn validate_queue(queue: &str) -> Result<(), String> {
+    if queue.is_empty()
+        || queue.len() > 16
+        || !queue
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+    {
+        return Err(format!(
+            "Invalid queue '{queue}', expected up to 16 letters, digits, dots or dashes (e.g. 3.1)"
+        ));
+    }
+    Ok(())
+}
+
+/// Parse a schedule. `queue` is used for ICS events without CATEGORIES, times without an offset
+/// are local to `tz`.
+pub fn parse(format: Format, body: &str, queue: Option<&str>, tz: Tz) -> Result<Vec<PlannedOutage>, String> {
+    if let Some(queue) = queue {
+        validate_queue(queue)?;
+    }
+    let outages = match format {
+        Format::Json => parse_json(body, tz)?,
+        Format::Csv => parse_csv(body, tz)?,
+        Format::Ics => parse_ics(body, queue, tz)?,
+    };
+    if outages.len() > MAX_IMPORT_OUTAGES {
+        return Err(format!(
+            "At most {MAX_IMPORT_OUTAGES} planned outages can be imported at once"
+        ));
+    }
+    for outage in &outages {
+        validate_queue(&outage.queue)?;
+        if outage.ends_at <= outage.starts_at {
+            return Err(format!("Planned outage of queue {} ends before it starts", outage.queue));
+        }
+    }
+    Ok(outages)
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+struct JsonOutage {
+    queue: Option<String>,
+    start: String,
+    end: String,
+}
+
+fn parse_json(body: &str, tz: Tz) -> Result<Vec<PlannedOutage>, String> {
+    let value: Value = json::from_str(body).map_err(|err| format!("Invalid JSON: {err}"))?;
+    let rows: Vec<(Option<String>, Value)> = match value {
+        Value::Array(rows) => rows.into_iter().map(|row| (None, row)).collect(),
+        Value::Object(queues) => queues
+            .into_iter()
+            .flat_map(|(queue, rows)| match rows {
+                Value::Array(rows) => rows.into_iter().map(|row| (Some(queue.clone()), row)).collect(),
+                other => vec![(Some(queue), other)],
+            })
+            .collect(),
+        _ => return Err("Expected a JSON array of outages, or an object with the outages of each queue".to_string()),
+    };
+    rows.into_iter()
+        .map(|(queue, row)| {
+            let row: JsonOutage = json::from_value(row).map_err(|err| format!("Invalid outage: {err}"))?;
+            let Some(queue) = queue.or(row.queue) else {
+                return Err("Every outage needs a queue".to_string());
+            };
+            let (start, end) = (parse_time(&row.start, tz)?, parse_time(&row.end, tz)?);
+            Ok(PlannedOutage::new(queue, start, end))
+        })
+        .collect()
+}
+
+fn parse_csv(body: &str, tz: Tz) -> Result<Vec<PlannedOutage>, String> {
+    let mut outages = Vec::new();
+    for (i, line) in body.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || (i == 0 && line.to_ascii_lowercase().starts_with("queue")) {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim().trim_matches('"')).collect();
+        let (queue, start, end) = match fields.as_slice() {
+            [queue, start, end] => (queue, parse_time(start, tz)?, parse_time(end, tz)?),
+            [queue, date, start, end] => {
+                let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .map_err(|_| format!("Line {}: invalid date '{date}', expected YYYY-MM-DD", i + 1))?;
+                let start = local_time(date, start, tz).map_err(|err| format!("Line {}: {err}", i + 1))?;
+                let mut end = local_time(date, end, tz).map_err(|err| format!("Line {}: {err}", i + 1))?;
+                // 22:00-02:00 ends the next day
+                if end <= start {
+                    end = local_time(date + ChronoDuration::days(1), fields[3], tz)?;
+                }
+                (queue, start, end)
+            }
+            _ => return Err(format!("Line {}: expected queue,start,end or queue,date,start,end", i + 1)),
+        };
+        outages.push(PlannedOutage::new(queue.to_string(), start, end));
+    }
+    Ok(outages)
+}
+
+fn parse_ics(body: &str, queue: Option<&str>, tz: Tz) -> Result<Vec<PlannedOutage>, String> {
+    // Long lines are folded onto continuation lines starting with a space or tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in body.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.trim_end().to_string()),
+        }
+    }
+    let mut outages = Vec::new();
+    let mut event: Option<(Option<SystemTime>, Option<SystemTime>, Option<String>)> = None;
+    for line in &lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+        match (name.to_ascii_uppercase().as_str(), event.as_mut()) {
+            ("BEGIN", _) if value.eq_ignore_ascii_case("VEVENT") => event = Some((None, None, None)),
+            ("END", Some((start, end, categories))) if value.eq_ignore_ascii_case("VEVENT") => {
+                let (Some(start), Some(end)) = (*start, *end) else {
+                    return Err("Every event needs DTSTART and DTEND".to_string());
+                };
+                let Some(queue) = categories.take().or(queue.map(str::to_string)) else {
+                    return Err("Event without CATEGORIES, give the queue of the calendar with the import".to_string());
+                };
+                outages.push(PlannedOutage::new(queue, start, end));
+                event = None;
+            }
+            ("DTSTART", Some((start, _, _))) => *start = Some(parse_ics_time(value, params, tz)?),
+            ("DTEND", Some((_, end, _))) => *end = Some(parse_ics_time(value, params, tz)?),
+            ("CATEGORIES", Some((_, _, categories))) => *categories = value.split(',').next().map(|c| c.trim().to_string()),
+            ("RRULE", Some(_)) => return Err("Recurring events are not supported".to_string()),
+            _ => {}
+        }
+    }
+    Ok(outages)
+}
+
+/// ICS date-time: `20261020T080000Z` (UTC), `20261020T080000` with an optional TZID parameter, or a
+/// whole day as `20261020`.
+fn parse_ics_time(value: &str, params: &str, tz: Tz) -> Result<SystemTime, String> {
+    let tz = match params.split(';').find_map(|p| p.strip_prefix("TZID=")) {
+        Some(tzid) => tzid.trim_matches('"').parse().map_err(|_| format!("Unknown TZID '{tzid}'"))?,
+        None => tz,
+    };
+    let invalid = || format!("Invalid ICS time '{value}'");
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").map_err(|_| invalid())?;
+        return Ok(Utc.from_utc_datetime(&naive).into());
+    }
+    let naive = match NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        Ok(naive) => naive,
+        Err(_) => NaiveDate::parse_from_str(value, "%Y%m%d")
+            .map_err(|_| invalid())?
+            .and_time(NaiveTime::MIN),
+    };
+    from_local(naive, tz)
+}
+
+/// RFC 3339 (`2026-10-20T08:00:00+03:00`), or a local `YYYY-MM-DDTHH:MM[:SS]` (a space instead of
+/// the T works too) in `tz`. "24:00" is midnight at the end of the day, as schedules write it.
+fn parse_time(value: &str, tz: Tz) -> Result<SystemTime, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc).into());
+    }
+    let Some((date, time)) = value.split_once(['T', ' ']) else {
+        return Err(format!("Invalid time '{value}', expected YYYY-MM-DDTHH:MM"));
+    };
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("Invalid date in '{value}'"))?;
+    local_time(date, time, tz)
+}
+
+/// `time` (HH:MM or HH:MM:SS, up to 24:00) on the local `date` in `tz`.
+fn local_time(date: NaiveDate, time: &str, tz: Tz) -> Result<SystemTime, String> {
+    if time == "24:00" || time == "24:00:00" {
+        return from_local((date + ChronoDuration::days(1)).and_time(NaiveTime::MIN), tz);
+    }
+    let time = NaiveTime::parse_from_str(time, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
+        .map_err(|_| format!("Invalid time of day '{time}', expected HH:MM"))?;
+    from_local(date.and_time(time), tz)
+}
+
+/// A local time in `tz`. Of a time repeated when daylight saving time ends, the first occurrence is
+/// taken, one skipped when it starts is moved past the jump.
+fn from_local(naive: NaiveDateTime, tz: Tz) -> Result<SystemTime, String> {
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(naive + ChronoDuration::hours(1))).earliest())
+        .map(|time| time.with_timezone(&Utc).into())
+        .ok_or_else(|| format!("{naive} doesn't exist in {tz}"))
+}
+
+/// Where the schedule is fetched from periodically, configured by SCHEDULE_URL (see docs/SETUP.md).
+#[derive(Debug, Clone)]
+pub struct ScheduleSource {
+    pub url: String,
+    pub format: Format,
+    /// Queue of ICS events without CATEGORIES.
+    pub queue: Option<String>,
+    pub refresh: Duration,
+    client: reqwest::Client,
+}
+
+impl ScheduleSource {
+    /// None without SCHEDULE_URL. Panics on a format that can't be told, like the other settings
+    /// read on startup.
+    pub fn from_env() -> Option<ScheduleSource> {
+        let url = var("SCHEDULE_URL").ok().filter(|url| !url.is_empty())?;
+        let format = match var("SCHEDULE_FORMAT") {
+            Ok(name) => Format::from_name(&name).expect("SCHEDULE_FORMAT must be json, csv or ics"),
+            Err(_) => Format::guess(&url).expect("Set SCHEDULE_FORMAT, it can't be told from SCHEDULE_URL"),
+        };
+        let refresh = var("SCHEDULE_REFRESH_MINUTES")
+            .map(|m| {
+                m.parse::<u64>()
+                    .expect("SCHEDULE_REFRESH_MINUTES must be a number of minutes")
+            })
+            .unwrap_or(60)
+            .max(1);
+        let client = reqwest::Client::builder()
+            .user_agent("OpenUptimeBot/v0")
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("RIP");
+        Some(ScheduleSource {
+            url,
+            format,
+            queue: var("SCHEDULE_QUEUE").ok().filter(|q| !q.is_empty()),
+            refresh: Duration::from_secs(refresh * 60),
+            client,
+        })
+    }
+
+    pub async fn fetch(&self) -> Result<String, String> {
+        let response = self.client.get(&self.url).send().await.map_err(|err| err.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("HTTP {}", response.status()));
+        }
+        response.text().await.map_err(|err| err.to_string())
+    }
+}
+
+/// Span of the imported slots of each queue, an import replaces what is stored for it (see
+/// `db::replace_planned_outages`).
+pub fn import_spans(outages: &[PlannedOutage]) -> HashMap<&str, (SystemTime, SystemTime)> {
+    let mut spans: HashMap<&str, (SystemTime, SystemTime)> = HashMap::new();
+    for outage in outages {
+        let span = spans.entry(&outage.queue).or_insert((outage.starts_at, outage.ends_at));
+        *span = (span.0.min(outage.starts_at), span.1.max(outage.ends_at));
+    }
+    spans
+}
+
+/// All planned outages by queue, kept in memory for annotating notifications.
+#[derive(Debug, Default)]
+pub struct Schedule {
+    /// (starts_at, ends_at) of each queue, ordered by start.
+    queues: BTreeMap<String, Vec<(SystemTime, SystemTime)>>,
+}
+
+impl Schedule {
+    pub fn new(outages: &[PlannedOutage]) -> Schedule {
+        let mut schedule = Schedule::default();
+        schedule.insert(outages);
+        schedule
+    }
+
+    fn insert(&mut self, outages: &[PlannedOutage]) {
+        for outage in outages {
+            let slots = self.queues.entry(outage.queue.clone()).or_default();
+            let slot = (outage.starts_at, outage.ends_at);
+            let pos = slots.partition_point(|s| *s < slot);
+            slots.insert(pos, slot);
+        }
+    }
+
+    /// Apply an import the way `db::replace_planned_outages` stores it.
+    pub fn replace(&mut self, outages: &[PlannedOutage], prune_before: SystemTime) {
+        for (queue, (from, until)) in import_spans(outages) {
+            if let Some(slots) = self.queues.get_mut(queue) {
+                slots.retain(|(start, end)| !(*start < until && *end > from));
+            }
+        }
+        for slots in self.queues.values_mut() {
+            slots.retain(|(_, end)| *end >= prune_before);
+        }
+        self.queues.retain(|_, slots| !slots.is_empty());
+        self.insert(outages);
+    }
+
+    /// Names of the queues with planned outages, sorted.
+    pub fn queues(&self) -> impl Iterator<Item = &str> {
+        self.queues.keys().map(String::as_str)
+    }
+
+    /// Planned outages of `queue` that haven't ended before `now`, ordered by start.
+    pub fn upcoming(&self, queue: &str, now: SystemTime) -> &[(SystemTime, SystemTime)] {
+        let slots = self.queues.get(queue).map(Vec::as_slice).unwrap_or_default();
+        let first = slots.iter().position(|(_, end)| *end > now).unwrap_or(slots.len());
+        &slots[first..]
+    }
+
+    /// End of the planned outage of `queue` going on at `now`, None if there is none. Slots that
+    /// follow each other without a gap count as one outage, so this is when power should be back.
+    pub fn planned_until(&self, queue: &str, now: SystemTime) -> Option<SystemTime> {
+        let mut until: Option<SystemTime> = None;
+        for (start, end) in self.upcoming(queue, now) {
+            match until {
+                None if *start <= now => until = Some(*end),
+                Some(current) if *start <= current => until = Some(current.max(*end)),
+                _ => break,
+            }
+        }
+        until
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kyiv(d: u32, h: u32, min: u32) -> SystemTime {
+        chrono_tz::Europe::Kyiv
+            .with_ymd_and_hms(2026, 10, d, h, min, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+            .into()
+    }
+
+    fn slots(outages: &[PlannedOutage]) -> Vec<(&str, SystemTime, SystemTime)> {
+        outages.iter().map(|o| (o.queue.as_str(), o.starts_at, o.ends_at)).collect()
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        let tz = chrono_tz::Europe::Kyiv;
+        let expected = vec![
+            ("3.1", kyiv(20, 8, 0), kyiv(20, 12, 0)),
+            ("3.1", kyiv(20, 22, 0), kyiv(21, 0, 0)),
+        ];
+
+        let body = r#"[{"queue": "3.1", "start": "2026-10-20T08:00", "end": "2026-10-20T12:00"},
+            {"queue": "3.1", "start": "2026-10-20T19:00:00Z", "end": "2026-10-20T24:00"}]"#;
+        assert_eq!(slots(&parse(Format::Json, body, None, tz).unwrap()), expected);
+        let body = r#"{"3.1": [{"start": "2026-10-20 08:00", "end": "2026-10-20 12:00"},
+            {"start": "2026-10-20T22:00", "end": "2026-10-21T00:00"}]}"#;
+        assert_eq!(slots(&parse(Format::Json, body, None, tz).unwrap()), expected);
+
+        let body = "queue,date,start,end\n3.1,2026-10-20,08:00,12:00\n# comment\n3.1,2026-10-20,22:00,24:00\n";
+        assert_eq!(slots(&parse(Format::Csv, body, None, tz).unwrap()), expected);
+        let body = "3.1,2026-10-20T08:00,2026-10-20T12:00\n3.1,2026-10-20,22:00,00:00";
+        assert_eq!(slots(&parse(Format::Csv, body, None, tz).unwrap()), expected);
+
+        let body = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nSUMMARY:Outage\r\nDTSTART;TZID=Europe/Kyiv:20261020T080000\r\n\
+            DTEND;TZID=Europe/Kyiv:20261020T120000\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTART:20261020T190000Z\r\n\
+            DTEND:202610\r\n 21T000000\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+        assert_eq!(slots(&parse(Format::Ics, body, Some("3.1"), tz).unwrap()), expected);
+        assert!(parse(Format::Ics, body, None, tz).is_err());
+        let body = body.replace("SUMMARY:Outage", "CATEGORIES:3.1");
+        assert_eq!(parse(Format::Ics, &body, Some("4.2"), tz).unwrap()[0].queue, "3.1");
+
+        assert!(parse(Format::Csv, "3.1,2026-10-20,08:00", None, tz).is_err());
+        assert!(parse(Format::Csv, "3.1 x,2026-10-20,08:00,12:00", None, tz).is_err());
+        assert!(
+            parse(
+                Format::Json,
+                r#"[{"start": "2026-10-20T08:00", "end": "2026-10-20T12:00"}]"#,
+                None,
+                tz
+            )
+            .is_err()
+        );
+        assert_eq!(Format::guess("https://example.com/schedule.ICS?v=2"), Some(Format::Ics));
+    }
+
+    #[test]
+    fn test_planned_until_and_replace() {
+        let outage = |q: &str, from: SystemTime, until: SystemTime| PlannedOutage::new(q.to_string(), from, until);
+        let mut schedule = Schedule::new(&[
+            outage("3.1", kyiv(20, 12, 0), kyiv(20, 16, 0)),
+            outage("3.1", kyiv(20, 8, 0), kyiv(20, 12, 0)),
+            outage("3.1", kyiv(20, 20, 0), kyiv(20, 22, 0)),
+            outage("4.2", kyiv(20, 8, 0), kyiv(20, 9, 0)),
+        ]);
+        // Back-to-back slots are one outage
+        assert_eq!(schedule.planned_until("3.1", kyiv(20, 9, 0)), Some(kyiv(20, 16, 0)));
+        assert_eq!(schedule.planned_until("3.1", kyiv(20, 16, 0)), None);
+        assert_eq!(schedule.planned_until("3.1", kyiv(20, 21, 0)), Some(kyiv(20, 22, 0)));
+        assert_eq!(schedule.planned_until("5.1", kyiv(20, 9, 0)), None);
+        assert_eq!(schedule.upcoming("3.1", kyiv(20, 17, 0)).len(), 1);
+
+        // A new schedule for the afternoon of queue 3.1 replaces the slots it overlaps, the rest stays
+        schedule.replace(&[outage("3.1", kyiv(20, 14, 0), kyiv(20, 18, 0))], kyiv(1, 0, 0));
+        assert_eq!(schedule.planned_until("3.1", kyiv(20, 9, 0)), Some(kyiv(20, 12, 0)));
+        assert_eq!(schedule.planned_until("3.1", kyiv(20, 17, 0)), Some(kyiv(20, 18, 0)));
+        assert_eq!(schedule.planned_until("3.1", kyiv(20, 21, 0)), Some(kyiv(20, 22, 0)));
+        assert_eq!(schedule.planned_until("4.2", kyiv(20, 8, 30)), Some(kyiv(20, 9, 0)));
+
+        // Old slots are pruned
+        schedule.replace(&[], kyiv(20, 10, 0));
+        assert_eq!(schedule.queues().collect::<Vec<_>>(), vec!["3.1"]);
+    }
+}
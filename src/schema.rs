@@ -1,15 +1,83 @@
 // @generated automatically by Diesel CLI.
 
 pub mod sql_types {
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "channel_kind_enum"))]
+    pub struct ChannelKindEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "event_source_enum"))]
+    pub struct EventSourceEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "page_visibility_enum"))]
+    pub struct PageVisibilityEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "priority_enum"))]
+    pub struct PriorityEnum;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "status_enum"))]
     pub struct StatusEnum;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "token_scope_enum"))]
+    pub struct TokenScopeEnum;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "user_type_enum"))]
     pub struct UserTypeEnum;
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::TokenScopeEnum;
+
+    api_tokens (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        device_id -> Nullable<Uuid>,
+        name -> Text,
+        scope -> TokenScopeEnum,
+        token_hash -> Text,
+        created_at -> Timestamp,
+        expires_at -> Nullable<Timestamp>,
+        last_used_at -> Nullable<Timestamp>,
+        last_used_ip -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    devices (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        created_at -> Timestamp,
+        name -> Text,
+        token_hash -> Text,
+        up_delay -> Int2,
+        min_outage_secs -> Int4,
+        flap_threshold -> Nullable<Int2>,
+        flap_window_minutes -> Int2,
+        flap_stable_minutes -> Int2,
+        recovery_heartbeats -> Int2,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::PriorityEnum;
+
+    escalation_steps (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        delay_minutes -> Int4,
+        priority -> PriorityEnum,
+        channel_id -> Nullable<Uuid>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     invites (id) {
         id -> Uuid,
@@ -22,9 +90,52 @@ diesel::table! {
 }
 
 diesel::table! {
-    ntfy_users (id) {
+    maintenance_windows (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        device_id -> Nullable<Uuid>,
+        label -> Nullable<Text>,
+        weekdays -> Nullable<Int2>,
+        start_minute -> Nullable<Int2>,
+        end_minute -> Nullable<Int2>,
+        starts_at -> Nullable<Timestamp>,
+        ends_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ChannelKindEnum;
+
+    notification_channels (id) {
         id -> Uuid,
+        user_id -> Uuid,
+        kind -> ChannelKindEnum,
         enabled -> Bool,
+        config -> Jsonb,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    notification_outbox (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        channel_id -> Uuid,
+        event -> Text,
+        payload -> Jsonb,
+        attempts -> Int4,
+        next_attempt_at -> Timestamp,
+        last_error -> Nullable<Text>,
+        created_at -> Timestamp,
+        failed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    ntfy_users (id) {
+        id -> Uuid,
         topic -> Text,
         topic_permission -> Text,
         username -> Text,
@@ -33,6 +144,56 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    planned_outages (id) {
+        id -> Uuid,
+        queue -> Text,
+        starts_at -> Timestamp,
+        ends_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    status_page_monitors (page_id, device_id) {
+        page_id -> Uuid,
+        device_id -> Uuid,
+        display_name -> Text,
+        position -> Int2,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::PageVisibilityEnum;
+
+    status_pages (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        slug -> Text,
+        title -> Text,
+        visibility -> PageVisibilityEnum,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::StatusEnum;
+    use super::sql_types::EventSourceEnum;
+
+    uptime_events (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        from_status -> StatusEnum,
+        to_status -> StatusEnum,
+        at -> Timestamp,
+        duration_in_previous_state -> Int8,
+        source -> EventSourceEnum,
+        device_id -> Uuid,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::StatusEnum;
@@ -42,9 +203,14 @@ diesel::table! {
         created_at -> Timestamp,
         touched_at -> Timestamp,
         status -> StatusEnum,
-        user_id -> Nullable<Uuid>,
         state_changed_at -> Timestamp,
         pre_pause_status -> Nullable<StatusEnum>,
+        device_id -> Uuid,
+        flapping_since -> Nullable<Timestamp>,
+        held_outage_secs -> Nullable<Int8>,
+        recovery_started_at -> Nullable<Timestamp>,
+        recovery_count -> Int2,
+        escalated_minutes -> Int4,
     }
 }
 
@@ -58,16 +224,49 @@ diesel::table! {
         user_type -> UserTypeEnum,
         invites_limit -> Int8,
         invites_used -> Int8,
-        access_token -> Text,
-        up_delay -> Int2,
+        token_hash -> Text,
         ntfy_id -> Uuid,
         language_code -> Text,
-        maint_window_start_utc -> Nullable<Int2>,
-        maint_window_end_utc -> Nullable<Int2>,
+        notification_settings -> Jsonb,
+        quiet_hours_start -> Nullable<Int2>,
+        quiet_hours_end -> Nullable<Int2>,
+        timezone -> Text,
+        outage_queue -> Nullable<Text>,
+        status_page_key -> Text,
     }
 }
 
-diesel::joinable!(uptime_states -> users (user_id));
+diesel::joinable!(api_tokens -> devices (device_id));
+diesel::joinable!(api_tokens -> users (user_id));
+diesel::joinable!(devices -> users (user_id));
+diesel::joinable!(escalation_steps -> notification_channels (channel_id));
+diesel::joinable!(escalation_steps -> users (user_id));
+diesel::joinable!(maintenance_windows -> devices (device_id));
+diesel::joinable!(maintenance_windows -> users (user_id));
+diesel::joinable!(notification_channels -> users (user_id));
+diesel::joinable!(notification_outbox -> notification_channels (channel_id));
+diesel::joinable!(notification_outbox -> users (user_id));
+diesel::joinable!(status_page_monitors -> devices (device_id));
+diesel::joinable!(status_page_monitors -> status_pages (page_id));
+diesel::joinable!(status_pages -> users (user_id));
+diesel::joinable!(uptime_events -> devices (device_id));
+diesel::joinable!(uptime_events -> users (user_id));
+diesel::joinable!(uptime_states -> devices (device_id));
 diesel::joinable!(users -> ntfy_users (ntfy_id));
 
-diesel::allow_tables_to_appear_in_same_query!(invites, ntfy_users, uptime_states, users,);
+diesel::allow_tables_to_appear_in_same_query!(
+    api_tokens,
+    devices,
+    escalation_steps,
+    invites,
+    maintenance_windows,
+    notification_channels,
+    notification_outbox,
+    ntfy_users,
+    planned_outages,
+    status_page_monitors,
+    status_pages,
+    uptime_events,
+    uptime_states,
+    users,
+);
@@ -0,0 +1,311 @@
+use crate::db::{EventSource, UpStatus, UptimeEvent, UptimeState};
+use rocket::serde::Serialize;
+use std::time::{Duration, SystemTime};
+
+/// Supported reporting windows for /api/v1/me/stats.
+pub fn parse_window(window: &str) -> Option<Duration> {
+    match window {
+        "24h" => Some(Duration::from_secs(86400)),
+        "7d" => Some(Duration::from_secs(7 * 86400)),
+        "30d" => Some(Duration::from_secs(30 * 86400)),
+        _ => None,
+    }
+}
+
+/// What the device was doing during a stretch of time.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Segment {
+    Up,
+    Down,
+    Paused,
+    /// Silent inside the maintenance window (Down transition suppressed)
+    Maintenance,
+    /// Not connected yet, or before recorded history
+    Unknown,
+}
+
+impl Segment {
+    fn from_status(status: UpStatus) -> Segment {
+        match status {
+            UpStatus::Up => Segment::Up,
+            UpStatus::Down => Segment::Down,
+            UpStatus::Paused => Segment::Paused,
+            UpStatus::Uninitialized => Segment::Unknown,
+        }
+    }
+
+    /// State the device is in right after this event.
+    fn after(event: &UptimeEvent) -> Segment {
+        if event.source == EventSource::Maintenance {
+            Segment::Maintenance
+        } else {
+            Segment::from_status(event.to_status)
+        }
+    }
+}
+
+/// A stretch of time the device spent in one state, see `timeline`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Span {
+    pub start: SystemTime,
+    pub end: SystemTime,
+    pub segment: Segment,
+}
+
+/// State the device was in at `start` and from when that is actually known, see `compute`.
+fn initial_state(
+    start: SystemTime,
+    previous: Option<&UptimeEvent>,
+    events: &[UptimeEvent],
+    current: &UptimeState,
+) -> (Segment, SystemTime) {
+    match (previous, events.first()) {
+        (Some(prev), _) => (Segment::after(prev), start),
+        (None, Some(first)) => {
+            let began = first.at - Duration::from_secs(first.duration_in_previous_state.max(0) as u64);
+            (Segment::from_status(first.from_status), began.max(start))
+        }
+        (None, None) => (Segment::from_status(current.status), current.state_changed_at.max(start)),
+    }
+}
+
+/// Split `[start, end)` into the states the device was in, for graphs. Takes the same arguments as
+/// `compute`, neighbouring spans always differ.
+pub fn timeline(
+    start: SystemTime,
+    end: SystemTime,
+    previous: Option<&UptimeEvent>,
+    events: &[UptimeEvent],
+    current: &UptimeState,
+) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+    let mut push = |from: SystemTime, to: SystemTime, segment: Segment| {
+        if from >= to {
+            return;
+        }
+        match spans.last_mut() {
+            Some(last) if last.segment == segment => last.end = to,
+            _ => spans.push(Span {
+                start: from,
+                end: to,
+                segment,
+            }),
+        }
+    };
+    let (mut segment, known_from) = initial_state(start, previous, events, current);
+    let mut cursor = known_from.min(end);
+    push(start, cursor, Segment::Unknown);
+    for event in events {
+        let at = event.at.clamp(cursor, end);
+        push(cursor, at, segment);
+        segment = Segment::after(event);
+        cursor = at;
+    }
+    push(cursor, end, segment);
+    spans
+}
+
+/// Availability summary over a window. All durations are in seconds.
+/// @NOTE: MTTR is down time per outage and MTBF is up time per outage; both are None
+///  when there were no outages in the window.
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UptimeStats {
+    pub window: u64,
+    pub up: u64,
+    pub down: u64,
+    pub paused: u64,
+    pub maintenance: u64,
+    pub unknown: u64,
+    pub outages: u64,
+    pub longest_outage: u64,
+    pub mttr: Option<u64>,
+    pub mtbf: Option<u64>,
+    /// Up time as a percentage of monitored (up + down) time
+    pub availability: Option<f64>,
+}
+
+impl UptimeStats {
+    fn add(&mut self, segment: Segment, secs: u64) {
+        match segment {
+            Segment::Up => self.up += secs,
+            Segment::Down => self.down += secs,
+            Segment::Paused => self.paused += secs,
+            Segment::Maintenance => self.maintenance += secs,
+            Segment::Unknown => self.unknown += secs,
+        }
+    }
+}
+
+/// Compute stats for `[start, end)`.
+///
+/// `previous` is the last event before `start` (if any), `events` are the events inside the
+/// window in chronological order, and `current` is the live state, used when there is no
+/// recorded history to tell what the device was doing at `start`.
+pub fn compute(
+    start: SystemTime,
+    end: SystemTime,
+    previous: Option<&UptimeEvent>,
+    events: &[UptimeEvent],
+    current: &UptimeState,
+) -> UptimeStats {
+    let secs_between = |a: SystemTime, b: SystemTime| b.duration_since(a).unwrap_or_default().as_secs();
+    let mut stats = UptimeStats {
+        window: secs_between(start, end),
+        ..Default::default()
+    };
+
+    // Work out the state at `start`, and from when it is actually known
+    let (mut segment, known_from) = initial_state(start, previous, events, current);
+    stats.add(Segment::Unknown, secs_between(start, known_from.min(end)));
+
+    let mut cursor = known_from.min(end);
+    let mut outage = 0;
+    if segment == Segment::Down {
+        stats.outages += 1;
+    }
+    for event in events {
+        let secs = secs_between(cursor, event.at);
+        stats.add(segment, secs);
+        let next = Segment::after(event);
+        if segment == Segment::Down {
+            outage += secs;
+            if next != Segment::Down {
+                stats.longest_outage = stats.longest_outage.max(outage);
+                outage = 0;
+            }
+        } else if next == Segment::Down {
+            stats.outages += 1;
+        }
+        segment = next;
+        cursor = event.at;
+    }
+    let secs = secs_between(cursor, end);
+    stats.add(segment, secs);
+    if segment == Segment::Down {
+        outage += secs;
+    }
+    stats.longest_outage = stats.longest_outage.max(outage);
+
+    stats.mttr = stats.down.checked_div(stats.outages);
+    stats.mtbf = stats.up.checked_div(stats.outages);
+    if stats.up + stats.down > 0 {
+        stats.availability = Some(stats.up as f64 * 100.0 / (stats.up + stats.down) as f64);
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    const HOUR: u64 = 3600;
+
+    fn t(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + secs)
+    }
+
+    fn event(at: u64, from: UpStatus, to: UpStatus, previous: u64, source: EventSource) -> UptimeEvent {
+        UptimeEvent::new(
+            Uuid::nil(),
+            Uuid::nil(),
+            from,
+            to,
+            t(at),
+            Duration::from_secs(previous),
+            source,
+        )
+    }
+
+    fn state(status: UpStatus, changed: u64) -> UptimeState {
+        let mut state = UptimeState::new(Uuid::nil());
+        state.status = status;
+        state.state_changed_at = t(changed);
+        state
+    }
+
+    #[test]
+    fn test_no_history_uses_current_state() {
+        let stats = compute(t(0), t(24 * HOUR), None, &[], &state(UpStatus::Up, 0));
+        assert_eq!(stats.up, 24 * HOUR);
+        assert_eq!(stats.outages, 0);
+        assert_eq!(stats.mttr, None);
+        assert_eq!(stats.availability, Some(100.0));
+    }
+
+    #[test]
+    fn test_device_connected_inside_window() {
+        let stats = compute(t(0), t(24 * HOUR), None, &[], &state(UpStatus::Up, 6 * HOUR));
+        assert_eq!(stats.unknown, 6 * HOUR);
+        assert_eq!(stats.up, 18 * HOUR);
+    }
+
+    #[test]
+    fn test_outages_mttr_mtbf() {
+        let prev = event(0, UpStatus::Down, UpStatus::Up, 60, EventSource::Heartbeat);
+        let events = [
+            event(HOUR, UpStatus::Up, UpStatus::Down, 2 * HOUR, EventSource::Timeout),
+            event(3 * HOUR, UpStatus::Down, UpStatus::Up, 2 * HOUR, EventSource::Heartbeat),
+            event(10 * HOUR, UpStatus::Up, UpStatus::Down, 7 * HOUR, EventSource::Timeout),
+            event(14 * HOUR, UpStatus::Down, UpStatus::Up, 4 * HOUR, EventSource::Heartbeat),
+        ];
+        let stats = compute(t(0), t(24 * HOUR), Some(&prev), &events, &state(UpStatus::Up, 14 * HOUR));
+        assert_eq!(stats.down, 6 * HOUR);
+        assert_eq!(stats.up, 18 * HOUR);
+        assert_eq!(stats.outages, 2);
+        assert_eq!(stats.longest_outage, 4 * HOUR);
+        assert_eq!(stats.mttr, Some(3 * HOUR));
+        assert_eq!(stats.mtbf, Some(9 * HOUR));
+        assert_eq!(stats.availability, Some(75.0));
+    }
+
+    #[test]
+    fn test_ongoing_outage_from_before_window() {
+        let prev = event(0, UpStatus::Up, UpStatus::Down, HOUR, EventSource::Timeout);
+        let stats = compute(t(HOUR), t(3 * HOUR), Some(&prev), &[], &state(UpStatus::Down, 0));
+        assert_eq!(stats.down, 2 * HOUR);
+        assert_eq!(stats.outages, 1);
+        assert_eq!(stats.longest_outage, 2 * HOUR);
+    }
+
+    #[test]
+    fn test_paused_and_maintenance() {
+        let events = [
+            event(HOUR, UpStatus::Up, UpStatus::Up, HOUR, EventSource::Maintenance),
+            event(2 * HOUR, UpStatus::Up, UpStatus::Up, HOUR, EventSource::Heartbeat),
+            event(3 * HOUR, UpStatus::Up, UpStatus::Paused, 3 * HOUR, EventSource::Pause),
+            event(5 * HOUR, UpStatus::Paused, UpStatus::Up, 2 * HOUR, EventSource::Unpause),
+        ];
+        let stats = compute(t(0), t(6 * HOUR), None, &events, &state(UpStatus::Up, 5 * HOUR));
+        assert_eq!(stats.up, 3 * HOUR);
+        assert_eq!(stats.maintenance, HOUR);
+        assert_eq!(stats.paused, 2 * HOUR);
+        assert_eq!(stats.outages, 0);
+    }
+
+    #[test]
+    fn test_timeline_merges_spans() {
+        let events = [
+            event(2 * HOUR, UpStatus::Up, UpStatus::Up, HOUR, EventSource::Heartbeat),
+            event(3 * HOUR, UpStatus::Up, UpStatus::Down, 2 * HOUR, EventSource::Timeout),
+            event(4 * HOUR, UpStatus::Down, UpStatus::Up, HOUR, EventSource::Maintenance),
+            event(5 * HOUR, UpStatus::Up, UpStatus::Up, HOUR, EventSource::Heartbeat),
+        ];
+        let span = |start: u64, end: u64, segment: Segment| Span {
+            start: t(start * HOUR),
+            end: t(end * HOUR),
+            segment,
+        };
+        assert_eq!(
+            timeline(t(0), t(6 * HOUR), None, &events, &state(UpStatus::Up, 5 * HOUR)),
+            vec![
+                span(0, 1, Segment::Unknown),
+                span(1, 3, Segment::Up),
+                span(3, 4, Segment::Down),
+                span(4, 5, Segment::Maintenance),
+                span(5, 6, Segment::Up),
+            ]
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use crate::db::{UpStatus, UptimeState, User};
+use crate::email::OUBOT_PUBLIC_URL;
+use crate::graph::escape;
+use crate::ical::Outage;
+use crate::notifications::{format_duration, localize, localize_with_args};
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::fmt::Write;
+use std::time::SystemTime;
+use unic_langid::LanguageIdentifier;
+
+/// Windows the page links to, see `stats::parse_window`.
+pub const WINDOWS: [&str; 3] = ["24h", "7d", "30d"];
+/// Most recent outages listed per monitor.
+pub const MAX_OUTAGES: usize = 10;
+/// Seconds between reloads, there are no scripts to keep the page current.
+const REFRESH_SECS: u64 = 60;
+const STYLE: &str = "body{margin:0;font-family:'DejaVu Sans',Verdana,sans-serif;color:#3c4043;background:#f8f9fa}\
+main{max-width:760px;margin:0 auto;padding:16px}h1{font-size:1.4em}h2{font-size:1.15em;margin:0 0 8px}\
+h3{font-size:1em;margin:16px 0 4px}section{background:#fff;border-radius:8px;padding:16px;margin:16px 0}\
+svg{max-width:100%;height:auto}ul{margin:0;padding-left:20px}nav a{margin-right:12px}.muted{color:#80868b}\
+.status{font-size:1.2em;font-weight:bold}.up{color:#2e9e44}.down{color:#d93025}.paused,.unknown{color:#80868b}";
+
+/// Path of the account's status page, the key is what makes it private.
+pub fn path(key: &str) -> String {
+    format!("/api/v1/status/{key}")
+}
+
+/// Link to the account's status page, None unless OUBOT_PUBLIC_URL is set.
+pub fn url(user: &User) -> Option<String> {
+    OUBOT_PUBLIC_URL
+        .as_deref()
+        .map(|base| format!("{base}{}", path(&user.status_page_key)))
+}
+
+/// Path of a shareable page grouping several devices, see `api::view_page`.
+pub fn page_path(slug: &str) -> String {
+    format!("/api/v1/pages/{slug}")
+}
+
+/// Link to a shareable page, None unless OUBOT_PUBLIC_URL is set.
+pub fn page_url(slug: &str) -> Option<String> {
+    OUBOT_PUBLIC_URL.as_deref().map(|base| format!("{base}{}", page_path(slug)))
+}
+
+/// Slugs are 3-64 lowercase letters, digits and dashes, with no dash at either end.
+pub fn validate_slug(slug: &str) -> Result<(), String> {
+    let valid = (3..=64).contains(&slug.len())
+        && slug.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !slug.starts_with('-')
+        && !slug.ends_with('-');
+    if !valid {
+        return Err("Slug must be 3-64 lowercase letters, digits and dashes, not starting or ending with a dash".to_string());
+    }
+    Ok(())
+}
+
+/// Name of a status as pages show it, the CSS class of the HTML and `status` of the JSON.
+pub fn status_name(status: UpStatus) -> &'static str {
+    match status {
+        UpStatus::Up => "up",
+        UpStatus::Down => "down",
+        UpStatus::Paused => "paused",
+        UpStatus::Uninitialized => "unknown",
+    }
+}
+
+/// A device shown on a page.
+pub struct Monitor {
+    /// Heading, left out on pages with a single device
+    pub name: Option<String>,
+    pub uptime: UptimeState,
+    /// `graph::svg` of the window
+    pub graph: String,
+    /// Outages in the window, see `ical::outages`
+    pub outages: Vec<Outage>,
+}
+
+pub struct Page<'a> {
+    pub title: &'a str,
+    pub language: &'a str,
+    pub tz: Tz,
+    /// One of `WINDOWS`
+    pub window: &'a str,
+    pub now: SystemTime,
+}
+
+impl Page<'_> {
+    fn local(&self, at: SystemTime) -> String {
+        DateTime::<Utc>::from(at)
+            .with_timezone(&self.tz)
+            .format("%d.%m %H:%M")
+            .to_string()
+    }
+
+    fn duration(&self, lang: &LanguageIdentifier, from: SystemTime, to: SystemTime) -> String {
+        format_duration(lang, to.duration_since(from).unwrap_or_default())
+    }
+
+    fn monitor(&self, lang: &LanguageIdentifier, monitor: &Monitor, out: &mut String) {
+        out.push_str("<section>");
+        if let Some(name) = &monitor.name {
+            let _ = write!(out, "<h2>{}</h2>", escape(name));
+        }
+        let uptime = &monitor.uptime;
+        let since = self.duration(lang, uptime.state_changed_at, self.now);
+        let status = match uptime.status {
+            UpStatus::Up => localize_with_args(self.language, "status-up", &[("duration", &since)]),
+            UpStatus::Down => localize_with_args(self.language, "status-down", &[("duration", &since)]),
+            UpStatus::Paused => localize(self.language, "status-paused"),
+            UpStatus::Uninitialized => localize(self.language, "status-unknown"),
+        };
+        let _ = write!(
+            out,
+            r#"<p class="status {}">{}</p>"#,
+            status_name(uptime.status),
+            escape(&status)
+        );
+        if uptime.status != UpStatus::Uninitialized {
+            let ago = self.duration(lang, uptime.touched_at, self.now);
+            let heartbeat = localize_with_args(self.language, "status-last-heartbeat", &[("duration", &ago)]);
+            let _ = write!(out, r#"<p class="muted">{}</p>"#, escape(&heartbeat));
+        }
+        out.push_str(&monitor.graph);
+
+        let _ = write!(out, "<h3>{}</h3>", escape(&localize(self.language, "status-outages")));
+        if monitor.outages.is_empty() {
+            let _ = write!(
+                out,
+                r#"<p class="muted">{}</p>"#,
+                escape(&localize(self.language, "status-no-outages"))
+            );
+        } else {
+            out.push_str("<ul>");
+            for outage in monitor.outages.iter().rev().take(MAX_OUTAGES) {
+                let start = self.local(outage.start);
+                let text = match outage.end {
+                    Some(end) => localize_with_args(
+                        self.language,
+                        "status-outage",
+                        &[
+                            ("start", &start),
+                            ("end", &self.local(end)),
+                            ("duration", &self.duration(lang, outage.start, end)),
+                        ],
+                    ),
+                    None => localize_with_args(
+                        self.language,
+                        "status-outage-ongoing",
+                        &[("start", &start), ("duration", &self.duration(lang, outage.start, self.now))],
+                    ),
+                };
+                let _ = write!(out, "<li>{}</li>", escape(&text));
+            }
+            out.push_str("</ul>");
+        }
+        out.push_str("</section>");
+    }
+
+    /// The whole document. Links to the other windows keep the rest of the URL.
+    pub fn render(&self, monitors: &[Monitor]) -> String {
+        let lang: LanguageIdentifier = self.language.parse().unwrap_or_else(|_| "en".parse().unwrap());
+        let title = escape(self.title);
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            r#"<!DOCTYPE html><html lang="{}"><head><meta charset="utf-8"><meta name="viewport" content="width=device-width, initial-scale=1"><meta name="robots" content="noindex"><meta http-equiv="refresh" content="{REFRESH_SECS}"><title>{title}</title><style>{STYLE}</style></head><body><main><h1>{title}</h1><nav>"#,
+            escape(self.language)
+        );
+        for window in WINDOWS {
+            if window == self.window {
+                let _ = write!(out, "<a><b>{window}</b></a>");
+            } else {
+                let _ = write!(out, r#"<a href="?window={window}">{window}</a>"#);
+            }
+        }
+        out.push_str("</nav>");
+        for monitor in monitors {
+            self.monitor(&lang, monitor, &mut out);
+        }
+        let updated = localize_with_args(self.language, "status-updated", &[("time", &self.local(self.now))]);
+        let _ = write!(
+            out,
+            r#"<p class="muted">{} ({})</p></main></body></html>"#,
+            escape(&updated),
+            self.tz
+        );
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    fn t(secs: u64) -> SystemTime {
+        // 2026-10-18 00:00 in Kyiv
+        SystemTime::UNIX_EPOCH + Duration::from_secs(1_792_270_800 + secs)
+    }
+
+    #[test]
+    fn test_render_page() {
+        let mut uptime = UptimeState::new(Uuid::nil());
+        (uptime.status, uptime.state_changed_at, uptime.touched_at) = (UpStatus::Down, t(3600), t(3000));
+        let monitor = Monitor {
+            name: Some("Kitchen <1>".to_string()),
+            uptime,
+            graph: "<svg></svg>".to_string(),
+            outages: vec![
+                Outage {
+                    device_id: Uuid::nil(),
+                    start: t(0),
+                    end: Some(t(1800)),
+                },
+                Outage {
+                    device_id: Uuid::nil(),
+                    start: t(3600),
+                    end: None,
+                },
+            ],
+        };
+        let page = Page {
+            title: "Стан світла",
+            language: "uk",
+            tz: "Europe/Kyiv".parse().unwrap(),
+            window: "7d",
+            now: t(2 * 3600),
+        };
+        let html = page.render(&[monitor]);
+        assert!(html.starts_with("<!DOCTYPE html><html lang=\"uk\">"), "{html}");
+        assert!(html.contains("<h2>Kitchen &lt;1&gt;</h2>"), "{html}");
+        assert!(
+            html.contains(r#"<p class="status down">Світла немає вже 1 год</p>"#),
+            "{html}"
+        );
+        assert!(html.contains("Останній сигнал 1 год 10 хв тому"), "{html}");
+        assert!(html.contains(r#"<a href="?window=24h">24h</a><a><b>7d</b></a>"#), "{html}");
+        // Newest first
+        assert!(
+            html.contains("<ul><li>з 18.10 01:00, триває 1 год</li><li>18.10 00:00 – 18.10 00:30, 30 хв</li></ul>"),
+            "{html}"
+        );
+        assert!(html.contains("Оновлено 18.10 02:00 (Europe/Kyiv)"), "{html}");
+    }
+
+    #[test]
+    fn test_validate_slug() {
+        for slug in ["abc", "building-12", "2nd-entrance", &"a".repeat(64)] {
+            assert!(validate_slug(slug).is_ok(), "{slug}");
+        }
+        for slug in ["ab", "-abc", "abc-", "Abc", "a b c", "вулиця", "a_b", &"a".repeat(65)] {
+            assert!(validate_slug(slug).is_err(), "{slug}");
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use crate::channels::SendError;
+use lazy_static::lazy_static;
+use reqwest::StatusCode;
+use rocket::serde::json::{Value, json};
+use rocket::serde::{Deserialize, de::DeserializeOwned};
+use std::env::var;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+lazy_static! {
+    /// Pointed at a local fake Bot API server in tests.
+    pub static ref TELEGRAM_API_BASE_URL: String =
+        var("TELEGRAM_API_BASE_URL").unwrap_or_else(|_| "https://api.telegram.org".to_string());
+}
+
+/// Seconds a getUpdates call is held open by the Bot API when there is nothing new.
+pub const LONG_POLL_TIMEOUT: u64 = 30;
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct ApiResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Update {
+    pub update_id: i64,
+    pub message: Option<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Message {
+    pub chat: Chat,
+    pub from: Option<TgUser>,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Chat {
+    pub id: i64,
+    pub title: Option<String>,
+    pub username: Option<String>,
+    pub first_name: Option<String>,
+}
+
+impl Chat {
+    /// Name shown in channel listings: group title, @username or first name.
+    pub fn display_name(&self) -> String {
+        match (&self.title, &self.username, &self.first_name) {
+            (Some(title), _, _) => title.clone(),
+            (None, Some(username), _) => format!("@{username}"),
+            (None, None, Some(first_name)) => first_name.clone(),
+            (None, None, None) => self.id.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TgUser {
+    pub language_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct BotInfo {
+    username: String,
+}
+
+/// Telegram Bot API client, only usable when TELEGRAM_BOT_TOKEN is set.
+#[derive(Debug, Clone)]
+pub struct TelegramClient {
+    base_url: String,
+    token: Option<String>,
+    /// Filled in by `get_me` once the update loop starts.
+    bot_username: Arc<OnceLock<String>>,
+    client: reqwest::Client,
+}
+
+impl TelegramClient {
+    pub fn new() -> TelegramClient {
+        let client = reqwest::Client::builder()
+            .user_agent("OpenUptimeBot/v0")
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("RIP");
+        TelegramClient {
+            base_url: TELEGRAM_API_BASE_URL.clone(),
+            token: var("TELEGRAM_BOT_TOKEN").ok().filter(|t| !t.is_empty()),
+            bot_username: Default::default(),
+            client,
+        }
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.token.is_some()
+    }
+
+    pub fn bot_username(&self) -> Option<&str> {
+        self.bot_username.get().map(String::as_str)
+    }
+
+    /// Call a Bot API method. Requests it rejects with 400 or 403 (chat not found, bot blocked or
+    /// kicked, ...) are permanent errors, everything else may go through later.
+    async fn call<T: DeserializeOwned>(&self, method: &str, body: Value, timeout: Duration) -> Result<T, SendError> {
+        let Some(token) = &self.token else {
+            return Err("Telegram is not configured on this server".to_string().into());
+        };
+        // @WARNING: The bot token is part of the URL, errors are logged without it.
+        let response = self
+            .client
+            .post(format!("{base}/bot{token}/{method}", base = self.base_url))
+            .json(&body)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|err| format!("{:?}", err.without_url()))?;
+        let status = response.status();
+        let data: ApiResponse<T> = response
+            .json()
+            .await
+            .map_err(|err| format!("HTTP {status}: {:?}", err.without_url()))?;
+        match data {
+            ApiResponse {
+                ok: true,
+                result: Some(result),
+                ..
+            } => Ok(result),
+            ApiResponse { description, .. } => {
+                let err = format!("{method} failed (HTTP {status}): {}", description.unwrap_or_default());
+                match status {
+                    StatusCode::BAD_REQUEST | StatusCode::FORBIDDEN => Err(SendError::Permanent(err)),
+                    _ => Err(SendError::Transient(err)),
+                }
+            }
+        }
+    }
+
+    pub async fn get_me(&self) -> Result<String, String> {
+        let me: BotInfo = self
+            .call("getMe", json!({}), Duration::from_secs(10))
+            .await
+            .map_err(|err| err.to_string())?;
+        let _ = self.bot_username.set(me.username.clone());
+        Ok(me.username)
+    }
+
+    /// Long poll for new messages, `offset` is one past the last handled update_id.
+    pub async fn get_updates(&self, offset: i64) -> Result<Vec<Update>, String> {
+        let body = json!({"offset": offset, "timeout": LONG_POLL_TIMEOUT, "allowed_updates": ["message"]});
+        self.call("getUpdates", body, Duration::from_secs(LONG_POLL_TIMEOUT + 10))
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    /// Send an HTML formatted message, the caller escapes user supplied text.
+    pub async fn send_message(&self, chat_id: i64, html: &str) -> Result<(), SendError> {
+        let body = json!({"chat_id": chat_id, "text": html, "parse_mode": "HTML"});
+        self.call::<Value>("sendMessage", body, Duration::from_secs(10)).await?;
+        Ok(())
+    }
+}
+
+/// Escape text for messages sent with parse_mode=HTML.
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
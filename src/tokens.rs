@@ -0,0 +1,83 @@
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use rand::{Rng, distributions::Alphanumeric};
+use sha2::Sha256;
+use std::env::var;
+
+lazy_static! {
+    /// Key for token hashes. Changing it invalidates every account and heartbeat token.
+    pub static ref TOKEN_SECRET: String = var("OUBOT_TOKEN_SECRET").expect("OUBOT_TOKEN_SECRET required");
+}
+
+fn random_token(prefix: &str) -> String {
+    let rng = rand::thread_rng();
+    let secret_part: String = rng.sample_iter(&Alphanumeric).take(16).map(char::from).collect();
+    format!("{prefix}{secret_part}")
+}
+
+/// Account token (`tk_`), accepted by the management API.
+pub fn new_account_token() -> String {
+    random_token("tk_")
+}
+
+/// Device heartbeat token (`hb_`), accepted only by `/api/v1/up`.
+pub fn new_heartbeat_token() -> String {
+    random_token("hb_")
+}
+
+/// Status page key (`sp_`), the secret part of the status page URL.
+pub fn new_status_page_key() -> String {
+    random_token("sp_")
+}
+
+/// Email verification token (`ev_`), mailed as a link when a recipient is added.
+pub fn new_email_token() -> String {
+    random_token("ev_")
+}
+
+/// Hex HMAC-SHA256 of `data`, also used to sign webhook payloads.
+pub fn hmac_sha256_hex(secret: &[u8], data: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// What gets stored and used as the lookup key: hex HMAC-SHA256 of the token.
+pub fn hash_token(token: &str) -> String {
+    hmac_sha256_hex(TOKEN_SECRET.as_bytes(), token)
+}
+
+/// Tell stored hashes apart from plaintext tokens left over from before hashing.
+pub fn is_hashed(value: &str) -> bool {
+    value.len() == 64 && value.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_is_keyed_and_stable() {
+        let a = hmac_sha256_hex(b"secret-a", "tk_abcdefghijklmnop");
+        assert_eq!(a, hmac_sha256_hex(b"secret-a", "tk_abcdefghijklmnop"));
+        assert_ne!(a, hmac_sha256_hex(b"secret-b", "tk_abcdefghijklmnop"));
+        assert_ne!(a, hmac_sha256_hex(b"secret-a", "tk_abcdefghijklmnoq"));
+    }
+
+    #[test]
+    fn test_hash_matches_rfc4231() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            hmac_sha256_hex(b"Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_is_hashed() {
+        assert!(is_hashed(&hmac_sha256_hex(b"k", "tk_x")));
+        assert!(!is_hashed(&new_account_token()));
+        assert!(!is_hashed(&new_heartbeat_token()));
+        assert!(!is_hashed(&"A".repeat(64)));
+    }
+}
@@ -0,0 +1,92 @@
+#!/usr/bin/env python
+import asyncio
+
+import requests
+from lib.testbase import TestBase
+
+
+class ApiV1Calendar(TestBase):
+    def ping(self):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+
+    def feed(self, token, header=False):
+        url = f"{self.base_url}/api/v1/me/outages.ics"
+        if header:
+            return requests.get(url, headers={"authorization": token})
+        return requests.get(url, params={"token": token})
+
+    def events(self, ics):
+        # Unfold continuation lines, one dict per VEVENT
+        lines = ics.replace("\r\n ", "").split("\r\n")
+        events, current = [], None
+        for line in lines:
+            if line == "BEGIN:VEVENT":
+                current = {}
+            elif line == "END:VEVENT":
+                events.append(current)
+                current = None
+            elif current is not None:
+                name, value = line.split(":", 1)
+                current[name] = value
+        return events
+
+    async def setup(self):
+        r = requests.post(
+            f"{self.base_url}/api/v1/me/tokens",
+            json={"name": "calendar", "scope": "Account"},
+            headers={"authorization": self.access_token},
+        )
+        assert r.json()["status"] == 200, r.json()
+        self.calendar_token = r.json()["access_token"]
+
+        # Grid works every Tuesday and Thursday night
+        r = requests.post(
+            f"{self.base_url}/api/v1/me/maintenance",
+            json={"weekdays": ["tue", "thu"], "start_minute": 1380, "end_minute": 60, "label": "grid works"},
+            headers={"authorization": self.access_token},
+        )
+        assert r.json()["status"] == 200, r.json()
+
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        r = self.feed("tk_invalid")
+        assert r.status_code == 401, r.status_code
+        r = self.feed(self.heartbeat_token)
+        assert r.status_code == 403, r.status_code
+        r = self.feed(self.calendar_token)
+        assert r.status_code == 200 and r.headers["content-type"].startswith("text/calendar"), r.headers
+        assert r.text.startswith("BEGIN:VCALENDAR\r\n") and "X-WR-CALNAME:Відключення світла\r\n" in r.text, r.text
+        [window] = self.events(r.text)
+        assert window["SUMMARY"] == "Технічні роботи" and window["DESCRIPTION"] == "grid works", window
+        assert window["RRULE"] == "FREQ=WEEKLY;BYDAY=TU,TH", window
+        assert window["DTSTART"].endswith("T230000Z") and window["DTEND"].endswith("T010000Z"), window
+
+    async def on_connected(self, ws):
+        self.ping()
+        await asyncio.sleep(1)
+
+        # The device stops pinging, the ongoing outage shows up
+        for _ in range(30):
+            await asyncio.sleep(1)
+            outages = [e for e in self.events(self.feed(self.calendar_token).text) if "RRULE" not in e]
+            if outages:
+                break
+        [outage] = outages
+        self.log(f"Ongoing: {outage}")
+        assert outage["SUMMARY"] == "Немає світла", outage
+        assert outage["DESCRIPTION"].startswith("Світла немає вже"), outage
+
+        # Once power is back the same event gets its end
+        await asyncio.sleep(2)
+        self.ping()
+        await asyncio.sleep(1)
+        r = self.feed(self.access_token, header=True)
+        [ended] = [e for e in self.events(r.text) if "RRULE" not in e]
+        self.log(f"Ended: {ended}")
+        assert ended["UID"] == outage["UID"] and ended["DTSTART"] == outage["DTSTART"], (ended, outage)
+        assert ended["DESCRIPTION"].startswith("Світла не було") and ended["DTEND"] > outage["DTSTART"], ended
+
+
+if __name__ == "__main__":
+    test = ApiV1Calendar(timeout=120)
+    asyncio.run(test.run())
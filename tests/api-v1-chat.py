@@ -0,0 +1,124 @@
+#!/usr/bin/env python
+import asyncio
+import json
+import queue
+import threading
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+from urllib.parse import unquote
+
+import requests
+from lib.testbase import TestBase
+
+CHAT_PORT = 8097
+BASE = f"http://127.0.0.1:{CHAT_PORT}"
+MATRIX_TOKEN = "syt_test_token"
+ROOM_ID = "!outages:matrix.test"
+
+
+class FakeChat(BaseHTTPRequestHandler):
+    """Local stand-in for a Matrix homeserver and Discord/Slack incoming webhooks."""
+
+    received = queue.Queue()
+
+    def do_PUT(self):
+        # /_matrix/client/v3/rooms/<room>/send/m.room.message/<txn>
+        if self.headers.get("Authorization") != f"Bearer {MATRIX_TOKEN}":
+            return self.reply(401, {"errcode": "M_UNKNOWN_TOKEN"})
+        self.record("matrix")
+        self.reply(200, {"event_id": "$event"})
+
+    def do_POST(self):
+        if self.path == "/discord/api/webhooks/1/discord-token":
+            self.record("discord")
+            return self.reply(204, None)
+        if self.path == "/slack/services/T0/B0/slack-token":
+            self.record("slack")
+            return self.reply(200, "ok")
+        self.reply(404, None)
+
+    def record(self, service):
+        body = json.loads(self.rfile.read(int(self.headers["Content-Length"])))
+        FakeChat.received.put((service, self.path, body))
+
+    def reply(self, status, data):
+        payload = b"" if data is None else json.dumps(data).encode()
+        self.send_response(status)
+        self.send_header("Content-Length", str(len(payload)))
+        self.end_headers()
+        self.wfile.write(payload)
+
+    def log_message(self, *args):
+        pass
+
+
+class ApiV1Chat(TestBase):
+    async def next_messages(self, timeout=30):
+        """One message per service, keyed by service."""
+        messages = {}
+        for _ in range(3):
+            service, path, body = await asyncio.to_thread(FakeChat.received.get, timeout=timeout)
+            messages[service] = (path, body)
+        return messages
+
+    async def setup(self):
+        server = ThreadingHTTPServer(("127.0.0.1", CHAT_PORT), FakeChat)
+        threading.Thread(target=server.serve_forever, daemon=True).start()
+
+        headers = {"authorization": self.access_token}
+        channels = [
+            {"kind": "Matrix", "config": {"homeserver": BASE, "access_token": MATRIX_TOKEN, "room_id": ROOM_ID}},
+            {"kind": "Discord", "config": {"url": f"{BASE}/discord/api/webhooks/1/discord-token"}},
+            {"kind": "Slack", "config": {"url": f"{BASE}/slack/services/T0/B0/slack-token"}},
+        ]
+        for channel in channels:
+            r = requests.post(f"{self.base_url}/api/v1/me/channels", json=channel, headers=headers)
+            r.raise_for_status()
+            assert r.json()["status"] == 200, r.json()
+            self.log(f"Channel: {r.json()['channel']}")
+            # Tokens are part of the config, they never come back
+            for secret in (MATRIX_TOKEN, "discord-token", "slack-token"):
+                assert secret not in r.text, r.json()
+
+        # Aliases can't be posted to, only room IDs
+        r = requests.post(
+            f"{self.base_url}/api/v1/me/channels",
+            json={"kind": "Matrix", "config": {**channels[0]["config"], "room_id": "#outages:matrix.test"}},
+            headers=headers,
+        )
+        assert r.json()["status"] == 400, r.json()
+        # Stay under the per-IP rate limit
+        await asyncio.sleep(1)
+
+    async def on_connected(self, ws):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+
+        # Same localized titles as ntfy
+        message = await self.wait_for_message(ws)
+        assert message["title"] == "Девайс під'єднано!"
+        messages = await self.next_messages()
+        path, body = messages["matrix"]
+        assert unquote(path).startswith(f"/_matrix/client/v3/rooms/{ROOM_ID}/send/m.room.message/"), path
+        assert body["body"] == "Девайс під'єднано!", body
+        assert body["formatted_body"] == "<b>Девайс під'єднано!</b>", body
+        assert messages["discord"][1]["content"] == "**Девайс під'єднано!**", messages["discord"]
+        assert messages["discord"][1]["allowed_mentions"] == {"parse": []}
+        assert messages["slack"][1]["text"] == "*Девайс під'єднано!*", messages["slack"]
+
+        message = await self.wait_for_message(ws)
+        assert message["title"] == "Відключення світла!"
+        messages = await self.next_messages()
+        assert messages["matrix"][1]["body"].startswith("Відключення світла!\nСвітло було"), messages["matrix"]
+        assert messages["matrix"][1]["formatted_body"].startswith("<b>Відключення світла!</b><br>Світло було")
+        assert messages["discord"][1]["content"].startswith("**Відключення світла!**\nСвітло було")
+        assert messages["slack"][1]["text"].startswith("*Відключення світла!*\nСвітло було")
+
+        metrics = requests.get(f"{self.base_url}/api/v1/metrics").text
+        for channel in ("matrix", "discord", "slack"):
+            lines = [line for line in metrics.splitlines() if f'channel="{channel}"' in line]
+            assert any('result="success"' in line and 'type="down"' in line for line in lines), lines
+
+
+if __name__ == "__main__":
+    test = ApiV1Chat(timeout=90)
+    asyncio.run(test.run())
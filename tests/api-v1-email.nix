@@ -0,0 +1,27 @@
+(import ./lib/lib.nix) {
+  name = "api-v1-email";
+
+  nodes = {
+    primary = {...}: let
+      c = import ./lib/config.nix;
+    in {
+      imports = [./lib/primary.nix];
+      # SMTP sink, started by the test script itself
+      systemd.services.open-uptime-bot.environment = {
+        SMTP_HOST = "127.0.0.1";
+        SMTP_PORT = c.smtp-port;
+        SMTP_TLS = "none";
+        SMTP_FROM = c.smtp-from;
+        OUBOT_PUBLIC_URL = "http://127.0.0.1:${c.oubot-port}";
+      };
+    };
+  };
+
+  testScript = let
+    c = import ./lib/config.nix;
+  in ''
+    primary.wait_for_unit("open-uptime-bot")
+    primary.wait_for_open_port(${c.oubot-port})
+    primary.succeed("tester-script-py")
+  '';
+}
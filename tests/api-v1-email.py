@@ -0,0 +1,135 @@
+#!/usr/bin/env python
+import asyncio
+import email
+import email.policy
+import queue
+import re
+import socketserver
+import threading
+
+import requests
+from lib.testbase import TestBase
+
+# Must match tests/api-v1-email.nix
+SMTP_PORT = 2525
+VERIFIED = "manager@example.com"
+UNVERIFIED = "someone@example.com"
+
+
+class SmtpSink(socketserver.StreamRequestHandler):
+    """Minimal plaintext SMTP server keeping every received mail, like a local mail catcher."""
+
+    received = queue.Queue()
+
+    def reply(self, line):
+        self.wfile.write(f"{line}\r\n".encode())
+
+    def handle(self):
+        self.reply("220 sink ESMTP")
+        rcpt = []
+        while line := self.rfile.readline():
+            command = line.decode().strip()
+            verb = command.split(" ")[0].upper()
+            if verb in ("EHLO", "HELO"):
+                self.reply("250-sink")
+                self.reply("250 8BITMIME")
+            elif verb == "MAIL":
+                rcpt = []
+                self.reply("250 OK")
+            elif verb == "RCPT":
+                rcpt.append(re.search(r"<(.*)>", command).group(1))
+                self.reply("250 OK")
+            elif verb == "DATA":
+                self.reply("354 End data with <CR><LF>.<CR><LF>")
+                data = b""
+                while (chunk := self.rfile.readline()) != b".\r\n":
+                    data += chunk[1:] if chunk.startswith(b"..") else chunk
+                message = email.message_from_bytes(data, policy=email.policy.default)
+                SmtpSink.received.put((rcpt, message))
+                self.reply("250 OK")
+            elif verb in ("RSET", "NOOP"):
+                self.reply("250 OK")
+            elif verb == "QUIT":
+                self.reply("221 Bye")
+                return
+            else:
+                self.reply("502 Not implemented")
+
+
+class ApiV1Email(TestBase):
+    async def next_mail(self, timeout=30):
+        rcpt, message = await asyncio.to_thread(SmtpSink.received.get, timeout=timeout)
+        return rcpt, message["Subject"], message.get_content().replace("\r\n", "\n").strip()
+
+    async def setup(self):
+        socketserver.ThreadingTCPServer.allow_reuse_address = True
+        server = socketserver.ThreadingTCPServer(("127.0.0.1", SMTP_PORT), SmtpSink)
+        server.daemon_threads = True
+        threading.Thread(target=server.serve_forever, daemon=True).start()
+
+        headers = {"authorization": self.access_token}
+        for config in ({}, {"to": "not-an-address"}, {"to": [VERIFIED, VERIFIED.upper()]}):
+            r = requests.post(
+                f"{self.base_url}/api/v1/me/channels", json={"kind": "Email", "config": config}, headers=headers
+            )
+            assert r.json()["status"] == 400, r.json()
+
+        r = requests.post(
+            f"{self.base_url}/api/v1/me/channels",
+            json={"kind": "Email", "config": {"to": [VERIFIED, UNVERIFIED]}},
+            headers=headers,
+        )
+        r.raise_for_status()
+        channel = r.json()["channel"]
+        self.log(f"Email channel: {channel}")
+        assert channel["description"] == f"email to {VERIFIED} (unverified), {UNVERIFIED} (unverified)", channel
+
+        # Both addresses get a link in the account's language, only one of them opens it
+        links = {}
+        for _ in range(2):
+            rcpt, subject, body = await self.next_mail()
+            assert subject == "Підтвердіть сповіщення про світло", subject
+            links[rcpt[0]] = re.search(r"http\S+", body).group(0)
+        assert set(links) == {VERIFIED, UNVERIFIED}, links
+
+        # Stay under the per-IP rate limit
+        await asyncio.sleep(1)
+        r = requests.get(links[VERIFIED])
+        assert r.status_code == 200 and VERIFIED in r.text, r.text
+        await asyncio.sleep(1)
+        r = requests.get(links[VERIFIED])
+        assert r.status_code == 404, "Links are single use"
+        r = requests.get(f"{self.base_url}/api/v1/email/verify", params={"token": "ev_wrong"})
+        assert r.status_code == 404
+
+        r = requests.get(f"{self.base_url}/api/v1/me/channels", headers=headers)
+        [channel] = [c for c in r.json()["channels"] if c["kind"] == "Email"]
+        assert channel["description"] == f"email to {VERIFIED}, {UNVERIFIED} (unverified)", channel
+
+    async def on_connected(self, ws):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+
+        # Same localized titles as ntfy, only the verified address receives them
+        message = await self.wait_for_message(ws)
+        assert message["title"] == "Девайс під'єднано!"
+        rcpt, subject, body = await self.next_mail()
+        assert rcpt == [VERIFIED], rcpt
+        assert subject == "Девайс під'єднано!" and body == subject, (subject, body)
+
+        message = await self.wait_for_message(ws)
+        assert message["title"] == "Відключення світла!"
+        rcpt, subject, body = await self.next_mail()
+        assert rcpt == [VERIFIED], rcpt
+        assert subject == "Відключення світла!", subject
+        assert body.startswith("Відключення світла!\nСвітло було"), body
+        assert SmtpSink.received.empty(), "Unverified recipients get nothing"
+
+        metrics = requests.get(f"{self.base_url}/api/v1/metrics").text
+        lines = [line for line in metrics.splitlines() if 'channel="email"' in line]
+        assert any('result="success"' in line and 'type="down"' in line for line in lines), lines
+
+
+if __name__ == "__main__":
+    test = ApiV1Email(timeout=90)
+    asyncio.run(test.run())
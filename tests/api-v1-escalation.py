@@ -0,0 +1,109 @@
+#!/usr/bin/env python
+import asyncio
+import json
+import queue
+import threading
+from http.server import BaseHTTPRequestHandler, HTTPServer
+
+import requests
+from lib.testbase import TestBase
+
+WEBHOOK_PORT = 8102
+
+
+class Recorder(BaseHTTPRequestHandler):
+    received = queue.Queue()
+
+    def do_POST(self):
+        body = self.rfile.read(int(self.headers["Content-Length"]))
+        Recorder.received.put((self.path, json.loads(body)))
+        self.send_response(200)
+        self.end_headers()
+
+    def log_message(self, *args):
+        pass
+
+
+class ApiV1Escalation(TestBase):
+    def ping(self):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+
+    def add_channel(self, path, enabled):
+        data = {"kind": "Webhook", "enabled": enabled, "config": {"url": f"http://127.0.0.1:{WEBHOOK_PORT}{path}"}}
+        r = requests.post(f"{self.base_url}/api/v1/me/channels", json=data, headers={"authorization": self.access_token})
+        r.raise_for_status()
+        assert r.json()["status"] == 200, r.json()
+        return r.json()["channel"]["id"]
+
+    def add_step(self, data):
+        r = requests.post(f"{self.base_url}/api/v1/me/escalation", json=data, headers={"authorization": self.access_token})
+        r.raise_for_status()
+        return r.json()
+
+    async def next_event(self, timeout=30):
+        path, payload = await asyncio.to_thread(Recorder.received.get, timeout=timeout)
+        self.log(f"Webhook {path}: {payload['event']} {payload['title']} {payload['message']}")
+        return path, payload
+
+    async def setup(self):
+        server = HTTPServer(("127.0.0.1", WEBHOOK_PORT), Recorder)
+        threading.Thread(target=server.serve_forever, daemon=True).start()
+
+        self.add_channel("/main", True)
+        # Disabled: only gets the reminders of the step it is the extra channel of
+        self.extra_id = self.add_channel("/extra", False)
+
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        for invalid in [{"delay_minutes": 0}, {"delay_minutes": 60, "channel_id": "00000000-0000-0000-0000-000000000000"}]:
+            result = self.add_step(invalid)
+            assert result["status"] == 400, result
+        result = self.add_step({"delay_minutes": 1, "priority": "max", "channel_id": self.extra_id})
+        assert result["status"] == 200, result
+        assert (result["step"]["priority"], result["step"]["channel_id"]) == ("max", self.extra_id)
+        await asyncio.sleep(1)
+        result = self.add_step({"delay_minutes": 1})
+        assert result["status"] == 400, result
+        # Too late to be reached by this test, the step after 1 minute must only be sent once anyway
+        late = self.add_step({"delay_minutes": 600})
+        assert late["status"] == 200 and late["step"]["priority"] == "high", late
+
+        r = requests.get(f"{self.base_url}/api/v1/me/escalation", headers={"authorization": self.access_token})
+        assert [s["delay_minutes"] for s in r.json()["steps"]] == [1, 600], r.json()
+        await asyncio.sleep(1)
+        url = f"{self.base_url}/api/v1/me/escalation/{late['step']['id']}"
+        r = requests.delete(url, headers={"authorization": self.access_token})
+        assert r.json()["status"] == 200, r.json()
+
+    async def on_connected(self, ws):
+        self.ping()
+        assert (await self.next_event())[1]["event"] == "connected"
+        path, payload = await self.next_event()
+        assert (path, payload["event"]) == ("/main", "down"), (path, payload)
+
+        # A minute into the outage the reminder goes to the enabled channel and the extra one
+        reminders = {}
+        for _ in range(2):
+            path, payload = await self.next_event(timeout=75)
+            reminders[path] = payload
+        assert set(reminders) == {"/main", "/extra"}, reminders
+        payload = reminders["/extra"]
+        assert payload["event"] == "still_down", payload
+        assert payload["title"] == "Світла досі немає"
+        assert payload["message"].startswith("Світла немає вже 1 хв"), payload["message"]
+        assert (payload["old_status"], payload["new_status"]) == ("Down", "Down")
+        assert payload["duration"] >= 60, payload
+
+        # Each step is sent once per outage, coming back is only notified on the enabled channel
+        await asyncio.sleep(10)
+        assert Recorder.received.empty(), "The reminder must be sent only once"
+        self.ping()
+        path, payload = await self.next_event()
+        assert (path, payload["event"]) == ("/main", "up"), (path, payload)
+        await asyncio.sleep(2)
+        assert Recorder.received.empty(), "Disabled channels only get reminders"
+
+
+if __name__ == "__main__":
+    test = ApiV1Escalation(timeout=180)
+    asyncio.run(test.run())
@@ -0,0 +1,100 @@
+#!/usr/bin/env python
+import asyncio
+
+import requests
+from lib.testbase import TestBase
+
+
+class ApiV1Events(TestBase):
+    def events(self, token=None, **params):
+        r = requests.get(
+            f"{self.base_url}/api/v1/me/events",
+            params=params,
+            headers={"authorization": token or self.access_token},
+        )
+        return r.json()
+
+    def post(self, path, token=None, **kwargs):
+        r = requests.post(f"{self.base_url}{path}", headers={"authorization": token or self.access_token}, **kwargs)
+        return r.json()
+
+    async def setup(self):
+        r = self.events()
+        assert r["status"] == 200 and r["events"] == [] and r["next_cursor"] is None, r
+        r = self.events(limit=0)
+        assert r["status"] == 400, r
+        r = self.events(since=2**64 - 1)
+        assert r["status"] == 400, r
+
+    async def on_connected(self, ws):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+        message = await self.wait_for_message(ws)
+        assert message["title"] == "Девайс під'єднано!", message
+
+        # Keep the heartbeat in an earlier second than the pauses, to filter on it
+        await asyncio.sleep(2)
+        for action in ["pause", "unpause", "pause", "unpause"]:
+            assert self.post(f"/api/v1/me/{action}")["status"] == 200
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+
+        r = self.events()
+        assert r["status"] == 200 and r["next_cursor"] is None, r
+        events = r["events"]
+        assert [(e["from_status"], e["to_status"]) for e in events] == [
+            ("Paused", "Up"),
+            ("Up", "Paused"),
+            ("Paused", "Up"),
+            ("Up", "Paused"),
+            ("Uninitialized", "Up"),
+        ], events
+        assert all(e["device_id"] == self.state["devices"][0]["device"]["id"] for e in events), events
+
+        # Pages of two, newest first, until there is no cursor
+        paged, cursor = [], None
+        for _ in range(3):
+            r = self.events(limit=2, cursor=cursor) if cursor else self.events(limit=2)
+            assert r["status"] == 200, r
+            paged += r["events"]
+            cursor = r["next_cursor"]
+        assert cursor is None and [e["id"] for e in paged] == [e["id"] for e in events], paged
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+
+        # `since` is inclusive, `until` exclusive
+        pause = events[3]
+        r = self.events(since=pause["at"])
+        assert [e["id"] for e in r["events"]] == [e["id"] for e in events[:4]], r
+        r = self.events(until=pause["at"])
+        assert [e["id"] for e in r["events"]] == [events[4]["id"]], r
+        r = self.events(since=pause["at"], limit=3)
+        assert len(r["events"]) == 3 and r["next_cursor"] == events[2]["id"], r
+        r = self.events(since=pause["at"], limit=3, cursor=r["next_cursor"])
+        assert [e["id"] for e in r["events"]] == [events[3]["id"]] and r["next_cursor"] is None, r
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+
+        # Cursors of other accounts are not accepted
+        invite = self.post("/api/v1/invites")["invite"]["token"]
+        data = {
+            "invite": invite,
+            "user_type": "Normal",
+            "invites_limit": 0,
+            "up_delay": 10,
+            "ntfy_enabled": False,
+            "language_code": "en",
+        }
+        other = requests.post(f"{self.base_url}/api/v1/users", json=data).json()
+        assert other["status"] == 200, other
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": other["heartbeat_token"]})
+        r.raise_for_status()
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        r = self.events(token=other["access_token"])
+        assert len(r["events"]) == 1, r
+        r = self.events(cursor=r["events"][0]["id"])
+        assert r["status"] == 400 and r["error"] == "Unknown cursor", r
+        r = self.events(cursor=events[0]["id"], token=other["access_token"])
+        assert r["status"] == 400, r
+
+
+if __name__ == "__main__":
+    test = ApiV1Events(timeout=60)
+    asyncio.run(test.run())
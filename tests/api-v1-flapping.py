@@ -0,0 +1,111 @@
+#!/usr/bin/env python
+import asyncio
+import json
+import queue
+import threading
+from http.server import BaseHTTPRequestHandler, HTTPServer
+
+import requests
+from lib.testbase import TestBase
+
+WEBHOOK_PORT = 8100
+
+
+class Recorder(BaseHTTPRequestHandler):
+    received = queue.Queue()
+
+    def do_POST(self):
+        body = self.rfile.read(int(self.headers["Content-Length"]))
+        Recorder.received.put(json.loads(body))
+        self.send_response(200)
+        self.end_headers()
+
+    def log_message(self, *args):
+        pass
+
+
+class ApiV1Flapping(TestBase):
+    def ping(self):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+
+    def settings(self, data):
+        r = requests.patch(f"{self.base_url}/api/v1/me/settings", json=data, headers={"authorization": self.access_token})
+        r.raise_for_status()
+        return r.json()
+
+    async def next_event(self, timeout=30):
+        payload = await asyncio.to_thread(Recorder.received.get, timeout=timeout)
+        self.log(f"Webhook: {payload['event']} {payload['title']} {payload['message']}")
+        return payload
+
+    async def keep_pinging(self, seconds):
+        for _ in range(seconds // 3):
+            self.ping()
+            await asyncio.sleep(3)
+
+    async def setup(self):
+        server = HTTPServer(("127.0.0.1", WEBHOOK_PORT), Recorder)
+        threading.Thread(target=server.serve_forever, daemon=True).start()
+
+        headers = {"authorization": self.access_token}
+        data = {"kind": "Webhook", "config": {"url": f"http://127.0.0.1:{WEBHOOK_PORT}/hook"}}
+        r = requests.post(f"{self.base_url}/api/v1/me/channels", json=data, headers=headers)
+        r.raise_for_status()
+        assert r.json()["status"] == 200, r.json()
+
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        # Even values too big for the column get a 400, not a bare 422 from the deserializer
+        for invalid in [{"flap_threshold": 1}, {"flap_window_minutes": 0}, {"min_outage_secs": -1}, {"up_delay": 40000}]:
+            result = self.settings(invalid)
+            assert result["status"] == 400, result
+        await asyncio.sleep(1)
+        result = self.settings({"flap_threshold": 2, "flap_window_minutes": 10, "flap_stable_minutes": 1})
+        assert result["status"] == 200, result
+        assert (result["flap_threshold"], result["flap_stable_minutes"], result["min_outage_secs"]) == (2, 1, 0)
+
+    async def on_connected(self, ws):
+        self.ping()
+        assert (await self.next_event())["event"] == "connected"
+
+        # First outage is notified as usual
+        payload = await self.next_event()
+        assert payload["event"] == "down"
+
+        # Coming back is the second flip within the window: one notice instead of "Power is back!"
+        self.ping()
+        payload = await self.next_event()
+        assert payload["event"] == "flapping", payload
+        assert payload["title"] == "Нестабільне з'єднання!"
+        assert payload["message"].startswith("2 зміни стану за 10 хв."), payload["message"]
+        assert (payload["old_status"], payload["new_status"]) == ("Down", "Up")
+
+        r = requests.get(f"{self.base_url}/api/v1/me", headers={"authorization": self.access_token})
+        assert r.json()["user"]["devices"][0]["uptime"]["flapping_since"] is not None, r.json()
+
+        # Further flips stay silent, a minute without any settles the device
+        await asyncio.sleep(13)
+        self.ping()
+        await self.keep_pinging(66)
+        payload = await self.next_event(timeout=15)
+        assert payload["event"] == "stable", payload
+        assert payload["message"] == "Світло є."
+        assert payload["new_status"] == "Up"
+
+        # Outages shorter than min_outage_secs are not notified, neither is their end
+        result = self.settings({"flap_threshold": None, "min_outage_secs": 20})
+        assert result["status"] == 200 and result["flap_threshold"] is None, result
+        await asyncio.sleep(13)
+        self.ping()
+        await asyncio.sleep(1)
+        assert Recorder.received.empty(), "Short outage must stay silent"
+
+        # A long one is notified once it lasted min_outage_secs
+        payload = await self.next_event(timeout=45)
+        assert payload["event"] == "down", payload
+        assert payload["title"] == "Відключення світла!"
+
+
+if __name__ == "__main__":
+    test = ApiV1Flapping(timeout=240)
+    asyncio.run(test.run())
@@ -0,0 +1,56 @@
+#!/usr/bin/env python
+import asyncio
+
+import requests
+from lib.testbase import TestBase
+
+DOWN = 'fill="#d93025"'
+
+
+class ApiV1Graph(TestBase):
+    def ping(self):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+
+    def graph(self, **params):
+        return requests.get(f"{self.base_url}/api/v1/me/graph", params=params, headers={"authorization": self.access_token})
+
+    async def setup(self):
+        r = self.graph(window="1y")
+        assert r.json()["status"] == 400, r.text
+        r = self.graph(format="gif")
+        assert r.json()["status"] == 400, r.text
+
+        # Nothing recorded yet, the whole window is unknown
+        r = self.graph(window="7d", format="svg")
+        assert r.status_code == 200 and r.headers["content-type"].startswith("image/svg+xml"), r.headers
+        assert r.text.startswith("<svg") and ">7d: –</text>" in r.text, r.text
+        assert DOWN not in r.text, r.text
+
+    async def on_connected(self, ws):
+        self.ping()
+        await asyncio.sleep(1)
+
+        # The device stops pinging, then comes back
+        for _ in range(30):
+            await asyncio.sleep(1)
+            r = self.graph()
+            if DOWN in r.text:
+                break
+        await asyncio.sleep(3)
+        self.ping()
+        await asyncio.sleep(1)
+
+        r = self.graph()
+        self.log(r.text[:200])
+        assert ">24h: " in r.text and ">24h: –" not in r.text and ">24h: 100.00%" not in r.text, r.text
+        assert r.text.count(DOWN) >= 1, r.text
+
+        r = self.graph(window="30d", format="png")
+        assert r.status_code == 200 and r.headers["content-type"] == "image/png", r.headers
+        assert r.content.startswith(b"\x89PNG"), r.content[:16]
+
+
+if __name__ == "__main__":
+    test = ApiV1Graph(timeout=120)
+    asyncio.run(test.run())
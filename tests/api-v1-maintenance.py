@@ -0,0 +1,134 @@
+#!/usr/bin/env python
+import asyncio
+import json
+import queue
+import threading
+import time
+from datetime import datetime, timedelta, timezone
+from http.server import BaseHTTPRequestHandler, HTTPServer
+
+import requests
+from lib.testbase import TestBase
+
+WEBHOOK_PORT = 8105
+
+
+class Recorder(BaseHTTPRequestHandler):
+    received = queue.Queue()
+
+    def do_POST(self):
+        body = self.rfile.read(int(self.headers["Content-Length"]))
+        Recorder.received.put(json.loads(body))
+        self.send_response(200)
+        self.end_headers()
+
+    def log_message(self, *args):
+        pass
+
+
+class ApiV1Maintenance(TestBase):
+    def ping(self):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+
+    def request(self, method, path, data=None):
+        url = f"{self.base_url}/api/v1/me/maintenance{path}"
+        r = requests.request(method, url, json=data, headers={"authorization": self.access_token})
+        r.raise_for_status()
+        return r.json()
+
+    async def next_event(self, timeout=30):
+        payload = await asyncio.to_thread(Recorder.received.get, timeout=timeout)
+        self.log(f"Webhook: {payload['event']} {payload['title']} {payload['message']!r}")
+        return payload
+
+    async def setup(self):
+        server = HTTPServer(("127.0.0.1", WEBHOOK_PORT), Recorder)
+        threading.Thread(target=server.serve_forever, daemon=True).start()
+
+        data = {"kind": "Webhook", "config": {"url": f"http://127.0.0.1:{WEBHOOK_PORT}/hook"}}
+        r = requests.post(f"{self.base_url}/api/v1/me/channels", json=data, headers={"authorization": self.access_token})
+        assert r.json()["status"] == 200, r.json()
+
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        for invalid in [
+            {"weekdays": [], "start_minute": 60, "end_minute": 120},
+            {"weekdays": ["someday"], "start_minute": 60, "end_minute": 120},
+            {"weekdays": ["tue"], "start_minute": 60, "end_minute": 60},
+            {"weekdays": ["tue"], "start_minute": 60, "end_minute": 120, "starts_at": "2030-01-01T10:00"},
+        ]:
+            result = self.request("POST", "", invalid)
+            assert result["status"] == 400, (invalid, result)
+        await asyncio.sleep(1)
+        for invalid in [
+            {"starts_at": "2020-01-01T10:00", "ends_at": "2020-01-01T12:00"},
+            {"starts_at": "2030-01-01T10:00", "ends_at": "2030-01-01T09:00"},
+            {"starts_at": "2030-01-01T10:00", "ends_at": "2030-03-01T10:00"},
+            {"weekdays": ["tue"], "start_minute": 60, "end_minute": 120, "device_id": "00000000-0000-0000-0000-000000000000"},
+        ]:
+            result = self.request("POST", "", invalid)
+            assert result["status"] == 400, (invalid, result)
+
+        # Planned maintenance tomorrow isn't in effect yet
+        tomorrow = datetime.now(timezone.utc) + timedelta(days=1)
+        await asyncio.sleep(1)
+        result = self.request("POST", "", {
+            "starts_at": tomorrow.strftime("%Y-%m-%dT09:00"),
+            "ends_at": tomorrow.strftime("%Y-%m-%dT17:00"),
+        })
+        assert result["status"] == 200, result
+        assert result["window"]["weekdays"] is None and result["window"]["active"] is False, result
+        self.one_off_id = result["window"]["id"]
+        result = self.request("PATCH", f"/{self.one_off_id}", {
+            "label": "grid works",
+            "starts_at": tomorrow.strftime("%Y-%m-%dT10:00"),
+            "ends_at": tomorrow.strftime("%Y-%m-%dT14:00"),
+        })
+        assert result["status"] == 200, result
+        assert result["window"]["id"] == self.one_off_id, result
+        assert result["window"]["starts_at"] == tomorrow.strftime("%Y-%m-%dT10:00"), result
+        assert result["window"]["label"] == "grid works", result
+
+    async def on_connected(self, ws):
+        self.ping()
+        assert (await self.next_event())["event"] == "connected"
+
+        # Every day from 5 minutes ago for the next 10 minutes
+        minute = int(time.time() // 60) % 1440
+        await asyncio.sleep(1)
+        result = self.request("POST", "", {
+            "weekdays": ["mon", "tue", "wed", "thu", "fri", "sat", "sun"],
+            "start_minute": (minute - 5) % 1440,
+            "end_minute": (minute + 10) % 1440,
+        })
+        assert result["status"] == 200, result
+        assert result["window"]["active"] is True and result["window"]["device_id"] is None, result
+        daily_id = result["window"]["id"]
+        result = self.request("GET", "")
+        assert result["timezone"] == "UTC", result
+        assert [w["id"] for w in result["windows"]] == [self.one_off_id, daily_id], result
+
+        # The device stops pinging, its outage is not notified during the window
+        await asyncio.sleep(25)
+        assert Recorder.received.empty(), "Outages during maintenance must not be notified"
+        # The silence counts as maintenance in the stats, but isn't listed as a transition
+        headers = {"authorization": self.access_token}
+        r = requests.get(f"{self.base_url}/api/v1/me/events", headers=headers)
+        transitions = [(e["from_status"], e["to_status"]) for e in r.json()["events"]]
+        assert all(start != end for start, end in transitions), transitions
+        r = requests.get(f"{self.base_url}/api/v1/me/stats", headers=headers)
+        assert r.json()["stats"]["maintenance"] > 0, r.json()
+
+        # Once the window is gone, the outage is notified
+        result = self.request("DELETE", f"/{daily_id}")
+        assert result["status"] == 200, result
+        assert (await self.next_event())["event"] == "down"
+        self.ping()
+        assert (await self.next_event())["event"] == "up"
+        result = self.request("DELETE", f"/{daily_id}")
+        assert result["status"] == 404, result
+
+
+if __name__ == "__main__":
+    test = ApiV1Maintenance(timeout=120)
+    asyncio.run(test.run())
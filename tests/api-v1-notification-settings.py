@@ -0,0 +1,112 @@
+#!/usr/bin/env python
+import asyncio
+import json
+import queue
+import threading
+from http.server import BaseHTTPRequestHandler, HTTPServer
+
+import requests
+from lib.testbase import TestBase
+
+WEBHOOK_PORT = 8103
+
+
+class Recorder(BaseHTTPRequestHandler):
+    received = queue.Queue()
+
+    def do_POST(self):
+        body = self.rfile.read(int(self.headers["Content-Length"]))
+        Recorder.received.put((self.headers["X-Oubot-Event"], json.loads(body)))
+        self.send_response(200)
+        self.end_headers()
+
+    def log_message(self, *args):
+        pass
+
+
+class ApiV1NotificationSettings(TestBase):
+    def ping(self):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+
+    def settings(self, notifications):
+        data = {"notifications": notifications}
+        r = requests.patch(f"{self.base_url}/api/v1/me/settings", json=data, headers={"authorization": self.access_token})
+        r.raise_for_status()
+        return r.json()
+
+    async def next_event(self, timeout=30):
+        event, payload = await asyncio.to_thread(Recorder.received.get, timeout=timeout)
+        self.log(f"Webhook: {event} {payload['title']} {payload['message']}")
+        return event, payload
+
+    async def setup(self):
+        server = HTTPServer(("127.0.0.1", WEBHOOK_PORT), Recorder)
+        threading.Thread(target=server.serve_forever, daemon=True).start()
+
+        data = {"kind": "Webhook", "config": {"url": f"http://127.0.0.1:{WEBHOOK_PORT}/hook"}}
+        r = requests.post(f"{self.base_url}/api/v1/me/channels", json=data, headers={"authorization": self.access_token})
+        assert r.json()["status"] == 200, r.json()
+
+        invalid = [
+            {"outage": {"enabled": False}},
+            {"down": {"priority": "urgent"}},
+            {"down": {"click": "javascript:alert(1)"}},
+            {"down": {"tags": ["a,b"]}},
+        ]
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        for notifications in invalid:
+            result = self.settings(notifications)
+            assert result["status"] == 400, (notifications, result)
+        await asyncio.sleep(1)
+        result = self.settings({"escalation": {"priority": "max"}})
+        assert result["status"] == 400, result
+
+        changes = {
+            "up": {"enabled": False},
+            "down": {"priority": "max", "tags": ["rotating_light"], "click": "https://example.com/outages"},
+        }
+        result = self.settings(changes)
+        assert result["status"] == 200, result
+        down = result["notifications"]["down"]
+        assert down == {
+            "enabled": True,
+            "priority": "max",
+            "tags": ["rotating_light"],
+            "icon": None,
+            "click": "https://example.com/outages",
+            "quiet": "silent",
+        }, down
+        assert result["notifications"]["up"]["enabled"] is False, result
+
+        # Changes merge into what is stored, null resets a single key
+        result = self.settings({"down": {"click": None}})
+        assert result["notifications"]["down"]["click"] is None, result
+        assert result["notifications"]["down"]["priority"] == "max", result
+
+        r = requests.get(f"{self.base_url}/api/v1/me/settings", headers={"authorization": self.access_token})
+        defaults = r.json()["notifications"]["connected"]
+        assert defaults == {
+            "enabled": True,
+            "priority": "high",
+            "tags": ["white_check_mark"],
+            "icon": None,
+            "click": None,
+            "quiet": "silent",
+        }, defaults
+
+    async def on_connected(self, ws):
+        self.ping()
+        assert (await self.next_event())[0] == "connected"
+        event, _ = await self.next_event()
+        assert event == "down", event
+
+        # Power restored notifications are disabled, nothing is sent when the device comes back
+        self.ping()
+        await asyncio.sleep(5)
+        assert Recorder.received.empty(), "Disabled event kinds must not be sent"
+
+
+if __name__ == "__main__":
+    test = ApiV1NotificationSettings(timeout=120)
+    asyncio.run(test.run())
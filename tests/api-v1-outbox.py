@@ -0,0 +1,134 @@
+#!/usr/bin/env python
+import asyncio
+import json
+import queue
+import threading
+import time
+from http.server import BaseHTTPRequestHandler, HTTPServer
+
+import requests
+from lib.testbase import TestBase
+
+WEBHOOK_PORT = 8099
+
+
+class FlakyWebhook(BaseHTTPRequestHandler):
+    """Rejects deliveries until `accepting` is set, so they stay in the outbox."""
+
+    received = queue.Queue()
+    accepting = False
+
+    def do_POST(self):
+        body = self.rfile.read(int(self.headers["Content-Length"]))
+        if self.path == "/gone":
+            status = 404
+        else:
+            status = 200 if FlakyWebhook.accepting else 503
+        FlakyWebhook.received.put((status, body))
+        self.send_response(status)
+        self.end_headers()
+
+    def log_message(self, *args):
+        pass
+
+
+class ApiV1Outbox(TestBase):
+    def admin_get(self, path):
+        r = requests.get(f"{self.base_url}{path}", headers={"authorization": self.access_token})
+        r.raise_for_status()
+        return r.json()
+
+    async def next_delivery(self, timeout=15):
+        return await asyncio.to_thread(FlakyWebhook.received.get, timeout=timeout)
+
+    async def metric(self, name):
+        metrics = requests.get(f"{self.base_url}/api/v1/metrics").text
+        values = [line.split()[1] for line in metrics.splitlines() if line.startswith(f"{name} ")]
+        assert values, f"Missing metric {name}"
+        return float(values[0])
+
+    async def setup(self):
+        server = HTTPServer(("127.0.0.1", WEBHOOK_PORT), FlakyWebhook)
+        threading.Thread(target=server.serve_forever, daemon=True).start()
+
+        headers = {"authorization": self.access_token}
+        data = {"kind": "Webhook", "config": {"url": f"http://127.0.0.1:{WEBHOOK_PORT}/hook"}}
+        r = requests.post(f"{self.base_url}/api/v1/me/channels", json=data, headers=headers)
+        r.raise_for_status()
+        assert r.json()["status"] == 200, r.json()
+
+    async def on_connected(self, ws):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+
+        # ntfy is delivered right away, the webhook rejects the first attempt
+        message = await self.wait_for_message(ws)
+        assert message["title"] == "Девайс під'єднано!"
+        status, first_body = await self.next_delivery()
+        assert status == 503
+
+        # The failed attempt stays queued for a retry
+        await asyncio.sleep(1)
+        result = self.admin_get("/api/v1/admin/outbox")
+        assert result["status"] == 200, result
+        [item] = result["outbox"]
+        self.log(f"Queued: {item}")
+        assert item["event"] == "connected"
+        assert item["attempts"] == 1
+        assert "503" in item["last_error"]
+        assert item["failed_at"] is None
+        assert item["next_attempt_at"] > time.time() + 20, "Retries must back off"
+        assert item["payload"]["title"] == "Девайс під'єднано!"
+
+        # Nothing ran out of attempts, so there is nothing to retry by hand
+        assert self.admin_get("/api/v1/admin/outbox?failed=true")["outbox"] == []
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        r = requests.post(
+            f"{self.base_url}/api/v1/admin/outbox/{item['id']}/retry", headers={"authorization": self.access_token}
+        )
+        assert r.json()["status"] == 404, r.json()
+
+        # Metrics are refreshed every few seconds
+        await asyncio.sleep(6)
+        assert await self.metric("oubot_notification_outbox_pending") == 1
+        assert await self.metric("oubot_notification_outbox_oldest_age_seconds") > 0
+
+        # The retry goes through once the webhook is back. The device went down meanwhile,
+        # that notification may arrive first.
+        FlakyWebhook.accepting = True
+        while True:
+            status, body = await self.next_delivery(timeout=45)
+            payload = json.loads(body)
+            if payload["event"] == "connected":
+                break
+            assert payload["event"] == "down", payload
+        assert status == 200
+        assert body == first_body, "Retries must resend the same payload"
+        assert "priority" not in payload
+
+        await asyncio.sleep(1)
+        queued = [i["id"] for i in self.admin_get("/api/v1/admin/outbox")["outbox"]]
+        assert item["id"] not in queued, "Delivered items must leave the outbox"
+        metrics = requests.get(f"{self.base_url}/api/v1/metrics").text
+        webhook_lines = [line for line in metrics.splitlines() if 'channel="webhook"' in line]
+        assert any('result="retry"' in line for line in webhook_lines), webhook_lines
+        assert any('result="success"' in line for line in webhook_lines), webhook_lines
+
+        # A webhook that is gone (404) won't come back, it isn't retried
+        data = {"kind": "Webhook", "config": {"url": f"http://127.0.0.1:{WEBHOOK_PORT}/gone"}}
+        r = requests.post(f"{self.base_url}/api/v1/me/channels", json=data, headers={"authorization": self.access_token})
+        assert r.json()["status"] == 200, r.json()
+        gone = r.json()["channel"]["id"]
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+        while (await self.next_delivery())[0] != 404:
+            pass
+        await asyncio.sleep(1)
+        [failed] = self.admin_get("/api/v1/admin/outbox?failed=true")["outbox"]
+        assert failed["channel_id"] == gone and failed["attempts"] == 1, failed
+        assert "404" in failed["last_error"], failed
+
+
+if __name__ == "__main__":
+    test = ApiV1Outbox(timeout=90)
+    asyncio.run(test.run())
@@ -0,0 +1,106 @@
+#!/usr/bin/env python
+import asyncio
+
+import requests
+from lib.testbase import TestBase
+
+
+class ApiV1Pages(TestBase):
+    def api(self, method, path, **kwargs):
+        r = requests.request(method, f"{self.base_url}{path}", headers={"authorization": self.access_token}, **kwargs)
+        return r.json()
+
+    def public(self, path, **params):
+        return requests.get(f"{self.base_url}{path}", params=params)
+
+    def assert_nothing_private(self, text):
+        for secret in [self.state["user"]["id"], self.kitchen, self.hall, self.access_token, self.heartbeat_token]:
+            assert secret not in text, (secret, text)
+
+    async def setup(self):
+        self.kitchen = self.state["devices"][0]["device"]["id"]
+        r = self.api("POST", "/api/v1/me/devices", json={"name": "hall"})
+        assert r["status"] == 200, r
+        self.hall = r["device"]["device"]["id"]
+
+        body = {"slug": "Bad Slug", "title": "Будинок 12", "monitors": [{"device_id": self.kitchen}]}
+        r = self.api("POST", "/api/v1/me/pages", json=body)
+        assert r["status"] == 400 and "Slug" in r["error"], r
+        body["slug"] = "budynok-12"
+        body["monitors"] = [{"device_id": self.hall, "name": "Кв. 2"}, {"device_id": "00000000-0000-0000-0000-000000000000"}]
+        r = self.api("POST", "/api/v1/me/pages", json=body)
+        assert r["status"] == 400 and "not found" in r["error"], r
+
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        # Private until said otherwise
+        body["monitors"] = [{"device_id": self.hall, "name": "Кв. 2"}, {"device_id": self.kitchen}]
+        r = self.api("POST", "/api/v1/me/pages", json=body)
+        assert r["status"] == 200 and r["page"]["visibility"] == "private", r
+        self.page = r["page"]
+        assert self.page["path"] == "/api/v1/pages/budynok-12", r
+        assert [m["name"] for m in self.page["monitors"]] == ["Кв. 2", "default"], r
+        assert self.public(self.page["path"]).status_code == 404
+        assert self.public(self.page["path"] + "/json").json()["status"] == 404
+
+        r = self.api("POST", "/api/v1/me/pages", json=body)
+        assert r["status"] == 400 and r["error"] == "Slug is already taken", r
+
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        # Unlisted: served, but not in the directory
+        body["visibility"] = "unlisted"
+        body["monitors"][1]["name"] = "Кв. 1"
+        r = self.api("PATCH", f"/api/v1/me/pages/{self.page['id']}", json=body)
+        assert r["status"] == 200 and r["page"]["visibility"] == "unlisted", r
+        assert self.public("/api/v1/pages").json()["pages"] == []
+
+        r = self.public(self.page["path"], window="7d")
+        assert r.status_code == 200 and "<title>Будинок 12</title>" in r.text, r.text
+        assert r.text.index("<h2>Кв. 2</h2>") < r.text.index("<h2>Кв. 1</h2>"), r.text
+        assert "<a><b>7d</b></a>" in r.text and "<script" not in r.text, r.text
+        self.assert_nothing_private(r.text)
+
+    async def on_connected(self, ws):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+        await self.wait_for_message(ws)
+
+        r = self.public(self.page["path"] + "/json")
+        assert r.headers["access-control-allow-origin"] == "*", r.headers
+        data = r.json()
+        assert data["status"] == 200 and data["title"] == "Будинок 12" and data["window"] == "24h", data
+        hall, kitchen = data["monitors"]
+        assert hall["name"] == "Кв. 2" and hall["status"] == "unknown" and hall["since"] is None, hall
+        assert kitchen["name"] == "Кв. 1" and kitchen["status"] == "up" and kitchen["last_heartbeat"] > 0, kitchen
+        assert set(kitchen) == {"name", "status", "since", "last_heartbeat", "availability", "outages"}, kitchen
+        self.assert_nothing_private(r.text)
+        assert self.public(self.page["path"] + "/json", window="1y").json()["status"] == 400
+
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        # Public pages are listed, keeping the visibility when it isn't given
+        del self.page["monitors"][0]["name"]
+        body = {"slug": "budynok-12", "title": "Будинок 12", "visibility": "public", "monitors": self.page["monitors"]}
+        assert self.api("PATCH", f"/api/v1/me/pages/{self.page['id']}", json=body)["status"] == 200
+        pages = self.public("/api/v1/pages").json()["pages"]
+        assert pages == [{"slug": "budynok-12", "title": "Будинок 12", "path": self.page["path"]}], pages
+        del body["visibility"]
+        r = self.api("PATCH", f"/api/v1/me/pages/{self.page['id']}", json=body)
+        assert r["page"]["visibility"] == "public" and r["page"]["monitors"][0]["name"] == "hall", r
+
+        r = self.api("GET", "/api/v1/me/pages")
+        assert [p["slug"] for p in r["pages"]] == ["budynok-12"], r
+
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        # Removing a device drops it from the page
+        assert self.api("DELETE", f"/api/v1/me/devices/{self.hall}")["status"] == 200
+        monitors = self.public(self.page["path"] + "/json").json()["monitors"]
+        assert [m["name"] for m in monitors] == ["default"], monitors
+
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        assert self.api("DELETE", f"/api/v1/me/pages/{self.page['id']}")["status"] == 200
+        assert self.public(self.page["path"]).status_code == 404
+        assert self.api("DELETE", f"/api/v1/me/pages/{self.page['id']}")["status"] == 404
+
+
+if __name__ == "__main__":
+    test = ApiV1Pages(timeout=60)
+    asyncio.run(test.run())
@@ -0,0 +1,106 @@
+#!/usr/bin/env python
+import asyncio
+import json
+import queue
+import threading
+import time
+from http.server import BaseHTTPRequestHandler, HTTPServer
+
+import requests
+from lib.testbase import TestBase
+
+WEBHOOK_PORT = 8104
+
+
+class Recorder(BaseHTTPRequestHandler):
+    received = queue.Queue()
+
+    def do_POST(self):
+        body = self.rfile.read(int(self.headers["Content-Length"]))
+        Recorder.received.put(json.loads(body))
+        self.send_response(200)
+        self.end_headers()
+
+    def log_message(self, *args):
+        pass
+
+
+class ApiV1QuietHours(TestBase):
+    def ping(self):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+
+    def settings(self, data):
+        r = requests.patch(f"{self.base_url}/api/v1/me/settings", json=data, headers={"authorization": self.access_token})
+        r.raise_for_status()
+        return r.json()
+
+    async def next_event(self, timeout=30):
+        payload = await asyncio.to_thread(Recorder.received.get, timeout=timeout)
+        self.log(f"Webhook: {payload['event']} {payload['title']} {payload['message']!r}")
+        return payload
+
+    async def setup(self):
+        server = HTTPServer(("127.0.0.1", WEBHOOK_PORT), Recorder)
+        threading.Thread(target=server.serve_forever, daemon=True).start()
+
+        data = {"kind": "Webhook", "config": {"url": f"http://127.0.0.1:{WEBHOOK_PORT}/hook"}}
+        r = requests.post(f"{self.base_url}/api/v1/me/channels", json=data, headers={"authorization": self.access_token})
+        assert r.json()["status"] == 200, r.json()
+
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        for invalid in [
+            {"quiet_hours_start": 60},
+            {"quiet_hours_start": 60, "quiet_hours_end": None},
+            {"quiet_hours_start": 60, "quiet_hours_end": 60},
+            {"quiet_hours_start": 60, "quiet_hours_end": 1440},
+        ]:
+            result = self.settings(invalid)
+            assert result["status"] == 400, (invalid, result)
+
+        # Quiet hours started 5 minutes ago and end in 3, the digest arrives then
+        minute = int(time.time() // 60) % 1440
+        self.quiet_end = (minute + 3) % 1440
+        await asyncio.sleep(1)
+        result = self.settings({
+            "quiet_hours_start": (minute - 5) % 1440,
+            "quiet_hours_end": self.quiet_end,
+            "notifications": {"connected": {"quiet": "send"}, "down": {"quiet": "defer"}, "up": {"quiet": "defer"}},
+        })
+        assert result["status"] == 200, result
+        assert result["quiet_hours_end"] == self.quiet_end, result
+        assert result["notifications"]["down"]["quiet"] == "defer", result
+        assert result["notifications"]["escalation"]["quiet"] == "silent", result
+
+    async def on_connected(self, ws):
+        self.ping()
+        assert (await self.next_event())["event"] == "connected"
+
+        # Going down and coming back during quiet hours is recorded, but held back
+        for _ in range(30):
+            await asyncio.sleep(2)
+            r = requests.get(f"{self.base_url}/api/v1/me", headers={"authorization": self.access_token})
+            if r.json()["user"]["devices"][0]["uptime"]["status"] == "Down":
+                break
+        self.ping()
+        await asyncio.sleep(5)
+        assert Recorder.received.empty(), "Deferred notifications must wait for the end of quiet hours"
+        r = requests.get(f"{self.base_url}/api/v1/me/events", headers={"authorization": self.access_token})
+        transitions = [(e["from_status"], e["to_status"]) for e in r.json()["events"]]
+        assert transitions[:2] == [("Down", "Up"), ("Up", "Down")], transitions
+
+        # Both arrive as one digest once the quiet hours are over
+        payload = await self.next_event(timeout=240)
+        assert payload["event"] == "digest", payload
+        # Without heartbeats the device went down again meanwhile, that is part of the digest too
+        lines = payload["message"].split("\n")
+        assert payload["title"] == f"{len(lines)} сповіщення за тихі години", payload
+        assert "Відключення світла!" in lines[0] and "Світло з'явилося!" in lines[1], lines
+        assert int(time.time() // 60) % 1440 == self.quiet_end, "Sent when the quiet hours end"
+        await asyncio.sleep(2)
+        assert Recorder.received.empty(), "Merged notifications are not sent again"
+
+
+if __name__ == "__main__":
+    test = ApiV1QuietHours(timeout=300)
+    asyncio.run(test.run())
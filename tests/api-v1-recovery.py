@@ -0,0 +1,100 @@
+#!/usr/bin/env python
+import asyncio
+import json
+import queue
+import threading
+import time
+from http.server import BaseHTTPRequestHandler, HTTPServer
+
+import requests
+from lib.testbase import TestBase
+
+WEBHOOK_PORT = 8101
+
+
+class Recorder(BaseHTTPRequestHandler):
+    received = queue.Queue()
+
+    def do_POST(self):
+        body = self.rfile.read(int(self.headers["Content-Length"]))
+        Recorder.received.put(json.loads(body))
+        self.send_response(200)
+        self.end_headers()
+
+    def log_message(self, *args):
+        pass
+
+
+class ApiV1Recovery(TestBase):
+    def ping(self):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+
+    def settings(self, data):
+        r = requests.patch(f"{self.base_url}/api/v1/me/settings", json=data, headers={"authorization": self.access_token})
+        r.raise_for_status()
+        return r.json()
+
+    def status(self):
+        r = requests.get(f"{self.base_url}/api/v1/me", headers={"authorization": self.access_token})
+        return r.json()["user"]["devices"][0]["uptime"]["status"]
+
+    async def next_event(self, timeout=30):
+        payload = await asyncio.to_thread(Recorder.received.get, timeout=timeout)
+        self.log(f"Webhook: {payload['event']} {payload['title']} {payload['message']}")
+        return payload
+
+    async def setup(self):
+        server = HTTPServer(("127.0.0.1", WEBHOOK_PORT), Recorder)
+        threading.Thread(target=server.serve_forever, daemon=True).start()
+
+        headers = {"authorization": self.access_token}
+        data = {"kind": "Webhook", "config": {"url": f"http://127.0.0.1:{WEBHOOK_PORT}/hook"}}
+        r = requests.post(f"{self.base_url}/api/v1/me/channels", json=data, headers=headers)
+        r.raise_for_status()
+        assert r.json()["status"] == 200, r.json()
+
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        for invalid in [0, 101]:
+            result = self.settings({"recovery_heartbeats": invalid})
+            assert result["status"] == 400, result
+        await asyncio.sleep(1)
+        result = self.settings({"recovery_heartbeats": 3})
+        assert result["status"] == 200 and result["recovery_heartbeats"] == 3, result
+
+    async def on_connected(self, ws):
+        # The first heartbeat connects right away, recovery only applies to a Down device
+        self.ping()
+        assert (await self.next_event())["event"] == "connected"
+        payload = await self.next_event()
+        assert payload["event"] == "down"
+        down_at = time.time()
+
+        # The grid flickers back for a moment: no "Power is back!"
+        self.ping()
+        await asyncio.sleep(13)
+        assert Recorder.received.empty(), "A single heartbeat must not restore the device"
+        assert self.status() == "Down"
+
+        # Three heartbeats in a row confirm the recovery
+        recovered_at = time.time()
+        self.ping()
+        await asyncio.sleep(3)
+        self.ping()
+        await asyncio.sleep(1)
+        assert Recorder.received.empty(), "Two heartbeats must not restore the device"
+        assert self.status() == "Down"
+        await asyncio.sleep(2)
+        self.ping()
+        payload = await self.next_event(timeout=10)
+        assert payload["event"] == "up", payload
+        assert payload["title"] == "Світло з'явилося!"
+        # The outage ends at the first heartbeat of the confirmed recovery, not the flicker or the third one
+        assert abs(payload["duration"] - (recovered_at - down_at)) <= 2, (payload, recovered_at - down_at)
+        await asyncio.sleep(1)
+        assert self.status() == "Up"
+
+
+if __name__ == "__main__":
+    test = ApiV1Recovery(timeout=120)
+    asyncio.run(test.run())
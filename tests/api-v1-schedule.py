@@ -0,0 +1,129 @@
+#!/usr/bin/env python
+import asyncio
+import json
+import queue
+import threading
+from datetime import datetime, timedelta, timezone
+from http.server import BaseHTTPRequestHandler, HTTPServer
+from zoneinfo import ZoneInfo
+
+import requests
+from lib.testbase import TestBase
+
+WEBHOOK_PORT = 8106
+# Times without an offset are local to SCHEDULE_TIMEZONE, which defaults to Kyiv
+GRID_TZ = ZoneInfo("Europe/Kyiv")
+
+
+class Recorder(BaseHTTPRequestHandler):
+    received = queue.Queue()
+
+    def do_POST(self):
+        body = self.rfile.read(int(self.headers["Content-Length"]))
+        Recorder.received.put(json.loads(body))
+        self.send_response(200)
+        self.end_headers()
+
+    def log_message(self, *args):
+        pass
+
+
+class ApiV1Schedule(TestBase):
+    def ping(self):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+
+    def import_schedule(self, body, query):
+        r = requests.post(
+            f"{self.base_url}/api/v1/admin/schedule?{query}",
+            data=body.encode(),
+            headers={"authorization": self.access_token},
+        )
+        r.raise_for_status()
+        return r.json()
+
+    def settings(self, data):
+        r = requests.patch(f"{self.base_url}/api/v1/me/settings", json=data, headers={"authorization": self.access_token})
+        r.raise_for_status()
+        return r.json()
+
+    async def next_event(self, timeout=30):
+        payload = await asyncio.to_thread(Recorder.received.get, timeout=timeout)
+        self.log(f"Webhook: {payload['event']} {payload['title']} {payload['message']!r}")
+        return payload
+
+    async def setup(self):
+        server = HTTPServer(("127.0.0.1", WEBHOOK_PORT), Recorder)
+        threading.Thread(target=server.serve_forever, daemon=True).start()
+
+        data = {"kind": "Webhook", "config": {"url": f"http://127.0.0.1:{WEBHOOK_PORT}/hook"}}
+        r = requests.post(f"{self.base_url}/api/v1/me/channels", json=data, headers={"authorization": self.access_token})
+        assert r.json()["status"] == 200, r.json()
+
+        await asyncio.sleep(1)  # Stay under the per-IP rate limit
+        for body, query in [
+            ("3.1,2026-10-20,08:00,12:00", "format=xlsx"),
+            ("3.1,2026-10-20,08:00", "format=csv"),
+            ("[]", "format=json"),
+            # ICS events without CATEGORIES need the queue
+            ("BEGIN:VEVENT\nDTSTART:20261020T080000Z\nDTEND:20261020T120000Z\nEND:VEVENT", "format=ics"),
+        ]:
+            result = self.import_schedule(body, query)
+            assert result["status"] == 400, (body, result)
+
+        # Queue 3.1 is off right now for the next two hours, 4.2 only tomorrow
+        now = datetime.now(timezone.utc).replace(second=0, microsecond=0)
+        self.until = now + timedelta(hours=2)
+        slots = [{"queue": "3.1", "start": (now - timedelta(hours=1)).isoformat(), "end": self.until.isoformat()}]
+        await asyncio.sleep(1)
+        result = self.import_schedule(json.dumps(slots), "format=json")
+        assert result == {"status": 200, "imported": 1, "queues": {"3.1": 1}}, result
+        tomorrow = (now + timedelta(days=1)).astimezone(GRID_TZ)
+        csv = f"queue,date,start,end\n# tomorrow\n4.2,{tomorrow:%Y-%m-%d},08:00,12:00\n4.2,{tomorrow:%Y-%m-%d},20:00,24:00\n"
+        result = self.import_schedule(csv, "format=csv")
+        assert result == {"status": 200, "imported": 2, "queues": {"4.2": 2}}, result
+
+        await asyncio.sleep(1)
+        result = self.settings({"outage_queue": "3 1"})
+        assert result["status"] == 400, result
+        result = self.settings({"outage_queue": "3.1"})
+        assert result["status"] == 200 and result["outage_queue"] == "3.1", result
+        r = requests.get(f"{self.base_url}/api/v1/me/schedule", headers={"authorization": self.access_token})
+        result = r.json()
+        assert result["queue"] == "3.1" and result["timezone"] == "UTC", result
+        assert result["queues"] == ["3.1", "4.2"], result
+        assert result["planned_until"] == self.until.strftime("%Y-%m-%dT%H:%M"), result
+        assert len(result["outages"]) == 1, result
+
+    async def on_connected(self, ws):
+        self.ping()
+        assert (await self.next_event())["event"] == "connected"
+
+        # The device stops pinging during a planned outage of its queue
+        payload = await self.next_event()
+        assert payload["event"] == "down", payload
+        until = self.until.strftime("%H:%M" if self.until.date() == datetime.now(timezone.utc).date() else "%d.%m %H:%M")
+        assert payload["message"].endswith(f". Планове відключення (черга 3.1, до {until})"), payload
+        assert payload["schedule"] == {"queue": "3.1", "planned": True, "until": int(self.until.timestamp())}, payload
+        self.ping()
+        payload = await self.next_event()
+        assert payload["event"] == "up" and payload["schedule"] is None, payload
+
+        # Nothing planned for queue 4.2 right now
+        await asyncio.sleep(1)
+        result = self.settings({"outage_queue": "4.2"})
+        assert result["status"] == 200, result
+        payload = await self.next_event()
+        assert payload["event"] == "down", payload
+        assert payload["message"].endswith(". Позапланове відключення"), payload
+        assert payload["schedule"] == {"queue": "4.2", "planned": False, "until": None}, payload
+        self.ping()
+        assert (await self.next_event())["event"] == "up"
+
+        result = self.settings({"outage_queue": None})
+        assert result["status"] == 200 and result["outage_queue"] is None, result
+
+
+if __name__ == "__main__":
+    test = ApiV1Schedule(timeout=120)
+    asyncio.run(test.run())
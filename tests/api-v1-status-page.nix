@@ -0,0 +1,23 @@
+(import ./lib/lib.nix) {
+  name = "api-v1-status-page";
+
+  nodes = {
+    primary = {...}: let
+      c = import ./lib/config.nix;
+    in {
+      imports = [./lib/primary.nix];
+      # Notifications link to the status page only when the public address is known
+      systemd.services.open-uptime-bot.environment = {
+        OUBOT_PUBLIC_URL = "http://127.0.0.1:${c.oubot-port}";
+      };
+    };
+  };
+
+  testScript = let
+    c = import ./lib/config.nix;
+  in ''
+    primary.wait_for_unit("open-uptime-bot")
+    primary.wait_for_open_port(${c.oubot-port})
+    primary.succeed("tester-script-py")
+  '';
+}
@@ -0,0 +1,63 @@
+#!/usr/bin/env python
+import asyncio
+
+import requests
+from lib.testbase import TestBase
+
+
+class ApiV1StatusPage(TestBase):
+    def ping(self):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+
+    def page(self, path, **params):
+        return requests.get(f"{self.base_url}{path}", params=params)
+
+    async def setup(self):
+        r = requests.get(f"{self.base_url}/api/v1/me/status-page", headers={"authorization": self.access_token})
+        assert r.json()["status"] == 200, r.json()
+        self.path = r.json()["path"]
+        self.url = r.json()["url"]
+        assert self.path.startswith("/api/v1/status/sp_") and self.url.endswith(self.path), r.json()
+
+        r = self.page(self.path)
+        assert r.status_code == 200 and r.headers["content-type"].startswith("text/html"), r.headers
+        assert '<html lang="uk">' in r.text and "<title>Стан світла</title>" in r.text, r.text
+        assert "Очікуємо перший сигнал від пристрою" in r.text and "<script" not in r.text, r.text
+
+        r = self.page("/api/v1/status/sp_unknown")
+        assert r.status_code == 404, r.status_code
+
+    async def on_connected(self, ws):
+        self.ping()
+
+        # Notifications open the status page when clicked
+        message = await self.wait_for_message(ws)
+        assert message["title"] == "Девайс під'єднано!", message
+        assert message["click"] == self.url, message
+
+        r = self.page(self.path, window="7d")
+        assert "Світло є вже 0 хв" in r.text and "Останній сигнал 0 хв тому" in r.text, r.text
+        assert "<a><b>7d</b></a>" in r.text and "<svg" in r.text, r.text
+        assert "За цей період відключень не було" in r.text, r.text
+
+        # The device stops pinging, the outage is listed
+        message = await self.wait_for_message(ws)
+        assert message["title"] == "Відключення світла!", message
+        r = self.page(self.path)
+        assert "Світла немає вже" in r.text and ", триває " in r.text, r.text
+
+        # A new link replaces the old one
+        r = requests.post(
+            f"{self.base_url}/api/v1/me/regenerate-token",
+            params={"kind": "status_page"},
+            headers={"authorization": self.access_token},
+        )
+        assert r.json()["status"] == 200 and r.json()["path"] != self.path, r.json()
+        assert self.page(self.path).status_code == 404
+        assert self.page(r.json()["path"]).status_code == 200
+
+
+if __name__ == "__main__":
+    test = ApiV1StatusPage(timeout=60)
+    asyncio.run(test.run())
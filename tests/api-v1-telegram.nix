@@ -0,0 +1,24 @@
+(import ./lib/lib.nix) {
+  name = "api-v1-telegram";
+
+  nodes = {
+    primary = {...}: let
+      c = import ./lib/config.nix;
+    in {
+      imports = [./lib/primary.nix];
+      # Fake Bot API server, started by the test script itself
+      systemd.services.open-uptime-bot.environment = {
+        TELEGRAM_BOT_TOKEN = c.telegram-bot-token;
+        TELEGRAM_API_BASE_URL = "http://127.0.0.1:${c.telegram-port}";
+      };
+    };
+  };
+
+  testScript = let
+    c = import ./lib/config.nix;
+  in ''
+    primary.wait_for_unit("open-uptime-bot")
+    primary.wait_for_open_port(${c.oubot-port})
+    primary.succeed("tester-script-py")
+  '';
+}
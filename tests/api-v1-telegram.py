@@ -0,0 +1,166 @@
+#!/usr/bin/env python
+import asyncio
+import json
+import queue
+import threading
+import time
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+
+import requests
+from lib.testbase import TestBase
+
+# Must match tests/api-v1-telegram.nix
+BOT_API_PORT = 8096
+BOT_TOKEN = "123456:test-bot-token"
+CHAT_ID = 4242
+
+
+class FakeBotApi(BaseHTTPRequestHandler):
+    """Local stand-in for api.telegram.org: queued updates are handed out by getUpdates."""
+
+    updates = queue.Queue()
+    sent = queue.Queue()
+    next_update_id = 1
+    blocked = False
+
+    def do_POST(self):
+        body = json.loads(self.rfile.read(int(self.headers["Content-Length"])) or b"{}")
+        prefix = f"/bot{BOT_TOKEN}/"
+        if not self.path.startswith(prefix):
+            return self.reply(401, {"ok": False, "description": "Unauthorized"})
+        method = self.path[len(prefix) :]
+        if method == "getMe":
+            return self.reply(200, {"ok": True, "result": {"id": 1, "is_bot": True, "username": "oubot_test_bot"}})
+        if method == "getUpdates":
+            # Short poll, the server asks again right away
+            try:
+                update = FakeBotApi.updates.get(timeout=1)
+                result = [update] if update["update_id"] >= body.get("offset", 0) else []
+            except queue.Empty:
+                result = []
+            return self.reply(200, {"ok": True, "result": result})
+        if method == "sendMessage":
+            if FakeBotApi.blocked:
+                return self.reply(403, {"ok": False, "description": "Forbidden: bot was blocked by the user"})
+            FakeBotApi.sent.put(body)
+            return self.reply(200, {"ok": True, "result": {"message_id": 1}})
+        self.reply(404, {"ok": False, "description": "Not Found"})
+
+    def reply(self, status, data):
+        payload = json.dumps(data).encode()
+        self.send_response(status)
+        self.send_header("Content-Type", "application/json")
+        self.send_header("Content-Length", str(len(payload)))
+        self.end_headers()
+        self.wfile.write(payload)
+
+    def log_message(self, *args):
+        pass
+
+    @classmethod
+    def send_text(cls, chat_id, text, language_code="en"):
+        chat = {"id": chat_id, "type": "private", "username": "tester", "first_name": "Test"}
+        cls.updates.put(
+            {
+                "update_id": cls.next_update_id,
+                "message": {
+                    "message_id": cls.next_update_id,
+                    "date": int(time.time()),
+                    "chat": chat,
+                    "from": {"id": chat_id, "is_bot": False, "first_name": "Test", "language_code": language_code},
+                    "text": text,
+                },
+            }
+        )
+        cls.next_update_id += 1
+
+
+class ApiV1Telegram(TestBase):
+    async def next_message(self, timeout=30):
+        return await asyncio.to_thread(FakeBotApi.sent.get, timeout=timeout)
+
+    def channels(self):
+        r = requests.get(f"{self.base_url}/api/v1/me/channels", headers={"authorization": self.access_token})
+        r.raise_for_status()
+        return r.json()["channels"]
+
+    async def setup(self):
+        server = ThreadingHTTPServer(("127.0.0.1", BOT_API_PORT), FakeBotApi)
+        threading.Thread(target=server.serve_forever, daemon=True).start()
+
+        headers = {"authorization": self.access_token}
+        # Telegram channels are set up by the server, a chat can't be picked directly
+        r = requests.post(
+            f"{self.base_url}/api/v1/me/channels", json={"kind": "Telegram", "config": {"chat_id": 1}}, headers=headers
+        )
+        assert r.json()["status"] == 400, r.json()
+
+        r = requests.post(f"{self.base_url}/api/v1/me/channels", json={"kind": "Telegram"}, headers=headers)
+        r.raise_for_status()
+        channel = r.json()["channel"]
+        self.log(f"Telegram channel: {channel}")
+        assert channel["enabled"] is False, "Channel must stay off until the chat is linked"
+        code = channel["link"]["code"]
+
+        # A wrong code is answered in the sender's language, nothing gets linked
+        FakeBotApi.send_text(CHAT_ID + 1, "/start not-a-code")
+        message = await self.next_message()
+        assert message["chat_id"] == CHAT_ID + 1
+        assert "Unknown or expired code" in message["text"], message
+
+        # The right code links the chat, the reply uses the account's language
+        FakeBotApi.send_text(CHAT_ID, f"/start {code}")
+        message = await self.next_message()
+        assert message["chat_id"] == CHAT_ID
+        assert message["text"].startswith("Готово!"), message
+
+        [telegram] = [c for c in self.channels() if c["kind"] == "Telegram"]
+        assert telegram["enabled"] is True
+        assert telegram["description"] == "Telegram chat @tester"
+        assert "link" not in telegram
+
+        # Codes are one-time
+        FakeBotApi.send_text(CHAT_ID + 2, code)
+        message = await self.next_message()
+        assert message["chat_id"] == CHAT_ID + 2 and "Unknown or expired code" in message["text"], message
+
+    async def on_connected(self, ws):
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+
+        # Same localized titles as ntfy
+        message = await self.wait_for_message(ws)
+        assert message["title"] == "Девайс під'єднано!"
+        message = await self.next_message()
+        assert message["chat_id"] == CHAT_ID
+        assert message["parse_mode"] == "HTML"
+        assert message["text"] == "<b>Девайс під'єднано!</b>", message
+
+        message = await self.wait_for_message(ws)
+        assert message["title"] == "Відключення світла!"
+        message = await self.next_message()
+        assert message["text"].startswith("<b>Відключення світла!</b>\nСвітло було"), message
+
+        metrics = requests.get(f"{self.base_url}/api/v1/metrics").text
+        lines = [line for line in metrics.splitlines() if 'channel="telegram"' in line]
+        assert any('result="success"' in line and 'type="down"' in line for line in lines), lines
+
+        # Once the user blocks the bot, retries are pointless
+        FakeBotApi.blocked = True
+        r = requests.get(f"{self.base_url}/api/v1/up", headers={"authorization": self.heartbeat_token})
+        r.raise_for_status()
+        failed = []
+        for _ in range(10):
+            await asyncio.sleep(1)
+            r = requests.get(f"{self.base_url}/api/v1/admin/outbox?failed=true", headers={"authorization": self.access_token})
+            r.raise_for_status()
+            failed = r.json()["outbox"]
+            if failed:
+                break
+        [item] = failed
+        assert item["attempts"] == 1 and "bot was blocked" in item["last_error"], item
+
+
+if __name__ == "__main__":
+    test = ApiV1Telegram(timeout=90)
+    asyncio.run(test.run())
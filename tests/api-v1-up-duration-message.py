@@ -19,7 +19,7 @@ class ApiV1UpDurationMessage(TestBase):
     DURATION_PATTERN = re.compile(r"(\d+\s+(день|дні|днів)\s+)?(\d+\s+год\s+)?(\d+\s+хв)")
 
     async def ping(self):
-        headers = {"authorization": self.state["user"]["access_token"]}
+        headers = {"authorization": self.heartbeat_token}
         r = requests.get(f"{self.base_url}/api/v1/up", headers=headers)
         r.raise_for_status()
 
@@ -8,7 +8,7 @@ from lib.testbase import TestBase
 
 class ApiV1UpTestSuccess(TestBase):
     async def ping(self):
-        headers = {"authorization": self.state["user"]["access_token"]}
+        headers = {"authorization": self.heartbeat_token}
         r = requests.get(f"{self.base_url}/api/v1/up", headers=headers)
         r.raise_for_status()
 
@@ -0,0 +1,109 @@
+#!/usr/bin/env python
+import asyncio
+import hashlib
+import hmac
+import json
+import queue
+import threading
+from http.server import BaseHTTPRequestHandler, HTTPServer
+
+import requests
+from lib.testbase import TestBase
+
+WEBHOOK_PORT = 8095
+WEBHOOK_SECRET = "webhook-test-secret"
+
+
+class WebhookStandIn(BaseHTTPRequestHandler):
+    """Local stand-in for e.g. Home Assistant: fails the first delivery to exercise retries."""
+
+    received = queue.Queue()
+    fail_next = True
+
+    def do_POST(self):
+        body = self.rfile.read(int(self.headers["Content-Length"]))
+        status = 500 if WebhookStandIn.fail_next else 200
+        WebhookStandIn.fail_next = False
+        WebhookStandIn.received.put((status, self.headers, body))
+        self.send_response(status)
+        self.end_headers()
+
+    def log_message(self, *args):
+        pass
+
+
+class ApiV1Webhook(TestBase):
+    async def ping(self):
+        headers = {"authorization": self.heartbeat_token}
+        r = requests.get(f"{self.base_url}/api/v1/up", headers=headers)
+        r.raise_for_status()
+
+    async def next_delivery(self, timeout=15):
+        return await asyncio.to_thread(WebhookStandIn.received.get, timeout=timeout)
+
+    async def setup(self):
+        server = HTTPServer(("127.0.0.1", WEBHOOK_PORT), WebhookStandIn)
+        threading.Thread(target=server.serve_forever, daemon=True).start()
+
+        headers = {"authorization": self.access_token}
+        data = {"kind": "Webhook", "config": {"url": f"http://127.0.0.1:{WEBHOOK_PORT}/hook", "secret": WEBHOOK_SECRET}}
+        r = requests.post(f"{self.base_url}/api/v1/me/channels", json=data, headers=headers)
+        r.raise_for_status()
+        assert r.json()["status"] == 200, r.json()
+        assert WEBHOOK_SECRET not in r.text, "Webhook secret must not be echoed back"
+
+    async def on_connected(self, ws):
+        await self.ping()
+
+        # ntfy still gets its notification next to the webhook
+        message = await self.wait_for_message(ws)
+        assert message["title"] == "Девайс під'єднано!"
+
+        # First delivery is answered with 500, the retry from the outbox (30s later) succeeds.
+        # The device keeps pinging meanwhile, so the retry is the next delivery.
+        status, _, first_body = await self.next_delivery()
+        assert status == 500
+        for _ in range(9):
+            await self.ping()
+            try:
+                status, headers, body = await self.next_delivery(timeout=5)
+                break
+            except queue.Empty:
+                pass
+        assert status == 200
+        assert body == first_body, "Retries must resend the same payload"
+
+        expected = "sha256=" + hmac.new(WEBHOOK_SECRET.encode(), body, hashlib.sha256).hexdigest()
+        assert hmac.compare_digest(headers["X-Oubot-Signature"], expected), "Invalid webhook signature"
+        assert headers["X-Oubot-Event"] == "connected"
+
+        payload = json.loads(body)
+        self.log(f"Webhook payload: {payload}")
+        assert payload["event"] == "connected"
+        assert payload["old_status"] == "Uninitialized"
+        assert payload["new_status"] == "Up"
+        assert payload["device_id"] == self.state["devices"][0]["device"]["id"]
+        assert payload["device_name"] == "default"
+        assert payload["title"] == "Девайс під'єднано!"
+        assert payload["language"] == "uk"
+        assert payload["duration"] is None
+        assert isinstance(payload["timestamp"], int)
+
+        # Downtime is delivered right away (no failure injected anymore)
+        message = await self.wait_for_message(ws)
+        assert message["title"] == "Відключення світла!"
+        status, _, body = await self.next_delivery()
+        payload = json.loads(body)
+        assert status == 200
+        assert (payload["event"], payload["old_status"], payload["new_status"]) == ("down", "Up", "Down")
+        assert payload["duration"] >= 10
+
+        metrics = requests.get(f"{self.base_url}/api/v1/metrics").text
+        webhook_lines = [line for line in metrics.splitlines() if 'channel="webhook"' in line]
+        assert any('result="success"' in line and 'type="connected"' in line for line in webhook_lines), webhook_lines
+        assert any('channel="ntfy"' in line for line in metrics.splitlines()), "Expected ntfy channel label"
+
+
+if __name__ == "__main__":
+    test = ApiV1Webhook(timeout=90)
+    asyncio.run(test.run())
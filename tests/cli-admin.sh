@@ -7,6 +7,7 @@
 # 2. Admin user <id> (get specific)
 # 3. Admin invites (list)
 # 4. Admin delete-invite
+# 5. Admin outbox and retry-notification
 #
 
 set -euo pipefail
@@ -149,6 +150,28 @@ echo "Invite deletion verified"
 
 sleep 1
 
+# Notification outbox: nothing was sent, so nothing is queued
+echo ""
+echo "[Step 5] Notification outbox"
+OUTBOX_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" admin outbox --failed)
+echo "$OUTBOX_OUTPUT"
+if ! echo "$OUTBOX_OUTPUT" | grep -q "No queued notifications."; then
+    echo "ERROR: Outbox should be empty"
+    exit 1
+fi
+if RETRY_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" admin retry-notification "$USER_ID" 2>&1); then
+    echo "ERROR: Retrying an unknown notification should fail"
+    exit 1
+fi
+echo "$RETRY_OUTPUT"
+if ! echo "$RETRY_OUTPUT" | grep -q "No undeliverable notification"; then
+    echo "ERROR: Expected not found error"
+    exit 1
+fi
+echo "Outbox commands verified"
+
+sleep 1
+
 # Cleanup: delete user
 echo ""
 echo "[Cleanup] Delete user"
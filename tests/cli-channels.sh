@@ -0,0 +1,188 @@
+#!/usr/bin/env bash
+#
+# CLI Notification Channels Test
+#
+# Tests the per-user channel list:
+# 1. A new account starts with its ntfy channel, toggled by `ntfy disable/enable`
+# 2. Add a webhook channel, its secret is never shown
+# 3. Invalid and duplicate channels are rejected, ntfy channels take no config
+# 4. Disable the webhook and change its URL, the channel keeps its ID
+# 5. Remove the webhook channel
+# 6. Channels from URLs: added once, then updated in place
+# 7. Escalation steps, the disabled Gotify channel as extra channel
+#
+
+set -euo pipefail
+
+SERVER="${OUBOT_BASE_URL:?OUBOT_BASE_URL must be set}"
+
+echo "============================================================"
+echo "CLI Notification Channels Test"
+echo "============================================================"
+
+# Setup: Initialize admin account
+echo ""
+echo "[Setup] Initialize admin account"
+INIT_OUTPUT=$(oubot-cli --server "$SERVER" init)
+ADMIN_TOKEN=$(echo "$INIT_OUTPUT" | grep "Your access token:" | awk '{print $4}')
+if [ -z "$ADMIN_TOKEN" ]; then
+    echo "ERROR: Failed to extract admin token"
+    exit 1
+fi
+echo "Admin token ready"
+
+# Step 1: The ntfy channel
+echo ""
+echo "[Step 1] New accounts have an ntfy channel"
+LIST_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" channel list)
+echo "$LIST_OUTPUT"
+NTFY_ID=$(echo "$LIST_OUTPUT" | grep " ntfy " | awk '{print $1}')
+if [ -z "$NTFY_ID" ] || ! echo "$LIST_OUTPUT" | grep " ntfy " | grep -q "\[ON\]"; then
+    echo "ERROR: Expected an enabled ntfy channel"
+    exit 1
+fi
+oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" ntfy disable
+LIST_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" channel list)
+if ! echo "$LIST_OUTPUT" | grep "$NTFY_ID" | grep -q "\[OFF\]"; then
+    echo "ERROR: 'ntfy disable' should disable the ntfy channel"
+    exit 1
+fi
+oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" channel enable "$NTFY_ID"
+NTFY_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" ntfy show)
+if ! echo "$NTFY_OUTPUT" | grep -q "\[ON\]"; then
+    echo "ERROR: Enabling the ntfy channel should show in 'ntfy show'"
+    exit 1
+fi
+echo "ntfy channel toggles both ways"
+
+# Step 2: Webhook channel
+sleep 1
+echo ""
+echo "[Step 2] Add a webhook channel"
+ADD_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" channel add webhook url=http://127.0.0.1:9/hook secret=s3cr3t-value)
+echo "$ADD_OUTPUT"
+HOOK_ID=$(echo "$ADD_OUTPUT" | grep "Channel added:" | awk '{print $3}')
+if [ -z "$HOOK_ID" ]; then
+    echo "ERROR: Failed to extract channel id"
+    exit 1
+fi
+RAW_LIST=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" --raw channel list)
+if echo "$RAW_LIST" | grep -q "s3cr3t-value"; then
+    echo "ERROR: Channel secrets must never be returned"
+    exit 1
+fi
+if ! echo "$RAW_LIST" | grep -q "http://127.0.0.1:9/hook"; then
+    echo "ERROR: Webhook URL should be listed as the channel description"
+    exit 1
+fi
+echo "Webhook channel added"
+
+# Step 3: Rejected channels
+sleep 1
+echo ""
+echo "[Step 3] Invalid and duplicate channels are rejected"
+for ARGS in "webhook url=file:///etc/passwd" "webhook" "webhook url=http://127.0.0.1:9/hook" "ntfy topic=someone-else" "carrier-pigeon"; do
+    # shellcheck disable=SC2086
+    if oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" channel add $ARGS 2>/dev/null; then
+        echo "ERROR: 'channel add $ARGS' should be rejected"
+        exit 1
+    fi
+    sleep 1
+done
+if oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" channel set "$NTFY_ID" topic=someone-else 2>/dev/null; then
+    echo "ERROR: The ntfy topic must not be changeable"
+    exit 1
+fi
+echo "Rejected as expected"
+
+# Step 4: Update
+sleep 1
+echo ""
+echo "[Step 4] Disable the webhook and change its URL"
+oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" channel disable "$HOOK_ID"
+sleep 1
+SET_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" channel set "$HOOK_ID" url=http://127.0.0.1:9/other secret=)
+echo "$SET_OUTPUT"
+if ! echo "$SET_OUTPUT" | grep -q "$HOOK_ID \[OFF\] http://127.0.0.1:9/other"; then
+    echo "ERROR: Expected the disabled webhook with its new URL"
+    exit 1
+fi
+echo "Webhook updated"
+
+# Step 5: Remove
+sleep 1
+echo ""
+echo "[Step 5] Remove the webhook channel"
+oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" channel remove "$HOOK_ID"
+LIST_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" channel list)
+if echo "$LIST_OUTPUT" | grep -q "$HOOK_ID"; then
+    echo "ERROR: Removed channel should be gone from the list"
+    exit 1
+fi
+echo "Webhook channel removed"
+
+# Step 6: URLs
+sleep 1
+echo ""
+echo "[Step 6] Add a Gotify channel from a URL, then update it with the same URL"
+URL_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" channel url "gotify://127.0.0.1:9/AppToken123")
+echo "$URL_OUTPUT"
+GOTIFY_ID=$(echo "$URL_OUTPUT" | grep "Channel added:" | awk '{print $3}')
+if [ -z "$GOTIFY_ID" ]; then
+    echo "ERROR: Expected a new channel from the URL"
+    exit 1
+fi
+sleep 1
+URL_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" channel url --disabled "gotify://127.0.0.1:9/AppToken123?priority=low")
+echo "$URL_OUTPUT"
+if ! echo "$URL_OUTPUT" | grep -q "$GOTIFY_ID \[OFF\] Gotify app"; then
+    echo "ERROR: The same URL should update the existing channel"
+    exit 1
+fi
+sleep 1
+RAW_LIST=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" --raw channel list)
+if echo "$RAW_LIST" | grep -q "AppToken123"; then
+    echo "ERROR: Gotify tokens must never be returned"
+    exit 1
+fi
+if oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" channel url "tgram://bot/chat" 2>/dev/null; then
+    echo "ERROR: Unsupported URL schemes should be rejected"
+    exit 1
+fi
+echo "Channel URLs work"
+
+# Step 7: Escalation
+sleep 1
+echo ""
+echo "[Step 7] Escalation steps for long outages"
+STEP_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" escalation add 240 --priority max --channel "$GOTIFY_ID")
+echo "$STEP_OUTPUT"
+STEP_ID=$(echo "$STEP_OUTPUT" | grep "Escalation step added:" | awk '{print $4}')
+if [ -z "$STEP_ID" ] || ! echo "$STEP_OUTPUT" | grep -q "still down after 4h, priority max"; then
+    echo "ERROR: Expected a step after 4h with max priority"
+    exit 1
+fi
+oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" escalation add 90
+sleep 1
+if oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" escalation add 90 2>/dev/null; then
+    echo "ERROR: Two steps with the same delay should be rejected"
+    exit 1
+fi
+LIST_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" escalation list)
+echo "$LIST_OUTPUT"
+if ! echo "$LIST_OUTPUT" | grep -A1 "1h 30m" | grep -q "$STEP_ID.*4h.*max.*$GOTIFY_ID"; then
+    echo "ERROR: Steps should be listed by delay with their extra channel"
+    exit 1
+fi
+sleep 1
+oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" escalation remove "$STEP_ID"
+if oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" escalation list | grep -q "$STEP_ID"; then
+    echo "ERROR: Removed step should be gone from the list"
+    exit 1
+fi
+echo "Escalation steps work"
+
+echo ""
+echo "============================================================"
+echo "All notification channel tests passed!"
+echo "============================================================"
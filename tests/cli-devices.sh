@@ -0,0 +1,185 @@
+#!/usr/bin/env bash
+#
+# CLI Devices Test
+#
+# Tests managing several monitored devices on one account:
+# 1. New account has a single 'default' device
+# 2. Add a second device, heartbeat with its token
+# 3. Device token is not accepted for account endpoints
+# 4. Settings require --device once there are several devices, outage debouncing settings
+# 5. Rename device, per-device settings
+# 6. Remove device, its token stops working
+#
+
+set -euo pipefail
+
+SERVER="${OUBOT_BASE_URL:?OUBOT_BASE_URL must be set}"
+
+echo "============================================================"
+echo "CLI Devices Test"
+echo "============================================================"
+
+# Setup: Initialize admin account
+echo ""
+echo "[Setup] Initialize admin account"
+INIT_OUTPUT=$(oubot-cli --server "$SERVER" init)
+ADMIN_TOKEN=$(echo "$INIT_OUTPUT" | grep "Your access token:" | awk '{print $4}')
+if [ -z "$ADMIN_TOKEN" ]; then
+    echo "ERROR: Failed to extract admin token"
+    exit 1
+fi
+echo "Admin token ready"
+
+# Step 1: Default device
+echo ""
+echo "[Step 1] List devices of a new account"
+LIST_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" device list)
+echo "$LIST_OUTPUT"
+if ! echo "$LIST_OUTPUT" | grep -q "default"; then
+    echo "ERROR: New account should have a 'default' device"
+    exit 1
+fi
+if ! echo "$LIST_OUTPUT" | grep -q "Total: 1 device(s)"; then
+    echo "ERROR: New account should have exactly one device"
+    exit 1
+fi
+DEFAULT_ID=$(echo "$LIST_OUTPUT" | grep "default" | awk '{print $1}')
+DEFAULT_TOKEN=$(echo "$INIT_OUTPUT" | grep -o 'hb_[A-Za-z0-9]*')
+curl -sf -H "Authorization: $DEFAULT_TOKEN" "$SERVER/api/v1/up" >/dev/null
+echo "Default device accepts its heartbeat token"
+
+# Step 2: Add a second device
+echo ""
+echo "[Step 2] Add 'Generator' device"
+ADD_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" device add Generator --delay 30)
+echo "$ADD_OUTPUT"
+GEN_ID=$(echo "$ADD_OUTPUT" | grep "^ID:" | awk '{print $2}')
+GEN_TOKEN=$(echo "$ADD_OUTPUT" | grep "^Token:" | awk '{print $2}')
+if [ -z "$GEN_ID" ] || [ -z "$GEN_TOKEN" ]; then
+    echo "ERROR: Failed to extract device id/token"
+    exit 1
+fi
+if [ "$GEN_TOKEN" = "$ADMIN_TOKEN" ]; then
+    echo "ERROR: New device must get its own token"
+    exit 1
+fi
+if oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" device add Generator 2>/dev/null; then
+    echo "ERROR: Duplicate device name should be rejected"
+    exit 1
+fi
+
+sleep 1
+
+curl -sf -H "Authorization: $GEN_TOKEN" "$SERVER/api/v1/up" >/dev/null
+SHOW_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" device show "$GEN_ID")
+echo "$SHOW_OUTPUT"
+if ! echo "$SHOW_OUTPUT" | grep -q "Status:.*Up"; then
+    echo "ERROR: Generator should be Up after its first heartbeat"
+    exit 1
+fi
+echo "Generator heartbeat recorded"
+
+# Step 3: Device token can't manage the account
+echo ""
+echo "[Step 3] Device token rejected for account endpoints"
+if oubot-cli --server "$SERVER" --token "$GEN_TOKEN" me 2>/dev/null; then
+    echo "ERROR: Device token should not grant account access"
+    exit 1
+fi
+echo "Device token correctly rejected"
+
+sleep 1
+
+# Step 4: Settings need --device with several devices
+echo ""
+echo "[Step 4] Settings with several devices"
+if oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" settings show 2>/dev/null; then
+    echo "ERROR: Settings without --device should fail with several devices"
+    exit 1
+fi
+oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" --device "$GEN_ID" maintenance add 23:50 00:10 --days fri --label reboot
+SETTINGS_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" --device "$GEN_ID" settings show)
+echo "$SETTINGS_OUTPUT"
+if ! echo "$SETTINGS_OUTPUT" | grep -q "Up delay:.*30s"; then
+    echo "ERROR: Generator up_delay should be 30s"
+    exit 1
+fi
+MAINT_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" maintenance list)
+echo "$MAINT_OUTPUT"
+if ! echo "$MAINT_OUTPUT" | grep -q "fri 23:50-00:10 *$GEN_ID .*reboot"; then
+    echo "ERROR: Generator maintenance window should be set"
+    exit 1
+fi
+if echo "$MAINT_OUTPUT" | grep -q "$DEFAULT_ID"; then
+    echo "ERROR: Default device should have no maintenance window"
+    exit 1
+fi
+echo "Per-device settings work"
+
+sleep 1
+
+echo ""
+echo "[Step 4b] Outage debouncing"
+oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" --device "$GEN_ID" settings min-outage 120
+oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" --device "$GEN_ID" settings flapping 4 --window 15
+SETTINGS_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" --device "$GEN_ID" settings show)
+echo "$SETTINGS_OUTPUT"
+if ! echo "$SETTINGS_OUTPUT" | grep -q "Min outage:.*120s"; then
+    echo "ERROR: Generator min outage should be 120s"
+    exit 1
+fi
+if ! echo "$SETTINGS_OUTPUT" | grep -q "Flapping:.*4 changes in 15m, stable after 15m"; then
+    echo "ERROR: Generator flap detection should be enabled"
+    exit 1
+fi
+sleep 1
+FLAP_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" --device "$GEN_ID" settings flapping)
+echo "$FLAP_OUTPUT"
+if ! echo "$FLAP_OUTPUT" | grep -q "flapping:.*\[off\]"; then
+    echo "ERROR: Flap detection should be disabled"
+    exit 1
+fi
+if oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" --device "$GEN_ID" settings flapping 1 2>/dev/null; then
+    echo "ERROR: A single change should not count as flapping"
+    exit 1
+fi
+sleep 1
+RECOVERY_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" --device "$GEN_ID" settings recovery 3)
+echo "$RECOVERY_OUTPUT"
+if ! echo "$RECOVERY_OUTPUT" | grep -q "recovery:.*3 heartbeats"; then
+    echo "ERROR: Generator should need 3 heartbeats to recover"
+    exit 1
+fi
+echo "Outage debouncing settings work"
+
+sleep 1
+
+# Step 5: Rename
+echo ""
+echo "[Step 5] Rename device"
+oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" device rename "$GEN_ID" "Neighbour line"
+LIST_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" device list)
+echo "$LIST_OUTPUT"
+if ! echo "$LIST_OUTPUT" | grep -q "Neighbour line"; then
+    echo "ERROR: Device should be renamed"
+    exit 1
+fi
+
+sleep 1
+
+# Step 6: Remove
+echo ""
+echo "[Step 6] Remove device"
+oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" device remove "$GEN_ID"
+if curl -sf -H "Authorization: $GEN_TOKEN" "$SERVER/api/v1/up" >/dev/null; then
+    echo "ERROR: Removed device token should be rejected"
+    exit 1
+fi
+# Back to a single device, settings work without --device again
+oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" settings show
+echo "Device removed"
+
+echo ""
+echo "============================================================"
+echo "All device tests passed!"
+echo "============================================================"
@@ -3,10 +3,14 @@
 # CLI Settings Test
 #
 # Tests user self-service settings commands:
-# 1. Token show
+# 1. Tokens are not echoed back (stored hashed)
 # 2. Language change + verify
 # 3. Ntfy disable/show/enable/show cycle
 # 4. Token regenerate + verify old fails + new works
+# 5. Heartbeat token scope + regenerate without touching the account token
+# 6. Per-event notification settings: set, show, reject invalid, reset
+# 7. Quiet hours: set, per-event behaviour, clear
+# 8. Time zone: local times with --tz, reject unknown zones
 #
 
 set -euo pipefail
@@ -26,19 +30,27 @@ if [ -z "$ADMIN_TOKEN" ]; then
     echo "ERROR: Failed to extract admin token"
     exit 1
 fi
+HB_TOKEN=$(echo "$INIT_OUTPUT" | grep -o 'hb_[A-Za-z0-9]*')
+if [ -z "$HB_TOKEN" ]; then
+    echo "ERROR: Failed to extract heartbeat token"
+    exit 1
+fi
 echo "Admin token ready"
 
-# Step 1: Token show
+# Step 1: Tokens are only shown once
 echo ""
-echo "[Step 1] Token show"
-TOKEN_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" token show)
-echo "Token show output: $TOKEN_OUTPUT"
-# Verify the shown token matches what we have
-if [ "$TOKEN_OUTPUT" != "$ADMIN_TOKEN" ]; then
-    echo "ERROR: Token show output '$TOKEN_OUTPUT' doesn't match admin token '$ADMIN_TOKEN'"
+echo "[Step 1] Verify tokens are not echoed back"
+ME_RAW=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" --raw me)
+if echo "$ME_RAW" | grep -qE "$ADMIN_TOKEN|$HB_TOKEN|token_hash"; then
+    echo "ERROR: 'me' should not expose tokens or their hashes"
     exit 1
 fi
-echo "Token show matches"
+DEVICES_RAW=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" --raw device list)
+if echo "$DEVICES_RAW" | grep -qE "$HB_TOKEN|token_hash"; then
+    echo "ERROR: 'device list' should not expose tokens or their hashes"
+    exit 1
+fi
+echo "Tokens not exposed"
 
 # Step 2: Language change
 echo ""
@@ -146,6 +158,151 @@ if ! echo "$ME_OUTPUT" | grep -q "User Info"; then
 fi
 echo "New token works correctly"
 
+# Step 5: Heartbeat token
+echo ""
+echo "[Step 5] Heartbeat token scope"
+echo "Heartbeat token: $HB_TOKEN"
+curl -sf -H "Authorization: $HB_TOKEN" "$SERVER/api/v1/up" >/dev/null
+STATUS=$(curl -s -o /dev/null -w '%{http_code}' -H "Authorization: $NEW_TOKEN" "$SERVER/api/v1/up")
+if [ "$STATUS" != "403" ]; then
+    echo "ERROR: Account token should be rejected by /api/v1/up with 403, got $STATUS"
+    exit 1
+fi
+if oubot-cli --server "$SERVER" --token "$HB_TOKEN" me 2>/dev/null; then
+    echo "ERROR: Heartbeat token should not grant account access"
+    exit 1
+fi
+echo "Token scopes enforced"
+
+sleep 1
+
+echo ""
+echo "[Step 5b] Heartbeat token regenerate"
+REGEN_OUTPUT=$(oubot-cli --server "$SERVER" --token "$NEW_TOKEN" --raw token regenerate --heartbeat)
+NEW_HB_TOKEN=$(echo "$REGEN_OUTPUT" | grep -o 'hb_[A-Za-z0-9]*')
+if [ -z "$NEW_HB_TOKEN" ]; then
+    echo "ERROR: Failed to extract new heartbeat token"
+    exit 1
+fi
+if curl -sf -H "Authorization: $HB_TOKEN" "$SERVER/api/v1/up" >/dev/null; then
+    echo "ERROR: Old heartbeat token should have been rejected"
+    exit 1
+fi
+curl -sf -H "Authorization: $NEW_HB_TOKEN" "$SERVER/api/v1/up" >/dev/null
+oubot-cli --server "$SERVER" --token "$NEW_TOKEN" me >/dev/null
+echo "Heartbeat token rotated, account token still works"
+
+sleep 1
+
+# Step 6: Per-event notification settings
+echo ""
+echo "[Step 6] Notification settings for power outages"
+OUTPUT=$(oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings notifications down --priority max --tags rotating_light,warning)
+echo "$OUTPUT"
+if ! echo "$OUTPUT" | grep -E "^down +\[ON\] +max +rotating_light,warning" >/dev/null; then
+    echo "ERROR: Expected max priority and custom tags for down"
+    exit 1
+fi
+
+echo ""
+echo "[Step 6b] Disable power restored notifications"
+OUTPUT=$(oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings notifications up --disable)
+echo "$OUTPUT"
+if ! echo "$OUTPUT" | grep -E "^up +\[OFF\]" >/dev/null; then
+    echo "ERROR: Expected up notifications to be disabled"
+    exit 1
+fi
+
+sleep 1
+
+echo ""
+echo "[Step 6c] Invalid settings are rejected"
+if oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings notifications down --click ftp://example.com 2>/dev/null; then
+    echo "ERROR: Non-http click URL should have been rejected"
+    exit 1
+fi
+if oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings notifications escalation --priority max 2>/dev/null; then
+    echo "ERROR: Escalation priority should have been rejected"
+    exit 1
+fi
+
+echo ""
+echo "[Step 6d] Reset to defaults"
+oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings notifications down --reset >/dev/null
+OUTPUT=$(oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings notifications)
+echo "$OUTPUT"
+if ! echo "$OUTPUT" | grep -E "^down +\[ON\] +high +warning" >/dev/null; then
+    echo "ERROR: Expected default settings for down after reset"
+    exit 1
+fi
+if ! echo "$OUTPUT" | grep -E "^up +\[OFF\]" >/dev/null; then
+    echo "ERROR: Resetting down should not touch up"
+    exit 1
+fi
+echo "Notification settings work"
+
+sleep 1
+
+# Step 7: Quiet hours
+echo ""
+echo "[Step 7] Quiet hours"
+OUTPUT=$(oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings quiet-hours 22:00 07:00)
+echo "$OUTPUT"
+if ! echo "$OUTPUT" | grep "quiet hours:  22:00-07:00 UTC" >/dev/null; then
+    echo "ERROR: Expected quiet hours 22:00-07:00 UTC"
+    exit 1
+fi
+OUTPUT=$(oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings notifications up --quiet defer)
+echo "$OUTPUT"
+if ! echo "$OUTPUT" | grep -E "^up +\[OFF\] +high +white_check_mark +defer" >/dev/null; then
+    echo "ERROR: Expected up notifications to be deferred during quiet hours"
+    exit 1
+fi
+if oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings quiet-hours 22:00 2>/dev/null; then
+    echo "ERROR: Quiet hours without an end should have been rejected"
+    exit 1
+fi
+
+sleep 1
+
+echo ""
+echo "[Step 7b] Clear quiet hours"
+oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings quiet-hours >/dev/null
+OUTPUT=$(oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings show)
+echo "$OUTPUT"
+if ! echo "$OUTPUT" | grep "Quiet hours:  \[not set\]" >/dev/null; then
+    echo "ERROR: Expected quiet hours to be cleared"
+    exit 1
+fi
+echo "Quiet hours work"
+
+sleep 1
+
+# Step 8: Time zone
+echo ""
+echo "[Step 8] Quiet hours in local time"
+OUTPUT=$(oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings quiet-hours 23:00 06:30 --tz Europe/Kyiv)
+echo "$OUTPUT"
+if ! echo "$OUTPUT" | grep "quiet hours:  23:00-06:30 Europe/Kyiv" >/dev/null; then
+    echo "ERROR: Expected quiet hours 23:00-06:30 in Europe/Kyiv"
+    exit 1
+fi
+if oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings timezone Europe/Atlantis 2>/dev/null; then
+    echo "ERROR: Unknown time zone should have been rejected"
+    exit 1
+fi
+
+echo ""
+echo "[Step 8b] Back to UTC"
+oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings timezone UTC >/dev/null
+OUTPUT=$(oubot-cli --server "$SERVER" --token "$NEW_TOKEN" settings show)
+echo "$OUTPUT"
+if ! echo "$OUTPUT" | grep "Quiet hours:  23:00-06:30 UTC" >/dev/null; then
+    echo "ERROR: Expected the quiet hours to be kept in the new time zone"
+    exit 1
+fi
+echo "Time zone works"
+
 echo ""
 echo "============================================================"
 echo "All settings tests passed!"
@@ -0,0 +1,146 @@
+#!/usr/bin/env bash
+#
+# CLI Named Tokens Test
+#
+# Tests named API tokens next to the account token:
+# 1. Create a named account token, use it, see it in the list with its last use
+# 2. Create a named heartbeat token, it only works for /api/v1/up
+# 3. Duplicate names are rejected
+# 4. Expired tokens are rejected
+# 5. Revoke a token, it stops working while the account token keeps working
+#
+
+set -euo pipefail
+
+SERVER="${OUBOT_BASE_URL:?OUBOT_BASE_URL must be set}"
+
+echo "============================================================"
+echo "CLI Named Tokens Test"
+echo "============================================================"
+
+# Setup: Initialize admin account
+echo ""
+echo "[Setup] Initialize admin account"
+INIT_OUTPUT=$(oubot-cli --server "$SERVER" init)
+ADMIN_TOKEN=$(echo "$INIT_OUTPUT" | grep "Your access token:" | awk '{print $4}')
+if [ -z "$ADMIN_TOKEN" ]; then
+    echo "ERROR: Failed to extract admin token"
+    exit 1
+fi
+echo "Admin token ready"
+
+# Step 1: Named account token
+echo ""
+echo "[Step 1] Create named account token"
+CREATE_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" token create ci --expires-in 30d)
+echo "$CREATE_OUTPUT"
+CI_ID=$(echo "$CREATE_OUTPUT" | grep "^ID:" | awk '{print $2}')
+CI_TOKEN=$(echo "$CREATE_OUTPUT" | grep "^Token:" | awk '{print $2}')
+if [ -z "$CI_ID" ] || ! echo "$CI_TOKEN" | grep -q '^tk_'; then
+    echo "ERROR: Failed to extract named token id/token"
+    exit 1
+fi
+if [ "$CI_TOKEN" = "$ADMIN_TOKEN" ]; then
+    echo "ERROR: Named token must differ from the account token"
+    exit 1
+fi
+oubot-cli --server "$SERVER" --token "$CI_TOKEN" me >/dev/null
+echo "Named account token works"
+
+sleep 1
+
+LIST_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" token list)
+echo "$LIST_OUTPUT"
+CI_ROW=$(echo "$LIST_OUTPUT" | grep "^$CI_ID")
+if ! echo "$CI_ROW" | grep -q "Account"; then
+    echo "ERROR: Token list should show the 'ci' account token"
+    exit 1
+fi
+if echo "$CI_ROW" | grep -q "never *$"; then
+    echo "ERROR: Token list should show the last use of 'ci'"
+    exit 1
+fi
+if echo "$LIST_OUTPUT" | grep -q "$CI_TOKEN"; then
+    echo "ERROR: Token list must not show the token itself"
+    exit 1
+fi
+echo "Token listed with its last use"
+
+# Step 2: Named heartbeat token
+echo ""
+echo "[Step 2] Create named heartbeat token"
+HB_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" token create spare-board --heartbeat)
+echo "$HB_OUTPUT"
+HB_TOKEN=$(echo "$HB_OUTPUT" | grep "^Token:" | awk '{print $2}')
+if ! echo "$HB_TOKEN" | grep -q '^hb_'; then
+    echo "ERROR: Heartbeat token should start with hb_"
+    exit 1
+fi
+if ! echo "$HB_OUTPUT" | grep -q "^Device:"; then
+    echo "ERROR: Heartbeat token should be bound to the default device"
+    exit 1
+fi
+
+sleep 1
+
+curl -sf -H "Authorization: $HB_TOKEN" "$SERVER/api/v1/up" >/dev/null
+if oubot-cli --server "$SERVER" --token "$HB_TOKEN" me 2>/dev/null; then
+    echo "ERROR: Named heartbeat token should not grant account access"
+    exit 1
+fi
+STATUS=$(curl -s -o /dev/null -w '%{http_code}' -H "Authorization: $CI_TOKEN" "$SERVER/api/v1/up")
+if [ "$STATUS" != "403" ]; then
+    echo "ERROR: Named account token should be rejected by /api/v1/up with 403, got $STATUS"
+    exit 1
+fi
+echo "Token scopes enforced"
+
+# Step 3: Duplicate name
+echo ""
+echo "[Step 3] Duplicate token name"
+sleep 1
+if oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" token create ci 2>/dev/null; then
+    echo "ERROR: Duplicate token name should be rejected"
+    exit 1
+fi
+echo "Duplicate name rejected"
+
+# Step 4: Expiry
+echo ""
+echo "[Step 4] Expired token"
+RESPONSE=$(curl -sf -X POST -H "Authorization: $ADMIN_TOKEN" -H 'Content-Type: application/json' \
+    -d '{"name": "short-lived", "scope": "Account", "expires_in": 1}' "$SERVER/api/v1/me/tokens")
+SHORT_TOKEN=$(echo "$RESPONSE" | grep -o 'tk_[A-Za-z0-9]*')
+if [ -z "$SHORT_TOKEN" ]; then
+    echo "ERROR: Failed to create short-lived token: $RESPONSE"
+    exit 1
+fi
+sleep 2
+STATUS=$(curl -s -o /dev/null -w '%{http_code}' -H "Authorization: $SHORT_TOKEN" "$SERVER/api/v1/me")
+if [ "$STATUS" != "401" ]; then
+    echo "ERROR: Expired token should be rejected with 401, got $STATUS"
+    exit 1
+fi
+echo "Expired token rejected"
+
+# Step 5: Revoke
+echo ""
+echo "[Step 5] Revoke named token"
+sleep 1
+oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" token revoke "$CI_ID"
+if oubot-cli --server "$SERVER" --token "$CI_TOKEN" me 2>/dev/null; then
+    echo "ERROR: Revoked token should be rejected"
+    exit 1
+fi
+oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" me >/dev/null
+LIST_OUTPUT=$(oubot-cli --server "$SERVER" --token "$ADMIN_TOKEN" token list)
+if echo "$LIST_OUTPUT" | grep -q "$CI_ID"; then
+    echo "ERROR: Revoked token should be gone from the list"
+    exit 1
+fi
+echo "Token revoked, account token still works"
+
+echo ""
+echo "============================================================"
+echo "All named token tests passed!"
+echo "============================================================"
@@ -49,6 +49,7 @@
             -e "NTFY_BASE_URL=http://${c.host}:${c.ntfy-port}" \
             -e "NTFY_ADMIN_TOKEN=$NTFY_ADMIN_TOKEN" \
             -e "NTFY_USER_TIER=${c.ntfy-tier}" \
+            -e "OUBOT_TOKEN_SECRET=${c.token-secret}" \
             -e "ROCKET_PORT=${c.oubot-port}" \
             open-uptime-bot:${docker-image.imageTag}
         '';
@@ -79,7 +80,8 @@
     )
     data = json.loads(response)
     assert data["status"] == 200, f"Expected status 200, got: {response}"
-    token = data["state"]["user"]["access_token"]
+    token = data["access_token"]
+    heartbeat_token = data["heartbeat_token"]
     print(f"Admin token: {token}")
 
     # --- Verify authenticated endpoint works ---
@@ -90,7 +92,7 @@
 
     # --- Send heartbeat and verify uptime state metric ---
     primary.succeed(
-        f"curl -sf -H 'Authorization: token {token}' "
+        f"curl -sf -H 'Authorization: token {heartbeat_token}' "
         f"http://localhost:${c.oubot-port}/api/v1/up"
     )
     time.sleep(1)
@@ -5,6 +5,11 @@
   oubot-port = "8000";
   ntfy-port = "8085";
   ntfy-tier = "cool-tier";
+  token-secret = "test-token-secret";
+  telegram-port = "8096";
+  telegram-bot-token = "123456:test-bot-token";
+  smtp-port = "2525";
+  smtp-from = "Uptime Bot <bot@oubot.test>";
   psql-port = "5432";
   psql-user = "postgres";
   psql-db = "postgres";
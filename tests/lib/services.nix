@@ -25,6 +25,7 @@ in {
       LD_LIBRARY_PATH = pkgs.lib.makeLibraryPath [pkgs.openssl];
       NTFY_BASE_URL = "http://${c.host}:${c.ntfy-port}";
       NTFY_USER_TIER = c.ntfy-tier;
+      OUBOT_TOKEN_SECRET = c.token-secret;
       DATABASE_URL = "postgres://${c.psql-user}:a@localhost:${c.psql-port}/${c.psql-db}";
       # @NOTE: Without Rocket.toml (which lives in the repo, not the Nix store),
       #  Rocket defaults to 127.0.0.1. Bind to 0.0.0.0 so multi-node tests can
@@ -37,6 +37,9 @@ class TestBase(ABC):
         # self.log(f"Result: {result} ...")
         assert result["status"] == 200
         self.state = result["state"]
+        # Plaintext tokens are only returned on creation
+        self.access_token = result["access_token"]
+        self.heartbeat_token = result["heartbeat_token"]
 
         username = self.state["ntfy"]["username"]
         password = self.state["ntfy"]["password"]